@@ -32,6 +32,8 @@ async fn main() -> Result<()> {
                 tool_calls: None,
                 function_call: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }
         ],
         max_tokens: Some(100),
@@ -49,6 +51,14 @@ async fn main() -> Result<()> {
         top_logprobs: None,
         tools: None,
         tool_choice: None,
+        modalities: None,
+        audio: None,
+        suffix: None,
+        system_prompt_ref: None,
+        response_format: None,
+        top_k: None,
+        reasoning_effort: None,
+        conversation_id: None,
     };
 
     println!("Sending request to backend...");
@@ -0,0 +1,280 @@
+//! # AWS Event Stream Decoding
+//!
+//! Bedrock's `InvokeModelWithResponseStream` answers with the binary
+//! `application/vnd.amazon.eventstream` framing rather than SSE. Each message
+//! is laid out as:
+//!
+//! ```text
+//! total_len: u32 | headers_len: u32 | prelude_crc: u32 | headers | payload | message_crc: u32
+//! ```
+//!
+//! with big-endian integers and CRC32 checksums over the prelude and over the
+//! whole message before its trailing checksum. Chunk payloads are JSON
+//! objects carrying the model's own JSON, base64-encoded, under `bytes`.
+
+use crate::error::ProxyError;
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Bytes in the prelude: total length, headers length and prelude CRC
+const PRELUDE_LEN: usize = 12;
+/// Bytes in the trailing message CRC
+const MESSAGE_CRC_LEN: usize = 4;
+/// Upper bound on a single message, as documented for the protocol
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// A decoded event-stream message
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventStreamMessage {
+    /// String-valued headers such as `:message-type` and `:event-type`
+    pub headers: HashMap<String, String>,
+    /// Raw message payload
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    /// Value of a string header
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Incremental decoder that buffers partial frames across reads
+#[derive(Debug, Default)]
+pub struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    /// Create a decoder with an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `bytes` and return every message that is now complete.
+    ///
+    /// Fails with [`ProxyError::Upstream`] on a checksum mismatch or a
+    /// malformed frame; the stream cannot be resynchronised after that.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<EventStreamMessage>, ProxyError> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut messages = Vec::new();
+        while self.buffer.len() >= PRELUDE_LEN {
+            let total_len = read_u32(&self.buffer[0..4]) as usize;
+            let headers_len = read_u32(&self.buffer[4..8]) as usize;
+            let prelude_crc = read_u32(&self.buffer[8..12]);
+
+            if crc32fast::hash(&self.buffer[0..8]) != prelude_crc {
+                return Err(ProxyError::Upstream("event stream prelude checksum mismatch".to_string()));
+            }
+            if total_len > MAX_MESSAGE_LEN || total_len < PRELUDE_LEN + headers_len + MESSAGE_CRC_LEN {
+                return Err(ProxyError::Upstream(format!("invalid event stream message length {}", total_len)));
+            }
+            if self.buffer.len() < total_len {
+                break;
+            }
+
+            let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+            let message_crc = read_u32(&frame[total_len - MESSAGE_CRC_LEN..]);
+            if crc32fast::hash(&frame[..total_len - MESSAGE_CRC_LEN]) != message_crc {
+                return Err(ProxyError::Upstream("event stream message checksum mismatch".to_string()));
+            }
+
+            let headers_end = PRELUDE_LEN + headers_len;
+            messages.push(EventStreamMessage {
+                headers: decode_headers(&frame[PRELUDE_LEN..headers_end])?,
+                payload: frame[headers_end..total_len - MESSAGE_CRC_LEN].to_vec(),
+            });
+        }
+
+        Ok(messages)
+    }
+
+    /// Whether a partial message is still buffered
+    pub fn has_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Decode the header block, keeping string values and skipping the others
+fn decode_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>, ProxyError> {
+    let malformed = || ProxyError::Upstream("malformed event stream headers".to_string());
+    let take = |bytes: &mut &[u8], len: usize| -> Result<Vec<u8>, ProxyError> {
+        if bytes.len() < len {
+            return Err(malformed());
+        }
+        let (value, rest) = bytes.split_at(len);
+        *bytes = rest;
+        Ok(value.to_vec())
+    };
+
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = take(&mut bytes, 1)?[0] as usize;
+        let name = String::from_utf8(take(&mut bytes, name_len)?).map_err(|_| malformed())?;
+        let value_type = take(&mut bytes, 1)?[0];
+        let fixed_len = match value_type {
+            // bool true, bool false
+            0 | 1 => 0,
+            // byte, short, int, long, timestamp, uuid
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            // byte array, string
+            6 | 7 => {
+                let len = take(&mut bytes, 2)?;
+                let value = take(&mut bytes, u16::from_be_bytes([len[0], len[1]]) as usize)?;
+                if value_type == 7 {
+                    headers.insert(name, String::from_utf8(value).map_err(|_| malformed())?);
+                }
+                0
+            }
+            _ => return Err(malformed()),
+        };
+        take(&mut bytes, fixed_len)?;
+    }
+
+    Ok(headers)
+}
+
+/// Text and stop reason carried by one Bedrock stream chunk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BedrockDelta {
+    /// Generated text in this chunk
+    pub text: String,
+    /// Why generation stopped, on the last chunk
+    pub stop_reason: Option<String>,
+}
+
+/// Interpret a Bedrock stream message.
+///
+/// Returns `None` for events that carry no completion text, and fails with
+/// [`ProxyError::Upstream`] for exception messages.
+pub fn bedrock_delta(message: &EventStreamMessage) -> Result<Option<BedrockDelta>, ProxyError> {
+    let payload = String::from_utf8_lossy(&message.payload);
+    match message.header(":message-type") {
+        Some("exception") => {
+            let kind = message.header(":exception-type").unwrap_or("exception");
+            return Err(ProxyError::Upstream(format!("AWS Bedrock {}: {}", kind, payload)));
+        }
+        Some("error") => {
+            let code = message.header(":error-code").unwrap_or("error");
+            let detail = message.header(":error-message").unwrap_or(&payload);
+            return Err(ProxyError::Upstream(format!("AWS Bedrock {}: {}", code, detail)));
+        }
+        _ => {}
+    }
+    if message.header(":event-type") != Some("chunk") {
+        return Ok(None);
+    }
+
+    let invalid = |e: String| ProxyError::Upstream(format!("invalid AWS Bedrock stream chunk: {}", e));
+    let envelope: Value = serde_json::from_slice(&message.payload).map_err(|e| invalid(e.to_string()))?;
+    let encoded = envelope
+        .get("bytes")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("missing bytes".to_string()))?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| invalid(e.to_string()))?;
+    let chunk: Value = serde_json::from_slice(&decoded).map_err(|e| invalid(e.to_string()))?;
+
+    let text = ["/completion", "/delta/text", "/outputText"]
+        .iter()
+        .find_map(|pointer| chunk.pointer(pointer).and_then(Value::as_str))
+        .unwrap_or_default()
+        .to_string();
+    let stop_reason = ["/stop_reason", "/delta/stop_reason", "/completionReason"]
+        .iter()
+        .find_map(|pointer| chunk.pointer(pointer).and_then(Value::as_str))
+        .map(str::to_string);
+
+    Ok(Some(BedrockDelta { text, stop_reason }))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Frame a message the way Bedrock does, with string headers only
+    pub(crate) fn encode(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+
+        let total_len = PRELUDE_LEN + header_bytes.len() + payload.len() + MESSAGE_CRC_LEN;
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(total_len as u32).to_be_bytes());
+        frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&crc32fast::hash(&frame).to_be_bytes());
+        frame.extend_from_slice(&header_bytes);
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&crc32fast::hash(&frame).to_be_bytes());
+        frame
+    }
+
+    /// Frame a Bedrock `chunk` event carrying `body`
+    pub(crate) fn chunk(body: Value) -> Vec<u8> {
+        let bytes = base64::engine::general_purpose::STANDARD.encode(body.to_string());
+        encode(
+            &[(":event-type", "chunk"), (":content-type", "application/json"), (":message-type", "event")],
+            json!({ "bytes": bytes }).to_string().as_bytes(),
+        )
+    }
+
+    #[test]
+    fn test_decodes_chunks_split_across_reads() {
+        let mut captured = Vec::new();
+        captured.extend(chunk(json!({"completion": " Hello", "stop_reason": null})));
+        captured.extend(chunk(json!({"completion": ", world", "stop_reason": null})));
+        captured.extend(chunk(json!({"completion": "!", "stop_reason": "stop_sequence"})));
+
+        let mut decoder = EventStreamDecoder::new();
+        let mut deltas = Vec::new();
+        for read in captured.chunks(7) {
+            for message in decoder.push(read).unwrap() {
+                deltas.extend(bedrock_delta(&message).unwrap());
+            }
+        }
+
+        assert!(!decoder.has_pending());
+        let text: Vec<_> = deltas.iter().map(|delta| delta.text.as_str()).collect();
+        assert_eq!(text, [" Hello", ", world", "!"]);
+        assert_eq!(deltas[2].stop_reason.as_deref(), Some("stop_sequence"));
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_upstream_error() {
+        let mut frame = chunk(json!({"completion": "Hi"}));
+        let last = frame.len() - 5;
+        frame[last] ^= 0xff;
+
+        let err = EventStreamDecoder::new().push(&frame).unwrap_err();
+        assert!(matches!(err, ProxyError::Upstream(message) if message.contains("checksum")));
+    }
+
+    #[test]
+    fn test_exception_messages_are_errors() {
+        let frame = encode(
+            &[(":message-type", "exception"), (":exception-type", "throttlingException")],
+            br#"{"message":"Too many requests"}"#,
+        );
+        let message = EventStreamDecoder::new().push(&frame).unwrap().remove(0);
+
+        let err = bedrock_delta(&message).unwrap_err();
+        assert!(err.to_string().contains("throttlingException"));
+    }
+}
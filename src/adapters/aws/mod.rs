@@ -3,6 +3,9 @@
 //! This module provides the AWS Bedrock adapter implementation
 //! with AWS-specific authentication and API format handling.
 
+#[cfg(feature = "streaming")]
+pub mod event_stream;
+
 use crate::{
     adapters::base::{AdapterTrait, AdapterUtils},
     error::ProxyError,
@@ -13,9 +16,10 @@ use crate::schemas::{Message, Choice, Usage};
 #[cfg(feature = "server")]
 use axum::response::Response;
 use reqwest::Client;
-use serde_json::Value;
+use std::time::Duration;
+use url::Url;
 #[cfg(feature = "adapter-aws")]
-use serde_json::json;
+use serde_json::{json, Value};
 #[cfg(feature = "adapter-aws")]
 use chrono::Utc;
 #[cfg(feature = "adapter-aws")]
@@ -40,16 +44,59 @@ pub struct AWSBedrockAdapter {
     /// AWS secret access key
     secret_access_key: Option<String>,
     /// AWS region
-    #[allow(dead_code)]
     region: String,
     /// HTTP client with connection pooling
-    #[allow(dead_code)]
+    #[cfg(feature = "adapter-aws")]
     client: Client,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
+    /// Regions tried in order, starting with `region`; later regions take a
+    /// request when an earlier one throttles (429) or fails with a 5xx
+    regions: Vec<RegionEndpoint>,
+}
+
+/// # Region Endpoint
+///
+/// A Bedrock region together with the runtime endpoint requests for it are
+/// sent to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionEndpoint {
+    /// Region requests are signed for
+    pub region: String,
+    /// Runtime endpoint, without a trailing slash
+    pub base: String,
+}
+
+impl RegionEndpoint {
+    /// The public `bedrock-runtime` endpoint of `region`
+    pub fn new(region: impl Into<String>) -> Self {
+        let region = region.into();
+        let base = format!("https://bedrock-runtime.{}.amazonaws.com", region);
+        Self { region, base }
+    }
+
+    /// A region served from a custom endpoint, such as a VPC interface endpoint
+    pub fn with_base(region: impl Into<String>, base: impl Into<String>) -> Self {
+        Self {
+            region: region.into(),
+            base: base.into().trim_end_matches('/').to_string(),
+        }
+    }
 }
 
 impl AWSBedrockAdapter {
-    /// Create a new AWS Bedrock adapter instance
-    pub fn new(base: String, model_id: String, access_key: Option<String>, client: Client) -> Self {
+    /// Create a new AWS Bedrock adapter instance.
+    ///
+    /// Requests are signed for `region` when given, otherwise for the region in
+    /// the host of `base` (`bedrock-runtime.{region}.amazonaws.com`). Fails with
+    /// [`ProxyError::BadRequest`] when neither names a region.
+    pub fn new(
+        base: String,
+        model_id: String,
+        access_key: Option<String>,
+        region: Option<String>,
+        client: Client,
+    ) -> Result<Self, ProxyError> {
         // Parse access_key as "access_key_id:secret_access_key" format
         let (access_key_id, secret_access_key) = if let Some(key) = access_key {
             if let Some((access, secret)) = key.split_once(':') {
@@ -61,32 +108,79 @@ impl AWSBedrockAdapter {
             (None, None)
         };
 
-        // Extract region from base URL or default to us-east-1
-        let region = if base.contains("us-west-2") {
-            "us-west-2".to_string()
-        } else if base.contains("eu-west-1") {
-            "eu-west-1".to_string()
-        } else {
-            "us-east-1".to_string()
-        };
+        let region = region
+            .filter(|region| !region.is_empty())
+            .or_else(|| Self::region_from_url(&base))
+            .ok_or_else(|| {
+                ProxyError::BadRequest(format!(
+                    "Cannot determine the AWS region from backend URL '{}'; set an explicit region",
+                    base
+                ))
+            })?;
+
+        // Requests are only sent when the adapter-aws feature is enabled
+        #[cfg(not(feature = "adapter-aws"))]
+        let _ = client;
 
-        Self {
+        Ok(Self {
             base,
             model_id,
             access_key_id,
             secret_access_key,
+            regions: vec![RegionEndpoint::new(region.clone())],
             region,
+            #[cfg(feature = "adapter-aws")]
             client,
-        }
+            first_byte_timeout: None,
+        })
+    }
+
+    /// AWS region named in the host of a Bedrock endpoint URL, such as
+    /// `ap-northeast-1` in `https://bedrock-runtime.ap-northeast-1.amazonaws.com`
+    pub fn region_from_url(base: &str) -> Option<String> {
+        let url = Url::parse(base).ok()?;
+        let host = url.host_str()?;
+        host.split('.')
+            .take_while(|label| *label != "amazonaws")
+            .find(|label| is_region(label))
+            .map(str::to_string)
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Fail over to `regions`, in order, when the primary region throttles or
+    /// returns a server error
+    pub fn with_failover_regions(mut self, regions: Vec<RegionEndpoint>) -> Self {
+        self.regions.truncate(1);
+        self.regions.extend(regions.into_iter().filter(|endpoint| endpoint.region != self.region));
+        self
+    }
+
+    /// Replace the endpoint the primary region is reached at
+    pub fn with_primary_endpoint(mut self, base: impl Into<String>) -> Self {
+        self.regions[0] = RegionEndpoint::with_base(self.region.clone(), base);
+        self
     }
 
     /// Convert OpenAI chat completion format to AWS Bedrock format
     #[cfg(feature = "adapter-aws")]
     fn convert_to_bedrock_format(&self, req: &ChatCompletionRequest) -> Result<Value, ProxyError> {
+        // A trailing assistant message is a prefill the completion continues
+        let (messages, prefill) = match req.messages.split_last() {
+            Some((last, earlier)) if last.role == "assistant" && last.content.is_some() => {
+                (earlier, last.content.as_deref())
+            }
+            _ => (req.messages.as_slice(), None),
+        };
+
         // Extract the conversation from OpenAI messages
         let mut prompt = String::new();
 
-        for message in &req.messages {
+        for message in messages {
             match message.role.as_str() {
                 "system" => {
                     if let Some(content) = &message.content {
@@ -109,15 +203,22 @@ impl AWSBedrockAdapter {
 
         // Add assistant prompt to get the model to respond
         prompt.push_str("Assistant:");
+        if let Some(prefill) = prefill {
+            prompt.push(' ');
+            prompt.push_str(prefill);
+        }
 
         // Create Bedrock request format (Claude-specific)
-        let bedrock_request = json!({
+        let mut bedrock_request = json!({
             "prompt": prompt,
             "max_tokens_to_sample": req.max_tokens.unwrap_or(1000),
             "temperature": req.temperature.unwrap_or(0.7),
             "top_p": req.top_p.unwrap_or(1.0),
             "stop_sequences": ["\nHuman:"],
         });
+        if let Some(top_k) = req.top_k {
+            bedrock_request["top_k"] = json!(top_k);
+        }
 
         Ok(bedrock_request)
     }
@@ -130,6 +231,13 @@ impl AWSBedrockAdapter {
             .and_then(|c| c.as_str())
             .unwrap_or("");
 
+        // A prefilled completion continues the prefill, so its leading space is kept
+        let prefilled = original_req
+            .messages
+            .last()
+            .is_some_and(|message| message.role == "assistant" && message.content.is_some());
+        let completion_text = if prefilled { completion.trim_end() } else { completion.trim() };
+
         // Get token usage from AWS response
         let prompt_tokens = aws_response.get("prompt_tokens")
             .and_then(|t| t.as_u64())
@@ -138,6 +246,13 @@ impl AWSBedrockAdapter {
             .and_then(|t| t.as_u64())
             .unwrap_or(0) as i32;
 
+        let finish_reason = aws_response.get("stop_reason")
+            .or_else(|| aws_response.get("stopReason"))
+            .and_then(|r| r.as_str())
+            .map(AdapterUtils::normalize_finish_reason)
+            .unwrap_or("stop")
+            .to_string();
+
         // Create OpenAI format response
         let response = ChatCompletionResponse {
             id: format!("chatcmpl-aws-{}", chrono::Utc::now().timestamp()),
@@ -148,20 +263,24 @@ impl AWSBedrockAdapter {
                 index: 0,
                 message: Message {
                     role: "assistant".to_string(),
-                    content: Some(completion.trim().to_string()),
+                    content: Some(completion_text.to_string()),
                     name: None,
                     function_call: None,
                     tool_calls: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 },
-                finish_reason: "stop".to_string(),
+                finish_reason,
                 logprobs: None,
+                content_filter_results: None,
             }],
             usage: Some(Usage {
                 prompt_tokens: prompt_tokens.max(0) as u32,
                 completion_tokens: completion_tokens.max(0) as u32,
                 total_tokens: (prompt_tokens + completion_tokens).max(0) as u32,
             }),
+            prompt_filter_results: None,
         };
 
         Ok(response)
@@ -169,7 +288,7 @@ impl AWSBedrockAdapter {
 
     /// Create AWS Signature V4 headers for authentication
     #[cfg(feature = "adapter-aws")]
-    async fn create_aws_headers(&self, payload: &Value, _endpoint: &str) -> Result<reqwest::header::HeaderMap, ProxyError> {
+    async fn create_aws_headers(&self, payload: &Value, endpoint: &str, region: &str) -> Result<reqwest::header::HeaderMap, ProxyError> {
         let mut headers = reqwest::header::HeaderMap::new();
 
         let access_key_id = self.access_key_id.as_ref()
@@ -177,9 +296,18 @@ impl AWSBedrockAdapter {
         let secret_access_key = self.secret_access_key.as_ref()
             .ok_or_else(|| ProxyError::Internal("AWS secret access key not set".to_string()))?;
 
+        // The signed host must be the one the request is sent to
+        let url = Url::parse(endpoint)
+            .map_err(|e| ProxyError::Internal(format!("Invalid AWS endpoint '{}': {}", endpoint, e)))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(ProxyError::Internal(format!("AWS endpoint '{}' has no host", endpoint))),
+        };
+
         // Basic headers
         headers.insert("content-type", "application/json".parse().unwrap());
-        headers.insert("host", format!("bedrock-runtime.{}.amazonaws.com", self.region).parse().unwrap());
+        headers.insert("host", host.parse().unwrap());
 
         // AWS date format
         let now = Utc::now();
@@ -193,12 +321,13 @@ impl AWSBedrockAdapter {
             .map_err(|e| ProxyError::Internal(format!("Failed to serialize payload: {}", e)))?;
         let payload_hash = format!("{:x}", Sha256::digest(payload_str.as_bytes()));
 
-        // Create canonical request
-        let canonical_uri = "/";
+        // Create canonical request; every path segment is encoded again, as
+        // SigV4 requires for services other than S3
+        let canonical_uri = url.path().split('/').map(uri_encode).collect::<Vec<_>>().join("/");
         let canonical_querystring = "";
         let canonical_headers = format!(
-            "content-type:application/json\nhost:bedrock-runtime.{}.amazonaws.com\nx-amz-date:{}\n",
-            self.region, amz_date
+            "content-type:application/json\nhost:{}\nx-amz-date:{}\n",
+            host, amz_date
         );
         let signed_headers = "content-type;host;x-amz-date";
 
@@ -209,14 +338,14 @@ impl AWSBedrockAdapter {
 
         // Create string to sign
         let algorithm = "AWS4-HMAC-SHA256";
-        let credential_scope = format!("{}/{}/bedrock/aws4_request", date_stamp, self.region);
+        let credential_scope = format!("{}/{}/bedrock/aws4_request", date_stamp, region);
         let string_to_sign = format!(
             "{}\n{}\n{}\n{:x}",
             algorithm, amz_date, credential_scope, Sha256::digest(canonical_request.as_bytes())
         );
 
         // Calculate signature
-        let signature = self.calculate_signature(secret_access_key, &date_stamp, &string_to_sign)?;
+        let signature = self.calculate_signature(secret_access_key, &date_stamp, region, &string_to_sign)?;
 
         // Create authorization header
         let authorization = format!(
@@ -231,7 +360,7 @@ impl AWSBedrockAdapter {
 
     /// Calculate AWS Signature V4 signature
     #[cfg(feature = "adapter-aws")]
-    fn calculate_signature(&self, secret_key: &str, date_stamp: &str, string_to_sign: &str) -> Result<String, ProxyError> {
+    fn calculate_signature(&self, secret_key: &str, date_stamp: &str, region: &str, string_to_sign: &str) -> Result<String, ProxyError> {
         let key = format!("AWS4{}", secret_key);
 
         let mut mac = HmacSha256::new_from_slice(key.as_bytes())
@@ -241,7 +370,7 @@ impl AWSBedrockAdapter {
 
         let mut mac = HmacSha256::new_from_slice(&k_date)
             .map_err(|e| ProxyError::Internal(format!("HMAC key error: {}", e)))?;
-        mac.update(self.region.as_bytes());
+        mac.update(region.as_bytes());
         let k_region = mac.finalize().into_bytes();
 
         let mut mac = HmacSha256::new_from_slice(&k_region)
@@ -263,29 +392,39 @@ impl AWSBedrockAdapter {
         Ok(sig_hex)
     }
 
-    /// Fallback implementations when AWS feature is not enabled
-    #[cfg(not(feature = "adapter-aws"))]
-    #[allow(dead_code)]
-    fn convert_to_bedrock_format(&self, _req: &ChatCompletionRequest) -> Result<Value, ProxyError> {
-        Err(ProxyError::BadRequest("AWS Bedrock adapter requires 'adapter-aws' feature".to_string()))
-    }
-
-    #[cfg(not(feature = "adapter-aws"))]
-    #[allow(dead_code)]
-    fn convert_from_bedrock_format(&self, _aws_response: Value, _original_req: &ChatCompletionRequest) -> Result<ChatCompletionResponse, ProxyError> {
-        Err(ProxyError::BadRequest("AWS Bedrock adapter requires 'adapter-aws' feature".to_string()))
-    }
+    /// POST `payload` to `path` in each configured region in turn, signing it
+    /// for that region, until one answers with something other than a
+    /// throttle (429) or server error; the last region's response is returned
+    /// as is
+    #[cfg(feature = "adapter-aws")]
+    async fn send_with_failover(
+        &self,
+        path: &str,
+        payload: &Value,
+        accept: Option<&str>,
+    ) -> Result<reqwest::Response, ProxyError> {
+        for (index, endpoint) in self.regions.iter().enumerate() {
+            let url = format!("{}{}", endpoint.base, path);
+            let headers = self.create_aws_headers(payload, &url, &endpoint.region).await?;
+            let mut request_builder = self.client.post(&url).headers(headers).json(payload);
+            if let Some(accept) = accept {
+                request_builder = request_builder.header("accept", accept);
+            }
+            let response = AdapterUtils::send(request_builder, self.first_byte_timeout).await?;
 
-    #[cfg(not(feature = "adapter-aws"))]
-    #[allow(dead_code)]
-    async fn create_aws_headers(&self, _payload: &Value, _endpoint: &str) -> Result<reqwest::header::HeaderMap, ProxyError> {
-        Err(ProxyError::BadRequest("AWS Bedrock adapter requires 'adapter-aws' feature".to_string()))
-    }
+            let status = response.status();
+            let unavailable = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !unavailable || index + 1 == self.regions.len() {
+                return Ok(response);
+            }
+            tracing::warn!(
+                region = %endpoint.region,
+                status = status.as_u16(),
+                "AWS region unavailable, failing over to the next region"
+            );
+        }
 
-    #[cfg(not(feature = "adapter-aws"))]
-    #[allow(dead_code)]
-    fn calculate_signature(&self, _secret_key: &str, _date_stamp: &str, _string_to_sign: &str) -> Result<String, ProxyError> {
-        Err(ProxyError::BadRequest("AWS Bedrock adapter requires 'adapter-aws' feature".to_string()))
+        Err(ProxyError::Internal("no AWS region configured".to_string()))
     }
 
     /// Process chat completion requests with AWS Bedrock-specific handling
@@ -318,39 +457,30 @@ impl AWSBedrockAdapter {
         // Convert OpenAI format to AWS Bedrock format
         let bedrock_request = self.convert_to_bedrock_format(&req)?;
 
-        // Build AWS Bedrock endpoint URL
+        // Send to the first region that is not throttled or failing
         let model = AdapterUtils::extract_model(&req, &self.model_id);
-        let endpoint = format!(
-            "https://bedrock-runtime.{}.amazonaws.com/model/{}/invoke",
-            self.region, model
-        );
-
-        // Create AWS Signature V4 headers
-        let headers = self.create_aws_headers(&bedrock_request, &endpoint).await?;
-
-        // Make the request to AWS Bedrock
-        let response = self.client
-            .post(&endpoint)
-            .headers(headers)
-            .json(&bedrock_request)
-            .send()
-            .await
-            .map_err(|e| ProxyError::Upstream(format!("AWS Bedrock request failed: {}", e)))?;
+        let path = format!("/model/{}/invoke", uri_encode(&model));
+        let response = self.send_with_failover(&path, &bedrock_request, None).await?;
 
         let response_time = start_time.elapsed().as_millis() as u64;
         let success = response.status().is_success();
         AdapterUtils::log_response("aws", &model, success, response_time);
 
+        let status = response.status();
+        let content_type = AdapterUtils::content_type(&response);
+        let response_bytes = AdapterUtils::read_body(response).await?;
+
         if !success {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(ProxyError::Upstream(format!(
-                "AWS Bedrock error {}: {}", status, error_text
-            )));
+            return Err(ProxyError::UpstreamStatus {
+                status,
+                body: AdapterUtils::describe_body(&response_bytes).to_string(),
+            });
         }
 
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
+
         // Parse AWS response and convert to OpenAI format
-        let aws_response: Value = response.json().await
+        let aws_response: Value = serde_json::from_slice(&response_bytes)
             .map_err(|e| ProxyError::Internal(format!("Failed to parse AWS response: {}", e)))?;
 
         let openai_response = self.convert_from_bedrock_format(aws_response, &req)?;
@@ -359,11 +489,54 @@ impl AWSBedrockAdapter {
         let json_response = serde_json::to_string(&openai_response)
             .map_err(|e| ProxyError::Internal(format!("Failed to serialize response: {}", e)))?;
 
-        Ok(Response::builder()
+        let http_response = Response::builder()
             .status(200)
             .header("content-type", "application/json")
             .body(axum::body::Body::from(json_response))
-            .map_err(|e| ProxyError::Internal(format!("Failed to build response: {}", e)))?)
+            .map_err(|e| ProxyError::Internal(format!("Failed to build response: {}", e)))?;
+
+        Ok(AdapterUtils::with_upstream_duration(http_response, response_time))
+        }
+    }
+
+    /// Perform a raw `invoke-with-response-stream` request, returning the
+    /// binary event stream without buffering it
+    #[cfg(feature = "server")]
+    pub async fn stream_chat_completions_raw(&self, req: ChatCompletionRequest) -> Result<reqwest::Response, ProxyError> {
+        AdapterUtils::log_request("aws", &AdapterUtils::extract_model(&req, &self.model_id), req.messages.len());
+
+        #[cfg(not(feature = "adapter-aws"))]
+        {
+            Err(ProxyError::BadRequest(
+                "AWS Bedrock adapter requires 'adapter-aws' feature to be enabled".to_string()
+            ))
+        }
+
+        #[cfg(feature = "adapter-aws")]
+        {
+        if !self.has_auth() {
+            return Err(ProxyError::BadRequest(
+                "AWS credentials (access_key_id:secret_access_key) required".to_string()
+            ));
+        }
+
+        let bedrock_request = self.convert_to_bedrock_format(&req)?;
+        let model = AdapterUtils::extract_model(&req, &self.model_id);
+        let path = format!("/model/{}/invoke-with-response-stream", uri_encode(&model));
+        let response = self
+            .send_with_failover(&path, &bedrock_request, Some("application/vnd.amazon.eventstream"))
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let response_bytes = AdapterUtils::read_body(response).await?;
+            return Err(ProxyError::UpstreamStatus {
+                status,
+                body: AdapterUtils::describe_body(&response_bytes).to_string(),
+            });
+        }
+
+        Ok(response)
         }
     }
 }
@@ -407,4 +580,207 @@ impl AdapterTrait for AWSBedrockAdapter {
     async fn chat_completions(&self, _request: ChatCompletionRequest) -> Result<ChatCompletionResponse, ProxyError> {
         Err(ProxyError::Internal("Server feature not enabled".to_string()))
     }
-}
\ No newline at end of file
+}
+/// Percent-encode every byte of `value` except the RFC 3986 unreserved
+/// characters, as SigV4 expects (model IDs such as `anthropic.claude-v2:1`
+/// contain `:`)
+#[cfg(feature = "adapter-aws")]
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Whether a host label has the shape of an AWS region: a partition prefix,
+/// one or more name parts and a trailing number (`us-east-1`, `us-gov-west-1`)
+fn is_region(label: &str) -> bool {
+    let parts: Vec<&str> = label.split('-').collect();
+    parts.len() >= 3
+        && parts[0].len() == 2
+        && parts[..parts.len() - 1].iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase()))
+        && parts[parts.len() - 1].parse::<u8>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "adapter-aws")]
+    fn test_trailing_assistant_message_is_prefilled() {
+        let adapter = AWSBedrockAdapter::new(
+            "https://bedrock-runtime.us-east-1.amazonaws.com".to_string(),
+            "anthropic.claude-v2".to_string(),
+            Some("key:secret".to_string()),
+            None,
+            Client::new(),
+        )
+        .unwrap();
+        let req = ChatCompletionRequest {
+            messages: vec![Message::user("List two colors as JSON".to_string()), Message::assistant(Some("{".to_string()))],
+            ..Default::default()
+        };
+
+        let payload = adapter.convert_to_bedrock_format(&req).unwrap();
+        assert_eq!(payload["prompt"], "Human: List two colors as JSON\nAssistant: {");
+
+        let response = adapter
+            .convert_from_bedrock_format(json!({"completion": " \"colors\": [\"red\"]}\n"}), &req)
+            .unwrap();
+        assert_eq!(response.choices[0].message.content.as_deref(), Some(" \"colors\": [\"red\"]}"));
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "adapter-aws", feature = "server"))]
+    async fn test_throttled_primary_region_fails_over_to_secondary() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+            let mut mac = HmacSha256::new_from_slice(key).unwrap();
+            mac.update(data.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        }
+
+        // The SigV4 authorization recomputed from the received request; the
+        // model ID in the path is sent encoded once and signed encoded twice
+        fn signed_for(region: &'static str, canonical_uri: &'static str) -> impl Fn(&Request) -> bool {
+            move |request| {
+                // wiremock splits header values on commas
+                let header = |name: &str| {
+                    let (_, values) = request.headers.iter().find(|(header, _)| header.as_str() == name)?;
+                    Some(values.iter().map(|value| value.as_str().trim()).collect::<Vec<_>>().join(", "))
+                };
+                let (Some(host), Some(amz_date), Some(authorization)) =
+                    (header("host"), header("x-amz-date"), header("authorization"))
+                else {
+                    return false;
+                };
+                let date_stamp = &amz_date[..8];
+                let canonical_request = format!(
+                    "POST\n{}\n\ncontent-type:application/json\nhost:{}\nx-amz-date:{}\n\ncontent-type;host;x-amz-date\n{:x}",
+                    canonical_uri,
+                    host,
+                    amz_date,
+                    Sha256::digest(request.body.as_slice())
+                );
+                let scope = format!("{}/{}/bedrock/aws4_request", date_stamp, region);
+                let string_to_sign = format!(
+                    "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+                    amz_date,
+                    scope,
+                    Sha256::digest(canonical_request.as_bytes())
+                );
+                let signing_key = [date_stamp, region, "bedrock", "aws4_request"]
+                    .iter()
+                    .fold(b"AWS4secret".to_vec(), |key: Vec<u8>, part| hmac(&key, part));
+                let signature: String =
+                    hmac(&signing_key, &string_to_sign).iter().map(|byte| format!("{:02x}", byte)).collect();
+                authorization
+                    == format!(
+                        "AWS4-HMAC-SHA256 Credential=key/{}, SignedHeaders=content-type;host;x-amz-date, Signature={}",
+                        scope, signature
+                    )
+            }
+        }
+
+        let primary = MockServer::start().await;
+        let secondary = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/model/anthropic.claude-v2%3A1/invoke"))
+            .and(signed_for("us-east-1", "/model/anthropic.claude-v2%253A1/invoke"))
+            .respond_with(ResponseTemplate::new(429).set_body_json(json!({"message": "Too many requests"})))
+            .expect(1)
+            .mount(&primary)
+            .await;
+        let secondary_host = secondary.address().to_string();
+        Mock::given(method("POST"))
+            .and(path("/model/anthropic.claude-v2%3A1/invoke"))
+            .and(signed_for("us-west-2", "/model/anthropic.claude-v2%253A1/invoke"))
+            .and(header("host", secondary_host.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "completion": " Hello from us-west-2",
+                "stop_reason": "stop_sequence",
+            })))
+            .expect(1)
+            .mount(&secondary)
+            .await;
+
+        let adapter = AWSBedrockAdapter::new(
+            "https://bedrock-runtime.us-east-1.amazonaws.com".to_string(),
+            "anthropic.claude-v2:1".to_string(),
+            Some("key:secret".to_string()),
+            None,
+            Client::new(),
+        )
+        .unwrap()
+        .with_primary_endpoint(primary.uri())
+        .with_failover_regions(vec![RegionEndpoint::with_base("us-west-2", secondary.uri())]);
+        let req = ChatCompletionRequest {
+            messages: vec![Message::user("Hello".to_string())],
+            ..Default::default()
+        };
+
+        let response = adapter.chat_completions(req).await.unwrap();
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Hello from us-west-2"));
+    }
+
+    #[test]
+    fn test_region_parsed_from_endpoint_host() {
+        let cases = [
+            ("https://bedrock-runtime.us-east-1.amazonaws.com", "us-east-1"),
+            ("https://bedrock-runtime.us-west-2.amazonaws.com/", "us-west-2"),
+            ("https://bedrock-runtime.eu-central-1.amazonaws.com", "eu-central-1"),
+            ("https://bedrock-runtime.ap-northeast-1.amazonaws.com", "ap-northeast-1"),
+            ("https://bedrock-runtime.ap-southeast-2.amazonaws.com", "ap-southeast-2"),
+            ("https://bedrock-runtime-fips.us-gov-west-1.amazonaws.com", "us-gov-west-1"),
+            ("https://bedrock.sa-east-1.amazonaws.com", "sa-east-1"),
+        ];
+
+        for (base, region) in cases {
+            let adapter = AWSBedrockAdapter::new(base.to_string(), "anthropic.claude-v2".to_string(), None, None, Client::new())
+                .unwrap();
+            assert_eq!(adapter.region, region, "region of {}", base);
+        }
+    }
+
+    #[test]
+    fn test_explicit_region_overrides_url() {
+        let adapter = AWSBedrockAdapter::new(
+            "https://bedrock-runtime.us-east-1.amazonaws.com".to_string(),
+            "anthropic.claude-v2".to_string(),
+            None,
+            Some("eu-west-3".to_string()),
+            Client::new(),
+        )
+        .unwrap();
+        assert_eq!(adapter.region, "eu-west-3");
+
+        let adapter = AWSBedrockAdapter::new(
+            "https://bedrock-proxy.internal.example.com".to_string(),
+            "anthropic.claude-v2".to_string(),
+            None,
+            Some("ca-central-1".to_string()),
+            Client::new(),
+        )
+        .unwrap();
+        assert_eq!(adapter.region, "ca-central-1");
+    }
+
+    #[test]
+    fn test_missing_region_is_rejected() {
+        let err = AWSBedrockAdapter::new(
+            "https://bedrock.amazonaws.com".to_string(),
+            "anthropic.claude-v2".to_string(),
+            None,
+            None,
+            Client::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ProxyError::BadRequest(_)));
+    }
+}
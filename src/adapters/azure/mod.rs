@@ -2,17 +2,26 @@
 //!
 //! This module provides the Azure OpenAI Service adapter implementation
 //! with Azure-specific authentication and endpoint handling.
+//!
+//! Azure's `content_filter_results` and `prompt_filter_results` annotations
+//! are passed through to the client unchanged. Prompts rejected by the content
+//! filter (HTTP 400 with code `content_filter`) are returned as a completion
+//! with `finish_reason: "content_filter"` unless that translation is disabled.
 
 use crate::{
-    adapters::base::{AdapterTrait, AdapterUtils},
+    adapters::base::{AdapterTrait, AdapterUtils, DEFAULT_REASONING_MODELS},
     error::ProxyError,
     schemas::{ChatCompletionRequest, ChatCompletionResponse},
 };
 #[cfg(feature = "server")]
 use axum::{http::StatusCode, response::{IntoResponse, Response}, Json};
 use reqwest::Client;
+use std::{collections::HashMap, time::Duration};
 use tracing::debug;
 
+/// Azure OpenAI API version used when none is configured
+pub const DEFAULT_AZURE_API_VERSION: &str = "2023-12-01-preview";
+
 /// # Azure OpenAI Adapter
 ///
 /// Adapter for Microsoft Azure OpenAI Service with Azure-specific
@@ -27,17 +36,101 @@ pub struct AzureOpenAIAdapter {
     api_key: Option<String>,
     /// HTTP client with connection pooling
     client: Client,
+    /// Client model name to Azure deployment name mapping
+    deployment_map: HashMap<String, String>,
+    /// Return content-filter blocks as `content_filter` completions
+    translate_content_filter: bool,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
+    /// `api-version` query parameter sent with every request
+    api_version: String,
+    /// Model name prefixes of models that accept `reasoning_effort`
+    reasoning_models: Vec<String>,
+    /// Send `system` messages to reasoning models as `developer` messages
+    developer_role: bool,
 }
 
 impl AzureOpenAIAdapter {
-    /// Create a new Azure OpenAI adapter instance
-    pub fn new(base: String, model_id: String, api_key: Option<String>, client: Client) -> Self {
-        Self {
+    /// Create a new Azure OpenAI adapter instance.
+    ///
+    /// Requests use `api_version` (`YYYY-MM-DD` or `YYYY-MM-DD-preview`), or
+    /// [`DEFAULT_AZURE_API_VERSION`] when none is given. Fails with
+    /// [`ProxyError::BadRequest`] for a malformed version.
+    pub fn new(
+        base: String,
+        model_id: String,
+        api_key: Option<String>,
+        api_version: Option<String>,
+        client: Client,
+    ) -> Result<Self, ProxyError> {
+        let api_version = api_version.unwrap_or_else(|| DEFAULT_AZURE_API_VERSION.to_string());
+        if !Self::is_valid_api_version(&api_version) {
+            return Err(ProxyError::BadRequest(format!(
+                "Invalid Azure API version '{}'. Expected YYYY-MM-DD or YYYY-MM-DD-preview.",
+                api_version
+            )));
+        }
+
+        Ok(Self {
             base,
             model_id,
             api_key,
             client,
-        }
+            deployment_map: HashMap::new(),
+            translate_content_filter: true,
+            first_byte_timeout: None,
+            api_version,
+            reasoning_models: DEFAULT_REASONING_MODELS.iter().map(|prefix| prefix.to_string()).collect(),
+            developer_role: true,
+        })
+    }
+
+    /// Whether `version` has the shape of an Azure OpenAI API version
+    /// (`2024-06-01`, `2024-08-01-preview`)
+    pub fn is_valid_api_version(version: &str) -> bool {
+        let date = version.strip_suffix("-preview").unwrap_or(version);
+        let parts: Vec<&str> = date.split('-').collect();
+        matches!(parts.as_slice(), [year, month, day]
+            if year.len() == 4 && month.len() == 2 && day.len() == 2
+                && parts.iter().all(|part| part.chars().all(|c| c.is_ascii_digit()))
+                && (1..=12).contains(&month.parse::<u8>().unwrap_or(0))
+                && (1..=31).contains(&day.parse::<u8>().unwrap_or(0)))
+    }
+
+    /// Translate client model names to Azure deployment names
+    pub fn with_deployment_map(mut self, deployment_map: HashMap<String, String>) -> Self {
+        self.deployment_map = deployment_map;
+        self
+    }
+
+    /// Choose whether content-filter blocks are returned as completions or errors
+    pub fn with_content_filter_translation(mut self, enabled: bool) -> Self {
+        self.translate_content_filter = enabled;
+        self
+    }
+
+    /// Authenticate with `token` instead of the configured credential
+    pub fn with_token(mut self, token: String) -> Self {
+        self.api_key = Some(token);
+        self
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Forward `reasoning_effort` only for models starting with one of `prefixes`
+    pub fn with_reasoning_models(mut self, prefixes: Vec<String>) -> Self {
+        self.reasoning_models = prefixes;
+        self
+    }
+
+    /// Whether to rename `system` messages to `developer` for reasoning models
+    pub fn with_developer_role(mut self, enabled: bool) -> Self {
+        self.developer_role = enabled;
+        self
     }
 
     /// Get the model ID for this adapter
@@ -45,17 +138,38 @@ impl AzureOpenAIAdapter {
         &self.model_id
     }
 
+    /// Resolve the deployment for a model, falling back to the model name itself
+    pub fn deployment_for<'a>(&'a self, model: &'a str) -> &'a str {
+        self.deployment_map
+            .get(model)
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+
+    /// Build the chat completions URL for the deployment serving `model`
+    pub fn chat_completions_url(&self, model: &str) -> String {
+        // Azure format: https://{resource}.openai.azure.com/openai/deployments/{deployment-id}/chat/completions?api-version={version}
+        format!("{}/openai/deployments/{}/chat/completions?api-version={}",
+                self.base, self.deployment_for(model), self.api_version)
+    }
+
     /// Process chat completion requests with Azure-specific handling
     #[cfg(feature = "server")]
-    pub async fn chat_completions_http(&self, req: ChatCompletionRequest) -> Result<Response, ProxyError> {
-        AdapterUtils::log_request("azure", &AdapterUtils::extract_model(&req, &self.model_id), req.messages.len());
+    pub async fn chat_completions_http(&self, mut req: ChatCompletionRequest) -> Result<Response, ProxyError> {
+        // Azure OpenAI rejects parameters it does not know, such as top_k
+        req.top_k = None;
+        let model_name = AdapterUtils::extract_model(&req, &self.model_id);
+        if !AdapterUtils::is_reasoning_model(&model_name, &self.reasoning_models) {
+            req.reasoning_effort = None;
+        } else if self.developer_role {
+            AdapterUtils::use_developer_role(&mut req);
+        }
+        AdapterUtils::log_request("azure", &model_name, req.messages.len());
 
         let start_time = std::time::Instant::now();
 
-        // Build Azure OpenAI endpoint URL
-        // Azure format: https://{resource}.openai.azure.com/openai/deployments/{deployment-id}/chat/completions?api-version=2023-12-01-preview
-        let url = format!("{}/openai/deployments/{}/chat/completions?api-version=2023-12-01-preview",
-                         self.base, self.model_id);
+        // Build Azure OpenAI endpoint URL for the requested model's deployment
+        let url = self.chat_completions_url(&model_name);
 
         // Forward the request to the Azure endpoint
         let mut request_builder = self.client.post(url).json(&req);
@@ -66,45 +180,91 @@ impl AzureOpenAIAdapter {
         }
 
         // Send the request and await the response
-        let resp = request_builder
-            .send()
-            .await
-            .map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await
+            .inspect_err(|e| {
                 debug!("Azure OpenAI request failed: {}", e);
-                ProxyError::Upstream(e.to_string())
             })?;
 
         let status = resp.status();
         debug!("Azure OpenAI response status: {}", status);
+        let content_type = AdapterUtils::content_type(&resp);
 
-        let response_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| {
+        // Streamed bodies are forwarded as they arrive; only errors are buffered
+        if status.is_success() && req.stream.unwrap_or(false) {
+            let response_time = start_time.elapsed().as_millis() as u64;
+            AdapterUtils::log_response("azure", &model_name, true, response_time);
+            return AdapterUtils::stream_passthrough(resp, response_time);
+        }
+
+        let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
                 debug!("Failed to read Azure response body: {}", e);
-                ProxyError::Upstream(format!("error reading response body: {}", e))
             })?;
 
         let response_time = start_time.elapsed().as_millis() as u64;
-        AdapterUtils::log_response("azure", &AdapterUtils::extract_model(&req, &self.model_id), status.is_success(), response_time);
+        AdapterUtils::log_response("azure", &model_name, status.is_success(), response_time);
+
+        if status == StatusCode::BAD_REQUEST && self.translate_content_filter {
+            let filtered = serde_json::from_slice::<serde_json::Value>(&response_bytes)
+                .ok()
+                .and_then(|body| content_filter_completion(&body, &model_name));
+            if let Some(completion) = filtered {
+                debug!("Azure content filter blocked the prompt");
+                return Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(completion)).into_response(), response_time));
+            }
+        }
 
         if !status.is_success() {
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!("Azure error response: {}", error_text);
-            return Err(ProxyError::Upstream(format!("HTTP {}: {}", status, error_text)));
+            return Err(ProxyError::UpstreamStatus { status, body: error_text.to_string() });
         }
 
-        let json = serde_json::from_slice::<serde_json::Value>(&response_bytes)
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
+
+        let mut json = serde_json::from_slice::<serde_json::Value>(&response_bytes)
             .map_err(|e| {
                 debug!("Failed to parse Azure JSON response: {}", e);
-                ProxyError::Upstream(format!("error decoding response body: {} (body: {})", e, String::from_utf8_lossy(&response_bytes)))
+                ProxyError::Upstream(format!("error decoding response body: {} (body: {})", e, AdapterUtils::describe_body(&response_bytes)))
             })?;
 
         debug!("Successfully forwarded Azure OpenAI request");
-        Ok((StatusCode::OK, Json(json)).into_response())
+        AdapterUtils::normalize_finish_reasons(&mut json);
+        Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(json)).into_response(), response_time))
     }
 }
 
+/// Build a `content_filter` completion from an Azure content-filter error body.
+///
+/// Returns `None` when the error was not raised by the content filter.
+pub fn content_filter_completion(error_body: &serde_json::Value, model: &str) -> Option<serde_json::Value> {
+    let error = error_body.get("error")?;
+    if error.get("code").and_then(|code| code.as_str()) != Some("content_filter") {
+        return None;
+    }
+    let filter_results = error
+        .pointer("/innererror/content_filter_result")
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+
+    Some(serde_json::json!({
+        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+        "object": "chat.completion",
+        "created": AdapterUtils::current_timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": null},
+            "finish_reason": "content_filter",
+            "logprobs": null,
+        }],
+        "usage": null,
+        "prompt_filter_results": [{
+            "prompt_index": 0,
+            "content_filter_results": filter_results,
+        }],
+    }))
+}
+
 #[async_trait::async_trait]
 impl AdapterTrait for AzureOpenAIAdapter {
     fn name(&self) -> &'static str {
@@ -144,4 +304,164 @@ impl AdapterTrait for AzureOpenAIAdapter {
     async fn chat_completions(&self, _request: ChatCompletionRequest) -> Result<ChatCompletionResponse, ProxyError> {
         Err(ProxyError::Internal("Server feature not enabled".to_string()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::http_client::HttpClientBuilder;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn adapter() -> AzureOpenAIAdapter {
+        let client = HttpClientBuilder::new().build().unwrap();
+        AzureOpenAIAdapter::new(
+            "https://example.openai.azure.com".to_string(),
+            "gpt-35-turbo".to_string(),
+            None,
+            None,
+            client,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mapped_model_uses_deployment_in_url() {
+        let adapter = adapter().with_deployment_map(HashMap::from([
+            ("gpt-4o".to_string(), "prod-gpt4o".to_string()),
+        ]));
+
+        assert_eq!(
+            adapter.chat_completions_url("gpt-4o"),
+            "https://example.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2023-12-01-preview"
+        );
+    }
+
+    #[test]
+    fn test_unmapped_model_is_used_as_deployment() {
+        let adapter = adapter().with_deployment_map(HashMap::from([
+            ("gpt-4o".to_string(), "prod-gpt4o".to_string()),
+        ]));
+
+        assert_eq!(adapter.deployment_for("gpt-4o-mini"), "gpt-4o-mini");
+        assert!(adapter.chat_completions_url("gpt-4o-mini").contains("/deployments/gpt-4o-mini/"));
+    }
+
+    #[test]
+    fn test_custom_api_version_in_url() {
+        let client = HttpClientBuilder::new().build().unwrap();
+        let adapter = AzureOpenAIAdapter::new(
+            "https://example.openai.azure.com".to_string(),
+            "gpt-4o".to_string(),
+            None,
+            Some("2024-06-01".to_string()),
+            client,
+        )
+        .unwrap();
+
+        assert_eq!(
+            adapter.chat_completions_url("gpt-4o"),
+            "https://example.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-06-01"
+        );
+    }
+
+    #[test]
+    fn test_default_api_version_in_url() {
+        assert!(adapter()
+            .chat_completions_url("gpt-4o")
+            .ends_with(&format!("?api-version={}", DEFAULT_AZURE_API_VERSION)));
+    }
+
+    #[test]
+    fn test_malformed_api_version_rejected() {
+        for version in ["2024-6-1", "latest", "2024-06-01-beta", "2024-13-01", "24-06-01-preview", ""] {
+            let client = HttpClientBuilder::new().build().unwrap();
+            let result = AzureOpenAIAdapter::new(
+                "https://example.openai.azure.com".to_string(),
+                "gpt-4o".to_string(),
+                None,
+                Some(version.to_string()),
+                client,
+            );
+            assert!(matches!(result, Err(ProxyError::BadRequest(_))), "version {:?}", version);
+        }
+        assert!(AzureOpenAIAdapter::is_valid_api_version("2024-08-01-preview"));
+    }
+
+    async fn azure_backend(response: ResponseTemplate) -> (MockServer, AzureOpenAIAdapter) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt-35-turbo/chat/completions"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        let client = HttpClientBuilder::new().build().unwrap();
+        let adapter = AzureOpenAIAdapter::new(server.uri(), "gpt-35-turbo".to_string(), None, None, client).unwrap();
+        (server, adapter)
+    }
+
+    fn request() -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_content_filter_annotations_survive() {
+        let filter = serde_json::json!({"hate": {"filtered": false, "severity": "safe"}});
+        let body = serde_json::json!({
+            "id": "chatcmpl-azure",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-35-turbo",
+            "prompt_filter_results": [{"prompt_index": 0, "content_filter_results": filter}],
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello!"},
+                "finish_reason": "stop",
+                "logprobs": null,
+                "content_filter_results": filter
+            }],
+            "usage": null
+        });
+        let (_server, adapter) = azure_backend(ResponseTemplate::new(200).set_body_json(body)).await;
+
+        let response = AdapterTrait::chat_completions(&adapter, request()).await.unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(json["prompt_filter_results"][0]["content_filter_results"], filter);
+        assert_eq!(json["choices"][0]["content_filter_results"], filter);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_prompt_maps_to_content_filter_finish_reason() {
+        let filter = serde_json::json!({"violence": {"filtered": true, "severity": "high"}});
+        let error = serde_json::json!({
+            "error": {
+                "message": "The response was filtered due to the prompt triggering Azure OpenAI's content management policy.",
+                "param": "prompt",
+                "code": "content_filter",
+                "status": 400,
+                "innererror": {
+                    "code": "ResponsibleAIPolicyViolation",
+                    "content_filter_result": filter
+                }
+            }
+        });
+        let (_server, adapter) = azure_backend(ResponseTemplate::new(400).set_body_json(error.clone())).await;
+
+        let response = AdapterTrait::chat_completions(&adapter, request()).await.unwrap();
+        assert_eq!(response.choices[0].finish_reason, "content_filter");
+        assert_eq!(response.prompt_filter_results.unwrap()[0]["content_filter_results"], filter);
+
+        let adapter = adapter.with_content_filter_translation(false);
+        let result = adapter.chat_completions_http(request()).await;
+        assert!(matches!(
+            result,
+            Err(ProxyError::UpstreamStatus { status: StatusCode::BAD_REQUEST, body }) if body.contains("content_filter")
+        ));
+    }
+}
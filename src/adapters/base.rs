@@ -5,15 +5,28 @@
 
 use crate::{
     config::Config,
-    error::ProxyError,
+    error::{ProxyError, TransportErrorKind},
     schemas::{ChatCompletionRequest, ChatCompletionResponse},
 };
 use crate::core::http_client::{HttpClientBuilder, HttpClientError};
 use reqwest::Client;
+use std::borrow::Cow;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::debug;
+#[cfg(feature = "server")]
+use axum::response::Response;
+
+/// Model name prefixes of OpenAI reasoning models, which accept `reasoning_effort`
+pub const DEFAULT_REASONING_MODELS: [&str; 3] = ["o1", "o3", "o4"];
+
+/// Time spent in the upstream HTTP call.
+///
+/// Adapters attach this to their responses as an extension so handlers can
+/// report upstream latency separately from total handler time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpstreamDuration(pub Duration);
 
 /// Common adapter configuration
 #[derive(Debug, Clone)]
@@ -76,18 +89,21 @@ impl AdapterUtils {
 
     /// Generate a consistent hash for caching and request deduplication
     pub fn generate_request_hash(request: &ChatCompletionRequest) -> u64 {
+        Self::hash_request_parameters(request, true)
+    }
+
+    /// Hash the parameters that determine a completion: model, messages,
+    /// temperature, top_p, stop and, when `include_max_tokens`, max_tokens
+    pub fn hash_request_parameters(request: &ChatCompletionRequest, include_max_tokens: bool) -> u64 {
         let mut hasher = DefaultHasher::new();
 
-        // Hash the essential parts of the request for caching
+        request.model.hash(&mut hasher);
         request.messages.hash(&mut hasher);
-        if let Some(ref model) = request.model {
-            model.hash(&mut hasher);
-        }
-        if let Some(temperature) = request.temperature {
-            temperature.to_bits().hash(&mut hasher);
-        }
-        if let Some(max_tokens) = request.max_tokens {
-            max_tokens.hash(&mut hasher);
+        request.temperature.map(f32::to_bits).hash(&mut hasher);
+        request.top_p.map(f32::to_bits).hash(&mut hasher);
+        request.stop.hash(&mut hasher);
+        if include_max_tokens {
+            request.max_tokens.hash(&mut hasher);
         }
 
         hasher.finish()
@@ -114,11 +130,202 @@ impl AdapterUtils {
         Ok(())
     }
 
+    /// Whether `model` is a reasoning model, i.e. equals one of `prefixes` or
+    /// starts with one followed by a dash (`o1`, `o3-mini`)
+    pub fn is_reasoning_model(model: &str, prefixes: &[String]) -> bool {
+        prefixes.iter().any(|prefix| {
+            model
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+    }
+
+    /// Rename `system` messages to `developer`, the role reasoning models
+    /// expect instructions under
+    pub fn use_developer_role(request: &mut ChatCompletionRequest) {
+        for message in request.messages.iter_mut().filter(|message| message.role == "system") {
+            message.role = "developer".to_string();
+        }
+    }
+
     /// Extract model from request or use default
     pub fn extract_model(request: &ChatCompletionRequest, default_model: &str) -> String {
         request.model.clone().unwrap_or_else(|| default_model.to_string())
     }
 
+    /// Attach the upstream call duration to an adapter response
+    #[cfg(feature = "server")]
+    pub fn with_upstream_duration(mut response: Response, response_time_ms: u64) -> Response {
+        response
+            .extensions_mut()
+            .insert(UpstreamDuration(Duration::from_millis(response_time_ms)));
+        response
+    }
+
+    /// Forward a successful streaming upstream response to the client chunk by
+    /// chunk as it arrives, without buffering the body
+    #[cfg(feature = "server")]
+    pub fn stream_passthrough(response: reqwest::Response, response_time_ms: u64) -> Result<Response, ProxyError> {
+        let mut builder = Response::builder().status(response.status());
+        if let Some(content_type) = response.headers().get(reqwest::header::CONTENT_TYPE) {
+            builder = builder.header(axum::http::header::CONTENT_TYPE, content_type);
+        }
+        let response = builder
+            .body(axum::body::Body::from_stream(response.bytes_stream()))
+            .map_err(|e| ProxyError::Internal(format!("Failed to build response: {}", e)))?;
+        Ok(Self::with_upstream_duration(response, response_time_ms))
+    }
+
+    /// Send an upstream request.
+    ///
+    /// With a `first_byte_timeout`, the request fails with a transport timeout
+    /// when the backend has not started responding within that window, even
+    /// though the overall request timeout has not elapsed yet.
+    #[cfg(feature = "server")]
+    pub async fn send(
+        request: reqwest::RequestBuilder,
+        first_byte_timeout: Option<Duration>,
+    ) -> Result<reqwest::Response, ProxyError> {
+        let Some(limit) = first_byte_timeout else {
+            return request.send().await.map_err(ProxyError::from);
+        };
+
+        match tokio::time::timeout(limit, request.send()).await {
+            Ok(result) => result.map_err(ProxyError::from),
+            Err(_) => Err(ProxyError::Transport {
+                kind: TransportErrorKind::Timeout,
+                message: format!("no response within the first-byte timeout of {:?}", limit),
+            }),
+        }
+    }
+
+    /// Send an OpenAI-compatible `GET /models` (or `/models/{id}`) request
+    /// and parse the JSON body
+    #[cfg(feature = "server")]
+    pub async fn fetch_models(request: reqwest::RequestBuilder) -> Result<serde_json::Value, ProxyError> {
+        let response = request.send().await.map_err(ProxyError::from)?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let content_type = Self::content_type(&response);
+        let body = Self::read_body(response).await?;
+        if !status.is_success() {
+            return Err(ProxyError::upstream_status(status, &headers, Self::describe_body(&body).to_string()));
+        }
+        Self::ensure_json_content_type(content_type.as_deref(), &body)?;
+        serde_json::from_slice(&body)
+            .map_err(|e| ProxyError::Upstream(format!("invalid model listing: {}: {}", e, Self::describe_body(&body))))
+    }
+
+    /// Read a whole upstream response body.
+    ///
+    /// A body that ends before its declared Content-Length, as when the
+    /// connection drops mid-response, fails with
+    /// [`ProxyError::IncompleteResponse`] rather than being returned truncated.
+    pub async fn read_body(mut response: reqwest::Response) -> Result<bytes::Bytes, ProxyError> {
+        let expected = response.content_length();
+        let mut body = bytes::BytesMut::with_capacity(expected.unwrap_or(0).min(1 << 20) as usize);
+
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(e) => {
+                    return Err(match expected {
+                        Some(expected) if (body.len() as u64) < expected => {
+                            ProxyError::IncompleteResponse { expected, received: body.len() as u64 }
+                        }
+                        _ => ProxyError::Upstream(format!("error reading response body: {}", e)),
+                    });
+                }
+            }
+        }
+
+        match expected {
+            Some(expected) if (body.len() as u64) < expected => {
+                Err(ProxyError::IncompleteResponse { expected, received: body.len() as u64 })
+            }
+            _ => Ok(body.freeze()),
+        }
+    }
+
+    /// Read the Content-Type of an upstream response
+    pub fn content_type(response: &reqwest::Response) -> Option<String> {
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    }
+
+    /// Check whether a Content-Type denotes JSON (`application/json` or `+json` types)
+    pub fn is_json_content_type(content_type: &str) -> bool {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        mime == "application/json" || mime.ends_with("+json")
+    }
+
+    /// Reject upstream bodies whose declared Content-Type is not JSON before parsing them.
+    ///
+    /// A missing Content-Type is tolerated because some backends omit it.
+    pub fn ensure_json_content_type(content_type: Option<&str>, body: &[u8]) -> Result<(), ProxyError> {
+        match content_type {
+            Some(content_type) if !Self::is_json_content_type(content_type) => Err(ProxyError::Upstream(format!(
+                "unexpected response content type '{}' (expected application/json): {}",
+                content_type,
+                Self::describe_body(body)
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Render an upstream body for error messages without producing garbage
+    /// for binary or otherwise non-UTF-8 content.
+    pub fn describe_body(body: &[u8]) -> Cow<'_, str> {
+        const MAX_PREVIEW_CHARS: usize = 512;
+
+        match std::str::from_utf8(body) {
+            Ok(text) if text.chars().count() > MAX_PREVIEW_CHARS => {
+                Cow::Owned(format!("{}... ({} bytes)", text.chars().take(MAX_PREVIEW_CHARS).collect::<String>(), body.len()))
+            }
+            Ok(text) => Cow::Borrowed(text),
+            Err(_) => Cow::Owned(format!("<{} bytes of non-UTF-8 data>", body.len())),
+        }
+    }
+
+    /// Map a backend-specific finish reason onto OpenAI's canonical set
+    /// (`stop`, `length`, `tool_calls`, `content_filter`).
+    ///
+    /// Unknown values are returned unchanged.
+    pub fn normalize_finish_reason(reason: &str) -> &str {
+        match reason.to_ascii_lowercase().as_str() {
+            "stop" | "end_turn" | "stop_sequence" | "eos" | "eos_token" | "end" | "complete" => "stop",
+            "length" | "max_tokens" | "max_length" | "model_length" => "length",
+            "tool_calls" | "tool_use" | "function_call" => "tool_calls",
+            "content_filter" | "safety" | "guardrail_intervened" => "content_filter",
+            _ => reason,
+        }
+    }
+
+    /// Normalize the `finish_reason` of every choice in an OpenAI-format response body
+    pub fn normalize_finish_reasons(body: &mut serde_json::Value) {
+        let Some(choices) = body.get_mut("choices").and_then(|choices| choices.as_array_mut()) else {
+            return;
+        };
+
+        for choice in choices {
+            if let Some(reason) = choice.get("finish_reason").and_then(|reason| reason.as_str()) {
+                let normalized = Self::normalize_finish_reason(reason);
+                if normalized != reason {
+                    choice["finish_reason"] = serde_json::Value::String(normalized.to_string());
+                }
+            }
+        }
+    }
+
     /// Log adapter request for debugging
     pub fn log_request(adapter_name: &str, model: &str, message_count: usize) {
         debug!(
@@ -174,6 +381,8 @@ mod tests {
                 tool_calls: None,
                 function_call: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }],
             model: Some("test-model".to_string()),
             temperature: Some(0.7),
@@ -203,4 +412,47 @@ mod tests {
 
         assert_eq!(AdapterUtils::extract_model(&request_no_model, "default"), "default");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_json_content_type_detection() {
+        assert!(AdapterUtils::is_json_content_type("application/json"));
+        assert!(AdapterUtils::is_json_content_type("application/json; charset=utf-8"));
+        assert!(AdapterUtils::is_json_content_type("application/problem+json"));
+        assert!(!AdapterUtils::is_json_content_type("text/html; charset=utf-8"));
+        assert!(!AdapterUtils::is_json_content_type("application/octet-stream"));
+    }
+
+    #[test]
+    fn test_unexpected_content_type_is_described() {
+        assert!(AdapterUtils::ensure_json_content_type(None, b"{}").is_ok());
+        assert!(AdapterUtils::ensure_json_content_type(Some("application/json"), b"{}").is_ok());
+
+        let err = AdapterUtils::ensure_json_content_type(Some("application/octet-stream"), &[0x1f, 0x8b, 0xff, 0x00])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unexpected response content type 'application/octet-stream'"));
+        assert!(err.contains("<4 bytes of non-UTF-8 data>"));
+    }
+
+    #[test]
+    fn test_finish_reason_normalization() {
+        assert_eq!(AdapterUtils::normalize_finish_reason("max_tokens"), "length");
+        assert_eq!(AdapterUtils::normalize_finish_reason("end_turn"), "stop");
+        assert_eq!(AdapterUtils::normalize_finish_reason("stop_sequence"), "stop");
+        assert_eq!(AdapterUtils::normalize_finish_reason("eos_token"), "stop");
+        assert_eq!(AdapterUtils::normalize_finish_reason("tool_calls"), "tool_calls");
+        assert_eq!(AdapterUtils::normalize_finish_reason("something_new"), "something_new");
+
+        let mut body = serde_json::json!({
+            "choices": [
+                {"index": 0, "finish_reason": "max_tokens"},
+                {"index": 1, "finish_reason": "end_turn"},
+                {"index": 2, "finish_reason": null}
+            ]
+        });
+        AdapterUtils::normalize_finish_reasons(&mut body);
+        assert_eq!(body["choices"][0]["finish_reason"], "length");
+        assert_eq!(body["choices"][1]["finish_reason"], "stop");
+        assert!(body["choices"][2]["finish_reason"].is_null());
+    }
+}
@@ -14,11 +14,13 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use reqwest::Client;
+#[cfg(feature = "request-signing")]
+use crate::adapters::signing::RequestSigner;
+use reqwest::{Client, RequestBuilder};
 use tracing::debug;
 
 #[cfg(feature = "server")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// # Custom Adapter
 ///
@@ -34,6 +36,11 @@ pub struct CustomAdapter {
     token: Option<String>,
     /// HTTP client with connection pooling
     client: Client,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
+    /// Optional HMAC signer for gateways that require signed requests
+    #[cfg(feature = "request-signing")]
+    signer: Option<RequestSigner>,
 }
 
 impl CustomAdapter {
@@ -44,9 +51,31 @@ impl CustomAdapter {
             model_id,
             token,
             client,
+            first_byte_timeout: None,
+            #[cfg(feature = "request-signing")]
+            signer: None,
         }
     }
 
+    /// Authenticate with `token` instead of the configured credential
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Sign every outgoing request with the given signer
+    #[cfg(feature = "request-signing")]
+    pub fn with_signer(mut self, signer: Option<RequestSigner>) -> Self {
+        self.signer = signer;
+        self
+    }
+
     /// Get base URL (public accessor)
     pub fn base_url(&self) -> &str {
         &self.base_url
@@ -62,6 +91,52 @@ impl CustomAdapter {
         &self.token
     }
 
+    /// Build the upstream chat completions request, signing it when configured
+    fn build_request(&self, req: &ChatCompletionRequest) -> Result<RequestBuilder, ProxyError> {
+        let url = format!("{}/chat/completions", self.base_url);
+        let body = serde_json::to_vec(req)
+            .map_err(|e| ProxyError::Serialization(format!("Failed to serialize request: {}", e)))?;
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            reqwest::header::HeaderValue::from_static("application/json"),
+        );
+        // Add authentication header if token is present
+        if let Some(token) = &self.token {
+            let value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| ProxyError::Internal("Invalid characters in backend token".to_string()))?;
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+
+        #[cfg(feature = "request-signing")]
+        if let Some(signer) = &self.signer {
+            signer.apply(&mut headers, &body);
+        }
+
+        Ok(self.client.post(url).headers(headers).body(body))
+    }
+
+    /// Fetch the endpoint's model listing, or one model's metadata when `id` is
+    /// given, signing the request when configured
+    #[cfg(feature = "server")]
+    pub async fn models(&self, id: Option<&str>) -> Result<serde_json::Value, ProxyError> {
+        let url = format!("{}/models{}", self.base_url, id.map(|id| format!("/{}", id)).unwrap_or_default());
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(token) = &self.token {
+            let value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|_| ProxyError::Internal("Invalid characters in backend token".to_string()))?;
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+
+        #[cfg(feature = "request-signing")]
+        if let Some(signer) = &self.signer {
+            signer.apply(&mut headers, &[]);
+        }
+
+        AdapterUtils::fetch_models(self.client.get(url).headers(headers)).await
+    }
+
     /// Process chat completion requests
     #[cfg(feature = "server")]
     pub async fn chat_completions_http(
@@ -76,29 +151,28 @@ impl CustomAdapter {
 
         let start_time = std::time::Instant::now();
 
-        // Build the endpoint URL - assume OpenAI-compatible
-        let url = format!("{}/chat/completions", self.base_url);
-
-        // Forward the request to the custom endpoint
-        let mut request_builder = self.client.post(url).json(&req);
-
-        // Add authentication header if token is present
-        if let Some(token) = &self.token {
-            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
-        }
+        // Forward the request to the custom endpoint - assume OpenAI-compatible
+        let request_builder = self.build_request(&req)?;
 
         // Send the request and await the response
-        let resp = request_builder.send().await.map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
             debug!("Custom endpoint request failed: {}", e);
-            ProxyError::Upstream(e.to_string())
         })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         debug!("Custom endpoint response status: {}", status);
+        let content_type = AdapterUtils::content_type(&resp);
 
-        let response_bytes = resp.bytes().await.map_err(|e| {
+        // Streamed bodies are forwarded as they arrive; only errors are buffered
+        if status.is_success() && req.stream.unwrap_or(false) {
+            let response_time = start_time.elapsed().as_millis() as u64;
+            AdapterUtils::log_response("custom", &AdapterUtils::extract_model(&req, &self.model_id), true, response_time);
+            return AdapterUtils::stream_passthrough(resp, response_time);
+        }
+
+        let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
             debug!("Failed to read custom endpoint response body: {}", e);
-            ProxyError::Upstream(format!("error reading response body: {}", e))
         })?;
 
         let response_time = start_time.elapsed().as_millis() as u64;
@@ -110,25 +184,25 @@ impl CustomAdapter {
         );
 
         if !status.is_success() {
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!("Custom endpoint error response: {}", error_text);
-            return Err(ProxyError::Upstream(format!(
-                "HTTP {}: {}",
-                status, error_text
-            )));
+            return Err(ProxyError::upstream_status(status, &headers, error_text.to_string()));
         }
 
-        let json = serde_json::from_slice::<serde_json::Value>(&response_bytes).map_err(|e| {
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
+
+        let mut json = serde_json::from_slice::<serde_json::Value>(&response_bytes).map_err(|e| {
             debug!("Failed to parse custom endpoint JSON response: {}", e);
             ProxyError::Upstream(format!(
                 "error decoding response body: {} (body: {})",
                 e,
-                String::from_utf8_lossy(&response_bytes)
+                AdapterUtils::describe_body(&response_bytes)
             ))
         })?;
 
         debug!("Successfully forwarded custom endpoint request");
-        Ok((StatusCode::OK, Json(json)).into_response())
+        AdapterUtils::normalize_finish_reasons(&mut json);
+        Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(json)).into_response(), response_time))
     }
 
     /// Perform a raw streaming request without buffering the upstream body
@@ -142,31 +216,22 @@ impl CustomAdapter {
 
         let start_time = Instant::now();
 
-        let url = format!("{}/chat/completions", self.base_url);
-        let mut request_builder = self.client.post(url).json(&req);
-
-        if let Some(token) = &self.token {
-            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
-        }
+        let request_builder = self.build_request(&req)?;
 
-        let resp = request_builder.send().await.map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
             debug!("Custom streaming request failed: {}", e);
-            ProxyError::Upstream(e.to_string())
         })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         if !status.is_success() {
-            let response_bytes = resp.bytes().await.map_err(|e| {
+            let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
                 debug!("Failed to read custom streaming error body: {}", e);
-                ProxyError::Upstream(format!("error reading response body: {}", e))
             })?;
 
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!("Custom streaming error response: {}", error_text);
-            return Err(ProxyError::Upstream(format!(
-                "HTTP {}: {}",
-                status, error_text
-            )));
+            return Err(ProxyError::upstream_status(status, &headers, error_text.to_string()));
         }
 
         let handshake_time = start_time.elapsed().as_millis() as u64;
@@ -223,3 +288,42 @@ impl AdapterTrait for CustomAdapter {
         ))
     }
 }
+
+#[cfg(all(test, feature = "request-signing"))]
+mod tests {
+    use super::*;
+    use crate::core::http_client::HttpClientBuilder;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    #[test]
+    fn test_signed_request_carries_signature_header() {
+        let signer = RequestSigner::new(
+            "gateway-secret".to_string(),
+            vec!["authorization".to_string(), "body".to_string()],
+            "x-gateway-signature",
+        )
+        .unwrap();
+        let adapter = CustomAdapter::new(
+            "https://gateway.example.com".to_string(),
+            "llama".to_string(),
+            Some("token".to_string()),
+            HttpClientBuilder::new().build().unwrap(),
+        )
+        .with_signer(Some(signer));
+
+        let request = adapter
+            .build_request(&ChatCompletionRequest::default())
+            .unwrap()
+            .build()
+            .unwrap();
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"gateway-secret").unwrap();
+        mac.update(b"authorization:Bearer token\n");
+        mac.update(body);
+        let expected: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+
+        assert_eq!(request.headers()["x-gateway-signature"], expected.as_str());
+    }
+}
@@ -229,15 +229,19 @@ impl DirectAdapter {
                     function_call: None,
                     tool_calls: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 },
                 finish_reason: "stop".to_string(),
                 logprobs: None,
+                content_filter_results: None,
             }],
             usage: Some(Usage {
                 prompt_tokens: prompt.split_whitespace().count() as u32,
                 completion_tokens: completion.split_whitespace().count() as u32,
                 total_tokens: (prompt.split_whitespace().count() + completion.split_whitespace().count()) as u32,
             }),
+            prompt_filter_results: None,
         };
 
         Ok(response)
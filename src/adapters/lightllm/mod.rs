@@ -11,6 +11,7 @@
 
 use crate::{
     adapters::base::{AdapterTrait, AdapterUtils},
+    core::tokens::{HeuristicTokenCounter, TokenCounter},
     error::ProxyError,
     schemas::{ChatCompletionRequest, ChatCompletionResponse, Message},
 };
@@ -21,14 +22,23 @@ use axum::{
     Json,
 };
 use reqwest::Client;
+use serde::Deserialize;
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    sync::Arc,
 };
 use tracing::debug;
 
 #[cfg(feature = "server")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+/// System prompt instruction sent to LightLLM for JSON `response_format` requests
+const JSON_MODE_INSTRUCTION: &str = "Respond only with valid JSON, without any surrounding text or markdown.";
+
+/// Most choices (`n`) answered by fanning out to parallel backend calls
+#[cfg(feature = "server")]
+const MAX_FAN_OUT_CHOICES: u32 = 16;
 
 /// # Role Enum for LightLLM Format
 ///
@@ -54,6 +64,53 @@ impl From<&str> for Role {
     }
 }
 
+/// # LightLLM Prompt Template
+///
+/// Role markers used to flatten a conversation into LightLLM's prompt.
+/// `tool` and `tool_call` are templates: `{name}` is replaced with the tool
+/// name, `{content}` with a tool result and `{arguments}` with the arguments
+/// of a call the assistant made.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LightLLMPromptTemplate {
+    /// Marker before a system message
+    pub system: String,
+    /// Marker before a user message
+    pub user: String,
+    /// Marker before an assistant message
+    pub assistant: String,
+    /// Rendering of a tool result message
+    pub tool: String,
+    /// Rendering of each tool call in an assistant message
+    pub tool_call: String,
+    /// Marker ending the prompt, after which the model generates its reply
+    pub generation: String,
+}
+
+impl Default for LightLLMPromptTemplate {
+    fn default() -> Self {
+        Self {
+            system: "<|system|>\n".to_string(),
+            user: "<|user|>\n".to_string(),
+            assistant: "<|assistant|>\n".to_string(),
+            tool: "<|tool|>\n{name}: {content}\n".to_string(),
+            tool_call: "<|tool_call|>\n{name}: {arguments}\n".to_string(),
+            generation: "<|assistant|> ".to_string(),
+        }
+    }
+}
+
+impl LightLLMPromptTemplate {
+    /// Read a template file whose markers override the defaults, failing with
+    /// [`ProxyError::BadRequest`] when it is missing or malformed
+    pub fn from_file(path: &str) -> Result<Self, ProxyError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ProxyError::BadRequest(format!("Cannot read LightLLM prompt template '{}': {}", path, e)))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| ProxyError::BadRequest(format!("Invalid LightLLM prompt template '{}': {}", path, e)))
+    }
+}
+
 /// # LightLLM Adapter
 ///
 /// Direct integration adapter for LightLLM servers that converts
@@ -68,27 +125,85 @@ pub struct LightLLMAdapter {
     model_id: String,
     /// Optional authentication token
     token: Option<String>,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
+    /// Counts usage tokens, which the native `/generate` API does not report
+    token_counter: Arc<dyn TokenCounter>,
+    /// Role markers used to build the prompt
+    prompt_template: LightLLMPromptTemplate,
 }
 
 impl LightLLMAdapter {
     /// Create a new LightLLM adapter instance
-    pub fn new(base: String, model_id: String, token: Option<String>, client: Client) -> Self {
+    pub fn new(
+        base: String,
+        model_id: String,
+        token: Option<String>,
+        client: Client,
+        prompt_template: LightLLMPromptTemplate,
+    ) -> Self {
         Self {
             base,
             client,
             model_id,
             token,
+            first_byte_timeout: None,
+            token_counter: Arc::new(HeuristicTokenCounter),
+            prompt_template,
         }
     }
 
+    /// Count usage tokens with `token_counter`
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
+    /// Authenticate with `token` instead of the configured credential
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
     /// Get the model ID for this adapter
     pub fn model_id(&self) -> &str {
         &self.model_id
     }
 
+    /// Ask for JSON in the system prompt when the request sets a JSON
+    /// `response_format`, since LightLLM has no native JSON mode. A requested
+    /// schema is included verbatim.
+    fn with_json_instruction(mut req: ChatCompletionRequest) -> ChatCompletionRequest {
+        if !req.requests_json() {
+            return req;
+        }
+        let mut instruction = JSON_MODE_INSTRUCTION.to_string();
+        if let Some(schema) = req.json_schema() {
+            instruction.push_str(&format!(" The JSON must match this JSON Schema: {}", schema));
+        }
+
+        match req.messages.first_mut().filter(|message| message.role == "system") {
+            Some(system) => {
+                let content = system.content.get_or_insert_with(String::new);
+                if !content.is_empty() {
+                    content.push_str("\n\n");
+                }
+                content.push_str(&instruction);
+            }
+            None => req.messages.insert(0, Message::system(instruction)),
+        }
+        req
+    }
+
     /// Convert OpenAI-format messages to LightLLM's prompt format with
     /// advanced memory optimization and capacity estimation.
-    fn messages_to_prompt(messages: &[Message]) -> String {
+    fn messages_to_prompt(messages: &[Message], template: &LightLLMPromptTemplate) -> String {
         // Enhanced capacity estimation for better memory management
         let estimated_capacity = messages
             .iter()
@@ -102,37 +217,53 @@ impl LightLLMAdapter {
         let mut out = String::with_capacity(estimated_capacity);
 
         // Process each message with optimized string operations
-        for msg in messages {
+        for (index, msg) in messages.iter().enumerate() {
             let role = Role::from(msg.role.as_str());
             match role {
                 Role::System => {
-                    out.push_str("<|system|>\n");
+                    out.push_str(&template.system);
                     if let Some(content) = &msg.content {
                         out.push_str(content);
                     }
                     out.push('\n');
                 }
                 Role::User => {
-                    out.push_str("<|user|>\n");
+                    out.push_str(&template.user);
                     if let Some(content) = &msg.content {
                         out.push_str(content);
                     }
                     out.push('\n');
                 }
                 Role::Assistant => {
-                    out.push_str("<|assistant|>\n");
+                    out.push_str(&template.assistant);
+                    let tool_calls = msg.tool_calls.as_deref().unwrap_or_default();
                     if let Some(content) = &msg.content {
                         out.push_str(content);
+                        out.push('\n');
+                    } else if tool_calls.is_empty() {
+                        out.push('\n');
+                    }
+                    for call in tool_calls {
+                        out.push_str(
+                            &template
+                                .tool_call
+                                .replace("{name}", &call.function.name)
+                                .replace("{arguments}", &call.function.arguments),
+                        );
                     }
-                    out.push('\n');
                 }
                 Role::Tool => {
-                    // Skip tool messages (not supported by LightLLM)
-                    debug!("Skipping tool message: {:?}", msg.content);
+                    let name = Self::tool_name(&messages[..index], msg);
+                    out.push_str(
+                        &template
+                            .tool
+                            .replace("{name}", name)
+                            .replace("{content}", msg.content.as_deref().unwrap_or_default()),
+                    );
                 }
             }
         }
-        out.push_str("<|assistant|> ");
+        out.push_str(&template.generation);
 
         // Verify capacity utilization for performance monitoring
         let actual_capacity = out.capacity();
@@ -146,6 +277,23 @@ impl LightLLMAdapter {
         out
     }
 
+    /// Name of the tool that produced a tool message: its own `name`, else
+    /// the name of the earlier assistant call it answers, else its call ID
+    fn tool_name<'a>(earlier: &'a [Message], msg: &'a Message) -> &'a str {
+        if let Some(name) = &msg.name {
+            return name;
+        }
+        let Some(call_id) = &msg.tool_call_id else {
+            return "tool";
+        };
+        earlier
+            .iter()
+            .rev()
+            .flat_map(|message| message.tool_calls.iter().flatten())
+            .find(|call| &call.id == call_id)
+            .map_or(call_id, |call| &call.function.name)
+    }
+
     /// Generate a deterministic hash for request deduplication and caching
     fn calculate_request_hash(req: &ChatCompletionRequest) -> u64 {
         let mut hasher = DefaultHasher::new();
@@ -172,6 +320,9 @@ impl LightLLMAdapter {
         if let Some(top_p) = req.top_p {
             top_p.to_bits().hash(&mut hasher);
         }
+        if let Some(top_k) = req.top_k {
+            top_k.hash(&mut hasher);
+        }
         if let Some(presence_penalty) = req.presence_penalty {
             presence_penalty.to_bits().hash(&mut hasher);
         }
@@ -187,6 +338,9 @@ impl LightLLMAdapter {
         if let Some(seed) = req.seed {
             seed.hash(&mut hasher);
         }
+        if let Some(ref suffix) = req.suffix {
+            suffix.hash(&mut hasher);
+        }
 
         hasher.finish()
     }
@@ -196,8 +350,77 @@ impl LightLLMAdapter {
     pub async fn chat_completions_http(
         &self,
         req: ChatCompletionRequest,
+    ) -> Result<Response, ProxyError> {
+        match req.n.unwrap_or(1) {
+            choices if choices > 1 => self.fan_out_choices(req, choices).await,
+            _ => self.single_completion_http(req).await,
+        }
+    }
+
+    /// Answer `n` > 1 with parallel single-choice requests, since the backend
+    /// returns one completion per call. Choices are numbered in order and
+    /// their completion tokens summed; the prompt is counted once.
+    #[cfg(feature = "server")]
+    async fn fan_out_choices(&self, req: ChatCompletionRequest, choices: u32) -> Result<Response, ProxyError> {
+        if req.stream.unwrap_or(false) {
+            return Err(ProxyError::BadRequest(
+                "n > 1 is not supported for streaming requests to the lightllm backend".to_string(),
+            ));
+        }
+        if choices > MAX_FAN_OUT_CHOICES {
+            return Err(ProxyError::BadRequest(format!(
+                "n must be at most {} for the lightllm backend",
+                MAX_FAN_OUT_CHOICES
+            )));
+        }
+
+        let start_time = Instant::now();
+        let single = ChatCompletionRequest { n: None, ..req };
+        let responses =
+            futures_util::future::try_join_all((0..choices).map(|_| self.single_completion_http(single.clone()))).await?;
+
+        let mut merged: Option<serde_json::Value> = None;
+        for (index, response) in responses.into_iter().enumerate() {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+            let mut completion: serde_json::Value = serde_json::from_slice(&body)?;
+            let mut choice = completion["choices"][0].take();
+            choice["index"] = serde_json::Value::from(index);
+
+            match merged.as_mut() {
+                None => {
+                    completion["choices"] = serde_json::json!([choice]);
+                    merged = Some(completion);
+                }
+                Some(merged) => {
+                    if let Some(merged_choices) = merged["choices"].as_array_mut() {
+                        merged_choices.push(choice);
+                    }
+                    let completion_tokens = completion["usage"]["completion_tokens"].as_u64().unwrap_or(0);
+                    for field in ["completion_tokens", "total_tokens"] {
+                        let total = merged["usage"][field].as_u64().unwrap_or(0) + completion_tokens;
+                        merged["usage"][field] = serde_json::Value::from(total);
+                    }
+                }
+            }
+        }
+
+        let response_time = start_time.elapsed().as_millis() as u64;
+        Ok(AdapterUtils::with_upstream_duration(
+            (StatusCode::OK, Json(merged.unwrap_or_default())).into_response(),
+            response_time,
+        ))
+    }
+
+    /// Request a single completion
+    #[cfg(feature = "server")]
+    async fn single_completion_http(
+        &self,
+        req: ChatCompletionRequest,
     ) -> Result<Response, ProxyError> {
         // Note: This adapter now supports OpenAI-compatible endpoints that may support streaming
+        let req = Self::with_json_instruction(req);
 
         let request_hash = Self::calculate_request_hash(&req);
         debug!("Processing LightLLM request with hash: {:x}", request_hash);
@@ -214,7 +437,7 @@ impl LightLLMAdapter {
         let is_openai_compatible = self.base.contains("/v1") || req.stream.unwrap_or(false);
 
         // Calculate prompt for token counting (needed later)
-        let prompt = Self::messages_to_prompt(&req.messages);
+        let prompt = Self::messages_to_prompt(&req.messages, &self.prompt_template);
         debug!("Converted prompt length: {} characters", prompt.len());
 
         let (url, payload) = if is_openai_compatible {
@@ -246,19 +469,35 @@ impl LightLLMAdapter {
                     payload["frequency_penalty"] = serde_json::Value::from(frequency_penalty);
                 }
             }
+            if let Some(suffix) = &req.suffix {
+                payload["suffix"] = serde_json::Value::from(suffix.as_str());
+            }
+            if let Some(top_k) = req.top_k {
+                payload["top_k"] = serde_json::Value::from(top_k);
+            }
 
             (url, payload)
         } else {
             // Use traditional LightLLM format
             let url = format!("{}/generate", self.base);
-            let payload = serde_json::json!({
+            let mut payload = serde_json::json!({
                 "prompt": prompt,
                 "max_new_tokens": req.max_tokens.unwrap_or(256),
                 "temperature": req.temperature.unwrap_or(1.0),
                 "top_p": req.top_p.unwrap_or(1.0),
-                "presence_penalty": req.presence_penalty.unwrap_or(0.0),
-                "frequency_penalty": req.frequency_penalty.unwrap_or(0.0),
             });
+            if let Some(presence_penalty) = req.presence_penalty {
+                payload["presence_penalty"] = serde_json::Value::from(presence_penalty);
+            }
+            if let Some(frequency_penalty) = req.frequency_penalty {
+                payload["frequency_penalty"] = serde_json::Value::from(frequency_penalty);
+            }
+            if let Some(suffix) = &req.suffix {
+                payload["suffix"] = serde_json::Value::from(suffix.as_str());
+            }
+            if let Some(top_k) = req.top_k {
+                payload["top_k"] = serde_json::Value::from(top_k);
+            }
 
             (url, payload)
         };
@@ -271,24 +510,31 @@ impl LightLLMAdapter {
         }
 
         // Send the request and await the response
-        let resp = request_builder.send().await.map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
             debug!("HTTP request failed for hash {:x}: {}", request_hash, e);
-            ProxyError::from(e)
         })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         debug!(
             "Received response status: {} for hash {:x}",
             status, request_hash
         );
+        let content_type = AdapterUtils::content_type(&resp);
+
+        // Streamed bodies are forwarded as they arrive; only errors are buffered
+        if status.is_success() && req.stream.unwrap_or(false) {
+            let response_time = start_time.elapsed().as_millis() as u64;
+            AdapterUtils::log_response("lightllm", &AdapterUtils::extract_model(&req, &self.model_id), true, response_time);
+            return AdapterUtils::stream_passthrough(resp, response_time);
+        }
 
         // Read response body
-        let response_bytes = resp.bytes().await.map_err(|e| {
+        let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
             debug!(
                 "Failed to read response body for hash {:x}: {}",
                 request_hash, e
             );
-            ProxyError::Upstream(format!("error reading response body: {}", e))
         })?;
 
         debug!(
@@ -297,14 +543,15 @@ impl LightLLMAdapter {
             request_hash
         );
 
-        // If streaming was requested, just return the raw response body for the streaming adapter to handle
-        if req.stream.unwrap_or(false) {
-            let response = Response::builder()
-                .status(status)
-                .body(axum::body::Body::from(response_bytes))
-                .map_err(|e| ProxyError::Internal(format!("Failed to build response: {}", e)))?;
-            return Ok(response);
+        // Non-JSON error pages are reported as-is rather than as a parse failure
+        if !status.is_success() && !content_type.as_deref().is_some_and(AdapterUtils::is_json_content_type) {
+            return Err(ProxyError::upstream_status(
+                status,
+                &headers,
+                AdapterUtils::describe_body(&response_bytes).to_string(),
+            ));
         }
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
 
         // Parse JSON directly from bytes (for non-streaming responses)
         let json = serde_json::from_slice::<serde_json::Value>(&response_bytes).map_err(|e| {
@@ -312,7 +559,7 @@ impl LightLLMAdapter {
             ProxyError::Upstream(format!(
                 "error decoding response body: {} (body: {})",
                 e,
-                String::from_utf8_lossy(&response_bytes)
+                AdapterUtils::describe_body(&response_bytes)
             ))
         })?;
 
@@ -322,7 +569,7 @@ impl LightLLMAdapter {
                 "Backend returned error status {} for hash {:x}",
                 status, request_hash
             );
-            return Err(ProxyError::Upstream(json.to_string()));
+            return Err(ProxyError::upstream_status(status, &headers, json.to_string()));
         }
 
         // Extract the generated text from the response
@@ -345,28 +592,32 @@ impl LightLLMAdapter {
         // Generate a unique ID for the response
         let now = AdapterUtils::current_timestamp() as i64;
 
+        let model = req.model.unwrap_or(self.model_id.clone());
+        let prompt_tokens = self.token_counter.count(&prompt, &model);
+        let completion_tokens = self.token_counter.count(text, &model);
+
         // Create OpenAI-compatible response envelope
         let envelope = serde_json::json!({
             "id": format!("chatcmpl-{}-{:x}", now, request_hash),
             "object": "chat.completion",
             "created": now,
-            "model": req.model.unwrap_or(self.model_id.clone()),
+            "model": model,
             "choices": [{
                 "index": 0,
                 "message": {"role": "assistant", "content": text},
                 "finish_reason": "stop"
             }],
             "usage": {
-                "prompt_tokens": prompt.len() / 4, // Rough estimate
-                "completion_tokens": text.len() / 4, // Rough estimate
-                "total_tokens": (prompt.len() + text.len()) / 4 // Rough estimate
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens
             }
         });
 
         debug!("Successfully processed request hash {:x}", request_hash);
 
         // Return the response as an HTTP response
-        Ok((StatusCode::OK, Json(envelope)).into_response())
+        Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(envelope)).into_response(), response_time))
     }
 
     /// Perform a raw streaming request without buffering the upstream body
@@ -375,6 +626,13 @@ impl LightLLMAdapter {
         &self,
         req: ChatCompletionRequest,
     ) -> Result<reqwest::Response, ProxyError> {
+        if req.n.is_some_and(|n| n > 1) {
+            return Err(ProxyError::BadRequest(
+                "n > 1 is not supported for streaming requests to the lightllm backend".to_string(),
+            ));
+        }
+
+        let req = Self::with_json_instruction(req);
         let request_hash = Self::calculate_request_hash(&req);
         AdapterUtils::log_request(
             "lightllm",
@@ -385,7 +643,7 @@ impl LightLLMAdapter {
         let start_time = Instant::now();
 
         let is_openai_compatible = self.base.contains("/v1") || req.stream.unwrap_or(false);
-        let prompt = Self::messages_to_prompt(&req.messages);
+        let prompt = Self::messages_to_prompt(&req.messages, &self.prompt_template);
 
         let (url, payload) = if is_openai_compatible {
             let url = if self.base.ends_with("/v1") {
@@ -413,19 +671,29 @@ impl LightLLMAdapter {
                     payload["frequency_penalty"] = serde_json::Value::from(frequency_penalty);
                 }
             }
+            if let Some(top_k) = req.top_k {
+                payload["top_k"] = serde_json::Value::from(top_k);
+            }
 
             (url, payload)
         } else {
             let url = format!("{}/generate", self.base);
-            let payload = serde_json::json!({
+            let mut payload = serde_json::json!({
                 "prompt": prompt,
                 "max_new_tokens": req.max_tokens.unwrap_or(256),
                 "temperature": req.temperature.unwrap_or(1.0),
                 "top_p": req.top_p.unwrap_or(1.0),
-                "presence_penalty": req.presence_penalty.unwrap_or(0.0),
-                "frequency_penalty": req.frequency_penalty.unwrap_or(0.0),
                 "stream": true,
             });
+            if let Some(presence_penalty) = req.presence_penalty {
+                payload["presence_penalty"] = serde_json::Value::from(presence_penalty);
+            }
+            if let Some(frequency_penalty) = req.frequency_penalty {
+                payload["frequency_penalty"] = serde_json::Value::from(frequency_penalty);
+            }
+            if let Some(top_k) = req.top_k {
+                payload["top_k"] = serde_json::Value::from(top_k);
+            }
 
             (url, payload)
         };
@@ -436,33 +704,29 @@ impl LightLLMAdapter {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
 
-        let resp = request_builder.send().await.map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
             debug!(
                 "Streaming HTTP request failed for hash {:x}: {}",
                 request_hash, e
             );
-            ProxyError::from(e)
         })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         if !status.is_success() {
-            let response_bytes = resp.bytes().await.map_err(|e| {
+            let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
                 debug!(
                     "Failed to read streaming response body for hash {:x}: {}",
                     request_hash, e
                 );
-                ProxyError::Upstream(format!("error reading response body: {}", e))
             })?;
 
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!(
                 "Streaming backend returned error status {} for hash {:x}: {}",
                 status, request_hash, error_text
             );
-            return Err(ProxyError::Upstream(format!(
-                "HTTP {}: {}",
-                status, error_text
-            )));
+            return Err(ProxyError::upstream_status(status, &headers, error_text.to_string()));
         }
 
         let handshake_time = start_time.elapsed().as_millis() as u64;
@@ -539,9 +803,11 @@ mod tests {
             function_call: None,
             tool_call_id: None,
             tool_calls: None,
+            audio: None,
+            reasoning_content: None,
         }];
 
-        let prompt = LightLLMAdapter::messages_to_prompt(&messages);
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &LightLLMPromptTemplate::default());
         assert_eq!(prompt, "<|user|>\nHello, how are you?\n<|assistant|> ");
     }
 
@@ -555,6 +821,8 @@ mod tests {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
             Message {
                 role: "user".to_string(),
@@ -563,10 +831,12 @@ mod tests {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
         ];
 
-        let prompt = LightLLMAdapter::messages_to_prompt(&messages);
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &LightLLMPromptTemplate::default());
         assert_eq!(
             prompt,
             "<|system|>\nYou are a helpful assistant.\n<|user|>\nWhat is 2+2?\n<|assistant|> "
@@ -583,6 +853,8 @@ mod tests {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
             Message {
                 role: "assistant".to_string(),
@@ -591,6 +863,8 @@ mod tests {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
             Message {
                 role: "user".to_string(),
@@ -599,10 +873,12 @@ mod tests {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
         ];
 
-        let prompt = LightLLMAdapter::messages_to_prompt(&messages);
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &LightLLMPromptTemplate::default());
         let expected = "<|user|>\nHello!\n<|assistant|>\nHi there! How can I help you?\n<|user|>\nWhat's the weather like?\n<|assistant|> ";
         assert_eq!(prompt, expected);
     }
@@ -610,12 +886,12 @@ mod tests {
     #[test]
     fn test_messages_to_prompt_empty_messages() {
         let messages = vec![];
-        let prompt = LightLLMAdapter::messages_to_prompt(&messages);
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &LightLLMPromptTemplate::default());
         assert_eq!(prompt, "<|assistant|> ");
     }
 
     #[test]
-    fn test_messages_to_prompt_tool_role_ignored() {
+    fn test_messages_to_prompt_tool_role_rendered() {
         let messages = vec![
             Message {
                 role: "user".to_string(),
@@ -624,19 +900,184 @@ mod tests {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
             Message {
                 role: "tool".to_string(),
-                content: Some("This should be ignored".to_string()),
+                content: Some("22°C and sunny".to_string()),
+                name: Some("get_weather".to_string()),
+                function_call: None,
+                tool_call_id: None,
+                tool_calls: None,
+                audio: None,
+                reasoning_content: None,
+            },
+        ];
+
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &LightLLMPromptTemplate::default());
+        assert_eq!(prompt, "<|user|>\nHello!\n<|tool|>\nget_weather: 22°C and sunny\n<|assistant|> ");
+
+        let template = LightLLMPromptTemplate {
+            tool: "[{name}] {content}\n".to_string(),
+            ..LightLLMPromptTemplate::default()
+        };
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &template);
+        assert_eq!(prompt, "<|user|>\nHello!\n[get_weather] 22°C and sunny\n<|assistant|> ");
+    }
+
+    #[test]
+    fn test_messages_to_prompt_assistant_tool_calls() {
+        use crate::schemas::{FunctionCall, ToolCall};
+
+        let messages = vec![
+            Message::user("Weather in Paris?".to_string()),
+            Message {
+                role: "assistant".to_string(),
+                content: None,
                 name: None,
                 function_call: None,
                 tool_call_id: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    tool_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: "get_weather".to_string(),
+                        arguments: r#"{"city":"Paris"}"#.to_string(),
+                    },
+                }]),
+                audio: None,
+                reasoning_content: None,
+            },
+            Message {
+                role: "tool".to_string(),
+                content: Some("22°C".to_string()),
+                name: None,
+                function_call: None,
+                tool_call_id: Some("call_1".to_string()),
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
         ];
 
-        let prompt = LightLLMAdapter::messages_to_prompt(&messages);
-        assert_eq!(prompt, "<|user|>\nHello!\n<|assistant|> ");
+        let prompt = LightLLMAdapter::messages_to_prompt(&messages, &LightLLMPromptTemplate::default());
+        assert_eq!(
+            prompt,
+            "<|user|>\nWeather in Paris?\n<|assistant|>\n<|tool_call|>\nget_weather: {\"city\":\"Paris\"}\n<|tool|>\nget_weather: 22°C\n<|assistant|> "
+        );
+    }
+
+    #[tokio::test]
+    async fn test_top_k_forwarded_to_lightllm() {
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"generated_text": ["4"]})))
+            .mount(&server)
+            .await;
+        let adapter = LightLLMAdapter::new(
+            server.uri(),
+            "llama".to_string(),
+            None,
+            Client::new(),
+            LightLLMPromptTemplate::default(),
+        );
+        let req: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "What is 2+2?"}],
+            "top_k": 40
+        }))
+        .unwrap();
+
+        adapter.chat_completions_http(req).await.unwrap();
+
+        let payload: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(payload["top_k"], 40);
+    }
+
+    #[tokio::test]
+    async fn test_json_mode_instruction_added_to_system_prompt() {
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"generated_text": ["{}"]})))
+            .mount(&server)
+            .await;
+        let adapter = LightLLMAdapter::new(
+            server.uri(),
+            "llama".to_string(),
+            None,
+            Client::new(),
+            LightLLMPromptTemplate::default(),
+        );
+        let req: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "system", "content": "You are terse."}, {"role": "user", "content": "Weather?"}],
+            "response_format": {"type": "json_schema", "json_schema": {"name": "weather", "schema": {"type": "object"}}}
+        }))
+        .unwrap();
+
+        adapter.chat_completions_http(req).await.unwrap();
+
+        let payload: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        let prompt = payload["prompt"].as_str().unwrap();
+        assert!(prompt.starts_with(&format!(
+            "<|system|>\nYou are terse.\n\n{} The JSON must match this JSON Schema: {{\"type\":\"object\"}}\n",
+            JSON_MODE_INSTRUCTION
+        )));
+        assert_eq!(prompt.matches("<|system|>").count(), 1);
+
+        // Without a system message the instruction becomes one
+        let req = ChatCompletionRequest {
+            messages: vec![Message::user("Weather?".to_string())],
+            response_format: Some(crate::schemas::ResponseFormat::JsonObject),
+            ..Default::default()
+        };
+        let req = LightLLMAdapter::with_json_instruction(req);
+        assert_eq!(req.messages[0].role, "system");
+        assert_eq!(req.messages[0].content.as_deref(), Some(JSON_MODE_INSTRUCTION));
+    }
+
+    #[tokio::test]
+    async fn test_n_choices_fanned_out_to_parallel_generate_calls() {
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"text": "Four"})))
+            .mount(&server)
+            .await;
+        let adapter = LightLLMAdapter::new(
+            server.uri(),
+            "llama".to_string(),
+            None,
+            Client::new(),
+            LightLLMPromptTemplate::default(),
+        );
+        let req: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "What is 2+2?"}],
+            "n": 3
+        }))
+        .unwrap();
+
+        let response = adapter.chat_completions_http(req).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let completion: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+        let choices = completion["choices"].as_array().unwrap();
+        let indices: Vec<_> = choices.iter().map(|choice| choice["index"].as_u64().unwrap()).collect();
+        assert_eq!(indices, [0, 1, 2]);
+        assert!(choices.iter().all(|choice| choice["message"]["content"] == "Four"));
+        let usage = &completion["usage"];
+        assert_eq!(
+            usage["total_tokens"].as_u64().unwrap(),
+            usage["prompt_tokens"].as_u64().unwrap() + usage["completion_tokens"].as_u64().unwrap()
+        );
     }
 
     #[test]
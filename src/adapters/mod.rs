@@ -12,12 +12,13 @@
 //! - **AWS Bedrock**: Amazon Web Services Bedrock
 //! - **OpenAI**: Direct OpenAI API integration
 //! - **Custom**: Any OpenAI-compatible endpoint
+//! - **Template**: Any JSON API described by a request template file
 //! - **Direct**: Embedded integration mode
 
 use crate::{
     config::Config,
     error::ProxyError,
-    schemas::ChatCompletionRequest,
+    schemas::{ChatCompletionRequest, ChatCompletionResponse},
 };
 use crate::core::http_client::HttpClientBuilder;
 #[cfg(feature = "server")]
@@ -33,19 +34,25 @@ pub mod azure;
 pub mod aws;
 pub mod vllm;
 pub mod custom;
+pub mod template;
 pub mod direct;
+#[cfg(feature = "request-signing")]
+pub mod signing;
 
 // Re-export adapters for convenience
-pub use lightllm::{LightLLMAdapter, Role};
+pub use lightllm::{LightLLMAdapter, LightLLMPromptTemplate, Role};
 pub use openai::OpenAIAdapter;
 pub use azure::AzureOpenAIAdapter;
-pub use aws::AWSBedrockAdapter;
+pub use aws::{AWSBedrockAdapter, RegionEndpoint};
 pub use vllm::VLLMAdapter;
 pub use custom::CustomAdapter;
+pub use template::{RequestTemplate, TemplateAdapter};
 pub use direct::DirectAdapter;
+#[cfg(feature = "request-signing")]
+pub use signing::RequestSigner;
 
 // Re-export base functionality
-pub use base::{AdapterTrait, AdapterConfig, AdapterUtils};
+pub use base::{AdapterTrait, AdapterConfig, AdapterUtils, UpstreamDuration};
 
 /// # Universal LLM Adapter Enum
 ///
@@ -65,73 +72,182 @@ pub enum Adapter {
     OpenAI(OpenAIAdapter),
     /// Custom OpenAI-compatible adapter - Generic endpoint support
     Custom(CustomAdapter),
+    /// Templated adapter - Arbitrary JSON APIs described by a template file
+    Template(TemplateAdapter),
     /// Direct integration mode - bypasses HTTP for maximum performance
     Direct(DirectAdapter),
 }
 
 impl Adapter {
-    /// Factory method for creating adapters based on configuration
+    /// Factory method for creating adapters based on configuration.
+    ///
+    /// Panics if the configuration cannot produce an adapter, which
+    /// `Config::validate` rules out; use [`Adapter::try_from_config`] for
+    /// configurations that have not been validated.
     pub fn from_config(cfg: &Config) -> Self {
+        Self::try_from_config(cfg).unwrap_or_else(|err| panic!("Invalid adapter configuration: {}", err))
+    }
+
+    /// Factory method for creating adapters, failing with
+    /// [`ProxyError::BadRequest`] when the configuration is unusable
+    pub fn try_from_config(cfg: &Config) -> Result<Self, ProxyError> {
         // Create HTTP client using our centralized factory
         let client = HttpClientBuilder::from_config(cfg)
             .build()
             .unwrap_or_else(|_| HttpClientBuilder::new().build().unwrap());
+        let first_byte_timeout = cfg.first_byte_timeout();
 
-        // Intelligent backend detection based on URL patterns
-        if cfg.backend_url.contains("azure.com") || cfg.backend_url.contains("azure.openai") {
-            // Azure OpenAI Service detected
-            Self::AzureOpenAI(AzureOpenAIAdapter::new(
+        match Self::backend_type(cfg) {
+            "azure" => Ok(Self::AzureOpenAI(AzureOpenAIAdapter::new(
                 cfg.backend_url.clone(),
                 cfg.model_id.clone(),
                 cfg.backend_token.clone(),
+                Some(cfg.azure_api_version.clone()).filter(|version| !version.is_empty()),
                 client,
-            ))
-        } else if cfg.backend_url.contains("bedrock") || cfg.backend_url.contains("amazonaws.com") {
-            // AWS Bedrock detected
-            Self::AWSBedrock(AWSBedrockAdapter::new(
+            )?
+            .with_deployment_map(cfg.azure_deployments())
+            .with_content_filter_translation(cfg.azure_content_filter_as_completion)
+            .with_reasoning_models(cfg.reasoning_model_prefixes())
+            .with_developer_role(cfg.reasoning_developer_role)
+            .with_first_byte_timeout(first_byte_timeout))),
+            "aws" => Ok(Self::AWSBedrock(AWSBedrockAdapter::new(
                 cfg.backend_url.clone(),
                 cfg.model_id.clone(),
                 cfg.backend_token.clone(),
+                cfg.aws_region.clone(),
                 client,
-            ))
-        } else if cfg.backend_url.contains("vllm") {
-            // vLLM server detected
-            Self::VLLM(VLLMAdapter::new(
+            )?
+            .with_failover_regions(cfg.failover_regions().into_iter().map(RegionEndpoint::new).collect())
+            .with_first_byte_timeout(first_byte_timeout))),
+            "vllm" => Ok(Self::VLLM(VLLMAdapter::new(
                 cfg.backend_url.clone(),
                 cfg.model_id.clone(),
                 cfg.backend_token.clone(),
                 client,
-            ))
-        } else if cfg.backend_url.contains("/v1") || cfg.backend_url.contains("openai.com") {
-            // OpenAI API or compatible endpoint detected
-            Self::OpenAI(OpenAIAdapter::new(
+            ).with_first_byte_timeout(first_byte_timeout))),
+            "openai" => Ok(Self::OpenAI(OpenAIAdapter::new(
                 cfg.backend_url.clone(),
                 cfg.model_id.clone(),
                 cfg.backend_token.clone(),
                 client,
-            ))
-        } else if cfg.backend_url == "direct" {
-            // Direct mode for embedded integration
-            Self::Direct(DirectAdapter::new(
+            )
+            .with_reasoning_models(cfg.reasoning_model_prefixes())
+            .with_developer_role(cfg.reasoning_developer_role)
+            .with_first_byte_timeout(first_byte_timeout))),
+            "template" => {
+                let path = cfg.request_template_file.as_deref().ok_or_else(|| {
+                    ProxyError::BadRequest("The template backend requires a request template file".to_string())
+                })?;
+                Ok(Self::Template(TemplateAdapter::new(
+                    cfg.backend_url.clone(),
+                    cfg.model_id.clone(),
+                    cfg.backend_token.clone(),
+                    RequestTemplate::from_file(path)?,
+                    client,
+                )?
+                .with_first_byte_timeout(first_byte_timeout)))
+            }
+            "direct" => Ok(Self::Direct(DirectAdapter::new(
                 cfg.model_id.clone(),
                 cfg.backend_token.clone(),
-            ))
-        } else if cfg.backend_url.contains("lightllm") || cfg.backend_url.contains("localhost") {
-            // LightLLM server detected
-            Self::LightLLM(LightLLMAdapter::new(
+            ))),
+            "lightllm" => Ok(Self::LightLLM(LightLLMAdapter::new(
                 cfg.backend_url.clone(),
                 cfg.model_id.clone(),
                 cfg.backend_token.clone(),
                 client,
-            ))
+                match &cfg.lightllm_prompt_template_file {
+                    Some(path) => LightLLMPromptTemplate::from_file(path)?,
+                    None => LightLLMPromptTemplate::default(),
+                },
+            )
+            .with_first_byte_timeout(first_byte_timeout)
+            .with_token_counter(crate::core::tokens::from_config(cfg)))),
+            _ => {
+                let adapter = CustomAdapter::new(
+                    cfg.backend_url.clone(),
+                    cfg.model_id.clone(),
+                    cfg.backend_token.clone(),
+                    client,
+                )
+                .with_first_byte_timeout(first_byte_timeout);
+                #[cfg(feature = "request-signing")]
+                let adapter = adapter.with_signer(RequestSigner::from_config(cfg));
+                Ok(Self::Custom(adapter))
+            }
+        }
+    }
+
+    /// Backend type an adapter is built for: `force_adapter` unless it is
+    /// "auto", then `backend_type` unless it is empty or "auto", and otherwise
+    /// the type detected from `backend_url`
+    pub fn backend_type(cfg: &Config) -> &str {
+        [cfg.force_adapter.as_str(), cfg.backend_type.as_str()]
+            .into_iter()
+            .find(|backend_type| !backend_type.is_empty() && *backend_type != "auto")
+            .unwrap_or_else(|| Self::detect_backend_type(&cfg.backend_url))
+    }
+
+    /// Guess the backend type from keywords in its URL.
+    ///
+    /// This is only a fallback for configurations without an explicit
+    /// `backend_type`: a server whose URL carries none of the keywords is
+    /// treated as a generic OpenAI-compatible ("custom") endpoint, and any
+    /// "localhost" URL is assumed to be LightLLM.
+    pub fn detect_backend_type(backend_url: &str) -> &'static str {
+        if backend_url.contains("azure.com") || backend_url.contains("azure.openai") {
+            "azure"
+        } else if backend_url.contains("bedrock") || backend_url.contains("amazonaws.com") {
+            "aws"
+        } else if backend_url.contains("vllm") {
+            "vllm"
+        } else if backend_url.contains("/v1") || backend_url.contains("openai.com") {
+            "openai"
+        } else if backend_url == "direct" {
+            "direct"
+        } else if backend_url.contains("lightllm") || backend_url.contains("localhost") {
+            "lightllm"
         } else {
-            // Generic OpenAI-compatible endpoint
-            Self::Custom(CustomAdapter::new(
-                cfg.backend_url.clone(),
-                cfg.model_id.clone(),
-                cfg.backend_token.clone(),
-                client,
-            ))
+            "custom"
+        }
+    }
+
+    /// Copy of this adapter that authenticates with `token` instead of the
+    /// configured backend credential.
+    ///
+    /// AWS Bedrock signs with access keys and direct mode has no upstream, so
+    /// neither can use the client's credential: rather than silently serving
+    /// the request with the operator's credentials, both fail with
+    /// [`ProxyError::BadRequest`].
+    pub fn with_backend_token(&self, token: String) -> Result<Self, ProxyError> {
+        Ok(match self {
+            Self::LightLLM(adapter) => Self::LightLLM(adapter.clone().with_token(token)),
+            Self::VLLM(adapter) => Self::VLLM(adapter.clone().with_token(token)),
+            Self::AzureOpenAI(adapter) => Self::AzureOpenAI(adapter.clone().with_token(token)),
+            Self::OpenAI(adapter) => Self::OpenAI(adapter.clone().with_token(token)),
+            Self::Custom(adapter) => Self::Custom(adapter.clone().with_token(token)),
+            Self::Template(adapter) => Self::Template(adapter.clone().with_token(token)),
+            Self::AWSBedrock(_) | Self::Direct(_) => {
+                return Err(ProxyError::BadRequest(format!(
+                    "The {} backend does not accept a client-supplied credential",
+                    self.name()
+                )))
+            }
+        })
+    }
+
+    /// Process a chat completion request and return the parsed upstream
+    /// response, for callers that work with completions rather than HTTP
+    pub async fn chat_completion_response(&self, req: ChatCompletionRequest) -> Result<ChatCompletionResponse, ProxyError> {
+        match self {
+            Self::LightLLM(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::VLLM(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::AzureOpenAI(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::AWSBedrock(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::OpenAI(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::Custom(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::Template(adapter) => AdapterTrait::chat_completions(adapter, req).await,
+            Self::Direct(adapter) => AdapterTrait::chat_completions(adapter, req).await,
         }
     }
 
@@ -145,6 +261,7 @@ impl Adapter {
             Self::AWSBedrock(adapter) => adapter.chat_completions_http(req).await,
             Self::OpenAI(adapter) => adapter.chat_completions_http(req).await,
             Self::Custom(adapter) => adapter.chat_completions_http(req).await,
+            Self::Template(adapter) => adapter.chat_completions_http(req).await,
             Self::Direct(adapter) => {
                 // Convert ChatCompletionResponse to Response for direct adapter
                 let chat_response = adapter.chat_completions(req).await?;
@@ -162,6 +279,42 @@ impl Adapter {
         }
     }
 
+    /// List the models the backend serves, in OpenAI's `{object: "list", data}` format.
+    ///
+    /// OpenAI-compatible backends (OpenAI, vLLM, custom) are asked for their
+    /// own `/models`; the others serve only the configured model.
+    #[cfg(feature = "server")]
+    pub async fn list_models(&self) -> Result<serde_json::Value, ProxyError> {
+        match self {
+            Self::OpenAI(adapter) => adapter.models(None).await,
+            Self::VLLM(adapter) => adapter.models(None).await,
+            Self::Custom(adapter) => adapter.models(None).await,
+            _ => Ok(serde_json::json!({ "object": "list", "data": [self.model_card()] })),
+        }
+    }
+
+    /// Metadata of the model `id`, or `None` when the backend does not serve it
+    #[cfg(feature = "server")]
+    pub async fn retrieve_model(&self, id: &str) -> Result<Option<serde_json::Value>, ProxyError> {
+        match self {
+            Self::OpenAI(adapter) => adapter.models(Some(id)).await.map(Some),
+            Self::VLLM(adapter) => adapter.models(Some(id)).await.map(Some),
+            Self::Custom(adapter) => adapter.models(Some(id)).await.map(Some),
+            _ => Ok((id == self.model_id()).then(|| self.model_card())),
+        }
+    }
+
+    /// OpenAI model object describing the configured model
+    #[cfg(feature = "server")]
+    fn model_card(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.model_id(),
+            "object": "model",
+            "created": 0,
+            "owned_by": self.name(),
+        })
+    }
+
     /// Check if adapter supports streaming
     pub fn supports_streaming(&self) -> bool {
         match self {
@@ -171,10 +324,36 @@ impl Adapter {
             Self::AWSBedrock(_) => true,    // AWS Bedrock supports streaming
             Self::OpenAI(_) => true,        // OpenAI API supports streaming
             Self::Custom(_) => true,        // Assume custom endpoints support streaming
+            Self::Template(_) => false,     // Templates describe a single JSON response
             Self::Direct(_) => true,        // Direct mode supports streaming
         }
     }
 
+    /// Check if adapter can forward audio output requests (`modalities`/`audio`)
+    pub fn supports_audio(&self) -> bool {
+        matches!(self, Self::OpenAI(_) | Self::AzureOpenAI(_))
+    }
+
+    /// Check if adapter continues a trailing assistant message natively
+    /// (Anthropic-format prompts) instead of starting a new turn
+    pub fn supports_prefill(&self) -> bool {
+        matches!(self, Self::AWSBedrock(_))
+    }
+
+    /// Check if adapter returns `n` > 1 choices for one request: forwarded to
+    /// OpenAI-compatible backends, fanned out into parallel calls for LightLLM
+    pub fn supports_n(&self) -> bool {
+        matches!(
+            self,
+            Self::OpenAI(_) | Self::VLLM(_) | Self::AzureOpenAI(_) | Self::Custom(_) | Self::LightLLM(_)
+        )
+    }
+
+    /// Check if adapter can forward the fill-in-the-middle `suffix` parameter
+    pub fn supports_suffix(&self) -> bool {
+        matches!(self, Self::VLLM(_) | Self::LightLLM(_))
+    }
+
     /// Get adapter name for logging and metrics
     pub fn name(&self) -> &'static str {
         match self {
@@ -184,6 +363,7 @@ impl Adapter {
             Self::AWSBedrock(adapter) => adapter.name(),
             Self::OpenAI(adapter) => adapter.name(),
             Self::Custom(adapter) => adapter.name(),
+            Self::Template(adapter) => AdapterTrait::name(adapter),
             Self::Direct(adapter) => adapter.name(),
         }
     }
@@ -197,6 +377,7 @@ impl Adapter {
             Self::AWSBedrock(adapter) => adapter.base_url(),
             Self::OpenAI(adapter) => adapter.base_url(),
             Self::Custom(adapter) => adapter.base_url(),
+            Self::Template(adapter) => AdapterTrait::base_url(adapter),
             Self::Direct(adapter) => adapter.base_url(),
         }
     }
@@ -210,6 +391,7 @@ impl Adapter {
             Self::AWSBedrock(adapter) => adapter.model_id(),
             Self::OpenAI(adapter) => adapter.model_id(),
             Self::Custom(adapter) => adapter.model_id(),
+            Self::Template(adapter) => AdapterTrait::model_id(adapter),
             Self::Direct(adapter) => adapter.model_id(),
         }
     }
@@ -223,6 +405,7 @@ impl Adapter {
             Self::AWSBedrock(adapter) => adapter.has_auth(),
             Self::OpenAI(adapter) => adapter.has_auth(),
             Self::Custom(adapter) => adapter.has_auth(),
+            Self::Template(adapter) => AdapterTrait::has_auth(adapter),
             Self::Direct(adapter) => adapter.has_auth(),
         }
     }
@@ -292,6 +475,58 @@ mod tests {
         assert_eq!(adapter.name(), "custom");
     }
 
+    #[test]
+    fn test_explicit_backend_type_overrides_url_detection() {
+        let cases = [
+            ("lightllm", "lightllm"),
+            ("vllm", "vllm"),
+            ("openai", "openai"),
+            ("azure", "azure"),
+            ("aws", "aws"),
+            ("custom", "custom"),
+            ("direct", "direct"),
+        ];
+
+        for (backend_type, name) in cases {
+            let mut config = Config::for_test();
+            config.backend_url = "https://bedrock-runtime.eu-central-1.amazonaws.com".to_string();
+            config.backend_type = backend_type.to_string();
+            if backend_type == "aws" {
+                // Otherwise detected from the URL anyway
+                config.backend_url = "http://inference-7:8000".to_string();
+                config.aws_region = Some("eu-central-1".to_string());
+            }
+
+            let adapter = Adapter::from_config(&config);
+            assert_eq!(adapter.name(), name, "backend_type {}", backend_type);
+        }
+    }
+
+    #[test]
+    fn test_vllm_at_plain_hostname_with_explicit_backend_type() {
+        let mut config = Config::for_test();
+        config.backend_url = "http://inference-7:8000".to_string();
+        assert!(matches!(Adapter::from_config(&config), Adapter::Custom(_)));
+
+        config.backend_type = "vllm".to_string();
+        assert!(matches!(Adapter::from_config(&config), Adapter::VLLM(_)));
+    }
+
+    #[test]
+    fn test_force_adapter_takes_precedence_over_backend_type() {
+        let mut config = Config::for_test();
+        config.backend_url = "http://localhost:8000".to_string();
+        config.backend_type = "vllm".to_string();
+        config.force_adapter = "openai".to_string();
+        assert!(matches!(Adapter::from_config(&config), Adapter::OpenAI(_)));
+
+        config.force_adapter = "auto".to_string();
+        assert!(matches!(Adapter::from_config(&config), Adapter::VLLM(_)));
+
+        config.backend_type = String::new();
+        assert!(matches!(Adapter::from_config(&config), Adapter::LightLLM(_)));
+    }
+
     #[test]
     fn test_streaming_support() {
         let mut config = Config::for_test();
@@ -299,13 +534,117 @@ mod tests {
         config.backend_url = "http://localhost:8000".to_string();
         let lightllm_adapter = Adapter::from_config(&config);
         assert!(lightllm_adapter.supports_streaming());
+        assert!(lightllm_adapter.supports_n());
 
         config.backend_url = "https://api.openai.com/v1".to_string();
         let openai_adapter = Adapter::from_config(&config);
         assert!(openai_adapter.supports_streaming());
+        assert!(openai_adapter.supports_n());
 
         config.backend_url = "direct".to_string();
         let direct_adapter = Adapter::from_config(&config);
         assert!(direct_adapter.supports_streaming());
+        assert!(!direct_adapter.supports_n());
+    }
+
+    #[tokio::test]
+    async fn test_first_byte_timeout_fires_before_total_timeout() {
+        // Accepts connections but never sends a response
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let mut config = Config::for_test();
+        config.backend_url = format!("http://{}/v1", addr);
+        config.http_client_timeout = 30;
+        config.first_byte_timeout = 1;
+        let adapter = Adapter::from_config(&config);
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}]
+        }))
+        .unwrap();
+
+        let started = std::time::Instant::now();
+        let result = adapter.chat_completions(request).await;
+
+        assert!(matches!(
+            result,
+            Err(ProxyError::Transport { kind: crate::error::TransportErrorKind::Timeout, .. })
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_lightllm_prompt_template_from_config() {
+        use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"generated_text": ["4"]})))
+            .mount(&server)
+            .await;
+        let template_file = std::env::temp_dir().join(format!("lightllm-template-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&template_file, r#"{"user": "USER: ", "generation": "BOT: "}"#).unwrap();
+        let mut config = Config::for_test();
+        config.backend_url = server.uri();
+        config.backend_type = "lightllm".to_string();
+        config.lightllm_prompt_template_file = Some(template_file.display().to_string());
+        assert!(config.validate().is_ok());
+        let adapter = Adapter::from_config(&config);
+        std::fs::remove_file(&template_file).unwrap();
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "What is 2+2?"}]
+        }))
+        .unwrap();
+
+        adapter.chat_completions(request).await.unwrap();
+
+        let payload: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        let prompt = payload["prompt"].as_str().unwrap();
+        assert!(prompt.starts_with("USER: What is 2+2?"), "{}", prompt);
+        assert!(prompt.ends_with("BOT: "), "{}", prompt);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_response_keeps_upstream_body() {
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-upstream",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": "gpt-test",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Real answer"},
+                    "finish_reason": "length"
+                }],
+                "usage": {"prompt_tokens": 7, "completion_tokens": 3, "total_tokens": 10}
+            })))
+            .mount(&server)
+            .await;
+
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let adapter = Adapter::from_config(&config);
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}]
+        }))
+        .unwrap();
+
+        let response = adapter.chat_completion_response(request).await.unwrap();
+
+        assert_eq!(response.id, "chatcmpl-upstream");
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Real answer"));
+        assert_eq!(response.choices[0].finish_reason, "length");
+        assert_eq!(response.usage.as_ref().map(|usage| usage.total_tokens), Some(10));
+    }
+}
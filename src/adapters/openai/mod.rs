@@ -11,7 +11,7 @@
 //! - Bearer token authentication
 
 use crate::{
-    adapters::base::{AdapterTrait, AdapterUtils},
+    adapters::base::{AdapterTrait, AdapterUtils, DEFAULT_REASONING_MODELS},
     error::ProxyError,
     schemas::{ChatCompletionRequest, ChatCompletionResponse},
 };
@@ -25,7 +25,7 @@ use reqwest::Client;
 use tracing::debug;
 
 #[cfg(feature = "server")]
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// # OpenAI Adapter
 ///
@@ -42,6 +42,12 @@ pub struct OpenAIAdapter {
     model_id: String,
     /// Optional authentication token
     token: Option<String>,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
+    /// Model name prefixes of models that accept `reasoning_effort`
+    reasoning_models: Vec<String>,
+    /// Send `system` messages to reasoning models as `developer` messages
+    developer_role: bool,
 }
 
 impl OpenAIAdapter {
@@ -52,9 +58,59 @@ impl OpenAIAdapter {
             client,
             model_id,
             token,
+            first_byte_timeout: None,
+            reasoning_models: DEFAULT_REASONING_MODELS.iter().map(|prefix| prefix.to_string()).collect(),
+            developer_role: true,
         }
     }
 
+    /// Authenticate with `token` instead of the configured credential
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Forward `reasoning_effort` only for models starting with one of `prefixes`
+    pub fn with_reasoning_models(mut self, prefixes: Vec<String>) -> Self {
+        self.reasoning_models = prefixes;
+        self
+    }
+
+    /// Whether to rename `system` messages to `developer` for reasoning models
+    pub fn with_developer_role(mut self, enabled: bool) -> Self {
+        self.developer_role = enabled;
+        self
+    }
+
+    /// Drop parameters the requested model does not accept
+    fn strip_unsupported(&self, req: &mut ChatCompletionRequest) {
+        // OpenAI rejects parameters it does not know, such as top_k
+        req.top_k = None;
+        let model = AdapterUtils::extract_model(req, &self.model_id);
+        if !AdapterUtils::is_reasoning_model(&model, &self.reasoning_models) {
+            req.reasoning_effort = None;
+        } else if self.developer_role {
+            AdapterUtils::use_developer_role(req);
+        }
+    }
+
+    /// Fetch the backend's model listing, or one model's metadata when `id` is given
+    #[cfg(feature = "server")]
+    pub async fn models(&self, id: Option<&str>) -> Result<serde_json::Value, ProxyError> {
+        let url = format!("{}/models{}", self.base, id.map(|id| format!("/{}", id)).unwrap_or_default());
+        let mut request_builder = self.client.get(url);
+        if let Some(token) = &self.token {
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
+        }
+        AdapterUtils::fetch_models(request_builder).await
+    }
+
     /// Get the model ID for this adapter
     pub fn model_id(&self) -> &str {
         &self.model_id
@@ -64,8 +120,9 @@ impl OpenAIAdapter {
     #[cfg(feature = "server")]
     pub async fn stream_chat_completions_raw(
         &self,
-        req: ChatCompletionRequest,
+        mut req: ChatCompletionRequest,
     ) -> Result<reqwest::Response, ProxyError> {
+        self.strip_unsupported(&mut req);
         let model_name = AdapterUtils::extract_model(&req, &self.model_id);
         AdapterUtils::log_request("openai", &model_name, req.messages.len());
 
@@ -78,24 +135,20 @@ impl OpenAIAdapter {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
         }
 
-        let resp = request_builder.send().await.map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
             debug!("OpenAI streaming request failed: {}", e);
-            ProxyError::Upstream(e.to_string())
         })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         if !status.is_success() {
-            let response_bytes = resp.bytes().await.map_err(|e| {
+            let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
                 debug!("Failed to read OpenAI streaming error body: {}", e);
-                ProxyError::Upstream(format!("error reading response body: {}", e))
             })?;
 
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!("OpenAI streaming error response: {}", error_text);
-            return Err(ProxyError::Upstream(format!(
-                "HTTP {}: {}",
-                status, error_text
-            )));
+            return Err(ProxyError::upstream_status(status, &headers, error_text.to_string()));
         }
 
         let handshake_time = start_time.elapsed().as_millis() as u64;
@@ -108,8 +161,9 @@ impl OpenAIAdapter {
     #[cfg(feature = "server")]
     pub async fn chat_completions_http(
         &self,
-        req: ChatCompletionRequest,
+        mut req: ChatCompletionRequest,
     ) -> Result<Response, ProxyError> {
+        self.strip_unsupported(&mut req);
         AdapterUtils::log_request(
             "openai",
             &AdapterUtils::extract_model(&req, &self.model_id),
@@ -130,18 +184,25 @@ impl OpenAIAdapter {
         }
 
         // Send the request and await the response
-        let resp = request_builder.send().await.map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
             debug!("OpenAI request failed: {}", e);
-            ProxyError::Upstream(e.to_string())
         })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         debug!("OpenAI response status: {}", status);
+        let content_type = AdapterUtils::content_type(&resp);
+
+        // Streamed bodies are forwarded as they arrive; only errors are buffered
+        if status.is_success() && req.stream.unwrap_or(false) {
+            let response_time = start_time.elapsed().as_millis() as u64;
+            AdapterUtils::log_response("openai", &AdapterUtils::extract_model(&req, &self.model_id), true, response_time);
+            return AdapterUtils::stream_passthrough(resp, response_time);
+        }
 
         // Use bytes() instead of text() to avoid unnecessary string conversion
-        let response_bytes = resp.bytes().await.map_err(|e| {
+        let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
             debug!("Failed to read OpenAI response body: {}", e);
-            ProxyError::Upstream(format!("error reading response body: {}", e))
         })?;
 
         let response_time = start_time.elapsed().as_millis() as u64;
@@ -154,37 +215,28 @@ impl OpenAIAdapter {
 
         // Check if the request was successful
         if !status.is_success() {
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!("OpenAI error response: {}", error_text);
-            return Err(ProxyError::Upstream(format!(
-                "HTTP {}: {}",
-                status, error_text
-            )));
+            return Err(ProxyError::upstream_status(status, &headers, error_text.to_string()));
         }
 
-        // If streaming was requested, just return the raw response body for the streaming adapter to handle
-        if req.stream.unwrap_or(false) {
-            let response = Response::builder()
-                .status(status)
-                .body(axum::body::Body::from(response_bytes))
-                .map_err(|e| ProxyError::Internal(format!("Failed to build response: {}", e)))?;
-            return Ok(response);
-        }
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
 
         // Parse JSON directly from bytes (zero-copy operation) for non-streaming responses
-        let json = serde_json::from_slice::<serde_json::Value>(&response_bytes).map_err(|e| {
+        let mut json = serde_json::from_slice::<serde_json::Value>(&response_bytes).map_err(|e| {
             debug!("Failed to parse OpenAI JSON response: {}", e);
             ProxyError::Upstream(format!(
                 "error decoding response body: {} (body: {})",
                 e,
-                String::from_utf8_lossy(&response_bytes)
+                AdapterUtils::describe_body(&response_bytes)
             ))
         })?;
 
         debug!("Successfully forwarded OpenAI request");
 
         // Return the response as-is (no format conversion needed)
-        Ok((StatusCode::OK, Json(json)).into_response())
+        AdapterUtils::normalize_finish_reasons(&mut json);
+        Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(json)).into_response(), response_time))
     }
 }
 
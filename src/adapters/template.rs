@@ -0,0 +1,465 @@
+//! # Template Adapter Module
+//!
+//! This module provides the Template adapter for JSON APIs whose request
+//! body matches no other adapter. Operators describe the API in a template
+//! file instead of writing an adapter:
+//!
+//! ```json
+//! {
+//!   "path": "/api/generate",
+//!   "body": {"input": {"dialog": "{messages}"}, "params": {"engine": "{model}", "length": "{max_tokens}"}},
+//!   "response_path": "$.result.outputs[0].text"
+//! }
+//! ```
+//!
+//! A string that is exactly one placeholder is replaced by the JSON value
+//! (`"{messages}"` becomes the message array, `"{max_tokens}"` a number or
+//! null); placeholders inside longer strings are replaced by their text.
+//! Available placeholders are `{messages}`, `{prompt}` (message contents
+//! joined by newlines), `{model}`, `{max_tokens}`, `{temperature}`, `{top_p}`
+//! and `{stop}`. The completion text is read from the response with the
+//! JSONPath in `response_path` (`$`, `.field`, `['field']` and `[index]`).
+
+use crate::{
+    adapters::base::{AdapterTrait, AdapterUtils},
+    error::ProxyError,
+    schemas::{ChatCompletionRequest, ChatCompletionResponse},
+};
+#[cfg(feature = "server")]
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::time::Duration;
+use tracing::debug;
+
+/// One step of a JSONPath
+#[derive(Clone, Debug, PartialEq)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+/// Parsed `response_path`, limited to field and index steps
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// Parse a path such as `$.choices[0]['text']`
+    pub fn parse(path: &str) -> Result<Self, ProxyError> {
+        let invalid = |reason: &str| ProxyError::BadRequest(format!("Invalid response path '{}': {}", path, reason));
+        let mut rest = path.strip_prefix('$').ok_or_else(|| invalid("must start with '$'"))?;
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after_dot) = rest.strip_prefix('.') {
+                let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+                if end == 0 {
+                    return Err(invalid("empty field name"));
+                }
+                segments.push(PathSegment::Field(after_dot[..end].to_string()));
+                rest = &after_dot[end..];
+            } else if let Some(after_bracket) = rest.strip_prefix('[') {
+                let end = after_bracket.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+                let selector = &after_bracket[..end];
+                let quoted = selector
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| selector.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                let segment = match quoted {
+                    Some(field) => PathSegment::Field(field.to_string()),
+                    None => PathSegment::Index(selector.parse().map_err(|_| invalid("expected an index or quoted field"))?),
+                };
+                segments.push(segment);
+                rest = &after_bracket[end + 1..];
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+
+        Ok(Self { source: path.to_string(), segments })
+    }
+
+    /// The value at this path, if present
+    pub fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments.iter().try_fold(value, |value, segment| match segment {
+            PathSegment::Field(field) => value.get(field),
+            PathSegment::Index(index) => value.get(index),
+        })
+    }
+}
+
+/// Contents of a request template file
+#[derive(Clone, Debug, Deserialize)]
+pub struct RequestTemplate {
+    /// Path appended to the backend URL
+    #[serde(default)]
+    pub path: String,
+    /// Request body with placeholders
+    pub body: Value,
+    /// JSONPath of the completion text in the response
+    pub response_path: String,
+}
+
+impl RequestTemplate {
+    /// Read a template file, failing with [`ProxyError::BadRequest`] when it
+    /// is missing or malformed
+    pub fn from_file(path: &str) -> Result<Self, ProxyError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ProxyError::BadRequest(format!("Cannot read request template '{}': {}", path, e)))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| ProxyError::BadRequest(format!("Invalid request template '{}': {}", path, e)))
+    }
+}
+
+/// Fill the placeholders in `template` from `vars`.
+///
+/// Each placeholder is filled once, left to right, so braces in substituted
+/// values (user prompts, say) are never read as placeholders.
+fn render(template: &Value, vars: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(text) => {
+            let whole = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')).and_then(|name| vars.get(name));
+            if let Some(value) = whole {
+                return value.clone();
+            }
+            let mut rendered = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find('{') {
+                rendered.push_str(&rest[..start]);
+                let after = &rest[start + 1..];
+                let placeholder = after
+                    .find('}')
+                    .and_then(|end| vars.get(&after[..end]).map(|value| (value, end)));
+                match placeholder {
+                    Some((value, end)) => {
+                        match value {
+                            Value::String(s) => rendered.push_str(s),
+                            Value::Null => {}
+                            other => rendered.push_str(&other.to_string()),
+                        }
+                        rest = &after[end + 1..];
+                    }
+                    None => {
+                        rendered.push('{');
+                        rest = after;
+                    }
+                }
+            }
+            rendered.push_str(rest);
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, vars)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// # Template Adapter
+///
+/// Adapter for arbitrary JSON APIs described by a [`RequestTemplate`].
+#[derive(Clone, Debug)]
+pub struct TemplateAdapter {
+    /// Base URL for the backend
+    base_url: String,
+    /// Model identifier
+    model_id: String,
+    /// Optional authentication token
+    token: Option<String>,
+    /// HTTP client with connection pooling
+    client: Client,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
+    /// Path appended to `base_url`
+    path: String,
+    /// Request body with placeholders
+    body: Value,
+    /// Where the completion text is in the response
+    response_path: JsonPath,
+}
+
+impl TemplateAdapter {
+    /// Create a new Template adapter instance, failing with
+    /// [`ProxyError::BadRequest`] when the response path is malformed
+    pub fn new(
+        base_url: String,
+        model_id: String,
+        token: Option<String>,
+        template: RequestTemplate,
+        client: Client,
+    ) -> Result<Self, ProxyError> {
+        Ok(Self {
+            base_url,
+            model_id,
+            token,
+            client,
+            first_byte_timeout: None,
+            path: template.path,
+            body: template.body,
+            response_path: JsonPath::parse(&template.response_path)?,
+        })
+    }
+
+    /// Authenticate with `token` instead of the configured credential
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Get base URL (public accessor)
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Get model ID (public accessor)
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// Render the template body for a request
+    fn request_body(&self, req: &ChatCompletionRequest) -> Result<Value, ProxyError> {
+        let messages = serde_json::to_value(&req.messages)
+            .map_err(|e| ProxyError::Serialization(format!("Failed to serialize messages: {}", e)))?;
+        let prompt = req
+            .messages
+            .iter()
+            .filter_map(|message| message.content.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut vars = Map::new();
+        vars.insert("messages".to_string(), messages);
+        vars.insert("prompt".to_string(), Value::from(prompt));
+        vars.insert("model".to_string(), Value::from(AdapterUtils::extract_model(req, &self.model_id)));
+        vars.insert("max_tokens".to_string(), req.max_tokens.map_or(Value::Null, Value::from));
+        vars.insert("temperature".to_string(), req.temperature.map_or(Value::Null, Value::from));
+        vars.insert("top_p".to_string(), req.top_p.map_or(Value::Null, Value::from));
+        vars.insert(
+            "stop".to_string(),
+            serde_json::to_value(&req.stop).map_err(|e| ProxyError::Serialization(format!("Failed to serialize stop: {}", e)))?,
+        );
+
+        Ok(render(&self.body, &vars))
+    }
+
+    /// Build the upstream request from the template
+    fn build_request(&self, req: &ChatCompletionRequest) -> Result<RequestBuilder, ProxyError> {
+        let url = format!("{}{}", self.base_url, self.path);
+        let mut request_builder = self.client.post(url).json(&self.request_body(req)?);
+
+        if let Some(token) = &self.token {
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
+        }
+
+        Ok(request_builder)
+    }
+
+    /// Process chat completion requests
+    #[cfg(feature = "server")]
+    pub async fn chat_completions_http(
+        &self,
+        req: ChatCompletionRequest,
+    ) -> Result<Response, ProxyError> {
+        let model = AdapterUtils::extract_model(&req, &self.model_id);
+        AdapterUtils::log_request("template", &model, req.messages.len());
+
+        let start_time = std::time::Instant::now();
+
+        let request_builder = self.build_request(&req)?;
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await.inspect_err(|e| {
+            debug!("Template backend request failed: {}", e);
+        })?;
+
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        debug!("Template backend response status: {}", status);
+        let content_type = AdapterUtils::content_type(&resp);
+
+        let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
+            debug!("Failed to read template backend response body: {}", e);
+        })?;
+
+        let response_time = start_time.elapsed().as_millis() as u64;
+        AdapterUtils::log_response("template", &model, status.is_success(), response_time);
+
+        if !status.is_success() {
+            return Err(ProxyError::upstream_status(
+                status,
+                &headers,
+                AdapterUtils::describe_body(&response_bytes).to_string(),
+            ));
+        }
+
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
+
+        let json = serde_json::from_slice::<Value>(&response_bytes).map_err(|e| {
+            ProxyError::Upstream(format!(
+                "error decoding response body: {} (body: {})",
+                e,
+                AdapterUtils::describe_body(&response_bytes)
+            ))
+        })?;
+
+        let text = match self.response_path.select(&json) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Null) | None => {
+                return Err(ProxyError::Upstream(format!(
+                    "response has no value at '{}' (body: {})",
+                    self.response_path.source,
+                    AdapterUtils::describe_body(&response_bytes)
+                )))
+            }
+            Some(other) => other.to_string(),
+        };
+
+        let now = AdapterUtils::current_timestamp() as i64;
+        let envelope = serde_json::json!({
+            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+            "object": "chat.completion",
+            "created": now,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": text},
+                "finish_reason": "stop"
+            }]
+        });
+
+        Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(envelope)).into_response(), response_time))
+    }
+}
+
+#[async_trait::async_trait]
+impl AdapterTrait for TemplateAdapter {
+    fn name(&self) -> &'static str {
+        "template"
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    fn has_auth(&self) -> bool {
+        self.token.is_some()
+    }
+
+    #[cfg(feature = "server")]
+    async fn chat_completions(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ProxyError> {
+        let http_response = self.chat_completions_http(request).await?;
+
+        let body_bytes = axum::body::to_bytes(http_response.into_body(), usize::MAX)
+            .await
+            .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+
+        serde_json::from_slice(&body_bytes)
+            .map_err(|e| ProxyError::Internal(format!("Failed to parse response JSON: {}", e)))
+    }
+
+    #[cfg(not(feature = "server"))]
+    async fn chat_completions(
+        &self,
+        _request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ProxyError> {
+        Err(ProxyError::Internal(
+            "Server feature not enabled".to_string(),
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::core::http_client::HttpClientBuilder;
+    use serde_json::json;
+    use wiremock::{matchers::{method, path}, Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_template_builds_request_and_extracts_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "result": {"outputs": [{"text": "4", "score": 0.9}]}
+            })))
+            .mount(&server)
+            .await;
+
+        let template: RequestTemplate = serde_json::from_value(json!({
+            "path": "/api/generate",
+            "body": {
+                "input": {"dialog": "{messages}", "instruction": "Answer: {prompt}"},
+                "params": {"engine": "{model}", "length": "{max_tokens}"}
+            },
+            "response_path": "$.result.outputs[0]['text']"
+        }))
+        .unwrap();
+        let adapter = TemplateAdapter::new(
+            server.uri(),
+            "exotic-1".to_string(),
+            None,
+            template,
+            HttpClientBuilder::new().build().unwrap(),
+        )
+        .unwrap();
+        let req: ChatCompletionRequest = serde_json::from_value(json!({
+            "messages": [{"role": "user", "content": "What is 2+2?"}],
+            "max_tokens": 16
+        }))
+        .unwrap();
+
+        let completion = AdapterTrait::chat_completions(&adapter, req).await.unwrap();
+        assert_eq!(completion.choices[0].message.content.as_deref(), Some("4"));
+        assert_eq!(completion.model, "exotic-1");
+
+        let payload: Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(payload["input"]["dialog"][0]["content"], "What is 2+2?");
+        assert_eq!(payload["input"]["instruction"], "Answer: What is 2+2?");
+        assert_eq!(payload["params"], json!({"engine": "exotic-1", "length": 16}));
+    }
+
+    #[test]
+    fn test_placeholders_in_substituted_values_left_alone() {
+        let vars = json!({
+            "prompt": "Explain {temperature} and {top_p}",
+            "temperature": 0.7,
+            "top_p": 0.9
+        });
+        let template = json!({"text": "Q: {prompt} ({unknown}) t={temperature}"});
+
+        let rendered = render(&template, vars.as_object().unwrap());
+
+        assert_eq!(rendered["text"], "Q: Explain {temperature} and {top_p} ({unknown}) t=0.7");
+    }
+
+    #[test]
+    fn test_malformed_response_path_rejected() {
+        for path in ["result.text", "$.", "$[0", "$[first]"] {
+            assert!(matches!(JsonPath::parse(path), Err(ProxyError::BadRequest(_))), "path {:?}", path);
+        }
+    }
+}
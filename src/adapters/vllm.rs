@@ -11,6 +11,7 @@ use crate::{
 #[cfg(feature = "server")]
 use axum::{http::StatusCode, response::{IntoResponse, Response}, Json};
 use reqwest::Client;
+use std::time::Duration;
 use tracing::debug;
 
 /// # vLLM Adapter
@@ -27,6 +28,8 @@ pub struct VLLMAdapter {
     token: Option<String>,
     /// HTTP client with connection pooling
     client: Client,
+    /// Give up when no response arrives within this window
+    first_byte_timeout: Option<Duration>,
 }
 
 impl VLLMAdapter {
@@ -37,9 +40,33 @@ impl VLLMAdapter {
             model_id,
             token,
             client,
+            first_byte_timeout: None,
         }
     }
 
+    /// Authenticate with `token` instead of the configured credential
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fail requests whose response has not started within `timeout`
+    pub fn with_first_byte_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Fetch the backend's model listing, or one model's metadata when `id` is given
+    #[cfg(feature = "server")]
+    pub async fn models(&self, id: Option<&str>) -> Result<serde_json::Value, ProxyError> {
+        let url = format!("{}/models{}", self.base, id.map(|id| format!("/{}", id)).unwrap_or_default());
+        let mut request_builder = self.client.get(url);
+        if let Some(token) = &self.token {
+            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
+        }
+        AdapterUtils::fetch_models(request_builder).await
+    }
+
     /// Get the model ID for this adapter
     pub fn model_id(&self) -> &str {
         &self.model_id
@@ -64,42 +91,47 @@ impl VLLMAdapter {
         }
 
         // Send the request and await the response
-        let resp = request_builder
-            .send()
-            .await
-            .map_err(|e| {
+        let resp = AdapterUtils::send(request_builder, self.first_byte_timeout).await
+            .inspect_err(|e| {
                 debug!("vLLM request failed: {}", e);
-                ProxyError::Upstream(e.to_string())
             })?;
 
         let status = resp.status();
+        let headers = resp.headers().clone();
         debug!("vLLM response status: {}", status);
+        let content_type = AdapterUtils::content_type(&resp);
 
-        let response_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| {
+        // Streamed bodies are forwarded as they arrive; only errors are buffered
+        if status.is_success() && req.stream.unwrap_or(false) {
+            let response_time = start_time.elapsed().as_millis() as u64;
+            AdapterUtils::log_response("vllm", &AdapterUtils::extract_model(&req, &self.model_id), true, response_time);
+            return AdapterUtils::stream_passthrough(resp, response_time);
+        }
+
+        let response_bytes = AdapterUtils::read_body(resp).await.inspect_err(|e| {
                 debug!("Failed to read vLLM response body: {}", e);
-                ProxyError::Upstream(format!("error reading response body: {}", e))
             })?;
 
         let response_time = start_time.elapsed().as_millis() as u64;
         AdapterUtils::log_response("vllm", &AdapterUtils::extract_model(&req, &self.model_id), status.is_success(), response_time);
 
         if !status.is_success() {
-            let error_text = String::from_utf8_lossy(&response_bytes);
+            let error_text = AdapterUtils::describe_body(&response_bytes);
             debug!("vLLM error response: {}", error_text);
-            return Err(ProxyError::Upstream(format!("HTTP {}: {}", status, error_text)));
+            return Err(ProxyError::upstream_status(status, &headers, error_text.to_string()));
         }
 
-        let json = serde_json::from_slice::<serde_json::Value>(&response_bytes)
+        AdapterUtils::ensure_json_content_type(content_type.as_deref(), &response_bytes)?;
+
+        let mut json = serde_json::from_slice::<serde_json::Value>(&response_bytes)
             .map_err(|e| {
                 debug!("Failed to parse vLLM JSON response: {}", e);
-                ProxyError::Upstream(format!("error decoding response body: {} (body: {})", e, String::from_utf8_lossy(&response_bytes)))
+                ProxyError::Upstream(format!("error decoding response body: {} (body: {})", e, AdapterUtils::describe_body(&response_bytes)))
             })?;
 
         debug!("Successfully forwarded vLLM request");
-        Ok((StatusCode::OK, Json(json)).into_response())
+        AdapterUtils::normalize_finish_reasons(&mut json);
+        Ok(AdapterUtils::with_upstream_duration((StatusCode::OK, Json(json)).into_response(), response_time))
     }
 }
 
@@ -2,13 +2,29 @@
 //!
 //! This module provides support for Anthropic's Claude API format,
 //! converting between Anthropic and OpenAI formats internally.
+//!
+//! Incoming Messages API requests have their top-level `system` prompt lifted
+//! into a leading system message and their text content blocks flattened into
+//! plain message content; responses map `finish_reason` back to Anthropic's
+//! `stop_reason` and usage to `input_tokens`/`output_tokens`. Streamed
+//! responses are re-framed from OpenAI chunks into Anthropic's
+//! `message_start` ... `message_stop` event sequence.
+//!
+//! Requests built for an Anthropic backend can carry prompt caching
+//! breakpoints (`cache_control: {"type": "ephemeral"}`) on the system prompt
+//! and on the last few turns, so long shared prefixes are billed at the
+//! cached rate on repeat requests.
 
 use serde::{Deserialize, Serialize, Deserializer};
 use serde::de::{self, Visitor};
 use std::fmt;
 use crate::schemas::{ChatCompletionRequest, ChatCompletionResponse, Message, Usage};
+use crate::config::Config;
 use crate::error::ProxyError;
 
+/// Maximum number of cache breakpoints Anthropic accepts per request
+pub const MAX_CACHE_BREAKPOINTS: usize = 4;
+
 /// System prompt that can be either a string or an array of content blocks
 #[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
@@ -22,15 +38,18 @@ impl SystemPrompt {
     pub fn to_string(&self) -> String {
         match self {
             SystemPrompt::Text(text) => text.clone(),
-            SystemPrompt::Blocks(blocks) => {
-                blocks
-                    .iter()
-                    .filter_map(|block| match block {
-                        AnthropicContentBlock::Text { text } => Some(text.as_str()),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n")
+            SystemPrompt::Blocks(blocks) => join_text_blocks(blocks),
+        }
+    }
+
+    /// Mark the end of the system prompt as a cache breakpoint
+    pub fn add_cache_breakpoint(&mut self) {
+        if let SystemPrompt::Text(text) = self {
+            *self = SystemPrompt::Blocks(vec![AnthropicContentBlock::text(std::mem::take(text))]);
+        }
+        if let SystemPrompt::Blocks(blocks) = self {
+            if let Some(last) = blocks.last_mut() {
+                last.set_cache_control(CacheControl::ephemeral());
             }
         }
     }
@@ -118,13 +137,87 @@ pub enum AnthropicContent {
     Array(Vec<AnthropicContentBlock>),
 }
 
+impl AnthropicContent {
+    /// Mark the end of this content as a cache breakpoint
+    pub fn add_cache_breakpoint(&mut self) {
+        if let AnthropicContent::Text(text) = self {
+            *self = AnthropicContent::Array(vec![AnthropicContentBlock::text(std::mem::take(text))]);
+        }
+        if let AnthropicContent::Array(blocks) = self {
+            if let Some(last) = blocks.last_mut() {
+                last.set_cache_control(CacheControl::ephemeral());
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum AnthropicContentBlock {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
     #[serde(rename = "image")]
-    Image { source: ImageSource },
+    Image {
+        source: ImageSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+}
+
+/// Concatenate the text of `blocks`, one block per line, skipping empty blocks
+fn join_text_blocks(blocks: &[AnthropicContentBlock]) -> String {
+    blocks
+        .iter()
+        .filter_map(|block| match block {
+            AnthropicContentBlock::Text { text, .. } if !text.is_empty() => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Anthropic `stop_reason` for an OpenAI `finish_reason`
+pub fn stop_reason(finish_reason: &str) -> &'static str {
+    match finish_reason {
+        "length" => "max_tokens",
+        "tool_calls" | "function_call" => "tool_use",
+        "content_filter" => "refusal",
+        _ => "end_turn",
+    }
+}
+
+impl AnthropicContentBlock {
+    /// Create a text block without a cache breakpoint
+    pub fn text(text: String) -> Self {
+        AnthropicContentBlock::Text { text, cache_control: None }
+    }
+
+    /// Set the prompt caching breakpoint of this block
+    pub fn set_cache_control(&mut self, control: CacheControl) {
+        match self {
+            AnthropicContentBlock::Text { cache_control, .. }
+            | AnthropicContentBlock::Image { cache_control, .. } => *cache_control = Some(control),
+        }
+    }
+}
+
+/// Prompt caching breakpoint: everything up to and including the marked
+/// block is cached by Anthropic
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CacheControl {
+    #[serde(rename = "type")]
+    pub cache_type: String,
+}
+
+impl CacheControl {
+    /// Short-lived cache breakpoint (the only type Anthropic supports)
+    pub fn ephemeral() -> Self {
+        Self { cache_type: "ephemeral".to_string() }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -179,9 +272,35 @@ pub struct AnthropicStreamEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_block: Option<AnthropicResponseContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub delta: Option<AnthropicDelta>,
+    pub delta: Option<AnthropicStreamDelta>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<AnthropicUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AnthropicStreamError>,
+}
+
+impl AnthropicStreamEvent {
+    /// Event of `event_type` with no payload
+    pub fn new(event_type: &str) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            message: None,
+            index: None,
+            content_block: None,
+            delta: None,
+            usage: None,
+            error: None,
+        }
+    }
+
+    /// Format as an SSE event named after its type
+    pub fn to_sse(&self) -> String {
+        format!(
+            "event: {}\ndata: {}",
+            self.event_type,
+            serde_json::to_string(self).unwrap_or_default()
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -206,39 +325,119 @@ pub enum AnthropicDelta {
     InputJsonDelta { partial_json: String },
 }
 
+/// Delta of a `content_block_delta` or `message_delta` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AnthropicStreamDelta {
+    Content(AnthropicDelta),
+    Message(AnthropicMessageDelta),
+}
+
+/// Top-level changes to the message, sent once at the end of a stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnthropicMessageDelta {
+    pub stop_reason: Option<String>,
+    pub stop_sequence: Option<String>,
+}
+
+/// Error reported mid-stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnthropicStreamError {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
+}
+
 impl AnthropicRequest {
-    /// Convert Anthropic request to OpenAI format
-    pub fn to_openai_request(&self) -> ChatCompletionRequest {
+    /// Build an Anthropic request from an OpenAI request.
+    ///
+    /// System messages become the `system` prompt and `max_tokens` (required
+    /// by Anthropic) falls back to `default_max_tokens`. Prompt caching
+    /// breakpoints are added as configured by `anthropic_prompt_caching`.
+    pub fn from_openai_request(req: &ChatCompletionRequest, default_max_tokens: u32, config: &Config) -> Self {
+        let system = req
+            .messages
+            .iter()
+            .filter(|message| message.role == "system")
+            .filter_map(|message| message.content.clone())
+            .filter(|content| !content.trim().is_empty())
+            .collect::<Vec<_>>();
+        let messages = req
+            .messages
+            .iter()
+            .filter(|message| message.role != "system")
+            .map(|message| AnthropicMessage {
+                role: message.role.clone(),
+                content: AnthropicContent::Text(message.content.clone().unwrap_or_default()),
+            })
+            .collect();
+
+        let mut request = AnthropicRequest {
+            model: req.model.clone().unwrap_or_default(),
+            messages,
+            max_tokens: req.max_tokens.unwrap_or(default_max_tokens),
+            system: (!system.is_empty()).then(|| SystemPrompt::Text(system.join("\n"))),
+            temperature: req.temperature,
+            top_p: req.top_p,
+            top_k: req.top_k,
+            stream: req.stream,
+            stop_sequences: req.stop.clone(),
+            metadata: req.user.clone().map(|user_id| AnthropicMetadata { user_id: Some(user_id) }),
+        };
+        if let Some(last_turns) = config.anthropic_cache_turns() {
+            request.apply_prompt_caching(last_turns);
+        }
+        request
+    }
+
+    /// Add `ephemeral` cache breakpoints to the system prompt and to the last
+    /// `last_turns` messages, within Anthropic's breakpoint limit
+    pub fn apply_prompt_caching(&mut self, last_turns: usize) {
+        let mut remaining = MAX_CACHE_BREAKPOINTS;
+        if let Some(system) = &mut self.system {
+            system.add_cache_breakpoint();
+            remaining -= 1;
+        }
+        for message in self.messages.iter_mut().rev().take(last_turns.min(remaining)) {
+            message.content.add_cache_breakpoint();
+        }
+    }
+
+    /// Convert Anthropic request to OpenAI format.
+    ///
+    /// A non-empty `system` prompt becomes the leading system message and the
+    /// text blocks of each message are joined into its content. Image blocks
+    /// are rejected because message content is forwarded as text only.
+    pub fn to_openai_request(&self) -> Result<ChatCompletionRequest, ProxyError> {
         let mut openai_messages = Vec::new();
 
         // Add system message if present
-        if let Some(system) = &self.system {
+        let system = self.system.as_ref().map(SystemPrompt::to_string).unwrap_or_default();
+        if !system.trim().is_empty() {
             openai_messages.push(Message {
                 role: "system".to_string(),
-                content: Some(system.to_string()),
+                content: Some(system),
                 name: None,
                 tool_calls: None,
                 function_call: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             });
         }
 
         // Convert Anthropic messages to OpenAI format
-        for msg in &self.messages {
+        for (index, msg) in self.messages.iter().enumerate() {
             let content = match &msg.content {
                 AnthropicContent::Text(text) => Some(text.clone()),
                 AnthropicContent::Array(blocks) => {
-                    // For now, concatenate text blocks
-                    // TODO: Handle image blocks properly
-                    let text = blocks
-                        .iter()
-                        .filter_map(|block| match block {
-                            AnthropicContentBlock::Text { text } => Some(text.as_str()),
-                            _ => None,
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    Some(text)
+                    if blocks.iter().any(|block| matches!(block, AnthropicContentBlock::Image { .. })) {
+                        return Err(ProxyError::BadRequest(format!(
+                            "messages[{}]: image content blocks are not supported by this endpoint",
+                            index
+                        )));
+                    }
+                    Some(join_text_blocks(blocks))
                 }
             };
 
@@ -249,10 +448,12 @@ impl AnthropicRequest {
                 tool_calls: None,
                 function_call: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             });
         }
 
-        ChatCompletionRequest {
+        Ok(ChatCompletionRequest {
             messages: openai_messages,
             model: Some(self.model.clone()),
             max_tokens: Some(self.max_tokens),
@@ -270,7 +471,15 @@ impl AnthropicRequest {
             top_logprobs: None,
             tools: None,
             tool_choice: None,
-        }
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: self.top_k,
+            reasoning_effort: None,
+            conversation_id: None,
+        })
     }
 }
 
@@ -282,15 +491,15 @@ impl AnthropicResponse {
             .first()
             .ok_or_else(|| ProxyError::Internal("No choices in OpenAI response".to_string()))?;
 
-        let content_text = choice
+        // Anthropic returns no content blocks rather than an empty text block
+        let content = choice
             .message
             .content
             .clone()
-            .unwrap_or_default();
-
-        let content = vec![AnthropicResponseContent::Text {
-            text: content_text,
-        }];
+            .filter(|text| !text.is_empty())
+            .map(|text| AnthropicResponseContent::Text { text })
+            .into_iter()
+            .collect();
 
         let usage = openai_resp.usage.unwrap_or(Usage {
             prompt_tokens: 0,
@@ -304,7 +513,7 @@ impl AnthropicResponse {
             role: "assistant".to_string(),
             content,
             model: openai_resp.model,
-            stop_reason: Some(choice.finish_reason.clone()),
+            stop_reason: Some(stop_reason(&choice.finish_reason).to_string()),
             stop_sequence: None,
             usage: AnthropicUsage {
                 input_tokens: usage.prompt_tokens,
@@ -313,3 +522,346 @@ impl AnthropicResponse {
         })
     }
 }
+
+/// Re-frames an OpenAI chat completion stream as Messages API events.
+///
+/// The first chunk opens the message (`message_start`) and the first text of
+/// choice 0 opens a text block at index 0; each content delta becomes a
+/// `content_block_delta` and the finish reason closes the block. Because
+/// OpenAI reports usage in a chunk after the finish reason, `message_delta`
+/// (carrying `stop_reason` and the usage) and `message_stop` are only sent
+/// once the stream ends. Without reported usage, `output_tokens` counts the
+/// text deltas.
+#[derive(Debug, Default)]
+pub struct AnthropicStreamTranslator {
+    /// Whether `message_start` has been sent
+    started: bool,
+    /// Index of the open content block
+    open_block: Option<u32>,
+    /// Content blocks opened so far
+    blocks: u32,
+    stop_reason: Option<&'static str>,
+    input_tokens: u32,
+    /// Completion tokens reported by the backend
+    output_tokens: Option<u32>,
+    text_deltas: u32,
+    /// Whether `message_stop` has been sent
+    finished: bool,
+}
+
+impl AnthropicStreamTranslator {
+    /// Events to emit for one OpenAI SSE event
+    pub fn process_event(&mut self, event: &str) -> Vec<String> {
+        let Some(data) = event.split('\n').find_map(|line| line.strip_prefix("data:")).map(str::trim) else {
+            return Vec::new();
+        };
+        if data == "[DONE]" {
+            return self.finish();
+        }
+        let Ok(chunk) = serde_json::from_str::<serde_json::Value>(data) else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        if let Some(error) = chunk.get("error") {
+            let mut event = AnthropicStreamEvent::new("error");
+            event.error = Some(AnthropicStreamError {
+                error_type: "api_error".to_string(),
+                message: error
+                    .get("message")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+            });
+            events.push(event.to_sse());
+            return events;
+        }
+
+        if let Some(usage) = chunk.get("usage").and_then(|usage| serde_json::from_value::<Usage>(usage.clone()).ok()) {
+            self.input_tokens = usage.prompt_tokens;
+            self.output_tokens = Some(usage.completion_tokens);
+        }
+        if !self.started {
+            self.started = true;
+            let text = |key: &str| chunk.get(key).and_then(serde_json::Value::as_str).unwrap_or_default().to_string();
+            let mut event = AnthropicStreamEvent::new("message_start");
+            event.message = Some(AnthropicStreamMessage {
+                id: text("id"),
+                message_type: "message".to_string(),
+                role: "assistant".to_string(),
+                content: Vec::new(),
+                model: text("model"),
+                stop_reason: None,
+                stop_sequence: None,
+                usage: AnthropicUsage { input_tokens: self.input_tokens, output_tokens: 0 },
+            });
+            events.push(event.to_sse());
+        }
+
+        // Anthropic responses have a single choice
+        let choice = chunk
+            .get("choices")
+            .and_then(serde_json::Value::as_array)
+            .and_then(|choices| {
+                choices
+                    .iter()
+                    .find(|choice| choice.get("index").and_then(serde_json::Value::as_u64).unwrap_or(0) == 0)
+            });
+        let Some(choice) = choice else {
+            return events;
+        };
+
+        let text = choice.pointer("/delta/content").and_then(serde_json::Value::as_str).unwrap_or_default();
+        if !text.is_empty() && self.stop_reason.is_none() {
+            let index = match self.open_block {
+                Some(index) => index,
+                None => {
+                    let index = self.blocks;
+                    self.blocks += 1;
+                    self.open_block = Some(index);
+                    let mut event = AnthropicStreamEvent::new("content_block_start");
+                    event.index = Some(index);
+                    event.content_block = Some(AnthropicResponseContent::Text { text: String::new() });
+                    events.push(event.to_sse());
+                    index
+                }
+            };
+            self.text_deltas += 1;
+            let mut event = AnthropicStreamEvent::new("content_block_delta");
+            event.index = Some(index);
+            event.delta = Some(AnthropicStreamDelta::Content(AnthropicDelta::TextDelta { text: text.to_string() }));
+            events.push(event.to_sse());
+        }
+
+        if let Some(finish_reason) = choice.get("finish_reason").and_then(serde_json::Value::as_str) {
+            self.stop_reason = Some(stop_reason(finish_reason));
+            events.extend(self.close_block());
+        }
+        events
+    }
+
+    /// Close the message, sent once when the stream ends
+    pub fn finish(&mut self) -> Vec<String> {
+        if !self.started || self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+        let mut events = self.close_block();
+
+        let mut event = AnthropicStreamEvent::new("message_delta");
+        event.delta = Some(AnthropicStreamDelta::Message(AnthropicMessageDelta {
+            stop_reason: Some(self.stop_reason.unwrap_or("end_turn").to_string()),
+            stop_sequence: None,
+        }));
+        event.usage = Some(AnthropicUsage {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens.unwrap_or(self.text_deltas),
+        });
+        events.push(event.to_sse());
+        events.push(AnthropicStreamEvent::new("message_stop").to_sse());
+        events
+    }
+
+    /// `content_block_stop` for the open content block, if any
+    fn close_block(&mut self) -> Vec<String> {
+        self.open_block
+            .take()
+            .map(|index| {
+                let mut event = AnthropicStreamEvent::new("content_block_stop");
+                event.index = Some(index);
+                event.to_sse()
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Wrap an OpenAI streaming (SSE) response so it is sent as Messages API events
+#[cfg(feature = "server")]
+pub fn to_anthropic_stream(response: axum::response::Response) -> axum::response::Response {
+    use futures_util::{stream, StreamExt};
+
+    let (parts, body) = response.into_parts();
+    let state = (body.into_data_stream(), AnthropicStreamTranslator::default(), String::new(), false);
+
+    let translated = stream::unfold(state, |(mut inner, mut translator, mut pending, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            let (events, finished) = match inner.next().await {
+                Some(Ok(bytes)) => {
+                    pending.push_str(&String::from_utf8_lossy(&bytes));
+                    let Some(end) = pending.rfind("\n\n") else {
+                        continue;
+                    };
+                    let complete: String = pending.drain(..end + 2).collect();
+                    let events: Vec<String> = complete
+                        .split("\n\n")
+                        .filter(|event| !event.is_empty())
+                        .flat_map(|event| translator.process_event(event))
+                        .collect();
+                    (events, false)
+                }
+                Some(Err(error)) => return Some((Err(error), (inner, translator, pending, true))),
+                None => {
+                    // Close the message even if the backend never sent [DONE]
+                    let rest = std::mem::take(&mut pending);
+                    let mut events = match rest.trim() {
+                        "" => Vec::new(),
+                        event => translator.process_event(event),
+                    };
+                    events.extend(translator.finish());
+                    (events, true)
+                }
+            };
+            if events.is_empty() {
+                if finished {
+                    return None;
+                }
+                continue;
+            }
+            let output: String = events.iter().map(|event| format!("{}\n\n", event)).collect();
+            return Some((Ok(bytes::Bytes::from(output)), (inner, translator, pending, finished)));
+        }
+    });
+
+    axum::response::Response::from_parts(parts, axum::body::Body::from_stream(translated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn openai_request() -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "claude-3-5-sonnet-20241022",
+            "messages": [
+                {"role": "system", "content": "You are a support agent for a very long manual..."},
+                {"role": "user", "content": "How do I reset?"},
+                {"role": "assistant", "content": "Hold the button."},
+                {"role": "user", "content": "For how long?"}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_outgoing_request_marks_system_prompt_for_caching() {
+        let mut config = Config::for_test();
+        config.anthropic_prompt_caching = true;
+        config.anthropic_cache_last_turns = 1;
+
+        let request = AnthropicRequest::from_openai_request(&openai_request(), 1024, &config);
+        let json = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(json["system"][0]["type"], "text");
+        assert_eq!(json["system"][0]["cache_control"], serde_json::json!({"type": "ephemeral"}));
+        assert_eq!(json["messages"][2]["content"][0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(json["messages"][1]["content"], "Hold the button.");
+    }
+
+    #[test]
+    fn test_prompt_caching_disabled_by_default() {
+        let request = AnthropicRequest::from_openai_request(&openai_request(), 1024, &Config::for_test());
+        let json = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(json["system"], "You are a support agent for a very long manual...");
+        assert!(!json.to_string().contains("cache_control"));
+    }
+    fn anthropic_request(json: serde_json::Value) -> AnthropicRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_system_and_content_blocks_round_trip_through_internal_request() {
+        let request = anthropic_request(serde_json::json!({
+            "model": "claude-3-5-sonnet-20241022",
+            "max_tokens": 512,
+            "system": [{"type": "text", "text": "You are terse."}, {"type": "text", "text": "Answer in English."}],
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "Summarize:"},
+                    {"type": "text", "text": "The quick brown fox."}
+                ]},
+                {"role": "assistant", "content": "A fox."},
+                {"role": "user", "content": "Shorter?"}
+            ],
+            "stop_sequences": ["END"]
+        }));
+
+        let internal = request.to_openai_request().unwrap();
+        assert_eq!(internal.messages.len(), 4);
+        assert_eq!(internal.messages[0].role, "system");
+        assert_eq!(internal.messages[0].content.as_deref(), Some("You are terse.\nAnswer in English."));
+        assert_eq!(internal.messages[1].content.as_deref(), Some("Summarize:\nThe quick brown fox."));
+        assert_eq!(internal.max_tokens, Some(512));
+
+        let back = serde_json::to_value(AnthropicRequest::from_openai_request(&internal, 1024, &Config::for_test())).unwrap();
+        assert_eq!(back["system"], "You are terse.\nAnswer in English.");
+        assert_eq!(back["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(back["messages"][0], serde_json::json!({"role": "user", "content": "Summarize:\nThe quick brown fox."}));
+        assert_eq!(back["messages"][2]["content"], "Shorter?");
+        assert_eq!(back["max_tokens"], 512);
+        assert_eq!(back["stop_sequences"], serde_json::json!(["END"]));
+    }
+
+    #[test]
+    fn test_empty_system_dropped_and_image_blocks_rejected() {
+        let request = anthropic_request(serde_json::json!({
+            "model": "claude-3-5-sonnet-20241022",
+            "max_tokens": 64,
+            "system": "",
+            "messages": [{"role": "user", "content": "Hi"}]
+        }));
+        let internal = request.to_openai_request().unwrap();
+        assert_eq!(internal.messages.len(), 1);
+        assert_eq!(internal.messages[0].role, "user");
+
+        let request = anthropic_request(serde_json::json!({
+            "model": "claude-3-5-sonnet-20241022",
+            "max_tokens": 64,
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}}
+            ]}]
+        }));
+        let error = request.to_openai_request().unwrap_err();
+        assert!(error.to_string().contains("messages[0]: image content blocks are not supported"));
+    }
+
+    #[test]
+    fn test_response_maps_stop_reason_and_usage() {
+        let response: ChatCompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "claude-3-5-sonnet-20241022",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "A fox jumps"},
+                "finish_reason": "length"
+            }],
+            "usage": {"prompt_tokens": 21, "completion_tokens": 3, "total_tokens": 24}
+        }))
+        .unwrap();
+
+        let json = serde_json::to_value(AnthropicResponse::from_openai_response(response).unwrap()).unwrap();
+        assert_eq!(json["type"], "message");
+        assert_eq!(json["content"], serde_json::json!([{"type": "text", "text": "A fox jumps"}]));
+        assert_eq!(json["stop_reason"], "max_tokens");
+        assert_eq!(json["usage"], serde_json::json!({"input_tokens": 21, "output_tokens": 3}));
+        assert_eq!(stop_reason("stop"), "end_turn");
+        assert_eq!(stop_reason("tool_calls"), "tool_use");
+    }
+
+    #[test]
+    fn test_top_k_forwarded_in_messages_payload() {
+        let mut request = openai_request();
+        request.top_k = Some(40);
+
+        let json = serde_json::to_value(AnthropicRequest::from_openai_request(&request, 1024, &Config::for_test())).unwrap();
+
+        assert_eq!(json["top_k"], 40);
+    }
+}
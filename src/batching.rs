@@ -2,11 +2,19 @@
 //!
 //! Implements intelligent request batching for improved throughput and efficiency.
 //! Groups multiple requests together to reduce overhead and improve performance.
+//!
+//! Batches, whether collected by [`BatchProcessor::add_request`] or submitted
+//! at once with [`BatchProcessor::process_requests`], are split into
+//! sub-batches of at most `max_batch_size`, with up to
+//! `max_concurrent_batches` sub-batches in flight; results come back in
+//! submission order. With batching disabled, requests go straight to the
+//! adapter.
 
 use crate::{
     adapters::Adapter,
     schemas::ChatCompletionRequest,
 };
+use futures_util::{future, stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::{
     sync::{
@@ -31,6 +39,8 @@ pub struct BatchConfig {
     pub max_wait_time_ms: u64,
     /// Whether to enable batching
     pub enabled: bool,
+    /// Maximum sub-batches of a split submission processed at once
+    pub max_concurrent_batches: usize,
 }
 
 impl Default for BatchConfig {
@@ -39,6 +49,7 @@ impl Default for BatchConfig {
             max_batch_size: 10,
             max_wait_time_ms: 100,
             enabled: true,
+            max_concurrent_batches: 4,
         }
     }
 }
@@ -99,6 +110,8 @@ pub struct BatchProcessor {
     adapter: Adapter,
     /// Request counter
     request_counter: Arc<AtomicU64>,
+    /// Sub-batches processed
+    batch_counter: Arc<AtomicU64>,
     /// Current batch
     current_batch: Arc<RwLock<Option<Batch>>>,
     /// Batch processing channel
@@ -114,6 +127,7 @@ impl BatchProcessor {
             config,
             adapter,
             request_counter: Arc::new(AtomicU64::new(0)),
+            batch_counter: Arc::new(AtomicU64::new(0)),
             current_batch: Arc::new(RwLock::new(None)),
             batch_tx,
         };
@@ -121,9 +135,10 @@ impl BatchProcessor {
         // Start batch processing task
         let adapter_clone = processor.adapter.clone();
         let config_clone = processor.config.clone();
+        let counters = (processor.request_counter.clone(), processor.batch_counter.clone());
         tokio::spawn(async move {
             while let Some(batch) = batch_rx.recv().await {
-                if let Err(e) = Self::process_batch(batch, &adapter_clone).await {
+                if let Err(e) = Self::process_batch(batch, &adapter_clone, &config_clone, &counters).await {
                     error!("Failed to process batch: {}", e);
                 }
             }
@@ -132,8 +147,13 @@ impl BatchProcessor {
         processor
     }
 
-    /// Add a request to the current batch
+    /// Add a request to the current batch, or send it straight to the
+    /// adapter when batching is disabled
     pub async fn add_request(&self, request: ChatCompletionRequest) -> Result<axum::response::Response, crate::error::ProxyError> {
+        if !self.config.enabled {
+            return self.adapter.chat_completions(request).await;
+        }
+
         let (response_tx, response_rx) = oneshot::channel();
         let batch_request = BatchRequest {
             request,
@@ -161,13 +181,76 @@ impl BatchProcessor {
         response_rx.await.map_err(|_| crate::error::ProxyError::Internal("Batch processing failed".to_string()))?
     }
 
+    /// Process a whole submission of requests, split into sub-batches of at
+    /// most `max_batch_size` instead of being rejected when it is larger.
+    ///
+    /// The requests of a sub-batch run together and up to
+    /// `max_concurrent_batches` sub-batches are in flight at once. Results are
+    /// returned in the order of `requests`. With batching disabled, each
+    /// request is sent to the adapter on its own.
+    pub async fn process_requests(
+        &self,
+        requests: Vec<ChatCompletionRequest>,
+    ) -> Vec<Result<axum::response::Response, crate::error::ProxyError>> {
+        if !self.config.enabled {
+            return future::join_all(requests.into_iter().map(|request| self.adapter.chat_completions(request))).await;
+        }
+        Self::process_sub_batches(
+            requests,
+            &self.adapter,
+            &self.config,
+            &(self.request_counter.clone(), self.batch_counter.clone()),
+        )
+        .await
+    }
+
+    /// Run `requests` as ordered, concurrency-limited sub-batches
+    async fn process_sub_batches(
+        requests: Vec<ChatCompletionRequest>,
+        adapter: &Adapter,
+        config: &BatchConfig,
+        (request_counter, batch_counter): &(Arc<AtomicU64>, Arc<AtomicU64>),
+    ) -> Vec<Result<axum::response::Response, crate::error::ProxyError>> {
+        let batch_size = config.max_batch_size.max(1);
+        let mut remaining = requests.into_iter().peekable();
+        let mut sub_batches = Vec::new();
+        while remaining.peek().is_some() {
+            sub_batches.push(remaining.by_ref().take(batch_size).collect::<Vec<_>>());
+        }
+        if sub_batches.len() > 1 {
+            info!("Splitting batch into {} sub-batches of up to {} requests", sub_batches.len(), batch_size);
+        }
+
+        stream::iter(sub_batches)
+            .map(|sub_batch| async move {
+                request_counter.fetch_add(sub_batch.len() as u64, Ordering::Relaxed);
+                batch_counter.fetch_add(1, Ordering::Relaxed);
+                future::join_all(sub_batch.into_iter().map(|request| adapter.chat_completions(request))).await
+            })
+            // `buffered` yields sub-batches in submission order
+            .buffered(config.max_concurrent_batches.max(1))
+            .flat_map(stream::iter)
+            .collect()
+            .await
+    }
+
     /// Process a batch of requests
-    async fn process_batch(batch: Batch, adapter: &Adapter) -> Result<(), crate::error::ProxyError> {
+    async fn process_batch(
+        batch: Batch,
+        adapter: &Adapter,
+        config: &BatchConfig,
+        counters: &(Arc<AtomicU64>, Arc<AtomicU64>),
+    ) -> Result<(), crate::error::ProxyError> {
         info!("Processing batch with {} requests", batch.len());
-        
-        for batch_request in batch.requests {
-            let result = adapter.chat_completions(batch_request.request).await;
-            if let Err(e) = batch_request.response_tx.send(result) {
+
+        let (requests, response_txs): (Vec<_>, Vec<_>) = batch
+            .requests
+            .into_iter()
+            .map(|batch_request| (batch_request.request, batch_request.response_tx))
+            .unzip();
+        let results = Self::process_sub_batches(requests, adapter, config, counters).await;
+        for (response_tx, result) in response_txs.into_iter().zip(results) {
+            if let Err(e) = response_tx.send(result) {
                 error!("Failed to send batch response: {:?}", e);
             }
         }
@@ -179,6 +262,7 @@ impl BatchProcessor {
     pub fn get_stats(&self) -> BatchStats {
         BatchStats {
             total_requests: self.request_counter.load(Ordering::Relaxed),
+            total_batches: self.batch_counter.load(Ordering::Relaxed),
             current_batch_size: 0, // Would need to check current batch
             config: self.config.clone(),
         }
@@ -192,8 +276,132 @@ impl BatchProcessor {
 pub struct BatchStats {
     /// Total number of requests processed
     pub total_requests: u64,
+    /// Total number of sub-batches processed for split submissions
+    pub total_batches: u64,
     /// Current batch size
     pub current_batch_size: usize,
     /// Batch configuration
     pub config: BatchConfig,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, Request, Respond, ResponseTemplate,
+    };
+
+    /// Backend answering each request with its own user message
+    struct Echo;
+
+    impl Respond for Echo {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = request.body_json().unwrap();
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "test-model",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": body["messages"][0]["content"]},
+                    "finish_reason": "stop"
+                }]
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_batch_split_with_ordered_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(Echo)
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let batch_config = BatchConfig {
+            max_batch_size: 2,
+            max_concurrent_batches: 2,
+            ..BatchConfig::default()
+        };
+        let processor = BatchProcessor::new(batch_config, Adapter::from_config(&config));
+        let requests = (0..5)
+            .map(|i| ChatCompletionRequest {
+                messages: vec![crate::schemas::Message::user(format!("request {}", i))],
+                ..Default::default()
+            })
+            .collect();
+
+        let results = processor.process_requests(requests).await;
+
+        let mut contents = Vec::new();
+        for result in results {
+            let body = axum::body::to_bytes(result.unwrap().into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            contents.push(json["choices"][0]["message"]["content"].as_str().unwrap().to_string());
+        }
+        assert_eq!(contents, (0..5).map(|i| format!("request {}", i)).collect::<Vec<_>>());
+        let stats = processor.get_stats();
+        assert_eq!((stats.total_requests, stats.total_batches), (5, 3));
+    }
+
+    async fn echo_adapter() -> (MockServer, Adapter) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(Echo)
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let adapter = Adapter::from_config(&config);
+        (server, adapter)
+    }
+
+    fn user_request(content: &str) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            messages: vec![crate::schemas::Message::user(content.to_string())],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_added_requests_processed_as_sub_batches() {
+        let (_server, adapter) = echo_adapter().await;
+        let batch_config = BatchConfig {
+            max_batch_size: 1,
+            ..BatchConfig::default()
+        };
+        let processor = BatchProcessor::new(batch_config, adapter);
+
+        let response = processor.add_request(user_request("hello")).await.unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let stats = processor.get_stats();
+        assert_eq!((stats.total_requests, stats.total_batches), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_batching_sends_requests_directly() {
+        let (server, adapter) = echo_adapter().await;
+        let batch_config = BatchConfig {
+            enabled: false,
+            ..BatchConfig::default()
+        };
+        let processor = BatchProcessor::new(batch_config, adapter);
+
+        // Would wait for a full batch of 10 if batching were on
+        let response = processor.add_request(user_request("hello")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let results = processor.process_requests(vec![user_request("a"), user_request("b")]).await;
+        assert!(results.iter().all(Result::is_ok));
+
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+        let stats = processor.get_stats();
+        assert_eq!((stats.total_requests, stats.total_batches), (0, 0));
+    }
+}
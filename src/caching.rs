@@ -7,11 +7,14 @@ use std::sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
     };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
 use tokio::sync::RwLock;
 use std::time::{SystemTime, UNIX_EPOCH, Duration};
+use crate::adapters::AdapterUtils;
 use crate::schemas::{ChatCompletionRequest, ChatCompletionResponse};
 use crate::error::ProxyError;
+use crate::config::Config;
 
 /// # Cache Configuration
 ///
@@ -30,6 +33,10 @@ pub struct CacheConfig {
     pub min_response_size: usize,
     /// Cache eviction strategy
     pub eviction_strategy: EvictionStrategy,
+    /// Serve a miss from an entry with a larger `max_tokens` (and otherwise
+    /// identical parameters), truncating its content to the requested budget
+    #[serde(default)]
+    pub reuse_longer_max_tokens: bool,
 }
 
 /// Cache eviction strategies
@@ -52,6 +59,19 @@ impl Default for CacheConfig {
             similarity_caching: true,
             min_response_size: 100,
             eviction_strategy: EvictionStrategy::LRU,
+            reuse_longer_max_tokens: false,
+        }
+    }
+}
+
+impl From<&Config> for CacheConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_size: config.cache_max_size,
+            ttl_seconds: config.cache_ttl_seconds,
+            enabled: config.enable_caching,
+            reuse_longer_max_tokens: config.cache_reuse_longer_max_tokens,
+            ..Self::default()
         }
     }
 }
@@ -69,10 +89,16 @@ struct CacheEntry {
     access_count: u64,
     /// Entry order for FIFO eviction
     entry_order: u64,
+    /// Order of the most recent access, for LRU eviction
+    last_access_order: u64,
+    /// Key of the request parameters excluding `max_tokens`
+    base_key: String,
+    /// `max_tokens` of the request that produced this entry
+    max_tokens: Option<u32>,
 }
 
 impl CacheEntry {
-    fn new(response: ChatCompletionResponse, entry_order: u64) -> Self {
+    fn new(response: ChatCompletionResponse, entry_order: u64, base_key: String, max_tokens: Option<u32>) -> Self {
         let now = current_timestamp();
         Self {
             response,
@@ -80,6 +106,9 @@ impl CacheEntry {
             last_accessed: now,
             access_count: 1,
             entry_order,
+            last_access_order: entry_order,
+            base_key,
+            max_tokens,
         }
     }
 
@@ -88,12 +117,127 @@ impl CacheEntry {
         now > self.created_at + ttl_seconds
     }
 
-    fn access(&mut self) {
+    fn access(&mut self, access_order: u64) {
         self.last_accessed = current_timestamp();
+        self.last_access_order = access_order;
         self.access_count += 1;
     }
 }
 
+/// Truncate each choice of a cached response to `max_tokens` completion tokens.
+///
+/// Uses the cached usage figures, which cover all choices together, to
+/// estimate characters per token when available, otherwise the usual ~4
+/// characters per token heuristic.
+fn truncate_response(response: &ChatCompletionResponse, max_tokens: u32) -> ChatCompletionResponse {
+    let mut truncated = response.clone();
+    let choices = response.choices.len().max(1) as u32;
+    let cached_tokens = response.usage.as_ref().map(|u| u.completion_tokens / choices).unwrap_or(0);
+
+    for choice in &mut truncated.choices {
+        let Some(content) = choice.message.content.as_mut() else {
+            continue;
+        };
+        let total_chars = content.chars().count();
+        let keep_chars = if cached_tokens > 0 {
+            total_chars * max_tokens as usize / cached_tokens as usize
+        } else {
+            max_tokens as usize * 4
+        };
+        if keep_chars < total_chars {
+            *content = content.chars().take(keep_chars).collect();
+            choice.finish_reason = "length".to_string();
+        }
+    }
+
+    if let Some(usage) = truncated.usage.as_mut() {
+        usage.completion_tokens = usage.completion_tokens.min(max_tokens.saturating_mul(choices));
+        usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+    }
+
+    truncated
+}
+
+/// Cached entries plus an index of the entries that share request
+/// parameters, ordered by `max_tokens`, for reusing longer responses.
+#[derive(Debug, Default)]
+struct CacheStore {
+    entries: HashMap<String, CacheEntry>,
+    /// base key -> `max_tokens` -> cache key
+    by_max_tokens: HashMap<String, BTreeMap<u32, String>>,
+}
+
+impl CacheStore {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &CacheEntry)> {
+        self.entries.iter()
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut CacheEntry> {
+        self.entries.get_mut(key)
+    }
+
+    fn insert(&mut self, key: String, entry: CacheEntry) {
+        if let Some(max_tokens) = entry.max_tokens {
+            self.by_max_tokens
+                .entry(entry.base_key.clone())
+                .or_default()
+                .insert(max_tokens, key.clone());
+        }
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<CacheEntry> {
+        let entry = self.entries.remove(key)?;
+        if let Some(max_tokens) = entry.max_tokens {
+            if let Some(index) = self.by_max_tokens.get_mut(&entry.base_key) {
+                index.remove(&max_tokens);
+                if index.is_empty() {
+                    self.by_max_tokens.remove(&entry.base_key);
+                }
+            }
+        }
+        Some(entry)
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&CacheEntry) -> bool) {
+        let removed: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| !keep(entry))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in removed {
+            self.remove(&key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.by_max_tokens.clear();
+    }
+
+    /// Smallest unexpired entry with the same base key and a `max_tokens`
+    /// above `requested`.
+    fn longer_entry(&mut self, base_key: &str, requested: u32, ttl_seconds: u64) -> Option<&mut CacheEntry> {
+        let key = self
+            .by_max_tokens
+            .get(base_key)?
+            .range((Bound::Excluded(requested), Bound::Unbounded))
+            .map(|(_, key)| key)
+            .find(|key| self.entries.get(*key).is_some_and(|entry| !entry.is_expired(ttl_seconds)))?
+            .clone();
+        self.entries.get_mut(&key)
+    }
+}
+
 /// Get current timestamp in seconds
 fn current_timestamp() -> u64 {
     SystemTime::now()
@@ -110,12 +254,14 @@ pub struct CacheManager {
     /// Configuration
     config: CacheConfig,
     /// Cache storage
-    cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    cache: Arc<RwLock<CacheStore>>,
     /// Hit counter
     hit_counter: Arc<AtomicU64>,
     /// Miss counter
     miss_counter: Arc<AtomicU64>,
-    /// Entry counter for FIFO ordering
+    /// Entries evicted to stay within `max_size`
+    eviction_counter: Arc<AtomicU64>,
+    /// Counter ordering insertions (FIFO) and accesses (LRU)
     entry_counter: Arc<AtomicU64>,
 }
 
@@ -124,42 +270,48 @@ impl CacheManager {
     pub fn new(config: CacheConfig) -> Self {
         Self {
             config,
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: Arc::new(RwLock::new(CacheStore::default())),
             hit_counter: Arc::new(AtomicU64::new(0)),
             miss_counter: Arc::new(AtomicU64::new(0)),
+            eviction_counter: Arc::new(AtomicU64::new(0)),
             entry_counter: Arc::new(AtomicU64::new(0)),
         }
     }
 
     /// Generate cache key from request
     fn generate_cache_key(&self, request: &ChatCompletionRequest) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-
-        // Hash relevant request parameters for caching
-        request.model.hash(&mut hasher);
-        request.messages.hash(&mut hasher);
-
-        if let Some(temp) = request.temperature {
-            ((temp * 10000.0) as u64).hash(&mut hasher);
-        }
-
-        if let Some(max_tokens) = request.max_tokens {
-            max_tokens.hash(&mut hasher);
-        }
+        self.hash_request(request, true)
+    }
 
-        if let Some(top_p) = request.top_p {
-            ((top_p * 10000.0) as u64).hash(&mut hasher);
-        }
+    /// Generate key from every cached parameter except `max_tokens`
+    fn generate_base_key(&self, request: &ChatCompletionRequest) -> String {
+        self.hash_request(request, false)
+    }
 
-        // Include stop sequences if present
-        if let Some(stop) = &request.stop {
-            stop.hash(&mut hasher);
+    fn hash_request(&self, request: &ChatCompletionRequest, include_max_tokens: bool) -> String {
+        let hash = AdapterUtils::hash_request_parameters(request, include_max_tokens);
+        if include_max_tokens {
+            format!("cache:{:x}", hash)
+        } else {
+            format!("base:{:x}", hash)
         }
+    }
 
-        format!("cache:{:x}", hasher.finish())
+    /// Find an unexpired entry generated with a larger `max_tokens` for the
+    /// same parameters and truncate it to the requested budget.
+    fn reuse_longer_entry(
+        &self,
+        cache: &mut CacheStore,
+        request: &ChatCompletionRequest,
+    ) -> Option<ChatCompletionResponse> {
+        let requested = request.max_tokens?;
+        let base_key = self.generate_base_key(request);
+
+        // Prefer the smallest larger entry so the least content is discarded
+        let entry = cache.longer_entry(&base_key, requested, self.config.ttl_seconds)?;
+
+        entry.access(self.entry_counter.fetch_add(1, Ordering::Relaxed));
+        Some(truncate_response(&entry.response, requested))
     }
 
     /// Check if response should be cached
@@ -194,11 +346,20 @@ impl CacheManager {
                 None
             } else {
                 // Update access metadata
-                entry.access();
+                entry.access(self.entry_counter.fetch_add(1, Ordering::Relaxed));
                 self.hit_counter.fetch_add(1, Ordering::Relaxed);
                 tracing::debug!("Cache hit for key: {}", cache_key);
                 Some(entry.response.clone())
             }
+        } else if let Some(response) = self
+            .config
+            .reuse_longer_max_tokens
+            .then(|| self.reuse_longer_entry(&mut cache, request))
+            .flatten()
+        {
+            self.hit_counter.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("Cache hit for key: {} (truncated from longer max_tokens)", cache_key);
+            Some(response)
         } else {
             self.miss_counter.fetch_add(1, Ordering::Relaxed);
             tracing::debug!("Cache miss for key: {}", cache_key);
@@ -213,8 +374,9 @@ impl CacheManager {
         }
 
         let cache_key = self.generate_cache_key(request);
+        let base_key = self.generate_base_key(request);
         let entry_order = self.entry_counter.fetch_add(1, Ordering::Relaxed);
-        let entry = CacheEntry::new(response, entry_order);
+        let entry = CacheEntry::new(response, entry_order, base_key, request.max_tokens);
 
         let mut cache = self.cache.write().await;
 
@@ -230,7 +392,7 @@ impl CacheManager {
     }
 
     /// Evict entries based on configured strategy
-    async fn evict_entries(&self, cache: &mut HashMap<String, CacheEntry>) {
+    async fn evict_entries(&self, cache: &mut CacheStore) {
         if cache.is_empty() {
             return;
         }
@@ -242,7 +404,7 @@ impl CacheManager {
             EvictionStrategy::LRU => {
                 // Remove least recently used entries
                 let mut entries: Vec<_> = cache.iter().collect();
-                entries.sort_by_key(|(_, entry)| entry.last_accessed);
+                entries.sort_by_key(|(_, entry)| entry.last_access_order);
 
                 for (key, _) in entries.iter().take(entries_to_remove) {
                     keys_to_remove.push((*key).clone());
@@ -271,6 +433,7 @@ impl CacheManager {
         for key in keys_to_remove {
             cache.remove(&key);
         }
+        self.eviction_counter.fetch_add(entries_to_remove as u64, Ordering::Relaxed);
 
         tracing::debug!("Evicted {} entries using {:?} strategy", entries_to_remove, self.config.eviction_strategy);
     }
@@ -280,7 +443,7 @@ impl CacheManager {
         let mut cache = self.cache.write().await;
         let initial_size = cache.len();
 
-        cache.retain(|_, entry| !entry.is_expired(self.config.ttl_seconds));
+        cache.retain(|entry| !entry.is_expired(self.config.ttl_seconds));
 
         let removed = initial_size - cache.len();
         if removed > 0 {
@@ -315,6 +478,7 @@ impl CacheManager {
         CacheStats {
             hits,
             misses,
+            evictions: self.eviction_counter.load(Ordering::Relaxed),
             hit_rate,
             current_size,
             max_size: self.config.max_size,
@@ -355,6 +519,8 @@ pub struct CacheStats {
     pub hits: u64,
     /// Number of cache misses
     pub misses: u64,
+    /// Number of entries evicted to stay within `max_size`
+    pub evictions: u64,
     /// Cache hit rate (0.0 to 1.0)
     pub hit_rate: f64,
     /// Current number of cached entries
@@ -365,4 +531,157 @@ pub struct CacheStats {
     pub memory_usage_bytes: usize,
     /// Cache configuration
     pub config: CacheConfig,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{Choice, Message, Usage};
+
+    fn request(max_tokens: u32) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: Some("test-model".to_string()),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: Some("Tell me a story".to_string()),
+                name: None,
+                tool_calls: None,
+                function_call: None,
+                tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
+            }],
+            max_tokens: Some(max_tokens),
+            ..Default::default()
+        }
+    }
+
+    fn response(content: &str, completion_tokens: u32) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: "chatcmpl-test".to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "test-model".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: "assistant".to_string(),
+                    content: Some(content.to_string()),
+                    name: None,
+                    tool_calls: None,
+                    function_call: None,
+                    tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
+                },
+                finish_reason: "stop".to_string(),
+                logprobs: None,
+                content_filter_results: None,
+            }],
+            usage: Some(Usage {
+                prompt_tokens: 10,
+                completion_tokens,
+                total_tokens: 10 + completion_tokens,
+            }),
+            prompt_filter_results: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reuses_longer_max_tokens_entry_truncated() {
+        let cache = CacheManager::new(CacheConfig {
+            reuse_longer_max_tokens: true,
+            min_response_size: 0,
+            ..CacheConfig::default()
+        });
+
+        // 100 tokens of 4 characters each
+        let content = "abc ".repeat(100);
+        cache.put(&request(100), response(&content, 100)).await.unwrap();
+
+        let reused = cache.get(&request(50)).await.expect("should reuse the 100-token entry");
+        let choice = &reused.choices[0];
+        assert_eq!(choice.message.content.as_deref(), Some("abc ".repeat(50).as_str()));
+        assert_eq!(choice.finish_reason, "length");
+        let usage = reused.usage.unwrap();
+        assert_eq!(usage.completion_tokens, 50);
+        assert_eq!(usage.total_tokens, 60);
+
+        // A larger request can never be served from a shorter entry
+        assert!(cache.get(&request(200)).await.is_none());
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+    }
+
+    #[test]
+    fn test_truncation_budgets_each_choice() {
+        // Two choices of 100 tokens each, usage counting both
+        let mut cached = response(&"abc ".repeat(100), 200);
+        cached.choices.push(cached.choices[0].clone());
+
+        let truncated = truncate_response(&cached, 50);
+
+        for choice in &truncated.choices {
+            assert_eq!(choice.message.content.as_deref(), Some("abc ".repeat(50).as_str()));
+        }
+        assert_eq!(truncated.usage.unwrap().completion_tokens, 100);
+    }
+
+    #[tokio::test]
+    async fn test_longer_max_tokens_reuse_disabled_by_default() {
+        let cache = CacheManager::new(CacheConfig {
+            min_response_size: 0,
+            ..CacheConfig::default()
+        });
+
+        cache.put(&request(100), response(&"abc ".repeat(100), 100)).await.unwrap();
+
+        assert!(cache.get(&request(50)).await.is_none());
+        assert!(cache.get(&request(100)).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_entry_evicted_at_max_size() {
+        let cache = CacheManager::new(CacheConfig {
+            max_size: 2,
+            min_response_size: 0,
+            ..CacheConfig::default()
+        });
+
+        cache.put(&request(10), response("first", 10)).await.unwrap();
+        cache.put(&request(20), response("second", 20)).await.unwrap();
+        assert!(cache.get(&request(10)).await.is_some());
+        cache.put(&request(30), response("third", 30)).await.unwrap();
+
+        assert!(cache.get(&request(10)).await.is_some());
+        assert!(cache.get(&request(20)).await.is_none());
+        assert!(cache.get(&request(30)).await.is_some());
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.current_size, 2);
+    }
+
+    #[tokio::test]
+    async fn test_longer_max_tokens_reuse_follows_evictions() {
+        let cache = CacheManager::new(CacheConfig {
+            max_size: 2,
+            reuse_longer_max_tokens: true,
+            min_response_size: 0,
+            ..CacheConfig::default()
+        });
+
+        cache.put(&request(200), response(&"abc ".repeat(200), 200)).await.unwrap();
+        cache.put(&request(100), response(&"xyz ".repeat(100), 100)).await.unwrap();
+
+        // The smallest larger entry is preferred
+        let reused = cache.get(&request(50)).await.unwrap();
+        assert_eq!(reused.choices[0].message.content.as_deref(), Some("xyz ".repeat(50).as_str()));
+
+        // Evicts the 200-token entry, which was used least recently
+        cache.put(&request(10), response("short", 10)).await.unwrap();
+        assert!(cache.get(&request(150)).await.is_none());
+        assert!(cache.get(&request(50)).await.is_some());
+    }
+}
@@ -28,6 +28,9 @@ pub struct ClientConfig {
     pub retry_attempts: u32,
     pub retry_base_delay: Duration,
     pub max_retry_delay: Duration,
+    /// Cap on the cumulative time spent across all attempts and backoffs.
+    /// Retries stop at whichever of this budget or `retry_attempts` is hit first.
+    pub retry_total_budget_ms: Option<u64>,
 }
 
 impl Default for ClientConfig {
@@ -40,6 +43,7 @@ impl Default for ClientConfig {
             retry_attempts: 3,
             retry_base_delay: Duration::from_millis(100),
             max_retry_delay: Duration::from_secs(5),
+            retry_total_budget_ms: None,
         }
     }
 }
@@ -166,6 +170,7 @@ impl HighPerformanceClient {
         let mut attempt = 0;
         let mut last_error = None;
         let start_time = Instant::now();
+        let retry_budget = self.config.retry_total_budget_ms.map(Duration::from_millis);
 
         while attempt < self.config.retry_attempts {
             attempt += 1;
@@ -176,9 +181,16 @@ impl HighPerformanceClient {
                 return Err(ClientError::DeadlineExceeded);
             }
 
-            let attempt_timeout = remaining_time - elapsed;
+            // Never let a single attempt outlive the retry budget
+            let mut attempt_timeout = remaining_time - elapsed;
+            if let Some(budget) = retry_budget {
+                if elapsed >= budget {
+                    return Err(ClientError::RetryBudgetExhausted { attempts: attempt - 1 });
+                }
+                attempt_timeout = attempt_timeout.min(budget - elapsed);
+            }
             
-            match self.make_single_request(body, attempt_timeout, idempotency_key).await {
+            let retry_delay = match self.make_single_request(body, attempt_timeout, idempotency_key).await {
                 Ok(response) => {
                     // Parse response efficiently
                     let data: Value = response.json().await.map_err(ClientError::ParseError)?;
@@ -186,36 +198,37 @@ impl HighPerformanceClient {
                 }
                 Err(ClientError::RateLimited { retry_after }) => {
                     // Respect Retry-After header
-                    let retry_delay = Duration::from_secs(retry_after);
-                    if elapsed + retry_delay >= remaining_time {
-                        return Err(ClientError::DeadlineExceeded);
-                    }
-                    tokio::time::sleep(retry_delay).await;
                     last_error = Some(ClientError::RateLimited { retry_after });
+                    Duration::from_secs(retry_after)
                 }
                 Err(ClientError::ServerError(_)) => {
                     // Retry on 5xx errors
-                    let backoff = self.calculate_backoff(attempt);
-                    if elapsed + backoff >= remaining_time {
-                        return Err(ClientError::DeadlineExceeded);
-                    }
-                    tokio::time::sleep(backoff).await;
                     last_error = Some(ClientError::ServerError(500));
+                    self.calculate_backoff(attempt)
                 }
                 Err(ClientError::Timeout) => {
                     // Retry on timeouts
-                    let backoff = self.calculate_backoff(attempt);
-                    if elapsed + backoff >= remaining_time {
-                        return Err(ClientError::DeadlineExceeded);
-                    }
-                    tokio::time::sleep(backoff).await;
                     last_error = Some(ClientError::Timeout);
+                    self.calculate_backoff(attempt)
                 }
                 Err(e) => {
                     // Don't retry on client errors (4xx except 429)
                     return Err(e);
                 }
+            };
+
+            if elapsed + retry_delay >= remaining_time {
+                return Err(ClientError::DeadlineExceeded);
+            }
+
+            // Stop once the next backoff would exhaust the total retry budget
+            if let Some(budget) = retry_budget {
+                if attempt < self.config.retry_attempts && start_time.elapsed() + retry_delay >= budget {
+                    return Err(ClientError::RetryBudgetExhausted { attempts: attempt });
+                }
             }
+
+            tokio::time::sleep(retry_delay).await;
         }
 
         Err(last_error.unwrap_or(ClientError::MaxRetriesExceeded))
@@ -371,6 +384,8 @@ pub enum ClientError {
     ConcurrencyLimit,
     #[error("Max retries exceeded")]
     MaxRetriesExceeded,
+    #[error("Retry time budget exhausted after {attempts} attempts")]
+    RetryBudgetExhausted { attempts: u32 },
     #[error("Parse error: {0}")]
     ParseError(reqwest::Error),
     #[error("Stream error: {0}")]
@@ -418,4 +433,36 @@ mod tests {
         // Result depends on whether Mockoon is running
         println!("Result: {:?}", result);
     }
+
+    #[tokio::test]
+    async fn test_retries_stop_when_time_budget_elapses() {
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_delay(Duration::from_millis(150)))
+            .mount(&server)
+            .await;
+
+        let config = ClientConfig {
+            base_url: server.uri(),
+            retry_attempts: 20,
+            retry_base_delay: Duration::from_millis(50),
+            max_retry_delay: Duration::from_millis(50),
+            retry_total_budget_ms: Some(600),
+            ..Default::default()
+        };
+        let client = HighPerformanceClient::new(config).unwrap();
+
+        let started = Instant::now();
+        let deadline = started + Duration::from_secs(30);
+        let messages = vec![serde_json::json!({"role": "user", "content": "test"})];
+        let result = client.chat_completion(messages, deadline).await;
+        let elapsed = started.elapsed();
+
+        let attempts = server.received_requests().await.unwrap().len();
+        assert!(matches!(result, Err(ClientError::RetryBudgetExhausted { .. })), "got {:?}", result);
+        assert!(attempts >= 2 && attempts < 20, "made {} attempts", attempts);
+        assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+    }
 }
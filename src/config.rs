@@ -1,7 +1,9 @@
 #[cfg(feature = "cli")]
 use clap::Parser;
+use std::collections::HashMap;
 use std::env;
 use url::Url;
+use crate::schemas::STRIPPABLE_PARAMETERS;
 
 /// # NexusNitroLLM Configuration
 /// 
@@ -25,6 +27,64 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "HOST", default_value = "0.0.0.0"))]
     pub host: String,
 
+    /// Maximum number of client connections served concurrently (0 = unlimited)
+    #[cfg_attr(feature = "cli", arg(long, env = "MAX_CONCURRENT_CONNECTIONS", default_value = "1024"))]
+    pub max_concurrent_connections: usize,
+
+    /// What to do with connections beyond the limit: "wait" for a free slot or "drop" them
+    #[cfg_attr(feature = "cli", arg(long, env = "CONNECTION_LIMIT_BEHAVIOR", default_value = "wait"))]
+    pub connection_limit_behavior: String,
+
+    /// HTTP version spoken with clients: "http1", "http2" (h2c with prior
+    /// knowledge) or "auto" to detect it per connection
+    #[cfg_attr(feature = "cli", arg(long, env = "INBOUND_PROTOCOL", default_value = "auto"))]
+    pub inbound_protocol: String,
+
+    /// Seconds open connections may keep running after a shutdown signal
+    /// before they are closed forcibly
+    #[cfg_attr(feature = "cli", arg(long, env = "SHUTDOWN_DRAIN_TIMEOUT_SECS", default_value = "30"))]
+    pub shutdown_drain_timeout_secs: u64,
+
+    /// Largest request body accepted by the API routes, in bytes (0 = unlimited);
+    /// larger requests are rejected with 413 (UI proxy routes are not limited)
+    #[cfg_attr(feature = "cli", arg(long, env = "MAX_REQUEST_BODY_BYTES", default_value = "2097152"))]
+    pub max_request_body_bytes: usize,
+
+    /// Per-model limit on concurrent backend requests; requests beyond it queue
+    /// (e.g. "llama-70b=2,gpt-4o=16")
+    #[cfg_attr(feature = "cli", arg(long, env = "MODEL_CONCURRENCY_LIMITS"))]
+    pub model_concurrency_limits: Option<String>,
+
+    /// Share a model's concurrency slots fairly between API keys instead of
+    /// first-come-first-served, so one busy key cannot starve the others
+    #[cfg_attr(feature = "cli", arg(long, env = "FAIR_QUEUING", default_value = "false"))]
+    pub fair_queuing: bool,
+
+    /// Relative share of queued slots per API key under fair queuing; keys not
+    /// listed have weight 1 (e.g. "sk-batch=1,sk-interactive=4")
+    #[cfg_attr(feature = "cli", arg(long, env = "FAIR_QUEUE_WEIGHTS"))]
+    pub fair_queue_weights: Option<String>,
+
+    /// Cheaper models served instead of expensive ones while under load
+    /// (e.g. "gpt-4o=gpt-4o-mini,llama-70b=llama-8b")
+    #[cfg_attr(feature = "cli", arg(long, env = "LOAD_SHEDDING"))]
+    pub load_shedding: Option<String>,
+
+    /// Queue depth of an expensive model at which its requests are downgraded
+    #[cfg_attr(feature = "cli", arg(long, env = "LOAD_SHEDDING_QUEUE_DEPTH", default_value = "8"))]
+    pub load_shedding_queue_depth: usize,
+
+    /// p95 latency in milliseconds of an expensive model at which its requests
+    /// are downgraded (0 disables the latency trigger)
+    #[cfg_attr(feature = "cli", arg(long, env = "LOAD_SHEDDING_P95_MS", default_value = "0"))]
+    pub load_shedding_p95_ms: u64,
+
+    /// Backends chosen by estimated prompt size, each serving prompts of up to
+    /// the given number of tokens (e.g. "2000=http://small:8000,32000=http://large:8000");
+    /// larger prompts go to `backend_url`
+    #[cfg_attr(feature = "cli", arg(long, env = "SIZE_ROUTING"))]
+    pub size_routing: Option<String>,
+
     // =============================================================================
     // LLM BACKEND CONFIGURATION
     // =============================================================================
@@ -33,10 +93,21 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "nnLLM_URL", default_value = "http://localhost:8000"))]
     pub backend_url: String,
 
-    /// LLM backend type (lightllm, vllm, openai, azure, aws, etc.)
-    #[cfg_attr(feature = "cli", arg(long, env = "nnLLM_BACKEND_TYPE", default_value = "lightllm"))]
+    /// LLM backend type (lightllm, vllm, openai, azure, aws, custom, template,
+    /// direct); "auto" detects it from keywords in the backend URL
+    #[cfg_attr(feature = "cli", arg(long, env = "nnLLM_BACKEND_TYPE", default_value = "auto"))]
     pub backend_type: String,
 
+    /// JSON file with the request body template and response JSONPath used by
+    /// the "template" backend type
+    #[cfg_attr(feature = "cli", arg(long, env = "REQUEST_TEMPLATE_FILE"))]
+    pub request_template_file: Option<String>,
+
+    /// JSON file overriding the role markers LightLLM prompts are built with
+    /// (any of "system", "user", "assistant", "tool", "tool_call", "generation")
+    #[cfg_attr(feature = "cli", arg(long, env = "LIGHTLLM_PROMPT_TEMPLATE_FILE"))]
+    pub lightllm_prompt_template_file: Option<String>,
+
     /// Default model ID to use (set to "auto" for automatic detection)
     #[cfg_attr(feature = "cli", arg(long, env = "nnLLM_MODEL", default_value = "llama"))]
     pub model_id: String,
@@ -45,6 +116,45 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "nnLLM_TOKEN"))]
     pub backend_token: Option<String>,
 
+    /// AWS region to sign Bedrock requests for (e.g. "eu-central-1"); by
+    /// default the region is taken from the backend URL host
+    #[cfg_attr(feature = "cli", arg(long, env = "AWS_REGION"))]
+    pub aws_region: Option<String>,
+
+    /// AWS regions to fail over to, in order, when the primary Bedrock region
+    /// throttles or returns a server error (e.g. "us-west-2,eu-central-1")
+    #[cfg_attr(feature = "cli", arg(long, env = "AWS_FAILOVER_REGIONS"))]
+    pub aws_failover_regions: Option<String>,
+
+    /// Static headers sent with every backend request (e.g. "anthropic-version=2023-06-01,x-deployment=eu")
+    #[cfg_attr(feature = "cli", arg(long, env = "BACKEND_DEFAULT_HEADERS"))]
+    pub backend_default_headers: Option<String>,
+
+    /// How usage tokens are counted for backends that do not report them:
+    /// "heuristic" (4 bytes per token) or "bpe" (model vocabulary, requires the
+    /// `tokenizer` feature)
+    #[cfg_attr(feature = "cli", arg(long, env = "TOKEN_COUNTER", default_value = "heuristic"))]
+    pub token_counter: String,
+
+    /// Let clients supply their own backend credential (bring your own key)
+    /// in `byok_header`, used instead of `backend_token` for that request
+    #[cfg_attr(feature = "cli", arg(long, env = "ALLOW_BYOK", default_value = "false"))]
+    pub allow_byok: bool,
+
+    /// Request header carrying a client's own backend credential; never logged
+    #[cfg_attr(feature = "cli", arg(long, env = "BYOK_HEADER", default_value = "x-upstream-authorization"))]
+    pub byok_header: String,
+
+    /// Model name prefixes of reasoning models that accept `reasoning_effort`;
+    /// it is dropped for other models on OpenAI and Azure (e.g. "o1,o3,o4")
+    #[cfg_attr(feature = "cli", arg(long, env = "REASONING_MODELS", default_value = "o1,o3,o4"))]
+    pub reasoning_models: String,
+
+    /// Send `system` messages to reasoning models as `developer` messages,
+    /// the role those models expect; disable for backends without the rename
+    #[cfg_attr(feature = "cli", arg(long, env = "REASONING_DEVELOPER_ROLE", default_value = "true"))]
+    pub reasoning_developer_role: bool,
+
     // =============================================================================
     // UI CONFIGURATION
     // =============================================================================
@@ -73,6 +183,54 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "LITELLM_VIRTUAL_KEY"))]
     pub litellm_virtual_key: Option<String>,
 
+    // =============================================================================
+    // AZURE OPENAI CONFIGURATION
+    // =============================================================================
+
+    /// Model name to Azure deployment name mapping (e.g. "gpt-4o=prod-gpt4o,gpt-35-turbo=chat")
+    #[cfg_attr(feature = "cli", arg(long, env = "AZURE_DEPLOYMENT_MAP"))]
+    pub azure_deployment_map: Option<String>,
+
+    /// Return Azure content-filter blocks (HTTP 400 with filter results) as a
+    /// completion with finish_reason "content_filter" instead of an error
+    #[cfg_attr(feature = "cli", arg(long, env = "AZURE_CONTENT_FILTER_AS_COMPLETION", default_value = "true"))]
+    pub azure_content_filter_as_completion: bool,
+
+    /// Azure OpenAI `api-version` query parameter (YYYY-MM-DD or YYYY-MM-DD-preview);
+    /// empty uses the adapter's default version
+    #[cfg_attr(feature = "cli", arg(long, env = "AZURE_API_VERSION", default_value = "2023-12-01-preview"))]
+    pub azure_api_version: String,
+
+    // =============================================================================
+    // ANTHROPIC CONFIGURATION
+    // =============================================================================
+
+    /// Add prompt caching breakpoints (`cache_control: ephemeral`) to the system
+    /// prompt of requests sent to Anthropic
+    #[cfg_attr(feature = "cli", arg(long, env = "ANTHROPIC_PROMPT_CACHING", default_value = "false"))]
+    pub anthropic_prompt_caching: bool,
+
+    /// Number of most recent turns that also get a caching breakpoint (at most 3)
+    #[cfg_attr(feature = "cli", arg(long, env = "ANTHROPIC_CACHE_LAST_TURNS", default_value = "0"))]
+    pub anthropic_cache_last_turns: usize,
+
+    // =============================================================================
+    // REQUEST SIGNING (CUSTOM GATEWAYS)
+    // =============================================================================
+
+    /// Shared secret used to HMAC-sign requests sent by the custom adapter
+    #[cfg_attr(feature = "cli", arg(long, env = "SIGNING_SECRET"))]
+    pub signing_secret: Option<String>,
+
+    /// Headers covered by the signature, in order; "body" signs the request body
+    /// (e.g. "x-client-id,content-type,body")
+    #[cfg_attr(feature = "cli", arg(long, env = "SIGNING_HEADERS", default_value = "body"))]
+    pub signing_headers: String,
+
+    /// Header that carries the request signature
+    #[cfg_attr(feature = "cli", arg(long, env = "SIGNING_HEADER_NAME", default_value = "x-signature"))]
+    pub signing_header_name: String,
+
     // =============================================================================
     // PERFORMANCE AND OPTIMIZATION
     // =============================================================================
@@ -81,6 +239,11 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "HTTP_CLIENT_TIMEOUT", default_value = "30"))]
     pub http_client_timeout: u64,
 
+    /// Seconds to wait for a backend to start responding before giving up,
+    /// independent of the overall request timeout (0 disables)
+    #[cfg_attr(feature = "cli", arg(long, env = "FIRST_BYTE_TIMEOUT", default_value = "0"))]
+    pub first_byte_timeout: u64,
+
     /// Maximum number of HTTP connections
     #[cfg_attr(feature = "cli", arg(long, env = "HTTP_CLIENT_MAX_CONNECTIONS", default_value = "100"))]
     pub http_client_max_connections: usize,
@@ -89,6 +252,32 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "HTTP_CLIENT_MAX_CONNECTIONS_PER_HOST", default_value = "10"))]
     pub http_client_max_connections_per_host: usize,
 
+    /// Request compressed upstream responses and decode them transparently
+    #[cfg_attr(feature = "cli", arg(long, env = "HTTP_CLIENT_COMPRESSION", default_value = "true"))]
+    pub http_client_compression: bool,
+
+    /// Talk HTTP/2 to plaintext backends without negotiation (h2c prior knowledge),
+    /// multiplexing concurrent requests and streams over pooled connections.
+    /// HTTPS backends negotiate HTTP/2 via ALPN regardless of this setting.
+    #[cfg_attr(feature = "cli", arg(long, env = "HTTP_CLIENT_HTTP2_PRIOR_KNOWLEDGE", default_value = "false"))]
+    pub http_client_http2_prior_knowledge: bool,
+
+    /// Grow HTTP/2 flow-control windows adaptively so many concurrent streams
+    /// on one connection are not throttled by the default window size
+    #[cfg_attr(feature = "cli", arg(long, env = "HTTP_CLIENT_HTTP2_ADAPTIVE_WINDOW", default_value = "true"))]
+    pub http_client_http2_adaptive_window: bool,
+
+    /// Milliseconds a request may wait for one of `http_client_max_connections`
+    /// upstream connections before pool exhaustion is reported (0 disables the
+    /// connection cap and its tracking)
+    #[cfg_attr(feature = "cli", arg(long, env = "POOL_EXHAUSTION_THRESHOLD_MS", default_value = "0"))]
+    pub pool_exhaustion_threshold_ms: u64,
+
+    /// Fail requests with 503 once the pool exhaustion threshold is reached
+    /// instead of waiting for a connection
+    #[cfg_attr(feature = "cli", arg(long, env = "POOL_EXHAUSTION_FAIL_FAST", default_value = "false"))]
+    pub pool_exhaustion_fail_fast: bool,
+
     /// Streaming chunk size in bytes
     #[cfg_attr(feature = "cli", arg(long, env = "STREAMING_CHUNK_SIZE", default_value = "1024"))]
     pub streaming_chunk_size: usize,
@@ -125,14 +314,345 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "ENABLE_METRICS", default_value = "true"))]
     pub enable_metrics: bool,
 
+    /// Path of the metrics endpoint (empty disables the endpoint)
+    #[cfg_attr(feature = "cli", arg(long, env = "METRICS_ENDPOINT", default_value = "/metrics"))]
+    pub metrics_endpoint: String,
+
     /// Enable health checks
     #[cfg_attr(feature = "cli", arg(long, env = "ENABLE_HEALTH_CHECKS", default_value = "true"))]
     pub enable_health_checks: bool,
 
-    /// Force specific adapter (auto, lightllm, openai)
+    /// Seconds between background backend probes; `/health` reuses a probe
+    /// this recent instead of calling the backend (0 disables background probes)
+    #[cfg_attr(feature = "cli", arg(long, env = "HEALTH_PROBE_INTERVAL_SECS", default_value = "30"))]
+    pub health_probe_interval_secs: u64,
+
+    /// Minimum seconds between probes triggered by health checks, including
+    /// `?deep=true`; each probe is a billed completion and the health routes
+    /// are unauthenticated, so more frequent checks reuse the latest probe
+    #[cfg_attr(feature = "cli", arg(long, env = "HEALTH_PROBE_MIN_INTERVAL_SECS", default_value = "10"))]
+    pub health_probe_min_interval_secs: u64,
+
+    /// Error rate of real requests (0.0 to 1.0) above which `/health/ready`
+    /// reports not-ready
+    #[cfg_attr(feature = "cli", arg(long, env = "READINESS_ERROR_RATE_THRESHOLD", default_value = "0.5"))]
+    pub readiness_error_rate_threshold: f64,
+
+    /// Sliding window in seconds over which the readiness error rate is measured
+    /// (0 disables the error-rate readiness check)
+    #[cfg_attr(feature = "cli", arg(long, env = "READINESS_ERROR_WINDOW_SECS", default_value = "30"))]
+    pub readiness_error_window_secs: u64,
+
+    /// Minimum requests within the window before the error rate can flip readiness
+    #[cfg_attr(feature = "cli", arg(long, env = "READINESS_MIN_REQUESTS", default_value = "10"))]
+    pub readiness_min_requests: u64,
+
+    /// Per-model latency targets in milliseconds for SLO compliance tracking
+    /// (e.g. "gpt-4o=2000,llama-70b=5000")
+    #[cfg_attr(feature = "cli", arg(long, env = "SLA_TARGET_MS"))]
+    pub sla_target_ms: Option<String>,
+
+    /// Webhook notified with a JSON alert when a model's SLO compliance drops
+    /// below `sla_alert_below_percent`
+    #[cfg_attr(feature = "cli", arg(long, env = "SLA_ALERT_WEBHOOK_URL"))]
+    pub sla_alert_webhook_url: Option<String>,
+
+    /// SLO compliance percentage (0 to 100) below which an SLA alert fires
+    #[cfg_attr(feature = "cli", arg(long, env = "SLA_ALERT_BELOW_PERCENT", default_value = "95.0"))]
+    pub sla_alert_below_percent: f64,
+
+    /// Sliding window in seconds over which SLO compliance is checked for alerts
+    #[cfg_attr(feature = "cli", arg(long, env = "SLA_ALERT_WINDOW_SECS", default_value = "300"))]
+    pub sla_alert_window_secs: u64,
+
+    /// Emit x-request-duration-ms and x-upstream-duration-ms response headers
+    #[cfg_attr(feature = "cli", arg(long, env = "ENABLE_TIMING_HEADERS", default_value = "true"))]
+    pub enable_timing_headers: bool,
+
+    /// Echo the request ID (x-request-id, or a generated one) in the response
+    /// header and in the `error` object of chat completion error bodies
+    #[cfg_attr(feature = "cli", arg(long, env = "RETURN_REQUEST_ID", default_value = "true"))]
+    pub return_request_id: bool,
+
+    /// Force a specific adapter regardless of `backend_type` (auto, lightllm,
+    /// vllm, openai, azure, aws, custom, template, direct)
     #[cfg_attr(feature = "cli", arg(long, env = "FORCE_ADAPTER", default_value = "auto"))]
     pub force_adapter: String,
 
+    // =============================================================================
+    // RETRY CONFIGURATION
+    // =============================================================================
+
+    /// Number of times a failed upstream request is retried (0 disables retries)
+    #[cfg_attr(feature = "cli", arg(long, env = "UPSTREAM_MAX_RETRIES", default_value = "0"))]
+    pub upstream_max_retries: u32,
+
+    /// Initial backoff between upstream retries in milliseconds (doubles per retry)
+    #[cfg_attr(feature = "cli", arg(long, env = "UPSTREAM_RETRY_BACKOFF_MS", default_value = "100"))]
+    pub upstream_retry_backoff_ms: u64,
+
+    /// Upstream HTTP statuses retried, comma-separated
+    #[cfg_attr(feature = "cli", arg(long, env = "UPSTREAM_RETRYABLE_STATUS_CODES", default_value = "408,429,500,502,503,504"))]
+    pub upstream_retryable_status_codes: String,
+
+    /// Retry upstream responses whose body ended before its declared
+    /// Content-Length, as a dropped connection does
+    #[cfg_attr(feature = "cli", arg(long, env = "UPSTREAM_RETRY_INCOMPLETE", default_value = "true"))]
+    pub upstream_retry_incomplete: bool,
+
+    /// Seconds to keep retrying while the backend answers 503 "model is
+    /// loading" (0 returns a model_loading error right away)
+    #[cfg_attr(feature = "cli", arg(long, env = "MODEL_LOADING_WAIT_SECS", default_value = "0"))]
+    pub model_loading_wait_secs: u64,
+
+    /// Upper bound for the per-request X-Max-Retries header
+    #[cfg_attr(feature = "cli", arg(long, env = "MAX_RETRIES_CEILING", default_value = "5"))]
+    pub max_retries_ceiling: u32,
+
+    /// Request header in which clients pass their deadline, as milliseconds
+    /// from now or an absolute Unix time in milliseconds (empty disables)
+    #[cfg_attr(feature = "cli", arg(long, env = "REQUEST_DEADLINE_HEADER", default_value = "x-request-deadline"))]
+    pub request_deadline_header: String,
+
+    // =============================================================================
+    // RESPONSE HANDLING
+    // =============================================================================
+
+    /// Assistant message returned instead of backend safety refusals (finish_reason "content_filter")
+    #[cfg_attr(feature = "cli", arg(long, env = "REFUSAL_FALLBACK_MESSAGE"))]
+    pub refusal_fallback_message: Option<String>,
+
+    /// Backend URL that non-streaming requests blocked by a content filter
+    /// (finish_reason "content_filter" or a safety block error) are retried
+    /// against, e.g. one with a more permissive filter
+    #[cfg_attr(feature = "cli", arg(long, env = "RETRY_ON_CONTENT_FILTER_BACKEND"))]
+    pub retry_on_content_filter_backend: Option<String>,
+
+    /// Parameters removed from requests before dispatch because the backend
+    /// rejects them, per model with '*' for every model; parameters are
+    /// separated by '|' (e.g. "mistral-large=presence_penalty|frequency_penalty,*=logit_bias")
+    #[cfg_attr(feature = "cli", arg(long, env = "UNSUPPORTED_PARAMETERS"))]
+    pub unsupported_parameters: Option<String>,
+
+    /// Leading boilerplate removed from assistant content, separated by '|'
+    /// (e.g. "Assistant:|As an AI model,")
+    #[cfg_attr(feature = "cli", arg(long, env = "RESPONSE_STRIP_PREFIXES"))]
+    pub response_strip_prefixes: Option<String>,
+
+    /// Repair nearly-valid JSON (code fences, trailing commas, surrounding text)
+    /// in responses to `json_object`/`json_schema` requests
+    #[cfg_attr(feature = "cli", arg(long, env = "REPAIR_JSON_OUTPUT", default_value = "false"))]
+    pub repair_json_output: bool,
+
+    /// Treat a trailing assistant message as a prefill the reply must start
+    /// with: sent natively to Anthropic-format backends and requested with an
+    /// instruction from others
+    #[cfg_attr(feature = "cli", arg(long, env = "ASSISTANT_PREFILL", default_value = "false"))]
+    pub assistant_prefill: bool,
+
+    /// Streaming mode used when a client does not set `stream`
+    #[cfg_attr(feature = "cli", arg(long, env = "STREAM_DEFAULT", default_value = "false"))]
+    pub stream_default: bool,
+
+    /// Let clients request `n > 1` streams with each choice sent as one
+    /// contiguous sequence (header `x-stream-choices: separate`)
+    #[cfg_attr(feature = "cli", arg(long, env = "STREAM_CHOICE_DEMUX", default_value = "false"))]
+    pub stream_choice_demux: bool,
+
+    /// Let clients request `n > 1` candidates ranked best-first by mean token
+    /// logprob (header `x-rank-by: logprob`)
+    #[cfg_attr(feature = "cli", arg(long, env = "RANK_CHOICES", default_value = "false"))]
+    pub rank_choices: bool,
+
+    /// Handling of streams that end inside a tool call: "signal" sends a
+    /// `tool_calls_truncated` finish reason and an error event when the
+    /// reassembled arguments are incomplete JSON, "off" passes streams through
+    #[cfg_attr(feature = "cli", arg(long, env = "STREAM_TOOL_CALL_TRUNCATION", default_value = "signal"))]
+    pub stream_tool_call_truncation: String,
+
+    /// Handling of `reasoning_content` in responses and stream deltas:
+    /// "forward" passes it through, "strip" removes it and "move" renames it
+    /// to `reasoning_content_field`
+    #[cfg_attr(feature = "cli", arg(long, env = "REASONING_CONTENT", default_value = "forward"))]
+    pub reasoning_content: String,
+
+    /// Field that receives reasoning content when `reasoning_content` is "move"
+    #[cfg_attr(feature = "cli", arg(long, env = "REASONING_CONTENT_FIELD", default_value = "reasoning"))]
+    pub reasoning_content_field: String,
+
+    /// Override client streaming requests: "off" always buffers the full response
+    /// and returns it as JSON (empty or "none" leaves the client's choice)
+    #[cfg_attr(feature = "cli", arg(long, env = "STREAM_FORCE", default_value = "none"))]
+    pub stream_force: String,
+
+    /// Serve identical concurrent streaming requests from a single upstream
+    /// stream, fanning its chunks out to every waiting client
+    #[cfg_attr(feature = "cli", arg(long, env = "STREAM_DEDUP_ENABLED", default_value = "false"))]
+    pub stream_dedup_enabled: bool,
+
+    /// Compare the model a backend reports serving against the requested model:
+    /// "off", "warn" (log mismatches) or "reject" (fail the request)
+    #[cfg_attr(feature = "cli", arg(long, env = "PIN_MODEL_VERSION", default_value = "off"))]
+    pub pin_model_version: String,
+
+    /// How closely the served model must match when pinning: "exact", or
+    /// "snapshot" to also accept dated snapshots of the requested model
+    /// (e.g. "gpt-4-0613" for "gpt-4")
+    #[cfg_attr(feature = "cli", arg(long, env = "PIN_MODEL_TOLERANCE", default_value = "snapshot"))]
+    pub pin_model_tolerance: String,
+
+    /// JSON file mapping system prompt IDs to prompt text; clients reference a
+    /// prompt with `system_prompt_ref` instead of sending it on every request
+    #[cfg_attr(feature = "cli", arg(long, env = "SYSTEM_PROMPTS_FILE"))]
+    pub system_prompts_file: Option<String>,
+
+    /// Reject requests without a system (or developer) message with 400
+    #[cfg_attr(feature = "cli", arg(long, env = "REQUIRE_SYSTEM_MESSAGE", default_value = "false"))]
+    pub require_system_message: bool,
+
+    /// Reject requests whose tool messages have a `tool_call_id` that no
+    /// preceding assistant tool call made
+    #[cfg_attr(feature = "cli", arg(long, env = "VALIDATE_TOOL_CALL_IDS", default_value = "false"))]
+    pub validate_tool_call_ids: bool,
+
+    /// Language responses are requested in when the client sends no
+    /// X-Response-Language header (e.g. "es"; unset adds no instruction)
+    #[cfg_attr(feature = "cli", arg(long, env = "DEFAULT_RESPONSE_LANGUAGE"))]
+    pub default_response_language: Option<String>,
+
+    /// Where the response language instruction goes in an existing system
+    /// message: "append" or "prepend"
+    #[cfg_attr(feature = "cli", arg(long, env = "RESPONSE_LANGUAGE_PLACEMENT", default_value = "append"))]
+    pub response_language_placement: String,
+
+    // =============================================================================
+    // CONVERSATION STORAGE
+    // =============================================================================
+
+    /// Server-side conversation history for requests carrying a `conversation_id`:
+    /// "off", "memory" or "redis"
+    #[cfg_attr(feature = "cli", arg(long, env = "CONVERSATION_STORE", default_value = "off"))]
+    pub conversation_store: String,
+
+    /// Redis server holding conversations when `conversation_store` is "redis"
+    #[cfg_attr(feature = "cli", arg(long, env = "CONVERSATION_REDIS_URL", default_value = "redis://localhost:6379"))]
+    pub conversation_redis_url: String,
+
+    /// Seconds a conversation is kept after its last turn
+    #[cfg_attr(feature = "cli", arg(long, env = "CONVERSATION_TTL_SECS", default_value = "3600"))]
+    pub conversation_ttl_secs: u64,
+
+    /// Token budget of a stored conversation, counted with `token_counter` (0 = unlimited)
+    #[cfg_attr(feature = "cli", arg(long, env = "MAX_CONVERSATION_TOKENS", default_value = "0"))]
+    pub max_conversation_tokens: u64,
+
+    /// What happens when a conversation outgrows `max_conversation_tokens`:
+    /// "truncate" drops its oldest turns, "reject" refuses the new turn
+    #[cfg_attr(feature = "cli", arg(long, env = "CONVERSATION_BUDGET_STRATEGY", default_value = "truncate"))]
+    pub conversation_budget_strategy: String,
+
+    // =============================================================================
+    // REPLAY PROTECTION
+    // =============================================================================
+
+    /// Reject replayed API requests by their `X-Request-Nonce` and
+    /// `X-Request-Timestamp` headers, remembering seen nonces in "memory" or
+    /// "redis"; "off" disables the check
+    #[cfg_attr(feature = "cli", arg(long, env = "REPLAY_PROTECTION", default_value = "off"))]
+    pub replay_protection: String,
+
+    /// Redis server holding seen nonces when `replay_protection` is "redis"
+    #[cfg_attr(feature = "cli", arg(long, env = "REPLAY_REDIS_URL", default_value = "redis://localhost:6379"))]
+    pub replay_redis_url: String,
+
+    /// Maximum allowed skew of `X-Request-Timestamp` from the proxy clock, in
+    /// seconds; nonces are remembered for twice this window
+    #[cfg_attr(feature = "cli", arg(long, env = "REPLAY_WINDOW_SECS", default_value = "300"))]
+    pub replay_window_secs: u64,
+
+    // =============================================================================
+    // PROMPT CAPTURE
+    // =============================================================================
+
+    /// Fraction of chat completions (0.0 to 1.0) whose full request and response
+    /// are written to `prompt_capture_path` for model-quality debugging
+    #[cfg_attr(feature = "cli", arg(long, env = "PROMPT_CAPTURE_SAMPLE_RATE", default_value = "0.0"))]
+    pub prompt_capture_sample_rate: f64,
+
+    /// JSONL file that receives prompt capture records
+    #[cfg_attr(feature = "cli", arg(long, env = "PROMPT_CAPTURE_PATH"))]
+    pub prompt_capture_path: Option<String>,
+
+    /// JSON fields replaced with "[REDACTED]" in capture records, separated by commas
+    /// (e.g. "user,api_key")
+    #[cfg_attr(feature = "cli", arg(long, env = "PROMPT_CAPTURE_REDACT_FIELDS"))]
+    pub prompt_capture_redact_fields: Option<String>,
+
+    /// Capture every failed chat completion, whatever the sample rate, when a
+    /// capture file is configured
+    #[cfg_attr(feature = "cli", arg(long, env = "PROMPT_CAPTURE_ERRORS", default_value = "true"))]
+    pub prompt_capture_errors: bool,
+
+    // =============================================================================
+    // SHADOW TRAFFIC
+    // =============================================================================
+
+    /// Backend URL that receives a background copy of sampled chat completions
+    /// for validation; its responses are discarded and only its latency and
+    /// errors are recorded
+    #[cfg_attr(feature = "cli", arg(long, env = "SHADOW_BACKEND_URL"))]
+    pub shadow_backend_url: Option<String>,
+
+    /// Percentage of chat completions (0 to 100) copied to `shadow_backend_url`
+    #[cfg_attr(feature = "cli", arg(long, env = "SHADOW_PERCENTAGE", default_value = "0.0"))]
+    pub shadow_percentage: f64,
+
+    // =============================================================================
+    // CHAOS TESTING (ignored in production)
+    // =============================================================================
+
+    /// Inject artificial latency and errors to exercise client resilience
+    #[cfg_attr(feature = "cli", arg(long, env = "CHAOS_ENABLED", default_value = "false"))]
+    pub chaos_enabled: bool,
+
+    /// Delay added to every request in milliseconds
+    #[cfg_attr(feature = "cli", arg(long, env = "CHAOS_DELAY_MS", default_value = "0"))]
+    pub chaos_delay_ms: u64,
+
+    /// Probability (0.0 to 1.0) that a request fails with `chaos_error_status`
+    #[cfg_attr(feature = "cli", arg(long, env = "CHAOS_ERROR_RATE", default_value = "0.0"))]
+    pub chaos_error_rate: f64,
+
+    /// HTTP status returned for injected errors
+    #[cfg_attr(feature = "cli", arg(long, env = "CHAOS_ERROR_STATUS", default_value = "503"))]
+    pub chaos_error_status: u16,
+
+    // =============================================================================
+    // TOOL EXECUTION CONFIGURATION
+    // =============================================================================
+
+    /// Timeout for a single tool function execution in seconds (0 disables the timeout)
+    #[cfg_attr(feature = "cli", arg(long, env = "TOOL_EXECUTION_TIMEOUT", default_value = "30"))]
+    pub tool_execution_timeout: u64,
+
+    /// Maximum number of tool calls executed concurrently (0 is treated as 1)
+    #[cfg_attr(feature = "cli", arg(long, env = "MAX_CONCURRENT_TOOLS", default_value = "8"))]
+    pub max_concurrent_tools: usize,
+
+    /// Maximum tool-call/tool-result cycles in a tool loop (0 uses the default of 10)
+    #[cfg_attr(feature = "cli", arg(long, env = "MAX_TOOL_ITERATIONS", default_value = "10"))]
+    pub max_tool_iterations: usize,
+
+    /// How tool-call arguments appear in logs, tool call history and prompt
+    /// captures: "raw", "mask" (keep keys, mask values) or "drop"
+    #[cfg_attr(feature = "cli", arg(long, env = "TOOL_ARGUMENT_REDACTION", default_value = "raw"))]
+    pub tool_argument_redaction: String,
+
+    /// Path of the endpoint listing the server-side function registry
+    /// (empty disables the endpoint)
+    #[cfg_attr(feature = "cli", arg(long, env = "TOOLS_ENDPOINT", default_value = "/v1/tools"))]
+    pub tools_endpoint: String,
+
     // =============================================================================
     // LOGGING AND MONITORING
     // =============================================================================
@@ -141,6 +661,10 @@ pub struct Config {
     #[cfg_attr(feature = "cli", arg(long, env = "RUST_LOG", default_value = "info"))]
     pub log_level: String,
 
+    /// Log output format (text, json)
+    #[cfg_attr(feature = "cli", arg(long, env = "LOG_FORMAT", default_value = "text"))]
+    pub log_format: String,
+
     /// Enable backtrace on panic
     #[cfg_attr(feature = "cli", arg(long, env = "RUST_BACKTRACE"))]
     pub rust_backtrace: Option<String>,
@@ -153,7 +677,8 @@ pub struct Config {
     // SECURITY CONFIGURATION
     // =============================================================================
     
-    /// CORS origin (use * for development only)
+    /// CORS allowed origins, comma-separated (use * for development only).
+    /// Entries like `https://*.example.com` allow the domain and all its subdomains.
     #[cfg_attr(feature = "cli", arg(long, env = "CORS_ORIGIN", default_value = "*"))]
     pub cors_origin: String,
 
@@ -196,6 +721,14 @@ pub struct Config {
     /// Maximum cache size
     #[cfg_attr(feature = "cli", arg(long, env = "CACHE_MAX_SIZE", default_value = "1000"))]
     pub cache_max_size: usize,
+
+    /// Serve cache misses from entries cached with a larger max_tokens, truncated to fit
+    #[cfg_attr(feature = "cli", arg(long, env = "CACHE_REUSE_LONGER_MAX_TOKENS", default_value = "false"))]
+    pub cache_reuse_longer_max_tokens: bool,
+
+    /// JSONL file of chat completion requests run at startup to pre-populate the cache
+    #[cfg_attr(feature = "cli", arg(long, env = "CACHE_WARM_FILE"))]
+    pub cache_warm_file: Option<String>,
 }
 
 impl Config {
@@ -307,18 +840,54 @@ impl Config {
         Self {
             port: 8080,
             host: "127.0.0.1".to_string(),
+            max_concurrent_connections: 1024,
+            connection_limit_behavior: "wait".to_string(),
+            inbound_protocol: "auto".to_string(),
+            shutdown_drain_timeout_secs: 30,
+            max_request_body_bytes: 2 * 1024 * 1024,
+            model_concurrency_limits: None,
+            fair_queuing: false,
+            fair_queue_weights: None,
+            load_shedding: None,
+            load_shedding_queue_depth: 8,
+            load_shedding_p95_ms: 0,
+            size_routing: None,
             backend_url: "http://localhost:8000".to_string(),
-            backend_type: "lightllm".to_string(),
+            backend_type: "auto".to_string(),
+            request_template_file: None,
+            lightllm_prompt_template_file: None,
             model_id: "llama".to_string(),
             backend_token: None,
+            aws_region: None,
+            aws_failover_regions: None,
+            backend_default_headers: None,
+            token_counter: "heuristic".to_string(),
+            allow_byok: false,
+            byok_header: "x-upstream-authorization".to_string(),
+            reasoning_models: "o1,o3,o4".to_string(),
+            reasoning_developer_role: true,
             ui_username: None,
             ui_password: None,
             litellm_base_url: None,
             litellm_admin_token: None,
             litellm_virtual_key: None,
+            azure_deployment_map: None,
+            azure_content_filter_as_completion: true,
+            azure_api_version: "2023-12-01-preview".to_string(),
+            anthropic_prompt_caching: false,
+            anthropic_cache_last_turns: 0,
+            signing_secret: None,
+            signing_headers: "body".to_string(),
+            signing_header_name: "x-signature".to_string(),
             http_client_timeout: 30,
+            first_byte_timeout: 0,
             http_client_max_connections: 100,
             http_client_max_connections_per_host: 10,
+            http_client_compression: true,
+            http_client_http2_prior_knowledge: false,
+            http_client_http2_adaptive_window: true,
+            pool_exhaustion_threshold_ms: 0,
+            pool_exhaustion_fail_fast: false,
             streaming_chunk_size: 1024,
             streaming_timeout: 300,
             streaming_keep_alive_interval: 30,
@@ -327,9 +896,73 @@ impl Config {
             enable_rate_limiting: true,
             enable_caching: false,
             enable_metrics: true,
+            metrics_endpoint: "/metrics".to_string(),
             enable_health_checks: true,
+            health_probe_interval_secs: 30,
+            health_probe_min_interval_secs: 10,
+            readiness_error_rate_threshold: 0.5,
+            readiness_error_window_secs: 30,
+            readiness_min_requests: 10,
+            sla_target_ms: None,
+            sla_alert_webhook_url: None,
+            sla_alert_below_percent: 95.0,
+            sla_alert_window_secs: 300,
+            enable_timing_headers: true,
+            return_request_id: true,
             force_adapter: "auto".to_string(),
+            upstream_max_retries: 0,
+            upstream_retry_backoff_ms: 100,
+            upstream_retryable_status_codes: "408,429,500,502,503,504".to_string(),
+            upstream_retry_incomplete: true,
+            model_loading_wait_secs: 0,
+            max_retries_ceiling: 5,
+            request_deadline_header: "x-request-deadline".to_string(),
+            refusal_fallback_message: None,
+            retry_on_content_filter_backend: None,
+            unsupported_parameters: None,
+            response_strip_prefixes: None,
+            repair_json_output: false,
+            assistant_prefill: false,
+            stream_default: false,
+            stream_choice_demux: false,
+            rank_choices: false,
+            stream_tool_call_truncation: "signal".to_string(),
+            reasoning_content: "forward".to_string(),
+            reasoning_content_field: "reasoning".to_string(),
+            stream_force: "none".to_string(),
+            stream_dedup_enabled: false,
+            pin_model_version: "off".to_string(),
+            pin_model_tolerance: "snapshot".to_string(),
+            system_prompts_file: None,
+            require_system_message: false,
+            validate_tool_call_ids: false,
+            default_response_language: None,
+            response_language_placement: "append".to_string(),
+            conversation_store: "off".to_string(),
+            conversation_redis_url: "redis://localhost:6379".to_string(),
+            conversation_ttl_secs: 3600,
+            max_conversation_tokens: 0,
+            conversation_budget_strategy: "truncate".to_string(),
+            replay_protection: "off".to_string(),
+            replay_redis_url: "redis://localhost:6379".to_string(),
+            replay_window_secs: 300,
+            prompt_capture_sample_rate: 0.0,
+            prompt_capture_path: None,
+            prompt_capture_redact_fields: None,
+            prompt_capture_errors: true,
+            shadow_backend_url: None,
+            shadow_percentage: 0.0,
+            chaos_enabled: false,
+            chaos_delay_ms: 0,
+            chaos_error_rate: 0.0,
+            chaos_error_status: 503,
+            tool_execution_timeout: 30,
+            max_concurrent_tools: 8,
+            max_tool_iterations: 10,
+            tool_argument_redaction: "raw".to_string(),
+            tools_endpoint: "/v1/tools".to_string(),
             log_level: "info".to_string(),
+            log_format: "text".to_string(),
             rust_backtrace: None,
             environment: "development".to_string(),
             cors_origin: "*".to_string(),
@@ -341,6 +974,8 @@ impl Config {
             rate_limit_burst_size: 10,
             cache_ttl_seconds: 300,
             cache_max_size: 1000,
+            cache_reuse_longer_max_tokens: false,
+            cache_warm_file: None,
         }
     }
 
@@ -356,12 +991,33 @@ impl Config {
 
         // Initialize tracing subscriber with environment filter
         #[cfg(feature = "cli")]
-        let _ = tracing_subscriber::fmt()
+        {
+            use tracing_subscriber::util::SubscriberInitExt;
+            let _ = self.log_subscriber(std::io::stdout).try_init();
+        }
+    }
+
+    /// Build the tracing subscriber for the configured log level and format.
+    ///
+    /// With `log_format = "json"` every event is written as a single JSON
+    /// object whose fields (request_id, model, adapter, ...) are top-level keys.
+    #[cfg(feature = "cli")]
+    pub fn log_subscriber<W>(&self, make_writer: W) -> Box<dyn tracing::Subscriber + Send + Sync>
+    where
+        W: for<'writer> tracing_subscriber::fmt::MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        let builder = tracing_subscriber::fmt()
             .with_env_filter(&self.log_level)
+            .with_writer(make_writer)
             .with_target(false)
             .with_thread_ids(false)
-            .with_thread_names(false)
-            .try_init();
+            .with_thread_names(false);
+
+        if self.log_format == "json" {
+            Box::new(builder.json().flatten_event(true).finish())
+        } else {
+            Box::new(builder.finish())
+        }
     }
 
     /// Validate configuration values and provide helpful error messages.
@@ -460,7 +1116,7 @@ impl Config {
         }
 
         // Validate adapter selection
-        let valid_adapters = ["auto", "lightllm", "openai"];
+        let valid_adapters = ["auto", "lightllm", "vllm", "openai", "azure", "aws", "custom", "template", "direct"];
         if !valid_adapters.contains(&self.force_adapter.as_str()) {
             return Err(format!(
                 "Invalid adapter '{}'. Valid options are: {}",
@@ -469,6 +1125,156 @@ impl Config {
             ));
         }
 
+        // Validate backend default headers
+        if let Some(headers) = &self.backend_default_headers {
+            for (name, value) in parse_key_value_pairs(headers)
+                .map_err(|err| format!("Invalid backend default headers: {}", err))?
+            {
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| format!("Invalid backend default header name '{}'", name))?;
+                reqwest::header::HeaderValue::from_str(&value)
+                    .map_err(|_| format!("Invalid value for backend default header '{}'", name))?;
+            }
+        }
+
+        let valid_token_counters = ["heuristic", "bpe"];
+        if !self.token_counter.is_empty() && !valid_token_counters.contains(&self.token_counter.as_str()) {
+            return Err(format!(
+                "Invalid token counter '{}'. Valid options are: {}",
+                self.token_counter,
+                valid_token_counters.join(", ")
+            ));
+        }
+        if self.token_counter == "bpe" && cfg!(not(feature = "tokenizer")) {
+            return Err("The bpe token counter requires the 'tokenizer' feature.".to_string());
+        }
+
+        // Validate request deadline header name
+        if !self.request_deadline_header.is_empty() {
+            reqwest::header::HeaderName::from_bytes(self.request_deadline_header.as_bytes())
+                .map_err(|_| format!("Invalid request deadline header name '{}'", self.request_deadline_header))?;
+        }
+
+        if self.allow_byok {
+            reqwest::header::HeaderName::from_bytes(self.byok_header.as_bytes())
+                .map_err(|_| format!("Invalid BYOK header name '{}'", self.byok_header))?;
+        }
+
+        // Validate per-model concurrency limits
+        if let Some(limits) = &self.model_concurrency_limits {
+            for (model, limit) in parse_key_value_pairs(limits)
+                .map_err(|err| format!("Invalid model concurrency limits: {}", err))?
+            {
+                if !limit.parse::<usize>().is_ok_and(|limit| limit > 0) {
+                    return Err(format!(
+                        "Invalid concurrency limit '{}' for model '{}'. Expected a positive integer.",
+                        limit, model
+                    ));
+                }
+            }
+        }
+
+        // Validate fair queuing weights
+        if let Some(weights) = &self.fair_queue_weights {
+            for (key, weight) in parse_key_value_pairs(weights)
+                .map_err(|err| format!("Invalid fair queue weights: {}", err))?
+            {
+                if !weight.parse::<u32>().is_ok_and(|weight| weight > 0) {
+                    return Err(format!(
+                        "Invalid fair queue weight '{}' for API key '{}...'. Expected a positive integer.",
+                        weight,
+                        key.chars().take(6).collect::<String>()
+                    ));
+                }
+            }
+        }
+
+        // Validate load shedding fallbacks
+        if let Some(fallbacks) = &self.load_shedding {
+            parse_key_value_pairs(fallbacks)
+                .map_err(|err| format!("Invalid load shedding fallbacks: {}", err))?;
+            if self.load_shedding_queue_depth == 0 {
+                return Err("Load shedding queue depth must be greater than 0".to_string());
+            }
+            if self.concurrency_limits().is_empty() && self.load_shedding_p95_ms == 0 {
+                eprintln!("⚠️  Warning: Load shedding is configured but can never trigger without model concurrency limits or a p95 latency threshold");
+            }
+        }
+
+        // Validate retryable upstream statuses
+        for code in self.upstream_retryable_status_codes.split(',').map(str::trim).filter(|code| !code.is_empty()) {
+            if !code.parse::<u16>().is_ok_and(|code| (100..=599).contains(&code)) {
+                return Err(format!("Invalid retryable status code '{}'. Expected an HTTP status", code));
+            }
+        }
+
+        // Validate the content filter retry backend
+        if let Some(backend) = &self.retry_on_content_filter_backend {
+            Url::parse(backend)
+                .map_err(|err| format!("Invalid content filter retry backend '{}': {}", backend, err))?;
+            self.validate_aws_region(backend)?;
+        }
+
+        // Validate the unsupported parameter map
+        if let Some(unsupported) = &self.unsupported_parameters {
+            for (model, parameters) in parse_key_value_pairs(unsupported)
+                .map_err(|err| format!("Invalid unsupported parameters: {}", err))?
+            {
+                for parameter in parameters.split('|').map(str::trim) {
+                    if !STRIPPABLE_PARAMETERS.contains(&parameter) {
+                        return Err(format!(
+                            "Invalid unsupported parameter '{}' for model '{}'. Valid options are: {}",
+                            parameter,
+                            model,
+                            STRIPPABLE_PARAMETERS.join(", ")
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Validate size-based routing
+        if let Some(routes) = &self.size_routing {
+            for (threshold, backend) in parse_key_value_pairs(routes)
+                .map_err(|err| format!("Invalid size routing: {}", err))?
+            {
+                threshold.parse::<usize>().map_err(|_| {
+                    format!("Invalid size routing: '{}' is not a token count", threshold)
+                })?;
+                Url::parse(&backend)
+                    .map_err(|err| format!("Invalid size routing backend '{}': {}", backend, err))?;
+                self.validate_aws_region(&backend)?;
+            }
+        }
+
+        // Validate Azure deployment mapping
+        if let Some(map) = &self.azure_deployment_map {
+            parse_key_value_pairs(map)
+                .map_err(|err| format!("Invalid Azure deployment map: {}", err))?;
+        }
+
+        // Validate Azure API version
+        if !self.azure_api_version.is_empty()
+            && !crate::adapters::AzureOpenAIAdapter::is_valid_api_version(&self.azure_api_version)
+        {
+            return Err(format!(
+                "Invalid Azure API version '{}'. Expected YYYY-MM-DD or YYYY-MM-DD-preview.",
+                self.azure_api_version
+            ));
+        }
+
+        // Validate request signing
+        if self.signing_secret.is_some() {
+            if cfg!(not(feature = "request-signing")) {
+                return Err("Request signing requires the 'request-signing' feature.".to_string());
+            }
+            if self.signing_secret.as_deref().is_some_and(str::is_empty) {
+                return Err("Signing secret cannot be empty.".to_string());
+            }
+            reqwest::header::HeaderName::from_bytes(self.signing_header_name.as_bytes())
+                .map_err(|_| format!("Invalid signing header name '{}'", self.signing_header_name))?;
+        }
+
         // Validate environment
         let valid_environments = ["development", "staging", "production"];
         if !valid_environments.contains(&self.environment.as_str()) {
@@ -490,6 +1296,14 @@ impl Config {
                 self.http_client_timeout
             );
         }
+        if self.first_byte_timeout > 0 && self.first_byte_timeout >= self.http_client_timeout {
+            eprintln!(
+                "⚠️  Warning: First-byte timeout of {} seconds is not shorter than the HTTP client timeout \
+                of {} seconds, so it will never fire.",
+                self.first_byte_timeout,
+                self.http_client_timeout
+            );
+        }
         
         if self.http_client_max_connections == 0 {
             return Err("HTTP client max connections must be greater than 0.".to_string());
@@ -514,6 +1328,10 @@ impl Config {
             );
         }
 
+        if self.pool_exhaustion_fail_fast && self.pool_exhaustion_threshold_ms == 0 {
+            eprintln!("⚠️  Warning: Pool exhaustion fail-fast has no effect without a pool exhaustion threshold");
+        }
+
         // Validate streaming configuration
         if self.streaming_timeout == 0 {
             return Err("Streaming timeout must be greater than 0 seconds.".to_string());
@@ -561,6 +1379,103 @@ impl Config {
                 Consider setting a reasonable cache size (e.g., 100-10000 entries)."
             );
         }
+        if let Some(path) = &self.cache_warm_file {
+            if !self.enable_caching {
+                eprintln!(
+                    "⚠️  Warning: Cache warm file '{}' is ignored because caching is disabled. \
+                    Set ENABLE_CACHING=true to warm the cache at startup.",
+                    path
+                );
+            } else if !std::path::Path::new(path).is_file() {
+                return Err(format!("Cache warm file '{}' does not exist.", path));
+            }
+        }
+
+        if let Some(path) = &self.system_prompts_file {
+            if !std::path::Path::new(path).is_file() {
+                return Err(format!("System prompts file '{}' does not exist.", path));
+            }
+        }
+
+        if crate::adapters::Adapter::backend_type(self) == "template" {
+            let path = self
+                .request_template_file
+                .as_deref()
+                .ok_or_else(|| "The template backend requires a request template file (request_template_file)".to_string())?;
+            crate::adapters::RequestTemplate::from_file(path)
+                .and_then(|template| crate::adapters::template::JsonPath::parse(&template.response_path))
+                .map_err(|err| err.to_string())?;
+        }
+
+        if let Some(path) = &self.lightllm_prompt_template_file {
+            crate::adapters::LightLLMPromptTemplate::from_file(path).map_err(|err| err.to_string())?;
+        }
+
+        // Validate tool argument redaction
+        let valid_redactions = ["raw", "mask", "drop"];
+        if !self.tool_argument_redaction.is_empty() && !valid_redactions.contains(&self.tool_argument_redaction.as_str()) {
+            return Err(format!(
+                "Invalid tool argument redaction '{}'. Valid options are: {}",
+                self.tool_argument_redaction,
+                valid_redactions.join(", ")
+            ));
+        }
+
+        // Validate conversation storage
+        let valid_conversation_stores = ["off", "memory", "redis"];
+        if !self.conversation_store.is_empty() && !valid_conversation_stores.contains(&self.conversation_store.as_str()) {
+            return Err(format!(
+                "Invalid conversation store '{}'. Valid options are: {}",
+                self.conversation_store,
+                valid_conversation_stores.join(", ")
+            ));
+        }
+        if self.conversation_store == "redis" {
+            if cfg!(not(feature = "redis")) {
+                return Err("The redis conversation store requires the 'redis' feature.".to_string());
+            }
+            if !self.conversation_redis_url.starts_with("redis://") {
+                return Err(format!(
+                    "Invalid conversation Redis URL '{}'. Expected redis://host:port",
+                    self.conversation_redis_url
+                ));
+            }
+        }
+        if matches!(self.conversation_store.as_str(), "memory" | "redis") && self.conversation_ttl_secs == 0 {
+            return Err("Conversation TTL must be greater than 0".to_string());
+        }
+        let valid_budget_strategies = ["truncate", "reject"];
+        if !self.conversation_budget_strategy.is_empty() && !valid_budget_strategies.contains(&self.conversation_budget_strategy.as_str()) {
+            return Err(format!(
+                "Invalid conversation budget strategy '{}'. Valid options are: {}",
+                self.conversation_budget_strategy,
+                valid_budget_strategies.join(", ")
+            ));
+        }
+
+        // Validate replay protection
+        let valid_replay_stores = ["off", "memory", "redis"];
+        if !self.replay_protection.is_empty() && !valid_replay_stores.contains(&self.replay_protection.as_str()) {
+            return Err(format!(
+                "Invalid replay protection '{}'. Valid options are: {}",
+                self.replay_protection,
+                valid_replay_stores.join(", ")
+            ));
+        }
+        if self.replay_protection == "redis" {
+            if cfg!(not(feature = "redis")) {
+                return Err("Redis replay protection requires the 'redis' feature.".to_string());
+            }
+            if !self.replay_redis_url.starts_with("redis://") {
+                return Err(format!(
+                    "Invalid replay protection Redis URL '{}'. Expected redis://host:port",
+                    self.replay_redis_url
+                ));
+            }
+        }
+        if matches!(self.replay_protection.as_str(), "memory" | "redis") && self.replay_window_secs == 0 {
+            return Err("Replay window must be greater than 0".to_string());
+        }
 
         // Validate CORS configuration for production
         if self.environment == "production" {
@@ -588,8 +1503,8 @@ impl Config {
         }
         
         // Validate backend_type
-        let valid_backend_types = ["lightllm", "vllm", "openai", "azure", "aws", "custom", "direct"];
-        if !valid_backend_types.contains(&self.backend_type.as_str()) {
+        let valid_backend_types = ["auto", "lightllm", "vllm", "openai", "azure", "aws", "custom", "template", "direct"];
+        if !self.backend_type.is_empty() && !valid_backend_types.contains(&self.backend_type.as_str()) {
             eprintln!(
                 "⚠️  Warning: Unknown backend type '{}'. Valid options are: {}",
                 self.backend_type,
@@ -605,6 +1520,8 @@ impl Config {
             );
         }
         
+        self.validate_aws_region(&self.backend_url)?;
+
         // Validate log level
         let valid_log_levels = ["error", "warn", "info", "debug", "trace"];
         if !valid_log_levels.contains(&self.log_level.as_str()) {
@@ -615,7 +1532,215 @@ impl Config {
             ));
         }
 
+        // Validate log format
+        let valid_log_formats = ["text", "json"];
+        if !self.log_format.is_empty() && !valid_log_formats.contains(&self.log_format.as_str()) {
+            return Err(format!(
+                "Invalid log format '{}'. Valid options are: {}",
+                self.log_format,
+                valid_log_formats.join(", ")
+            ));
+        }
+
+        // Validate connection limit behavior
+        let valid_limit_behaviors = ["wait", "drop"];
+        if !self.connection_limit_behavior.is_empty() && !valid_limit_behaviors.contains(&self.connection_limit_behavior.as_str()) {
+            return Err(format!(
+                "Invalid connection limit behavior '{}'. Valid options are: wait, drop",
+                self.connection_limit_behavior
+            ));
+        }
+
+        // Validate inbound protocol
+        let valid_inbound_protocols = ["http1", "http2", "auto"];
+        if !self.inbound_protocol.is_empty() && !valid_inbound_protocols.contains(&self.inbound_protocol.as_str()) {
+            return Err(format!(
+                "Invalid inbound protocol '{}'. Valid options are: {}",
+                self.inbound_protocol,
+                valid_inbound_protocols.join(", ")
+            ));
+        }
+
+        // Validate stream override
+        let valid_stream_force = ["none", "off"];
+        if !self.stream_force.is_empty() && !valid_stream_force.contains(&self.stream_force.as_str()) {
+            return Err(format!(
+                "Invalid stream force '{}'. Valid options are: none, off",
+                self.stream_force
+            ));
+        }
+
+        // Validate truncated tool call handling
+        let valid_truncation_modes = ["signal", "off"];
+        if !self.stream_tool_call_truncation.is_empty() && !valid_truncation_modes.contains(&self.stream_tool_call_truncation.as_str()) {
+            return Err(format!(
+                "Invalid tool call truncation mode '{}'. Valid options are: {}",
+                self.stream_tool_call_truncation,
+                valid_truncation_modes.join(", ")
+            ));
+        }
+
+        let valid_language_placements = ["append", "prepend"];
+        if !self.response_language_placement.is_empty() && !valid_language_placements.contains(&self.response_language_placement.as_str()) {
+            return Err(format!(
+                "Invalid response language placement '{}'. Valid options are: {}",
+                self.response_language_placement,
+                valid_language_placements.join(", ")
+            ));
+        }
+
+        // Validate reasoning content handling
+        let valid_reasoning_modes = ["forward", "strip", "move"];
+        if !self.reasoning_content.is_empty() && !valid_reasoning_modes.contains(&self.reasoning_content.as_str()) {
+            return Err(format!(
+                "Invalid reasoning content mode '{}'. Valid options are: {}",
+                self.reasoning_content,
+                valid_reasoning_modes.join(", ")
+            ));
+        }
+        if self.reasoning_content == "move"
+            && matches!(self.reasoning_content_field.as_str(), "" | "reasoning_content" | "content" | "role")
+        {
+            return Err(format!(
+                "Invalid reasoning content field '{}'. Choose a field name not used by chat messages.",
+                self.reasoning_content_field
+            ));
+        }
+
+        // Validate Anthropic prompt caching
+        if self.anthropic_cache_last_turns > 3 {
+            return Err(format!(
+                "Invalid Anthropic cache turn count {}. At most 3 turns can be cached \
+                alongside the system prompt.",
+                self.anthropic_cache_last_turns
+            ));
+        }
+
+        // Validate model pinning
+        let valid_pin_modes = ["off", "warn", "reject"];
+        if !self.pin_model_version.is_empty() && !valid_pin_modes.contains(&self.pin_model_version.as_str()) {
+            return Err(format!(
+                "Invalid model pinning mode '{}'. Valid options are: {}",
+                self.pin_model_version,
+                valid_pin_modes.join(", ")
+            ));
+        }
+        let valid_pin_tolerances = ["exact", "snapshot"];
+        if !self.pin_model_tolerance.is_empty() && !valid_pin_tolerances.contains(&self.pin_model_tolerance.as_str()) {
+            return Err(format!(
+                "Invalid model pinning tolerance '{}'. Valid options are: {}",
+                self.pin_model_tolerance,
+                valid_pin_tolerances.join(", ")
+            ));
+        }
+
+        // Validate prompt capture configuration
+        if !(0.0..=1.0).contains(&self.prompt_capture_sample_rate) {
+            return Err(format!(
+                "Invalid prompt capture sample rate {}. It must be between 0.0 and 1.0.",
+                self.prompt_capture_sample_rate
+            ));
+        }
+        if self.prompt_capture_sample_rate > 0.0 && self.prompt_capture_path.is_none() {
+            return Err("Prompt capture requires a capture file (prompt_capture_path)".to_string());
+        }
+
+        // Validate shadow traffic configuration
+        if !(0.0..=100.0).contains(&self.shadow_percentage) {
+            return Err(format!(
+                "Invalid shadow percentage {}. It must be between 0 and 100.",
+                self.shadow_percentage
+            ));
+        }
+        if let Some(backend) = &self.shadow_backend_url {
+            Url::parse(backend).map_err(|err| format!("Invalid shadow backend '{}': {}", backend, err))?;
+            self.validate_aws_region(backend)?;
+        }
+
+        // Validate chaos testing configuration
+        if !(0.0..=1.0).contains(&self.chaos_error_rate) {
+            return Err(format!(
+                "Invalid chaos error rate {}. It must be between 0.0 and 1.0.",
+                self.chaos_error_rate
+            ));
+        }
+        if self.chaos_enabled {
+            if !(400..=599).contains(&self.chaos_error_status) {
+                return Err(format!(
+                    "Invalid chaos error status {}. It must be an HTTP error status (400-599).",
+                    self.chaos_error_status
+                ));
+            }
+            if self.environment == "production" {
+                eprintln!("⚠️  Warning: Chaos testing is enabled but ignored in production.");
+            }
+        }
+
+        // Validate readiness circuit configuration
+        if !(0.0..=1.0).contains(&self.readiness_error_rate_threshold) {
+            return Err(format!(
+                "Invalid readiness error rate threshold {}. It must be between 0.0 and 1.0.",
+                self.readiness_error_rate_threshold
+            ));
+        }
+
+        // Validate SLA tracking configuration
+        if let Some(targets) = &self.sla_target_ms {
+            for (model, target) in parse_key_value_pairs(targets)
+                .map_err(|err| format!("Invalid SLA targets: {}", err))?
+            {
+                if !target.parse::<u64>().is_ok_and(|target| target > 0) {
+                    return Err(format!(
+                        "Invalid SLA target '{}' for model '{}'. Expected a positive number of milliseconds.",
+                        target, model
+                    ));
+                }
+            }
+        }
+        if let Some(webhook_url) = &self.sla_alert_webhook_url {
+            Url::parse(webhook_url)
+                .map_err(|err| format!("Invalid SLA alert webhook URL '{}': {}", webhook_url, err))?;
+        }
+        if !(0.0..=100.0).contains(&self.sla_alert_below_percent) {
+            return Err(format!(
+                "Invalid SLA alert threshold {}. It must be between 0 and 100.",
+                self.sla_alert_below_percent
+            ));
+        }
+        if self.sla_target_ms.is_some() && self.sla_alert_window_secs == 0 {
+            return Err("SLA alert window must be at least 1 second".to_string());
+        }
+
+        // Validate metrics endpoint path
+        if !self.metrics_endpoint.is_empty() && !self.metrics_endpoint.starts_with('/') {
+            return Err(format!(
+                "Invalid metrics endpoint '{}'. The path must start with '/'.",
+                self.metrics_endpoint
+            ));
+        }
+
+        // Validate tools endpoint path
+        if !self.tools_endpoint.is_empty() && !self.tools_endpoint.starts_with('/') {
+            return Err(format!(
+                "Invalid tools endpoint '{}'. The path must start with '/'.",
+                self.tools_endpoint
+            ));
+        }
+
         // Validate CORS configuration
+        for origin in self.cors_origins() {
+            let wildcard_ok = origin == "*"
+                || origin
+                    .split_once("://*.")
+                    .is_some_and(|(scheme, domain)| !scheme.contains('*') && !domain.is_empty() && !domain.contains('*'));
+            if origin.contains('*') && !wildcard_ok {
+                return Err(format!(
+                    "Invalid CORS origin '{}'. Wildcards are only supported as '*' or a leading subdomain \
+                    wildcard such as 'https://*.example.com'.",
+                    origin
+                ));
+            }
+        }
         if self.cors_methods.is_empty() {
             return Err("CORS methods cannot be empty. Please specify valid HTTP methods.".to_string());
         }
@@ -642,6 +1767,227 @@ impl Config {
         Ok(())
     }
 
+    /// Require a signing region for an AWS Bedrock backend URL, from `aws_region`
+    /// or the URL host
+    fn validate_aws_region(&self, backend_url: &str) -> Result<(), String> {
+        let config = Config { backend_url: backend_url.to_string(), ..self.clone() };
+        if crate::adapters::Adapter::backend_type(&config) == "aws"
+            && self.aws_region.is_none()
+            && crate::adapters::AWSBedrockAdapter::region_from_url(backend_url).is_none()
+        {
+            return Err(format!(
+                "Cannot determine the AWS region from backend URL '{}'. Use a regional \
+                endpoint (bedrock-runtime.<region>.amazonaws.com) or set AWS_REGION.",
+                backend_url
+            ));
+        }
+        Ok(())
+    }
+
+    /// Resolve whether a request should stream, applying `stream_force` and `stream_default`
+    pub fn resolve_stream(&self, requested: Option<bool>) -> bool {
+        if self.stream_force == "off" {
+            return false;
+        }
+        requested.unwrap_or(self.stream_default)
+    }
+
+    /// Get the configured CORS origins as a list of trimmed, non-empty entries
+    pub fn cors_origins(&self) -> Vec<&str> {
+        self.cors_origin
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .collect()
+    }
+
+    /// Get the parameters each model (or '*', every model) does not support.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn unsupported_parameter_map(&self) -> HashMap<String, Vec<String>> {
+        self.unsupported_parameters
+            .as_deref()
+            .and_then(|unsupported| parse_key_value_pairs(unsupported).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(model, parameters)| {
+                let parameters = parameters
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|parameter| STRIPPABLE_PARAMETERS.contains(parameter))
+                    .map(str::to_string)
+                    .collect();
+                (model, parameters)
+            })
+            .collect()
+    }
+
+    /// Get the upstream HTTP statuses that are retried, skipping invalid entries
+    pub fn retryable_status_codes(&self) -> Vec<u16> {
+        self.upstream_retryable_status_codes
+            .split(',')
+            .filter_map(|code| code.trim().parse().ok())
+            .collect()
+    }
+
+    /// Get the prefixes stripped from the start of assistant content
+    pub fn strip_prefixes(&self) -> Vec<String> {
+        self.response_strip_prefixes
+            .as_deref()
+            .map(|prefixes| {
+                prefixes
+                    .split('|')
+                    .filter(|prefix| !prefix.trim().is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the number of recent turns cached alongside the system prompt,
+    /// if Anthropic prompt caching is enabled
+    pub fn anthropic_cache_turns(&self) -> Option<usize> {
+        self.anthropic_prompt_caching.then_some(self.anthropic_cache_last_turns)
+    }
+
+    /// Get the first-byte timeout, if enabled
+    pub fn first_byte_timeout(&self) -> Option<std::time::Duration> {
+        (self.first_byte_timeout > 0).then(|| std::time::Duration::from_secs(self.first_byte_timeout))
+    }
+
+    /// Get the JSON fields redacted from prompt capture records
+    pub fn capture_redact_fields(&self) -> Vec<String> {
+        self.prompt_capture_redact_fields
+            .as_deref()
+            .map(|fields| {
+                fields
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|field| !field.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the static headers sent with every backend request.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn backend_headers(&self) -> HashMap<String, String> {
+        self.backend_default_headers
+            .as_deref()
+            .and_then(|headers| parse_key_value_pairs(headers).ok())
+            .unwrap_or_default()
+    }
+
+    /// Get the headers (and `body`) covered by the request signature, in order
+    pub fn signing_components(&self) -> Vec<String> {
+        self.signing_headers
+            .split(',')
+            .map(str::trim)
+            .filter(|component| !component.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Get the model name prefixes that identify reasoning models
+    pub fn reasoning_model_prefixes(&self) -> Vec<String> {
+        self.reasoning_models
+            .split(',')
+            .map(str::trim)
+            .filter(|prefix| !prefix.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Get the AWS regions Bedrock requests fail over to, in order.
+    pub fn failover_regions(&self) -> Vec<String> {
+        self.aws_failover_regions
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|region| !region.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Get the per-model concurrency limits.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn concurrency_limits(&self) -> HashMap<String, usize> {
+        self.model_concurrency_limits
+            .as_deref()
+            .and_then(|limits| parse_key_value_pairs(limits).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(model, limit)| Some((model, limit.parse().ok().filter(|limit| *limit > 0)?)))
+            .collect()
+    }
+
+    /// Get the per-model SLA latency targets in milliseconds.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn sla_targets(&self) -> HashMap<String, u64> {
+        self.sla_target_ms
+            .as_deref()
+            .and_then(|targets| parse_key_value_pairs(targets).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(model, target)| Some((model, target.parse().ok().filter(|target| *target > 0)?)))
+            .collect()
+    }
+
+    /// Get the fair queuing weight of each listed API key.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn fair_queue_weights(&self) -> HashMap<String, u32> {
+        self.fair_queue_weights
+            .as_deref()
+            .and_then(|weights| parse_key_value_pairs(weights).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(key, weight)| Some((key, weight.parse().ok().filter(|weight| *weight > 0)?)))
+            .collect()
+    }
+
+    /// Get the load shedding fallback model for each expensive model.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn load_shedding_fallbacks(&self) -> HashMap<String, String> {
+        self.load_shedding
+            .as_deref()
+            .and_then(|fallbacks| parse_key_value_pairs(fallbacks).ok())
+            .unwrap_or_default()
+    }
+
+    /// Get the size-based routes as (maximum prompt tokens, backend URL),
+    /// smallest first.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn size_routes(&self) -> Vec<(usize, String)> {
+        let mut routes: Vec<(usize, String)> = self
+            .size_routing
+            .as_deref()
+            .and_then(|routes| parse_key_value_pairs(routes).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(threshold, backend)| Some((threshold.parse().ok()?, backend)))
+            .collect();
+        routes.sort();
+        routes
+    }
+
+    /// Get the Azure model-to-deployment mapping.
+    ///
+    /// Malformed entries are rejected by `validate()`, so they are skipped here.
+    pub fn azure_deployments(&self) -> HashMap<String, String> {
+        self.azure_deployment_map
+            .as_deref()
+            .and_then(|map| parse_key_value_pairs(map).ok())
+            .unwrap_or_default()
+    }
+
     /// Get the effective LightLLM token, checking multiple sources.
     /// 
     /// This method checks for tokens in the following order:
@@ -677,3 +2023,20 @@ impl Config {
     }
 
 }
+
+/// Parse a comma-separated list of `key=value` pairs.
+///
+/// Whitespace around keys and values is trimmed and empty items are ignored,
+/// so `"a=b, c=d,"` yields two entries.
+pub(crate) fn parse_key_value_pairs(raw: &str) -> Result<HashMap<String, String>, String> {
+    let mut pairs = HashMap::new();
+    for item in raw.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let (key, value) = item
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+            .ok_or_else(|| format!("expected 'key=value', got '{}'", item))?;
+        pairs.insert(key.to_string(), value.to_string());
+    }
+    Ok(pairs)
+}
@@ -3,9 +3,13 @@
 //! Centralized HTTP client creation and configuration to eliminate
 //! duplication across the codebase and ensure consistent client settings.
 
-use crate::config::Config;
-use reqwest::Client;
-use std::time::Duration;
+use crate::{config::Config, error::ProxyError};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client,
+};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 /// HTTP client configuration errors
@@ -42,7 +46,12 @@ pub struct HttpClientConfig {
     pub connect_timeout: Duration,
     pub pool: PoolConfig,
     pub compression: bool,
+    /// Use HTTP/2 without negotiation, multiplexing requests over one connection per host
     pub http2_prior_knowledge: bool,
+    /// Size HTTP/2 flow-control windows adaptively (BDP estimation)
+    pub http2_adaptive_window: bool,
+    /// Headers sent with every request made by the client
+    pub default_headers: HashMap<String, String>,
 }
 
 impl Default for HttpClientConfig {
@@ -53,6 +62,8 @@ impl Default for HttpClientConfig {
             pool: PoolConfig::default(),
             compression: true,
             http2_prior_knowledge: false,
+            http2_adaptive_window: false,
+            default_headers: HashMap::new(),
         }
     }
 }
@@ -67,8 +78,10 @@ impl From<&Config> for HttpClientConfig {
                 idle_timeout: Duration::from_secs(120),
                 keepalive: Some(Duration::from_secs(60)),
             },
-            compression: true,
-            http2_prior_knowledge: false,
+            compression: config.http_client_compression,
+            http2_prior_knowledge: config.http_client_http2_prior_knowledge,
+            http2_adaptive_window: config.http_client_http2_adaptive_window,
+            default_headers: config.backend_headers(),
         }
     }
 }
@@ -93,6 +106,11 @@ impl HttpClientBuilder {
         }
     }
 
+    /// Create HTTP client builder from an explicit client configuration
+    pub fn with_config(config: HttpClientConfig) -> Self {
+        Self { config }
+    }
+
     /// Create production-optimized HTTP client configuration
     pub fn production() -> Self {
         Self {
@@ -106,6 +124,8 @@ impl HttpClientBuilder {
                 },
                 compression: true,
                 http2_prior_knowledge: true,
+                http2_adaptive_window: true,
+                default_headers: HashMap::new(),
             },
         }
     }
@@ -123,6 +143,8 @@ impl HttpClientBuilder {
                 },
                 compression: false,
                 http2_prior_knowledge: false,
+                http2_adaptive_window: false,
+                default_headers: HashMap::new(),
             },
         }
     }
@@ -151,25 +173,51 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Use HTTP/2 prior knowledge so concurrent requests share a connection
+    pub fn http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.config.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Add a header sent with every request
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.default_headers.insert(name.into(), value.into());
+        self
+    }
+
     /// Build the HTTP client
     pub fn build(self) -> Result<Client, HttpClientError> {
+        let mut default_headers = HeaderMap::new();
+        for (name, value) in &self.config.default_headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| HttpClientError::InvalidConfig(format!("invalid header name '{}'", name)))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|_| HttpClientError::InvalidConfig(format!("invalid value for header '{}'", name)))?;
+            default_headers.insert(header_name, header_value);
+        }
+
         let mut builder = Client::builder()
             .timeout(self.config.timeout)
             .connect_timeout(self.config.connect_timeout)
             .pool_max_idle_per_host(self.config.pool.max_idle_per_host)
-            .pool_idle_timeout(self.config.pool.idle_timeout);
+            .pool_idle_timeout(self.config.pool.idle_timeout)
+            .default_headers(default_headers);
 
         if let Some(keepalive) = self.config.pool.keepalive {
             builder = builder.tcp_keepalive(keepalive);
         }
 
-        if self.config.compression {
-            builder = builder.gzip(true).brotli(true);
-        }
+        // reqwest decodes compressed responses by default, so disable it explicitly
+        builder = builder
+            .gzip(self.config.compression)
+            .brotli(self.config.compression);
 
         if self.config.http2_prior_knowledge {
             builder = builder.http2_prior_knowledge();
         }
+        if self.config.http2_adaptive_window {
+            builder = builder.http2_adaptive_window(true);
+        }
 
         builder.build().map_err(HttpClientError::from)
     }
@@ -181,6 +229,156 @@ impl Default for HttpClientBuilder {
     }
 }
 
+/// Upstream HTTP statuses retried by default
+pub const DEFAULT_RETRYABLE_STATUS_CODES: [u16; 6] = [408, 429, 500, 502, 503, 504];
+
+/// Retry policy for upstream requests
+///
+/// Retries use exponential backoff starting at `base_delay` with full jitter:
+/// each wait is a random duration up to the backoff, so clients that failed
+/// together do not retry together.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt (0 disables retries)
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub base_delay: Duration,
+    /// Upstream HTTP statuses worth retrying
+    pub retryable_status_codes: Vec<u16>,
+    /// Retry responses cut short of their declared Content-Length
+    pub retry_incomplete: bool,
+    /// How long to keep retrying while the backend reports its model loading
+    pub model_loading_budget: Duration,
+    /// No retry is started whose wait would end after this instant
+    pub deadline: Option<Instant>,
+}
+
+/// Longest wait between attempts while a model loads
+const MAX_MODEL_LOADING_DELAY: Duration = Duration::from_secs(2);
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::from_millis(100),
+            retryable_status_codes: DEFAULT_RETRYABLE_STATUS_CODES.to_vec(),
+            retry_incomplete: true,
+            model_loading_budget: Duration::ZERO,
+            deadline: None,
+        }
+    }
+}
+
+impl From<&Config> for RetryPolicy {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_retries: config.upstream_max_retries,
+            base_delay: Duration::from_millis(config.upstream_retry_backoff_ms),
+            retryable_status_codes: config.retryable_status_codes(),
+            retry_incomplete: config.upstream_retry_incomplete,
+            model_loading_budget: Duration::from_secs(config.model_loading_wait_secs),
+            deadline: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Override the retry count, keeping the backoff settings
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Skip retries that could not start before `deadline`
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Whether a wait of `delay` starting now would run past the deadline
+    fn past_deadline(&self, delay: Duration) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() + delay >= deadline)
+    }
+
+    /// Backoff before the given retry (1-based)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+
+    /// Random wait before the given retry (1-based), up to its backoff
+    pub fn jittered_backoff(&self, retry: u32) -> Duration {
+        self.backoff(retry).mul_f64(fastrand::f64())
+    }
+
+    /// Whether a failed upstream request is worth retrying: connection errors
+    /// and timeouts, the configured HTTP statuses and, unless disabled,
+    /// incomplete response bodies
+    pub fn is_retryable(&self, error: &ProxyError) -> bool {
+        match error {
+            ProxyError::UpstreamStatus { status, .. } => self.retryable_status_codes.contains(&status.as_u16()),
+            ProxyError::IncompleteResponse { .. } => self.retry_incomplete,
+            other => other.is_retryable(),
+        }
+    }
+
+    /// Run `operation` until it succeeds, fails with a non-retryable error,
+    /// or the retries are used up.
+    pub async fn retry<T, E, F, Fut>(&self, mut operation: F, is_retryable: impl Fn(&E) -> bool) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        let mut retry = 0;
+        loop {
+            match operation().await {
+                Err(error) if retry < self.max_retries && is_retryable(&error) => {
+                    retry += 1;
+                    let delay = self.jittered_backoff(retry);
+                    if self.past_deadline(delay) {
+                        tracing::warn!("Skipping upstream retry that cannot finish before the request deadline: {}", error);
+                        return Err(error);
+                    }
+                    tracing::warn!("Retrying upstream request ({}/{}) in {:?}: {}", retry, self.max_retries, delay, error);
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run `operation` again while it fails with an error `is_loading`
+    /// accepts, waiting for the backend's model until `model_loading_budget`
+    /// has passed. Waits grow like retry backoff, up to two seconds.
+    pub async fn wait_for_model<T, E, F, Fut>(&self, mut operation: F, is_loading: impl Fn(&E) -> bool) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        let started = std::time::Instant::now();
+        let mut attempt = 0;
+        loop {
+            match operation().await {
+                Err(error) if is_loading(&error) => {
+                    let mut remaining = self.model_loading_budget.saturating_sub(started.elapsed());
+                    if let Some(deadline) = self.deadline {
+                        remaining = remaining.min(deadline.saturating_duration_since(Instant::now()));
+                    }
+                    if remaining.is_zero() {
+                        return Err(error);
+                    }
+                    attempt += 1;
+                    let delay = self.backoff(attempt).min(MAX_MODEL_LOADING_DELAY).min(remaining);
+                    tracing::info!("Backend model still loading, retrying in {:?}: {}", delay, error);
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,4 +404,44 @@ mod tests {
             .unwrap();
         assert!(client.get("https://httpbin.org/get").build().is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_retry_backoff_jittered_and_statuses_configurable() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            retryable_status_codes: vec![502],
+            ..RetryPolicy::default()
+        };
+        for retry in 1..=4 {
+            assert!(policy.jittered_backoff(retry) <= policy.backoff(retry));
+        }
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+
+        let status = |code: u16| ProxyError::UpstreamStatus {
+            status: reqwest::StatusCode::from_u16(code).unwrap(),
+            body: String::new(),
+        };
+        assert!(policy.is_retryable(&status(502)));
+        assert!(!policy.is_retryable(&status(503)));
+        assert!(policy.is_retryable(&ProxyError::Upstream("connection reset".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_retry_skipped_when_backoff_passes_deadline() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_secs(10),
+            ..RetryPolicy::default()
+        };
+        let attempts = std::cell::Cell::new(0);
+        let fail = || {
+            attempts.set(attempts.get() + 1);
+            async { Err::<(), _>(ProxyError::Upstream("connection reset".to_string())) }
+        };
+
+        // Even a fully jittered backoff of zero is not before an expired deadline
+        let expired = policy.clone().with_deadline(Some(Instant::now()));
+        assert!(expired.retry(fail, |error| expired.is_retryable(error)).await.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+}
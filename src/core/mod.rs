@@ -5,6 +5,9 @@
 //! error handling, HTTP client management, and common utilities.
 
 pub mod http_client;
+pub mod redaction;
+pub mod tokens;
 
 // Re-export commonly used core types
-pub use http_client::{HttpClientBuilder, HttpClientConfig, HttpClientError};
\ No newline at end of file
+pub use http_client::{HttpClientBuilder, HttpClientConfig, HttpClientError, RetryPolicy};
+pub use redaction::ToolArgumentRedaction;
\ No newline at end of file
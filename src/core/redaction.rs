@@ -0,0 +1,128 @@
+//! # Tool Argument Redaction
+//!
+//! Tool-call arguments often carry sensitive data such as addresses or
+//! account numbers. `tool_argument_redaction` controls how they appear
+//! wherever the proxy logs or records tool calls: as sent ("raw"), with every
+//! value replaced but the keys kept ("mask"), or not at all ("drop").
+
+use crate::config::Config;
+use serde_json::Value;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// How tool-call arguments are shown outside the request itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolArgumentRedaction {
+    /// Arguments as sent by the model
+    #[default]
+    Raw,
+    /// Object keys kept, every value replaced with [`REDACTED`]
+    Mask,
+    /// Arguments removed entirely
+    Drop,
+}
+
+impl ToolArgumentRedaction {
+    /// Read `tool_argument_redaction`
+    pub fn from_config(config: &Config) -> Self {
+        match config.tool_argument_redaction.as_str() {
+            "mask" => Self::Mask,
+            "drop" => Self::Drop,
+            _ => Self::Raw,
+        }
+    }
+
+    /// Redacted copy of parsed arguments
+    pub fn apply(&self, arguments: &Value) -> Value {
+        match self {
+            Self::Raw => arguments.clone(),
+            Self::Mask => mask(arguments),
+            Self::Drop => Value::Null,
+        }
+    }
+
+    /// Redacted copy of arguments in their JSON string form.
+    ///
+    /// Arguments that are not valid JSON are masked as a whole.
+    pub fn apply_str(&self, arguments: &str) -> String {
+        match self {
+            Self::Raw => arguments.to_string(),
+            Self::Mask => match serde_json::from_str::<Value>(arguments) {
+                Ok(parsed) => mask(&parsed).to_string(),
+                Err(_) => REDACTED.to_string(),
+            },
+            Self::Drop => String::new(),
+        }
+    }
+
+    /// Redact the `arguments` of every `function` / `function_call` object
+    /// in a request or response body, at any depth
+    pub fn redact_tool_calls(&self, value: &mut Value) {
+        if *self == Self::Raw {
+            return;
+        }
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if key == "function" || key == "function_call" {
+                        if let Some(arguments) = value.get_mut("arguments") {
+                            if let Value::String(text) = arguments {
+                                *text = self.apply_str(text);
+                            }
+                            continue;
+                        }
+                    }
+                    self.redact_tool_calls(value);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_tool_calls(item)),
+            _ => {}
+        }
+    }
+}
+
+/// Replace every leaf value, keeping object keys and array shapes
+fn mask(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter().map(|(key, value)| (key.clone(), mask(value))).collect()),
+        Value::Array(items) => Value::Array(items.iter().map(mask).collect()),
+        _ => Value::String(REDACTED.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_calls_in_body_redacted_per_policy() {
+        let body = json!({
+            "choices": [{"message": {"tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "transfer", "arguments": "{\"account_number\":\"DE89370400440532013000\",\"amount\":25}"}
+            }]}}]
+        });
+        let function = "/choices/0/message/tool_calls/0/function";
+
+        let mut masked = body.clone();
+        ToolArgumentRedaction::Mask.redact_tool_calls(&mut masked);
+        let masked = masked.pointer(function).unwrap();
+        assert_eq!(masked["name"], "transfer");
+        assert_eq!(
+            serde_json::from_str::<Value>(masked["arguments"].as_str().unwrap()).unwrap(),
+            json!({"account_number": REDACTED, "amount": REDACTED})
+        );
+
+        let mut dropped = body.clone();
+        ToolArgumentRedaction::Drop.redact_tool_calls(&mut dropped);
+        assert_eq!(dropped.pointer(function).unwrap()["arguments"], "");
+
+        let mut raw = body.clone();
+        ToolArgumentRedaction::Raw.redact_tool_calls(&mut raw);
+        assert_eq!(raw, body);
+        assert_eq!(ToolArgumentRedaction::Mask.apply_str("not json"), REDACTED);
+    }
+}
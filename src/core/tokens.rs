@@ -0,0 +1,123 @@
+//! # Token Counting
+//!
+//! Counts tokens for the `usage` block of responses from backends that do
+//! not report usage themselves. The default counter estimates one token per
+//! four bytes of text; with the `tokenizer` feature and `token_counter = "bpe"`
+//! the model's BPE vocabulary is used instead, for accurate billing.
+
+use crate::config::Config;
+use std::sync::Arc;
+
+/// Counts the tokens a model sees for a piece of text
+pub trait TokenCounter: Send + Sync + std::fmt::Debug {
+    /// Number of tokens in `text` for `model`
+    fn count(&self, text: &str, model: &str) -> usize;
+}
+
+/// Build the counter selected by `token_counter`
+pub fn from_config(config: &Config) -> Arc<dyn TokenCounter> {
+    match config.token_counter.as_str() {
+        #[cfg(feature = "tokenizer")]
+        "bpe" => Arc::new(BpeTokenCounter::new()),
+        _ => Arc::new(HeuristicTokenCounter),
+    }
+}
+
+/// Estimates one token per four bytes of text
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenCounter;
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count(&self, text: &str, _model: &str) -> usize {
+        text.len() / 4
+    }
+}
+
+/// Counts tokens with the model's tiktoken BPE vocabulary.
+///
+/// Vocabularies are loaded on first use and shared by every model using the
+/// same encoding. Models without a known encoding fall back to the heuristic.
+#[cfg(feature = "tokenizer")]
+#[derive(Default)]
+pub struct BpeTokenCounter {
+    vocabularies: std::sync::RwLock<
+        std::collections::HashMap<tiktoken_rs::tokenizer::Tokenizer, Option<Arc<tiktoken_rs::CoreBPE>>>,
+    >,
+}
+
+#[cfg(feature = "tokenizer")]
+impl BpeTokenCounter {
+    /// Create a counter with no vocabularies loaded yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached vocabulary for `model`, loading it on first use
+    fn vocabulary(&self, model: &str) -> Option<Arc<tiktoken_rs::CoreBPE>> {
+        let tokenizer = tiktoken_rs::tokenizer::get_tokenizer(model)?;
+        if let Some(vocabulary) = self.vocabularies.read().unwrap().get(&tokenizer) {
+            return vocabulary.clone();
+        }
+
+        let vocabulary = tiktoken_rs::get_bpe_from_tokenizer(tokenizer)
+            .map(Arc::new)
+            .map_err(|e| tracing::warn!("Failed to load {:?} vocabulary: {}", tokenizer, e))
+            .ok();
+        self.vocabularies
+            .write()
+            .unwrap()
+            .entry(tokenizer)
+            .or_insert(vocabulary)
+            .clone()
+    }
+}
+
+#[cfg(feature = "tokenizer")]
+impl std::fmt::Debug for BpeTokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let loaded: Vec<_> = self.vocabularies.read().unwrap().keys().copied().collect();
+        f.debug_struct("BpeTokenCounter").field("loaded", &loaded).finish()
+    }
+}
+
+#[cfg(feature = "tokenizer")]
+impl TokenCounter for BpeTokenCounter {
+    fn count(&self, text: &str, model: &str) -> usize {
+        match self.vocabulary(model) {
+            Some(vocabulary) => vocabulary.encode_ordinary(text).len(),
+            None => HeuristicTokenCounter.count(text, model),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_counts_bytes() {
+        let counter = HeuristicTokenCounter;
+        assert_eq!(counter.count("Hello, world!", "llama"), 3);
+        assert_eq!(counter.count("你好，世界", "llama"), 3);
+        assert_eq!(counter.count("👋🌍", "llama"), 2);
+    }
+
+    #[cfg(feature = "tokenizer")]
+    #[test]
+    fn test_bpe_counts_match_reference_values() {
+        let counter = BpeTokenCounter::new();
+
+        assert_eq!(counter.count("Hello, world!", "gpt-4"), 4);
+        assert_eq!(counter.count("hello world", "gpt-4"), 2);
+        assert_eq!(counter.count("你好，世界", "gpt-4"), 6);
+        assert_eq!(counter.count("👋🌍", "gpt-4"), 6);
+        assert_eq!(counter.count("Hello, world!", "gpt-4o"), 4);
+    }
+
+    #[cfg(feature = "tokenizer")]
+    #[test]
+    fn test_bpe_falls_back_for_unknown_models() {
+        let counter = BpeTokenCounter::new();
+        assert_eq!(counter.count("Hello, world!", "llama-70b"), 3);
+    }
+}
@@ -6,33 +6,201 @@ use axum::{
 };
 use serde_json::json;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ProxyError {
     BadRequest(String),
     Upstream(String),
+    /// The upstream answered with an error status, which is passed on to the client
+    UpstreamStatus {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    /// The backend is up but still loading the model (503 "model is loading")
+    ModelLoading(String),
+    /// The upstream HTTP call failed before a response was received
+    Transport {
+        kind: TransportErrorKind,
+        message: String,
+    },
+    /// The upstream response body ended before its declared Content-Length
+    IncompleteResponse {
+        expected: u64,
+        received: u64,
+    },
     Internal(String),
     Serialization(String),
 }
 
+/// Cause of a transport-level failure in the HTTP client layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportErrorKind {
+    /// The connection could not be established (e.g. connection refused)
+    Connect,
+    /// The request or connection timed out
+    Timeout,
+    /// TLS handshake or certificate failure
+    Tls,
+    /// The backend host name could not be resolved
+    Dns,
+    /// The response body could not be decoded
+    Decode,
+    /// No upstream connection became free within the pool exhaustion threshold
+    PoolExhausted,
+    /// Any other transport failure
+    Other,
+}
+
+impl TransportErrorKind {
+    /// Classify a reqwest error by inspecting its flags and source chain
+    pub fn classify(err: &reqwest::Error) -> Self {
+        if err.is_timeout() {
+            return TransportErrorKind::Timeout;
+        }
+        if err.is_decode() {
+            return TransportErrorKind::Decode;
+        }
+
+        // DNS and TLS failures surface as connect errors; their cause is only
+        // visible in the source chain
+        let mut source = std::error::Error::source(err);
+        while let Some(cause) = source {
+            if let Some(io_error) = cause.downcast_ref::<std::io::Error>() {
+                if io_error.kind() == std::io::ErrorKind::TimedOut {
+                    return TransportErrorKind::Timeout;
+                }
+            }
+
+            let message = cause.to_string().to_ascii_lowercase();
+            if message.contains("dns error") || message.contains("failed to lookup address") {
+                return TransportErrorKind::Dns;
+            }
+            if message.contains("certificate") || message.contains("tls") || message.contains("handshake") {
+                return TransportErrorKind::Tls;
+            }
+            source = cause.source();
+        }
+
+        if err.is_connect() {
+            TransportErrorKind::Connect
+        } else {
+            TransportErrorKind::Other
+        }
+    }
+
+    /// Short identifier used in error responses and logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransportErrorKind::Connect => "connect",
+            TransportErrorKind::Timeout => "timeout",
+            TransportErrorKind::Tls => "tls",
+            TransportErrorKind::Dns => "dns",
+            TransportErrorKind::Decode => "decode",
+            TransportErrorKind::PoolExhausted => "pool_exhausted",
+            TransportErrorKind::Other => "other",
+        }
+    }
+
+    /// Whether a request failing this way may succeed on retry.
+    ///
+    /// TLS and decode failures are deterministic and are not retried.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, TransportErrorKind::Tls | TransportErrorKind::Decode)
+    }
+}
+
+impl std::fmt::Display for TransportErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[cfg(feature = "server")]
-impl IntoResponse for ProxyError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            ProxyError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            ProxyError::Upstream(msg) => (StatusCode::BAD_GATEWAY, format!("Upstream error: {}", msg)),
-            ProxyError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Internal error: {}", msg)),
-            ProxyError::Serialization(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Serialization error: {}", msg)),
+impl ProxyError {
+    /// Error response whose `error` object also carries `request_id`, so a
+    /// client can quote the ID operators find in the logs
+    pub fn into_response_with_request_id(self, request_id: &str) -> Response {
+        let status = self.status_code();
+        let mut envelope = self.into_envelope();
+        if let Some(error) = envelope.get_mut("error").and_then(serde_json::Value::as_object_mut) {
+            error.insert("request_id".to_string(), json!(request_id));
+        }
+        (status, Json(envelope)).into_response()
+    }
+
+    /// OpenAI-style `{"error": {...}}` body for this error
+    fn into_envelope(self) -> serde_json::Value {
+        // OpenAI-style error bodies from the upstream reach the client unchanged
+        if let ProxyError::UpstreamStatus { body, .. } = &self {
+            if let Ok(envelope) = serde_json::from_str::<serde_json::Value>(body) {
+                if envelope.get("error").is_some_and(serde_json::Value::is_object) {
+                    return envelope;
+                }
+            }
+        }
+
+        let code = match &self {
+            ProxyError::Transport { kind, .. } => json!(format!("upstream_{}", kind)),
+            ProxyError::ModelLoading(_) => json!("model_loading"),
+            ProxyError::IncompleteResponse { .. } => json!("upstream_incomplete_response"),
+            _ => json!(null),
+        };
+        let error_message = match self {
+            ProxyError::BadRequest(msg) => msg,
+            ProxyError::Upstream(msg) => format!("Upstream error: {}", msg),
+            ProxyError::UpstreamStatus { status, body } => format!("Upstream error: HTTP {}: {}", status, body),
+            ProxyError::ModelLoading(msg) => format!("Model is warming up, retry shortly: {}", msg),
+            ProxyError::Transport { kind, message } => format!("Upstream {} error: {}", kind, message),
+            ProxyError::IncompleteResponse { expected, received } => format!(
+                "Incomplete upstream response: received {} of {} bytes",
+                received, expected
+            ),
+            ProxyError::Internal(msg) => format!("Internal error: {}", msg),
+            ProxyError::Serialization(msg) => format!("Serialization error: {}", msg),
         };
 
-        let body = Json(json!({
+        json!({
             "error": {
                 "message": error_message,
                 "type": "proxy_error",
-                "code": null
+                "code": code
             }
-        }));
+        })
+    }
+
+    /// Short identifier of the error variant, used to group errors in metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProxyError::BadRequest(_) => "bad_request",
+            ProxyError::Upstream(_) => "upstream",
+            ProxyError::UpstreamStatus { .. } => "upstream_status",
+            ProxyError::ModelLoading(_) => "model_loading",
+            ProxyError::Transport { .. } => "transport",
+            ProxyError::IncompleteResponse { .. } => "incomplete_response",
+            ProxyError::Internal(_) => "internal",
+            ProxyError::Serialization(_) => "serialization",
+        }
+    }
+
+    /// HTTP status code returned to the client for this error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ProxyError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ProxyError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ProxyError::UpstreamStatus { status, .. } => *status,
+            ProxyError::ModelLoading(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ProxyError::Transport { kind: TransportErrorKind::Timeout, .. } => StatusCode::GATEWAY_TIMEOUT,
+            ProxyError::Transport { kind: TransportErrorKind::PoolExhausted, .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ProxyError::Transport { .. } | ProxyError::IncompleteResponse { .. } => StatusCode::BAD_GATEWAY,
+            ProxyError::Internal(_) | ProxyError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
 
-        (status, body).into_response()
+#[cfg(feature = "server")]
+impl IntoResponse for ProxyError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        (status, Json(self.into_envelope())).into_response()
     }
 }
 
@@ -41,6 +209,12 @@ impl std::fmt::Display for ProxyError {
         match self {
             ProxyError::BadRequest(msg) => write!(f, "Bad Request: {}", msg),
             ProxyError::Upstream(msg) => write!(f, "Upstream Error: {}", msg),
+            ProxyError::UpstreamStatus { status, body } => write!(f, "Upstream Error: HTTP {}: {}", status, body),
+            ProxyError::ModelLoading(msg) => write!(f, "Model Loading: {}", msg),
+            ProxyError::Transport { kind, message } => write!(f, "Upstream {} Error: {}", kind, message),
+            ProxyError::IncompleteResponse { expected, received } => {
+                write!(f, "Incomplete Upstream Response: received {} of {} bytes", received, expected)
+            }
             ProxyError::Internal(msg) => write!(f, "Internal Error: {}", msg),
             ProxyError::Serialization(msg) => write!(f, "Serialization Error: {}", msg),
         }
@@ -49,6 +223,51 @@ impl std::fmt::Display for ProxyError {
 
 impl std::error::Error for ProxyError {}
 
+/// Header self-hosted backends set on 503 responses while the model loads
+pub const MODEL_LOADING_HEADER: &str = "x-model-loading";
+
+/// Lowercase fragments of 503 bodies sent by backends (TGI, vLLM and the
+/// like) that are still loading their model
+const MODEL_LOADING_MARKERS: &[&str] = &["loading", "not ready", "warming up"];
+
+impl ProxyError {
+    /// Error for an upstream error status. A 503 with the model loading
+    /// header or a known loading message becomes [`ProxyError::ModelLoading`].
+    pub fn upstream_status(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap, body: String) -> Self {
+        if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            let lowercase = body.to_ascii_lowercase();
+            if headers.contains_key(MODEL_LOADING_HEADER)
+                || MODEL_LOADING_MARKERS.iter().any(|marker| lowercase.contains(marker))
+            {
+                return ProxyError::ModelLoading(body);
+            }
+        }
+        ProxyError::UpstreamStatus { status, body }
+    }
+
+    /// Whether the backend reported that its model is still loading
+    pub fn is_model_loading(&self) -> bool {
+        matches!(self, ProxyError::ModelLoading(_))
+    }
+
+    /// Whether retrying the request could succeed.
+    ///
+    /// Upstream failures are retryable except for client error statuses (HTTP
+    /// 4xx other than 408 and 429), which would fail the same way again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ProxyError::Upstream(_) | ProxyError::ModelLoading(_) | ProxyError::IncompleteResponse { .. } => true,
+            ProxyError::UpstreamStatus { status, .. } => {
+                !status.is_client_error()
+                    || *status == reqwest::StatusCode::REQUEST_TIMEOUT
+                    || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            ProxyError::Transport { kind, .. } => kind.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
 /// # From Trait Implementations for Better Error Handling
 /// 
 /// These implementations allow automatic conversion from common error types
@@ -60,17 +279,23 @@ impl From<reqwest::Error> for ProxyError {
     /// This provides intelligent error classification based on the underlying
     /// HTTP error type, similar to catching specific exception types in C++.
     fn from(err: reqwest::Error) -> Self {
-        if err.is_timeout() {
-            ProxyError::Upstream("Request timeout - backend service did not respond in time".to_string())
-        } else if err.is_connect() {
-            ProxyError::Upstream("Connection failed - unable to reach backend service".to_string())
-        } else if err.is_request() {
+        if let Some(status) = err.status() {
+            ProxyError::UpstreamStatus { status, body: err.to_string() }
+        } else if err.is_builder() {
             ProxyError::BadRequest(format!("Invalid request: {}", err))
-        } else if err.status().is_some() {
-            let status = err.status().unwrap();
-            ProxyError::Upstream(format!("HTTP {}: {}", status.as_u16(), err))
         } else {
-            ProxyError::Upstream(format!("HTTP client error: {}", err))
+            // Include the source chain, which carries the actual cause
+            let mut message = err.to_string();
+            let mut source = std::error::Error::source(&err);
+            while let Some(cause) = source {
+                message.push_str(&format!(": {}", cause));
+                source = cause.source();
+            }
+
+            ProxyError::Transport {
+                kind: TransportErrorKind::classify(&err),
+                message,
+            }
         }
     }
 }
@@ -147,4 +372,87 @@ impl From<uuid::Error> for ProxyError {
     fn from(err: uuid::Error) -> Self {
         ProxyError::Internal(format!("UUID error: {}", err))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_connection_refused_classifies_as_connect() {
+        // Reserve a port and release it so nothing is listening there
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let err = reqwest::Client::new()
+            .get(format!("http://127.0.0.1:{}/", port))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert_eq!(TransportErrorKind::classify(&err), TransportErrorKind::Connect);
+        let error = ProxyError::from(err);
+        assert!(matches!(error, ProxyError::Transport { kind: TransportErrorKind::Connect, .. }));
+        assert!(error.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_classifies_as_timeout() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let err = reqwest::Client::builder()
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap()
+            .get(server.uri())
+            .send()
+            .await
+            .unwrap_err();
+
+        let error = ProxyError::from(err);
+        assert!(matches!(error, ProxyError::Transport { kind: TransportErrorKind::Timeout, .. }));
+        #[cfg(feature = "server")]
+        assert_eq!(error.status_code(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_upstream_status_without_error_envelope_is_wrapped() {
+        let error = ProxyError::UpstreamStatus {
+            status: StatusCode::BAD_REQUEST,
+            body: "<html>Bad Request</html>".to_string(),
+        };
+        assert!(!error.is_retryable());
+
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["message"], "Upstream error: HTTP 400 Bad Request: <html>Bad Request</html>");
+        assert_eq!(json["error"]["type"], "proxy_error");
+    }
+
+    #[test]
+    fn test_model_loading_recognized_from_503_body_or_header() {
+        use reqwest::{header::HeaderMap, StatusCode};
+
+        let none = HeaderMap::new();
+        let mut flagged = HeaderMap::new();
+        flagged.insert(MODEL_LOADING_HEADER, "true".parse().unwrap());
+
+        let loading = ProxyError::upstream_status(StatusCode::SERVICE_UNAVAILABLE, &none, "Model is loading".to_string());
+        assert!(loading.is_model_loading());
+        let loading = ProxyError::upstream_status(StatusCode::SERVICE_UNAVAILABLE, &flagged, "Unavailable".to_string());
+        assert!(loading.is_model_loading());
+
+        let overloaded = ProxyError::upstream_status(StatusCode::SERVICE_UNAVAILABLE, &none, "Overloaded".to_string());
+        assert!(!overloaded.is_model_loading());
+        let other = ProxyError::upstream_status(StatusCode::BAD_GATEWAY, &flagged, "Model is loading".to_string());
+        assert!(!other.is_model_loading());
+    }
+}
@@ -24,6 +24,7 @@ use std::sync::Arc;
 use std::time::Duration;
 #[cfg(feature = "server")]
 use tokio::signal;
+use tokio::sync::Notify;
 #[cfg(feature = "server")]
 use tokio::time::timeout;
 use tracing::{info, warn, error};
@@ -38,6 +39,8 @@ pub struct GracefulShutdown {
     pub shutdown_initiated: Arc<AtomicBool>,
     /// Flag indicating if shutdown is complete
     shutdown_complete: Arc<AtomicBool>,
+    /// Wakes tasks waiting in `initiated()`
+    initiated_notify: Arc<Notify>,
 }
 
 impl GracefulShutdown {
@@ -51,6 +54,7 @@ impl GracefulShutdown {
         Self {
             shutdown_initiated: Arc::new(AtomicBool::new(false)),
             shutdown_complete: Arc::new(AtomicBool::new(false)),
+            initiated_notify: Arc::new(Notify::new()),
         }
     }
     
@@ -81,6 +85,22 @@ impl GracefulShutdown {
     pub fn initiate_shutdown(&self) {
         info!("🛑 Graceful shutdown initiated");
         self.shutdown_initiated.store(true, Ordering::Relaxed);
+        self.initiated_notify.notify_waiters();
+    }
+
+    /// # Wait for shutdown to be initiated
+    ///
+    /// Resolves once `initiate_shutdown` has been called, immediately if it
+    /// already has been.
+    pub async fn initiated(&self) {
+        let notified = self.initiated_notify.notified();
+        tokio::pin!(notified);
+        // Register before checking the flag so a concurrent initiation is not missed
+        notified.as_mut().enable();
+        if self.is_shutdown_initiated() {
+            return;
+        }
+        notified.await;
     }
     
     /// # Complete shutdown
@@ -323,6 +343,22 @@ mod tests {
         assert!(!shutdown.is_shutdown_complete());
     }
     
+    #[tokio::test]
+    async fn test_initiated_wakes_waiters() {
+        let shutdown = GracefulShutdown::new();
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.initiated().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        shutdown.initiate_shutdown();
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        // Already initiated: resolves immediately
+        tokio::time::timeout(Duration::from_secs(1), shutdown.initiated()).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_shutdown_completion() {
         let shutdown = GracefulShutdown::new();
@@ -86,6 +86,9 @@ pub mod batching;
 #[cfg(feature = "metrics")]
 pub mod metrics;
 
+#[cfg(all(feature = "server", feature = "metrics"))]
+pub mod monitoring;
+
 #[cfg(feature = "rate-limiting")]
 pub mod rate_limiting;
 
@@ -108,7 +111,7 @@ pub mod nodejs;
 
 // Re-export commonly used types for convenience
 pub use config::Config;
-pub use error::ProxyError;
+pub use error::{ProxyError, TransportErrorKind};
 pub use adapters::{Adapter, LightLLMAdapter, OpenAIAdapter};
 pub use schemas::{ChatCompletionRequest, Message, Tool, ToolChoice, FunctionCall, ToolCall};
 pub use core::http_client::{HttpClientBuilder, HttpClientConfig};
@@ -127,7 +130,7 @@ pub use tools::{
 
 // Server re-exports (feature-gated)
 #[cfg(feature = "server")]
-pub use server::{AppState, create_router};
+pub use server::{AppState, ConnectionLimiter, InboundProtocol, ServeOptions, create_router, serve};
 
 #[cfg(feature = "server")]
 pub use server::handlers::chat_completions;
@@ -138,7 +141,7 @@ pub use streaming::{StreamingHandler, create_streaming_response};
 
 // Enhanced features re-exports (feature-gated)
 #[cfg(feature = "metrics")]
-pub use metrics::{LLMMetrics, MetricsCollector};
+pub use metrics::{LLMMetrics, MetricsCollector, SlaAlert, SlaCompliance, SlaTracker, WindowedErrorRate};
 
 #[cfg(feature = "caching")]
 pub use caching::{CacheManager, CacheConfig, CacheStats};
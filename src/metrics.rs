@@ -2,17 +2,21 @@
 //!
 //! Collects and aggregates performance metrics for monitoring and optimization.
 //! Provides real-time insights into system performance and usage patterns.
+//! Models with a latency target in `sla_target_ms` also get SLO compliance:
+//! the percentage of successful requests answered within the target.
 
+use crate::config::Config;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     sync::{
         atomic::{AtomicU64, AtomicUsize, Ordering},
-        Arc, RwLock,
+        Arc, Mutex, RwLock,
     },
     time::{Duration, Instant},
 };
 use tokio::time::interval;
-use tracing::info;
+use tracing::{info, warn};
 
 /// # LLM Metrics
 ///
@@ -52,6 +56,258 @@ impl Default for LLMMetrics {
     }
 }
 
+/// Request outcomes observed within the recent sliding window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowedErrorRate {
+    /// Requests completed within the window
+    pub requests: u64,
+    /// Failed requests within the window
+    pub failures: u64,
+    /// Error rate within the window (0.0 to 1.0)
+    pub error_rate: f64,
+}
+
+/// Default length of the sliding window used for recent error rates
+const DEFAULT_ERROR_WINDOW: Duration = Duration::from_secs(60);
+
+/// Requests a model needs within the alert window before an SLA alert can fire
+const SLA_ALERT_MIN_REQUESTS: u64 = 10;
+
+/// Upper bounds, in seconds, of the request duration histogram buckets
+const DURATION_BUCKETS: [f64; 11] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// Distinct `model` label values kept before further models, which come
+/// straight from client requests, are counted under [`OTHER_MODEL_LABEL`]
+const MAX_MODEL_LABELS: usize = 100;
+
+/// `model` label of requests for models beyond [`MAX_MODEL_LABELS`]
+const OTHER_MODEL_LABEL: &str = "other";
+
+/// Content type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Label set of the per-request Prometheus series
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestLabels {
+    backend: String,
+    model: String,
+    status: u16,
+}
+
+impl RequestLabels {
+    fn render(&self) -> String {
+        format!(
+            "backend=\"{}\",model=\"{}\",status=\"{}\"",
+            escape_label(&self.backend),
+            escape_label(&self.model),
+            self.status
+        )
+    }
+}
+
+/// Per-request series and the model label values they use
+#[derive(Debug, Default)]
+struct LabelledRequests {
+    series: BTreeMap<RequestLabels, DurationHistogram>,
+    models: HashSet<String>,
+}
+
+/// Request durations of one label set; `count` doubles as the request counter
+#[derive(Debug, Default)]
+struct DurationHistogram {
+    /// Non-cumulative count per bucket in `DURATION_BUCKETS`
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+}
+
+impl DurationHistogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum_seconds += seconds;
+    }
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Latency SLO compliance of one model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaCompliance {
+    /// Latency target in milliseconds
+    pub target_ms: u64,
+    /// Successful requests measured
+    pub requests: u64,
+    /// Requests answered within the target
+    pub within_target: u64,
+    /// Percentage of requests answered within the target
+    pub compliance_percent: f64,
+    /// Requests measured within the alert window
+    pub window_requests: u64,
+    /// Percentage of requests within the alert window answered within the target
+    pub window_compliance_percent: f64,
+}
+
+/// Sent to `sla_alert_webhook_url` when a model's compliance drops below the threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaAlert {
+    /// Model whose compliance dropped
+    pub model: String,
+    /// Latency target in milliseconds
+    pub target_ms: u64,
+    /// Compliance within the alert window
+    pub compliance_percent: f64,
+    /// Compliance the alert fires below
+    pub threshold_percent: f64,
+    /// Length of the alert window in seconds
+    pub window_secs: u64,
+    /// Requests measured within the alert window
+    pub window_requests: u64,
+}
+
+impl SlaAlert {
+    /// Post the alert to a webhook, logging delivery failures
+    pub async fn deliver(&self, client: &reqwest::Client, webhook_url: &str) {
+        let result = client
+            .post(webhook_url)
+            .json(self)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(error) = result {
+            warn!(model = %self.model, error = %error, "Failed to deliver SLA alert");
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ModelSla {
+    requests: u64,
+    within_target: u64,
+    /// Completion time and whether the target was met, for requests within the window
+    recent: VecDeque<(Instant, bool)>,
+    /// Set while compliance is below the threshold, so each drop alerts once
+    alerting: bool,
+}
+
+impl ModelSla {
+    fn window_compliance(&self) -> (u64, f64) {
+        let requests = self.recent.len() as u64;
+        let met = self.recent.iter().filter(|(_, met)| *met).count() as u64;
+        (requests, percent(met, requests))
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// # SLA Tracker
+///
+/// Tracks per-model latency SLO compliance and detects compliance drops
+/// within a sliding window.
+#[derive(Debug)]
+pub struct SlaTracker {
+    /// Latency target in milliseconds per model
+    targets: HashMap<String, u64>,
+    /// Windowed compliance percentage below which an alert fires
+    alert_below_percent: f64,
+    /// Sliding window for alerting
+    window: Duration,
+    models: Mutex<HashMap<String, ModelSla>>,
+}
+
+impl SlaTracker {
+    /// Create a tracker for the given per-model targets
+    pub fn new(targets: HashMap<String, u64>, alert_below_percent: f64, window: Duration) -> Self {
+        Self {
+            targets,
+            alert_below_percent,
+            window,
+            models: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build the tracker from `sla_target_ms` and the `sla_alert_*` settings
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.sla_targets(),
+            config.sla_alert_below_percent,
+            Duration::from_secs(config.sla_alert_window_secs),
+        )
+    }
+
+    /// Record the latency of a successful request for `model`.
+    ///
+    /// Returns an alert when the model's compliance within the window has
+    /// just dropped below the threshold.
+    pub fn record(&self, model: &str, latency_ms: u64) -> Option<SlaAlert> {
+        let target_ms = *self.targets.get(model)?;
+        let met = latency_ms <= target_ms;
+        let now = Instant::now();
+
+        let mut models = self.models.lock().unwrap();
+        let sla = models.entry(model.to_string()).or_default();
+        sla.requests += 1;
+        if met {
+            sla.within_target += 1;
+        }
+        MetricsCollector::expire(&mut sla.recent, now, self.window);
+        sla.recent.push_back((now, met));
+
+        let (window_requests, compliance_percent) = sla.window_compliance();
+        let below = window_requests >= SLA_ALERT_MIN_REQUESTS && compliance_percent < self.alert_below_percent;
+        let fire = below && !sla.alerting;
+        sla.alerting = below;
+        fire.then(|| SlaAlert {
+            model: model.to_string(),
+            target_ms,
+            compliance_percent,
+            threshold_percent: self.alert_below_percent,
+            window_secs: self.window.as_secs(),
+            window_requests,
+        })
+    }
+
+    /// Compliance of every model with a target
+    pub fn compliance(&self) -> BTreeMap<String, SlaCompliance> {
+        let mut models = self.models.lock().unwrap();
+        let now = Instant::now();
+        self.targets
+            .iter()
+            .map(|(model, &target_ms)| {
+                let sla = models.entry(model.clone()).or_default();
+                MetricsCollector::expire(&mut sla.recent, now, self.window);
+                let (window_requests, window_compliance_percent) = sla.window_compliance();
+                let compliance = SlaCompliance {
+                    target_ms,
+                    requests: sla.requests,
+                    within_target: sla.within_target,
+                    compliance_percent: percent(sla.within_target, sla.requests),
+                    window_requests,
+                    window_compliance_percent,
+                };
+                (model.clone(), compliance)
+            })
+            .collect()
+    }
+}
+
+impl Default for SlaTracker {
+    fn default() -> Self {
+        Self::new(HashMap::new(), 0.0, DEFAULT_ERROR_WINDOW)
+    }
+}
+
 /// # Metrics Collector
 ///
 /// Collects and aggregates metrics from various sources.
@@ -73,6 +329,14 @@ pub struct MetricsCollector {
     response_time_count: Arc<AtomicUsize>,
     /// Start time for rate calculations
     start_time: Instant,
+    /// Completion time and failure flag of requests within `error_window`
+    recent_outcomes: Arc<Mutex<VecDeque<(Instant, bool)>>>,
+    /// Length of the sliding window for recent error rates
+    error_window: Duration,
+    /// Per-model latency SLO compliance
+    sla: SlaTracker,
+    /// Request counts and durations by backend, model and status
+    labelled: Mutex<LabelledRequests>,
 }
 
 impl MetricsCollector {
@@ -87,9 +351,30 @@ impl MetricsCollector {
             response_time_accumulator: Arc::new(AtomicU64::new(0)),
             response_time_count: Arc::new(AtomicUsize::new(0)),
             start_time: Instant::now(),
+            recent_outcomes: Arc::new(Mutex::new(VecDeque::new())),
+            error_window: DEFAULT_ERROR_WINDOW,
+            sla: SlaTracker::default(),
+            labelled: Mutex::new(LabelledRequests::default()),
         }
     }
 
+    /// Set the length of the sliding window used by `recent_error_rate`
+    pub fn with_error_window(mut self, window: Duration) -> Self {
+        self.error_window = window;
+        self
+    }
+
+    /// Track per-model latency SLO compliance with `sla`
+    pub fn with_sla(mut self, sla: SlaTracker) -> Self {
+        self.sla = sla;
+        self
+    }
+
+    /// Get the per-model SLA tracker
+    pub fn sla(&self) -> &SlaTracker {
+        &self.sla
+    }
+
     /// Record a request
     pub fn record_request(&self) {
         self.request_counter.fetch_add(1, Ordering::Relaxed);
@@ -101,11 +386,114 @@ impl MetricsCollector {
         self.token_counter.fetch_add(tokens, Ordering::Relaxed);
         self.response_time_accumulator.fetch_add(response_time_ms, Ordering::Relaxed);
         self.response_time_count.fetch_add(1, Ordering::Relaxed);
+        self.record_outcome(false);
     }
 
     /// Record a failed request
     pub fn record_failure(&self) {
         self.failure_counter.fetch_add(1, Ordering::Relaxed);
+        self.record_outcome(true);
+    }
+
+    /// Add an outcome to the sliding window, dropping expired ones
+    fn record_outcome(&self, failed: bool) {
+        let now = Instant::now();
+        let mut outcomes = self.recent_outcomes.lock().unwrap();
+        Self::expire(&mut outcomes, now, self.error_window);
+        outcomes.push_back((now, failed));
+    }
+
+    fn expire(outcomes: &mut VecDeque<(Instant, bool)>, now: Instant, window: Duration) {
+        while outcomes
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            outcomes.pop_front();
+        }
+    }
+
+    /// Record a finished request under its backend, model and response status.
+    ///
+    /// Once `MAX_MODEL_LABELS` models have been seen, requests for any other
+    /// model are labelled `other` so clients cannot create unbounded series.
+    pub fn record_labelled(&self, backend: &str, model: &str, status: u16, duration: Duration) {
+        let mut labelled = self.labelled.lock().unwrap();
+        let model = if labelled.models.contains(model) {
+            model
+        } else if labelled.models.len() < MAX_MODEL_LABELS {
+            labelled.models.insert(model.to_string());
+            model
+        } else {
+            OTHER_MODEL_LABEL
+        };
+        let labels = RequestLabels {
+            backend: backend.to_string(),
+            model: model.to_string(),
+            status,
+        };
+        labelled.series.entry(labels).or_default().observe(duration.as_secs_f64());
+    }
+
+    /// Render the request metrics in the Prometheus text exposition format.
+    ///
+    /// `backend` labels the per-backend gauges.
+    pub fn render_prometheus(&self, backend: &str) -> String {
+        use std::fmt::Write;
+
+        let labelled = self.labelled.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP nnllm_requests_total Chat completion requests by backend, model and status.\n");
+        out.push_str("# TYPE nnllm_requests_total counter\n");
+        for (labels, histogram) in labelled.series.iter() {
+            let _ = writeln!(out, "nnllm_requests_total{{{}}} {}", labels.render(), histogram.count);
+        }
+
+        out.push_str("# HELP nnllm_request_duration_seconds Chat completion request duration in seconds.\n");
+        out.push_str("# TYPE nnllm_request_duration_seconds histogram\n");
+        for (labels, histogram) in labelled.series.iter() {
+            let labels = labels.render();
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "nnllm_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+            }
+            let _ = writeln!(out, "nnllm_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+            let _ = writeln!(out, "nnllm_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum_seconds);
+            let _ = writeln!(out, "nnllm_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+        }
+        drop(labelled);
+
+        let backend = escape_label(backend);
+        let recent = self.recent_error_rate();
+        out.push_str("# HELP nnllm_backend_recent_requests Requests completed within the recent error window.\n");
+        out.push_str("# TYPE nnllm_backend_recent_requests gauge\n");
+        let _ = writeln!(out, "nnllm_backend_recent_requests{{backend=\"{}\"}} {}", backend, recent.requests);
+        out.push_str("# HELP nnllm_backend_recent_error_rate Error rate within the recent error window (0 to 1).\n");
+        out.push_str("# TYPE nnllm_backend_recent_error_rate gauge\n");
+        let _ = writeln!(out, "nnllm_backend_recent_error_rate{{backend=\"{}\"}} {}", backend, recent.error_rate);
+
+        out
+    }
+
+    /// Error rate of the requests completed within the sliding window
+    pub fn recent_error_rate(&self) -> WindowedErrorRate {
+        let mut outcomes = self.recent_outcomes.lock().unwrap();
+        Self::expire(&mut outcomes, Instant::now(), self.error_window);
+
+        let requests = outcomes.len() as u64;
+        let failures = outcomes.iter().filter(|(_, failed)| *failed).count() as u64;
+        let error_rate = if requests > 0 {
+            failures as f64 / requests as f64
+        } else {
+            0.0
+        };
+
+        WindowedErrorRate {
+            requests,
+            failures,
+            error_rate,
+        }
     }
 
     /// Get current metrics
@@ -241,4 +629,72 @@ impl Default for MetricsCollector {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_labels_capped() {
+        let collector = MetricsCollector::new();
+        for i in 0..MAX_MODEL_LABELS + 5 {
+            collector.record_labelled("openai", &format!("model-{}", i), 400, Duration::from_millis(10));
+        }
+        collector.record_labelled("openai", "model-0", 200, Duration::from_millis(10));
+
+        let labelled = collector.labelled.lock().unwrap();
+        assert_eq!(labelled.models.len(), MAX_MODEL_LABELS);
+        assert_eq!(labelled.series.len(), MAX_MODEL_LABELS + 2);
+        let other = RequestLabels {
+            backend: "openai".to_string(),
+            model: OTHER_MODEL_LABEL.to_string(),
+            status: 400,
+        };
+        assert_eq!(labelled.series[&other].count, 5);
+    }
+
+    #[test]
+    fn test_recent_error_rate_only_counts_window() {
+        let collector = MetricsCollector::new().with_error_window(Duration::from_millis(50));
+        collector.record_failure();
+        collector.record_failure();
+        std::thread::sleep(Duration::from_millis(80));
+
+        collector.record_success(0, 10);
+        collector.record_failure();
+
+        let recent = collector.recent_error_rate();
+        assert_eq!(recent.requests, 2);
+        assert_eq!(recent.failures, 1);
+        assert_eq!(recent.error_rate, 0.5);
+    }
+
+    #[test]
+    fn test_sla_compliance_per_model_and_alert_on_drop() {
+        let targets = HashMap::from([("gpt-4o".to_string(), 1000), ("llama-70b".to_string(), 3000)]);
+        let tracker = SlaTracker::new(targets, 90.0, Duration::from_secs(60));
+
+        // 8 of 10 gpt-4o requests meet the 1000ms target
+        let mut alerts = Vec::new();
+        for latency_ms in [200, 400, 1000, 800, 950, 1500, 300, 2500, 100, 600] {
+            alerts.extend(tracker.record("gpt-4o", latency_ms));
+        }
+        tracker.record("llama-70b", 2000);
+        assert!(tracker.record("unlisted-model", 99_999).is_none());
+
+        let compliance = tracker.compliance();
+        assert_eq!(compliance.len(), 2);
+        let gpt = &compliance["gpt-4o"];
+        assert_eq!((gpt.requests, gpt.within_target), (10, 8));
+        assert_eq!(gpt.compliance_percent, 80.0);
+        assert_eq!(gpt.window_compliance_percent, 80.0);
+        assert_eq!(compliance["llama-70b"].compliance_percent, 100.0);
+
+        // Compliance dropped below 90% once the window held enough requests; alert once
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].model, "gpt-4o");
+        assert_eq!(alerts[0].compliance_percent, 80.0);
+        assert!(tracker.record("gpt-4o", 5000).is_none());
+    }
+}
@@ -17,25 +17,24 @@ use crate::{
     adapters::Adapter,
     error::ProxyError,
     schemas::ChatCompletionRequest,
+    streaming::{StreamingStats, StreamingStatsSnapshot},
 };
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{Response, IntoResponse, Json},
-    routing::{get, post},
+    response::Json,
+    routing::get,
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     sync::Arc,
-    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::{
     sync::RwLock,
     time::interval,
 };
-use tracing::{debug, info, warn, error, instrument};
+use tracing::{debug, info};
 use uuid::Uuid;
 
 /// # System Metrics
@@ -53,6 +52,8 @@ pub struct SystemMetrics {
     pub resources: ResourceMetrics,
     /// Backend metrics
     pub backends: HashMap<String, BackendHealthMetrics>,
+    /// Streaming metrics
+    pub streaming: StreamingStatsSnapshot,
     /// System information
     pub system_info: SystemInfo,
 }
@@ -164,6 +165,8 @@ pub struct BackendHealthMetrics {
     pub last_health_check: Option<SystemTime>,
     /// Circuit breaker status
     pub circuit_breaker_status: CircuitBreakerStatus,
+    /// Why the last health check failed
+    pub error_message: Option<String>,
 }
 
 /// # Backend Health Status
@@ -181,6 +184,18 @@ pub enum BackendHealthStatus {
     Unknown,
 }
 
+impl BackendHealthStatus {
+    /// Lowercase name used in health check responses
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendHealthStatus::Healthy => "healthy",
+            BackendHealthStatus::Degraded => "degraded",
+            BackendHealthStatus::Unhealthy => "unhealthy",
+            BackendHealthStatus::Unknown => "unknown",
+        }
+    }
+}
+
 /// # Circuit Breaker Status
 /// 
 /// Circuit breaker status for backends.
@@ -259,6 +274,8 @@ pub struct MonitoringConfig {
     pub health_check_interval: Duration,
     /// Maximum error events to keep
     pub max_error_events: usize,
+    /// Sliding window requests per second and the error rate are averaged over
+    pub rate_window: Duration,
     /// Enable performance profiling
     pub enable_profiling: bool,
     /// Metrics endpoint path
@@ -278,6 +295,7 @@ impl Default for MonitoringConfig {
             metrics_interval: Duration::from_secs(10),
             health_check_interval: Duration::from_secs(30),
             max_error_events: 1000,
+            rate_window: Duration::from_secs(60),
             enable_profiling: false,
             metrics_endpoint: "/metrics".to_string(),
             health_endpoint: "/health".to_string(),
@@ -302,6 +320,8 @@ pub struct MonitoringSystem {
     error_tracker: Arc<ErrorTracker>,
     /// Performance profiler
     profiler: Arc<PerformanceProfiler>,
+    /// Streaming statistics
+    streaming_stats: Arc<StreamingStats>,
     /// System start time
     start_time: SystemTime,
 }
@@ -322,10 +342,97 @@ pub struct MetricsCollector {
     active_connections: Arc<std::sync::atomic::AtomicU32>,
     /// Bytes transferred
     bytes_transferred: Arc<std::sync::atomic::AtomicU64>,
+    /// Recent per-second request and error counts
+    rate_window: Arc<std::sync::Mutex<RateWindow>>,
 }
 
 impl Default for MetricsCollector {
     fn default() -> Self {
+        Self::new(MonitoringConfig::default().rate_window)
+    }
+}
+
+/// # Rate Window
+/// 
+/// Sliding window of request and error counts, kept in a ring buffer with
+/// one slot per second of the window.
+#[derive(Debug)]
+pub struct RateWindow {
+    /// Per-second counts, indexed by second modulo the window length
+    slots: Vec<RateSlot>,
+    /// Instant second 0 of the window starts at
+    origin: Instant,
+}
+
+/// Counts recorded during one second of a [`RateWindow`]
+#[derive(Debug, Clone, Copy, Default)]
+struct RateSlot {
+    /// Second since the window origin these counts belong to
+    second: u64,
+    /// Requests recorded in that second
+    requests: u64,
+    /// Failed requests recorded in that second
+    errors: u64,
+}
+
+impl RateWindow {
+    /// # Create new rate window
+    /// 
+    /// Creates a window covering `window`, rounded down to whole seconds (at least one).
+    pub fn new(window: Duration) -> Self {
+        Self {
+            slots: vec![RateSlot::default(); window.as_secs().max(1) as usize],
+            origin: Instant::now(),
+        }
+    }
+
+    /// # Record request
+    /// 
+    /// Counts a request in the current second.
+    pub fn record(&mut self, success: bool) {
+        self.record_at(Instant::now(), success);
+    }
+
+    fn record_at(&mut self, at: Instant, success: bool) {
+        let second = at.saturating_duration_since(self.origin).as_secs();
+        let len = self.slots.len() as u64;
+        let slot = &mut self.slots[(second % len) as usize];
+        if slot.second != second {
+            *slot = RateSlot { second, ..RateSlot::default() };
+        }
+        slot.requests += 1;
+        if !success {
+            slot.errors += 1;
+        }
+    }
+
+    /// # Get rates
+    /// 
+    /// Returns requests per second and errors per minute over the window, or
+    /// over the time elapsed so far while the window is still filling.
+    pub fn rates(&self) -> (f64, f64) {
+        self.rates_at(Instant::now())
+    }
+
+    fn rates_at(&self, now: Instant) -> (f64, f64) {
+        let current = now.saturating_duration_since(self.origin).as_secs();
+        let len = self.slots.len() as u64;
+        let (requests, errors) = self
+            .slots
+            .iter()
+            .filter(|slot| slot.second <= current && slot.second + len > current)
+            .fold((0, 0), |(requests, errors), slot| (requests + slot.requests, errors + slot.errors));
+
+        let span = (current + 1).min(len) as f64;
+        (requests as f64 / span, errors as f64 * 60.0 / span)
+    }
+}
+
+impl MetricsCollector {
+    /// # Create new metrics collector
+    /// 
+    /// Creates a collector averaging request and error rates over `rate_window`.
+    pub fn new(rate_window: Duration) -> Self {
         Self {
             request_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             success_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
@@ -333,11 +440,17 @@ impl Default for MetricsCollector {
             response_times: Arc::new(RwLock::new(Vec::new())),
             active_connections: Arc::new(std::sync::atomic::AtomicU32::new(0)),
             bytes_transferred: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            rate_window: Arc::new(std::sync::Mutex::new(RateWindow::new(rate_window))),
         }
     }
-}
 
-impl MetricsCollector {
+    /// # Get error rate
+    /// 
+    /// Returns failed requests per minute over the rate window.
+    pub fn errors_per_minute(&self) -> f64 {
+        self.rate_window.lock().unwrap().rates().1
+    }
+
     /// # Record request
     /// 
     /// Records a new request with timing information.
@@ -350,6 +463,7 @@ impl MetricsCollector {
         } else {
             self.error_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         }
+        self.rate_window.lock().unwrap().record(success);
         
         // Record response time
         let response_time_ms = duration.as_millis() as f64;
@@ -358,7 +472,8 @@ impl MetricsCollector {
         
         // Keep only last 1000 response times for memory efficiency
         if response_times.len() > 1000 {
-            response_times.drain(0..response_times.len() - 1000);
+            let excess = response_times.len() - 1000;
+            response_times.drain(0..excess);
         }
     }
     
@@ -372,6 +487,8 @@ impl MetricsCollector {
         let active_connections = self.active_connections.load(std::sync::atomic::Ordering::Relaxed);
         let total_bytes = self.bytes_transferred.load(std::sync::atomic::Ordering::Relaxed);
         
+        let (requests_per_second, _) = self.rate_window.lock().unwrap().rates();
+        
         let response_times = self.response_times.read().await;
         let avg_duration = if response_times.is_empty() {
             0.0
@@ -382,7 +499,7 @@ impl MetricsCollector {
         let p95_duration = if response_times.len() >= 20 {
             let mut sorted = response_times.clone();
             sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            sorted[(sorted.len() * 95 / 100)]
+            sorted[sorted.len() * 95 / 100]
         } else {
             avg_duration
         };
@@ -390,7 +507,7 @@ impl MetricsCollector {
         let p99_duration = if response_times.len() >= 20 {
             let mut sorted = response_times.clone();
             sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            sorted[(sorted.len() * 99 / 100)]
+            sorted[sorted.len() * 99 / 100]
         } else {
             avg_duration
         };
@@ -399,7 +516,7 @@ impl MetricsCollector {
             total_requests,
             successful_requests,
             failed_requests,
-            requests_per_second: 0.0, // Will be calculated by the monitoring system
+            requests_per_second,
             avg_request_duration: avg_duration,
             p95_request_duration: p95_duration,
             p99_request_duration: p99_duration,
@@ -479,50 +596,41 @@ impl Default for HealthMonitor {
 impl HealthMonitor {
     /// # Check backend health
     /// 
-    /// Performs health check on a backend.
+    /// Performs health check on a backend with a one-token completion. A
+    /// backend that answers with a client error is reachable but degraded;
+    /// transport failures, server errors and timeouts make it unhealthy.
     pub async fn check_backend_health(&self, backend_id: &str, adapter: &Adapter) -> BackendHealthMetrics {
         let start_time = Instant::now();
         
-        // Create a simple health check request
+        // Create a simple health check request for the adapter's default model
         let health_request = ChatCompletionRequest {
-            model: Some("health-check".to_string()),
-            messages: vec![crate::schemas::Message {
-                role: "user".to_string(),
-                content: Some("health".to_string()),
-                name: None,
-                function_call: None,
-                tool_call_id: None,
-                tool_calls: None,
-            }],
+            messages: vec![crate::schemas::Message::user("health".to_string())],
             stream: Some(false),
             temperature: Some(0.1),
             max_tokens: Some(1),
-            top_p: None,
-            frequency_penalty: None,
-            presence_penalty: None,
-            tools: None,
-            tool_choice: None,
+            ..Default::default()
         };
         
         // Perform health check with timeout
-        let is_healthy = match tokio::time::timeout(
+        let (health_status, error_message) = match tokio::time::timeout(
             Duration::from_secs(5),
             adapter.chat_completions(health_request)
         ).await {
-            Ok(Ok(_)) => true,
-            Ok(Err(_)) => false,
-            Err(_) => false, // Timeout
+            Ok(Ok(_)) => (BackendHealthStatus::Healthy, None),
+            Ok(Err(error)) => {
+                let status = match &error {
+                    ProxyError::UpstreamStatus { status, .. } if status.is_client_error() => BackendHealthStatus::Degraded,
+                    _ => BackendHealthStatus::Unhealthy,
+                };
+                (status, Some(error.to_string()))
+            }
+            Err(_) => (BackendHealthStatus::Unhealthy, Some("health check timed out after 5s".to_string())),
         };
+        let is_healthy = !matches!(health_status, BackendHealthStatus::Unhealthy);
         
         let response_time = start_time.elapsed();
         let response_time_ms = response_time.as_millis() as f64;
         
-        let health_status = if is_healthy {
-            BackendHealthStatus::Healthy
-        } else {
-            BackendHealthStatus::Unhealthy
-        };
-        
         let metrics = BackendHealthMetrics {
             backend_id: backend_id.to_string(),
             health_status,
@@ -532,6 +640,7 @@ impl HealthMonitor {
             failed_requests: if is_healthy { 0 } else { 1 },
             last_health_check: Some(SystemTime::now()),
             circuit_breaker_status: CircuitBreakerStatus::Closed,
+            error_message,
         };
         
         // Update backend health
@@ -541,6 +650,22 @@ impl HealthMonitor {
         metrics
     }
     
+    /// # Get cached backend health
+    /// 
+    /// Returns the last health check of a backend if it is younger than `max_age`.
+    pub async fn cached_backend_health(&self, backend_id: &str, max_age: Duration) -> Option<BackendHealthMetrics> {
+        let backend_health = self.backend_health.read().await;
+        backend_health
+            .get(backend_id)
+            .filter(|metrics| {
+                metrics
+                    .last_health_check
+                    .and_then(|checked| checked.elapsed().ok())
+                    .is_some_and(|age| age < max_age)
+            })
+            .cloned()
+    }
+    
     /// # Get system health
     /// 
     /// Returns current system health status.
@@ -606,7 +731,8 @@ impl ErrorTracker {
         
         // Keep only the most recent events
         if error_events.len() > self.max_events {
-            error_events.drain(0..error_events.len() - self.max_events);
+            let excess = error_events.len() - self.max_events;
+            error_events.drain(0..excess);
         }
         
         // Update error counters
@@ -635,7 +761,7 @@ impl ErrorTracker {
             errors_by_type: error_counters.clone(),
             errors_by_endpoint: endpoint_error_counters.clone(),
             recent_errors,
-            error_rate: 0.0, // Will be calculated by the monitoring system
+            error_rate: 0.0, // Failed requests per minute, filled in from the metrics collector
         }
     }
 }
@@ -689,7 +815,8 @@ impl PerformanceProfiler {
         
         // Keep only the most recent samples
         if samples.len() > self.max_samples {
-            samples.drain(0..samples.len() - self.max_samples);
+            let excess = samples.len() - self.max_samples;
+            samples.drain(0..excess);
         }
     }
     
@@ -735,6 +862,7 @@ impl MonitoringSystem {
     /// Creates a new monitoring system with the specified configuration.
     pub fn new(config: MonitoringConfig) -> Self {
         let start_time = SystemTime::now();
+        let collector = Arc::new(MetricsCollector::new(config.rate_window));
         
         Self {
             config,
@@ -777,21 +905,23 @@ impl MonitoringSystem {
                     thread_count: 0,
                 },
                 backends: HashMap::new(),
+                streaming: StreamingStatsSnapshot::default(),
                 system_info: SystemInfo {
                     version: env!("CARGO_PKG_VERSION").to_string(),
-                    build_timestamp: env!("VERGEN_BUILD_TIMESTAMP").to_string(),
-                    git_commit: env!("VERGEN_GIT_SHA").to_string(),
-                    rust_version: env!("VERGEN_RUSTC_SEMVER").to_string(),
+                    build_timestamp: option_env!("VERGEN_BUILD_TIMESTAMP").unwrap_or("unknown").to_string(),
+                    git_commit: option_env!("VERGEN_GIT_SHA").unwrap_or("unknown").to_string(),
+                    rust_version: option_env!("VERGEN_RUSTC_SEMVER").unwrap_or("unknown").to_string(),
                     os: std::env::consts::OS.to_string(),
                     arch: std::env::consts::ARCH.to_string(),
                     uptime: Duration::from_secs(0),
                     start_time,
                 },
             })),
-            collector: Arc::new(MetricsCollector::default()),
+            collector,
             health_monitor: Arc::new(HealthMonitor::default()),
             error_tracker: Arc::new(ErrorTracker::new(1000)),
             profiler: Arc::new(PerformanceProfiler::new(1000)),
+            streaming_stats: Arc::new(StreamingStats::new()),
             start_time,
         }
     }
@@ -821,6 +951,7 @@ impl MonitoringSystem {
     async fn start_metrics_collection(&self) {
         let metrics = self.metrics.clone();
         let collector = self.collector.clone();
+        let error_tracker = self.error_tracker.clone();
         let interval_duration = self.config.metrics_interval;
         
         tokio::spawn(async move {
@@ -831,10 +962,14 @@ impl MonitoringSystem {
                 
                 // Collect metrics
                 let request_metrics = collector.get_metrics().await;
+                let mut error_metrics = error_tracker.get_error_metrics().await;
+                error_metrics.error_rate = collector.errors_per_minute();
                 
                 // Update system metrics
                 let mut system_metrics = metrics.write().await;
+                system_metrics.performance.throughput = request_metrics.requests_per_second;
                 system_metrics.requests = request_metrics;
+                system_metrics.errors = error_metrics;
                 system_metrics.system_info.uptime = system_metrics.system_info.start_time.elapsed().unwrap_or_default();
                 
                 debug!("📊 Metrics collected: {} requests, {} errors", 
@@ -930,7 +1065,34 @@ impl MonitoringSystem {
     /// 
     /// Returns current system metrics.
     pub async fn get_metrics(&self) -> SystemMetrics {
-        self.metrics.read().await.clone()
+        let mut metrics = self.metrics.read().await.clone();
+        metrics.requests = self.collector.get_metrics().await;
+        metrics.errors = self.error_tracker.get_error_metrics().await;
+        metrics.errors.error_rate = self.collector.errors_per_minute();
+        metrics.streaming = self.streaming_stats.snapshot();
+        metrics.system_info.uptime = self.start_time.elapsed().unwrap_or_default();
+        metrics
+    }
+
+    /// # Get streaming statistics
+    /// 
+    /// Returns the shared streaming statistics, to be populated by the streaming handler.
+    pub fn streaming_stats(&self) -> Arc<StreamingStats> {
+        self.streaming_stats.clone()
+    }
+    
+    /// # Probe backend
+    /// 
+    /// Runs a live health check against `adapter` and caches the result.
+    pub async fn probe_backend(&self, adapter: &Adapter) -> BackendHealthMetrics {
+        self.health_monitor.check_backend_health(adapter.base_url(), adapter).await
+    }
+    
+    /// # Get cached backend health
+    /// 
+    /// Returns the last probe of `adapter` if it is younger than `max_age`.
+    pub async fn cached_backend_health(&self, adapter: &Adapter, max_age: Duration) -> Option<BackendHealthMetrics> {
+        self.health_monitor.cached_backend_health(adapter.base_url(), max_age).await
     }
     
     /// # Get health status
@@ -945,14 +1107,21 @@ impl MonitoringSystem {
     /// Creates a router with monitoring endpoints.
     pub fn create_monitoring_router(&self) -> Router {
         let metrics = self.metrics.clone();
+        let collector = self.collector.clone();
+        let streaming_stats = self.streaming_stats.clone();
         let health_monitor = self.health_monitor.clone();
         let error_tracker = self.error_tracker.clone();
+        let metrics_error_tracker = self.error_tracker.clone();
         let profiler = self.profiler.clone();
         
         Router::new()
             .route("/metrics", get(move || async move {
-                let metrics = metrics.read().await;
-                Json(metrics.clone())
+                let mut metrics = metrics.read().await.clone();
+                metrics.requests = collector.get_metrics().await;
+                metrics.errors = metrics_error_tracker.get_error_metrics().await;
+                metrics.errors.error_rate = collector.errors_per_minute();
+                metrics.streaming = streaming_stats.snapshot();
+                Json(metrics)
             }))
             .route("/health", get(move || async move {
                 let health = health_monitor.get_system_health().await;
@@ -998,6 +1167,43 @@ mod tests {
         assert_eq!(metrics.total_bytes_transferred, 1536);
     }
     
+    #[test]
+    fn test_rate_window_averages_over_the_window() {
+        let mut window = RateWindow::new(Duration::from_secs(10));
+        let origin = window.origin;
+
+        // 5 requests per second for 20 seconds, every fifth one failing
+        for tick in 0..100u64 {
+            window.record_at(origin + Duration::from_millis(tick * 200), tick % 5 != 0);
+        }
+        let (rps, errors_per_minute) = window.rates_at(origin + Duration::from_millis(19_999));
+        assert!((rps - 5.0).abs() < 0.6, "requests per second was {}", rps);
+        assert!((errors_per_minute - 60.0).abs() < 7.0, "errors per minute was {}", errors_per_minute);
+
+        // Requests age out of the window
+        let (rps, errors_per_minute) = window.rates_at(origin + Duration::from_secs(35));
+        assert_eq!(rps, 0.0);
+        assert_eq!(errors_per_minute, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_requests_per_second_reported_in_metrics() {
+        let monitoring = MonitoringSystem::new(MonitoringConfig {
+            rate_window: Duration::from_secs(30),
+            ..MonitoringConfig::default()
+        });
+        for _ in 0..6 {
+            monitoring.record_request(Duration::from_millis(10), true, 0).await;
+        }
+        monitoring.record_request(Duration::from_millis(10), false, 0).await;
+
+        // One in seven requests failed, whatever span the rates are averaged over
+        let metrics = monitoring.get_metrics().await;
+        let rps = metrics.requests.requests_per_second;
+        assert!(rps > 0.0 && rps <= 7.0, "requests per second was {}", rps);
+        assert!((metrics.errors.error_rate - rps * 60.0 / 7.0).abs() < 1e-9);
+    }
+
     #[tokio::test]
     async fn test_error_tracking() {
         let tracker = ErrorTracker::new(100);
@@ -36,7 +36,8 @@ use tokio::runtime::Runtime;
 pub struct NodeConfig {
     /// Backend LLM server URL (null or "direct" for direct mode)
     pub backend_url: Option<String>,
-    /// Backend LLM type (lightllm, vllm, openai, azure, aws, etc.)
+    /// Backend LLM type (lightllm, vllm, openai, azure, aws, etc.); detected
+    /// from the URL when unset
     pub backend_type: Option<String>,
     /// Default model identifier
     pub model_id: String,
@@ -56,7 +57,7 @@ impl Default for NodeConfig {
     fn default() -> Self {
         Self {
             backend_url: None, // Default to direct mode for maximum performance
-            backend_type: Some("lightllm".to_string()),
+            backend_type: None,
             model_id: "llama".to_string(),
             port: Some(3000),
             token: None,
@@ -73,7 +74,7 @@ impl From<NodeConfig> for Config {
 
         // Handle URL - default to direct mode if not provided
         config.backend_url = node_config.backend_url.unwrap_or_else(|| "direct".to_string());
-        config.backend_type = node_config.backend_type.unwrap_or_else(|| "lightllm".to_string());
+        config.backend_type = node_config.backend_type.unwrap_or_else(|| "auto".to_string());
         config.model_id = node_config.model_id;
 
         if let Some(port) = node_config.port {
@@ -120,6 +121,8 @@ impl From<NodeMessage> for Message {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         }
     }
 }
@@ -267,7 +270,11 @@ impl NodeNexusNitroLLMClient {
                 format!("Failed to create async runtime: {}", e)
             ))?;
 
-        let adapter = Adapter::from_config(&rust_config);
+        let adapter = Adapter::try_from_config(&rust_config)
+            .map_err(|e| Error::new(
+                Status::InvalidArg,
+                format!("Invalid configuration: {}", e)
+            ))?;
 
         Ok(Self {
             adapter,
@@ -317,6 +324,7 @@ impl NodeNexusNitroLLMClient {
                 crate::adapters::Adapter::AzureOpenAI(_) => "azure".to_string(),
                 crate::adapters::Adapter::AWSBedrock(_) => "aws".to_string(),
                 crate::adapters::Adapter::Custom(_) => "custom".to_string(),
+                crate::adapters::Adapter::Template(_) => "template".to_string(),
                 crate::adapters::Adapter::Direct(_) => "direct".to_string(),
             },
             backend_url: self.config.backend_url.clone(),
@@ -372,6 +380,7 @@ impl Task for NodeChatCompletionTask {
             tools: None,
             tool_choice: None,
             seed: None,
+            ..Default::default()
         };
 
         // PERFORMANCE FIX: Use singleton runtime instead of creating new one per call
@@ -393,6 +402,7 @@ impl Task for NodeChatCompletionTask {
                     Adapter::AzureOpenAI(adapter) => adapter.chat_completions(rust_request).await,
                     Adapter::AWSBedrock(adapter) => adapter.chat_completions(rust_request).await,
                     Adapter::Custom(adapter) => adapter.chat_completions(rust_request).await,
+                    Adapter::Template(adapter) => crate::adapters::AdapterTrait::chat_completions(adapter, rust_request).await,
                     Adapter::Direct(adapter) => adapter.chat_completions(rust_request).await,
                 }
             }).map_err(|e| Error::new(
@@ -494,14 +504,7 @@ impl Task for NodeConnectionTestTask {
         let result = rt.block_on(async {
             let test_request = crate::schemas::ChatCompletionRequest {
                 model: Some("test".to_string()),
-                messages: vec![crate::schemas::Message {
-                    role: "user".to_string(),
-                    content: Some("test".to_string()),
-                    name: None,
-                    tool_calls: None,
-                    function_call: None,
-                    tool_call_id: None,
-                }],
+                messages: vec![crate::schemas::Message::user("test".to_string())],
                 max_tokens: Some(1),
                 temperature: Some(0.1),
                 top_p: None,
@@ -517,6 +520,7 @@ impl Task for NodeConnectionTestTask {
                 tools: None,
                 tool_choice: None,
                 seed: None,
+                ..Default::default()
             };
 
             match &self.adapter {
@@ -526,6 +530,7 @@ impl Task for NodeConnectionTestTask {
                 Adapter::AzureOpenAI(adapter) => adapter.chat_completions(test_request).await,
                 Adapter::AWSBedrock(adapter) => adapter.chat_completions(test_request).await,
                 Adapter::Custom(adapter) => adapter.chat_completions(test_request).await,
+                Adapter::Template(adapter) => crate::adapters::AdapterTrait::chat_completions(adapter, test_request).await,
                 Adapter::Direct(adapter) => adapter.chat_completions(test_request).await,
             }
         });
@@ -554,7 +559,11 @@ impl NodeNexusNitroLLMClient {
         let rust_config: Config = new_config.into();
 
         // Recreate adapter with new configuration
-        self.adapter = Adapter::from_config(&rust_config);
+        self.adapter = Adapter::try_from_config(&rust_config)
+            .map_err(|e| Error::new(
+                Status::InvalidArg,
+                format!("Invalid configuration: {}", e)
+            ))?;
         self.config = rust_config;
 
         Ok(())
@@ -643,7 +652,7 @@ pub fn create_direct_client(
 ) -> Result<NodeNexusNitroLLMClient> {
     let config = NodeConfig {
         backend_url: None, // Direct mode
-        backend_type: Some("lightllm".to_string()),
+        backend_type: Some("direct".to_string()),
         model_id: model_id.unwrap_or_else(|| "llama".to_string()),
         port: None,
         token,
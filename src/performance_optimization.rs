@@ -15,6 +15,7 @@
 
 use crate::{
     adapters::Adapter,
+    core::http_client::{HttpClientBuilder, HttpClientConfig, HttpClientError},
     error::ProxyError,
     schemas::{ChatCompletionRequest, ChatCompletionResponse},
 };
@@ -90,6 +91,8 @@ pub enum LoadBalancingStrategy {
     HealthBased,
     /// Latency-based selection (prefer fastest backends)
     LatencyBased,
+    /// Cost-aware selection: weighted random, inversely proportional to backend cost
+    CostAware,
 }
 
 /// # Backend Health Status
@@ -104,8 +107,17 @@ pub enum BackendHealth {
     Degraded,
     /// Backend is unhealthy and not responding
     Unhealthy,
-    /// Backend is in circuit breaker state
+    /// Circuit breaker is open: the backend is skipped until `circuit_breaker_timeout` passes
     CircuitBreaker,
+    /// Circuit breaker is half-open: a single probe request decides whether it closes or re-opens
+    HalfOpen,
+}
+
+impl BackendHealth {
+    /// Whether the circuit breaker is open or half-open
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self, Self::CircuitBreaker | Self::HalfOpen)
+    }
 }
 
 /// # Backend Metrics
@@ -133,6 +145,9 @@ pub struct BackendMetrics {
     /// Last circuit breaker reset time
     #[serde(skip)]
     pub last_circuit_breaker_reset: Option<Instant>,
+    /// When the circuit breaker last opened or admitted a probe
+    #[serde(skip)]
+    pub circuit_breaker_opened_at: Option<Instant>,
 }
 
 /// # Backend Instance
@@ -146,36 +161,86 @@ pub struct BackendInstance {
     pub adapter: Adapter,
     /// Backend weight for load balancing
     pub weight: u32,
+    /// Relative cost per 1K tokens, used by the cost-aware strategy (0 = unpriced)
+    pub cost_per_1k_tokens: f64,
     /// Performance metrics
     pub metrics: Arc<RwLock<BackendMetrics>>,
     /// Request semaphore for concurrency control
     pub semaphore: Arc<Semaphore>,
     /// HTTP client for this backend
     pub http_client: Client,
+    /// Consecutive failures that open the circuit breaker
+    pub circuit_breaker_threshold: u32,
+    /// How long an open circuit breaker waits before admitting a probe
+    pub circuit_breaker_timeout: Duration,
 }
 
 impl BackendInstance {
     /// # Create new backend instance
     /// 
-    /// Creates a new backend instance with the specified configuration.
+    /// Creates a new backend instance with the default HTTP client settings.
     pub fn new(id: String, adapter: Adapter, weight: u32, max_concurrent: usize) -> Self {
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(90))
-            .build()
-            .unwrap_or_else(|_| Client::new());
-        
+        let http_client = HttpClientBuilder::new().build().unwrap_or_else(|_| Client::new());
+        Self::with_client(id, adapter, weight, max_concurrent, http_client)
+    }
+    
+    /// # Create backend instance with HTTP client settings
+    /// 
+    /// Builds the backend's HTTP client from `http_config`, so its connection
+    /// pool, timeouts, compression and HTTP/2 settings follow the operator's
+    /// tuning (e.g. `HttpClientConfig::from(&config)`).
+    pub fn with_http_config(
+        id: String,
+        adapter: Adapter,
+        weight: u32,
+        max_concurrent: usize,
+        http_config: HttpClientConfig,
+    ) -> Result<Self, HttpClientError> {
+        let http_client = HttpClientBuilder::with_config(http_config).build()?;
+        Ok(Self::with_client(id, adapter, weight, max_concurrent, http_client))
+    }
+    
+    fn with_client(id: String, adapter: Adapter, weight: u32, max_concurrent: usize, http_client: Client) -> Self {
+        let defaults = LoadBalancerConfig::default();
         Self {
             id,
             adapter,
             weight,
+            cost_per_1k_tokens: 0.0,
             metrics: Arc::new(RwLock::new(BackendMetrics::default())),
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
             http_client,
+            circuit_breaker_threshold: defaults.circuit_breaker_threshold,
+            circuit_breaker_timeout: defaults.circuit_breaker_timeout,
         }
     }
     
+    /// # Set circuit breaker parameters
+    /// 
+    /// Sets the consecutive failure threshold and the open-state timeout.
+    pub fn with_circuit_breaker(mut self, threshold: u32, timeout: Duration) -> Self {
+        self.circuit_breaker_threshold = threshold;
+        self.circuit_breaker_timeout = timeout;
+        self
+    }
+    
+    /// # Set backend cost
+    /// 
+    /// Sets the per-1K-token cost from the pricing table for cost-aware balancing.
+    pub fn with_cost(mut self, cost_per_1k_tokens: f64) -> Self {
+        self.cost_per_1k_tokens = cost_per_1k_tokens;
+        self
+    }
+    
+    /// # Cost weight
+    /// 
+    /// Selection weight for the cost-aware strategy. Unpriced backends are
+    /// weighted like the cheapest possible backend.
+    fn cost_weight(&self) -> f64 {
+        const MIN_COST: f64 = 1e-6;
+        1.0 / self.cost_per_1k_tokens.max(MIN_COST)
+    }
+    
     /// # Update metrics
     /// 
     /// Updates backend metrics with request results.
@@ -199,34 +264,69 @@ impl BackendInstance {
             metrics.avg_response_time = (metrics.avg_response_time * 0.9) + (response_time_ms * 0.1);
         }
         
-        // Update health status based on failure rate
-        let failure_rate = metrics.failed_requests as f64 / metrics.total_requests as f64;
-        if failure_rate > 0.5 {
-            metrics.health_status = BackendHealth::Unhealthy;
-        } else if failure_rate > 0.2 {
-            metrics.health_status = BackendHealth::Degraded;
-        } else {
-            metrics.health_status = BackendHealth::Healthy;
+        match metrics.health_status {
+            // The probe decides: close the breaker or re-open it for another timeout
+            BackendHealth::HalfOpen if success => {
+                metrics.health_status = BackendHealth::Healthy;
+                metrics.circuit_breaker_opened_at = None;
+                metrics.last_circuit_breaker_reset = Some(Instant::now());
+                info!("Circuit breaker closed for backend {}", self.id);
+            }
+            BackendHealth::HalfOpen => {
+                metrics.health_status = BackendHealth::CircuitBreaker;
+                metrics.circuit_breaker_opened_at = Some(Instant::now());
+                warn!("Probe failed, circuit breaker re-opened for backend {}", self.id);
+            }
+            // Late results of requests sent before the breaker opened
+            BackendHealth::CircuitBreaker => {}
+            _ if metrics.circuit_breaker_failures >= self.circuit_breaker_threshold => {
+                metrics.health_status = BackendHealth::CircuitBreaker;
+                metrics.circuit_breaker_opened_at = Some(Instant::now());
+                warn!(
+                    "Circuit breaker opened for backend {} after {} consecutive failures",
+                    self.id, metrics.circuit_breaker_failures
+                );
+            }
+            // Unhealthy is left to active health checks
+            BackendHealth::Unhealthy => {}
+            _ => {
+                // Update health status based on failure rate
+                let failure_rate = metrics.failed_requests as f64 / metrics.total_requests as f64;
+                metrics.health_status = if failure_rate > 0.2 {
+                    BackendHealth::Degraded
+                } else {
+                    BackendHealth::Healthy
+                };
+            }
         }
     }
     
     /// # Check if backend is available
     /// 
-    /// Checks if the backend is available for new requests.
+    /// Checks if the backend is available for new requests. Backends that are
+    /// unhealthy or whose circuit breaker is open or half-open are skipped.
     pub async fn is_available(&self) -> bool {
         let metrics = self.metrics.read().await;
-        match metrics.health_status {
-            BackendHealth::Healthy | BackendHealth::Degraded => true,
-            BackendHealth::Unhealthy | BackendHealth::CircuitBreaker => {
-                // Check if circuit breaker should be reset
-                if let Some(reset_time) = metrics.last_circuit_breaker_reset {
-                    if reset_time.elapsed() > Duration::from_secs(60) {
-                        return true; // Circuit breaker timeout expired
-                    }
-                }
-                false
-            }
+        matches!(metrics.health_status, BackendHealth::Healthy | BackendHealth::Degraded)
+    }
+    
+    /// # Admit circuit breaker probe
+    /// 
+    /// Moves an open circuit breaker to half-open once `circuit_breaker_timeout`
+    /// has passed, returning true if the caller should send the single probe
+    /// request. A probe that never reports back is replaced after another timeout.
+    pub async fn admit_probe(&self) -> bool {
+        let mut metrics = self.metrics.write().await;
+        let due = metrics.health_status.is_circuit_open()
+            && metrics
+                .circuit_breaker_opened_at
+                .is_none_or(|opened_at| opened_at.elapsed() >= self.circuit_breaker_timeout);
+        if due {
+            metrics.health_status = BackendHealth::HalfOpen;
+            metrics.circuit_breaker_opened_at = Some(Instant::now());
+            info!("Circuit breaker half-open for backend {}, sending probe", self.id);
         }
+        due
     }
 }
 
@@ -293,6 +393,10 @@ impl AdvancedLoadBalancer {
     /// 
     /// Adds a new backend to the load balancer.
     pub async fn add_backend(&self, backend: BackendInstance) {
+        let backend = backend.with_circuit_breaker(
+            self.config.circuit_breaker_threshold,
+            self.config.circuit_breaker_timeout,
+        );
         let mut backends = self.backends.write().await;
         backends.push(backend);
         info!("Added backend to load balancer: {} backends total", backends.len());
@@ -316,14 +420,20 @@ impl AdvancedLoadBalancer {
             return None;
         }
         
-        // Filter available backends
-        let available_backends: Vec<_> = backends
-            .iter()
-            .filter(|backend| {
-                // Check if backend is healthy and available
-                backend.health_status == BackendHealth::Healthy
-            })
-            .collect();
+        // A backend whose circuit breaker timeout has passed gets the next request as its probe
+        for backend in backends.iter() {
+            if backend.admit_probe().await {
+                return Some(backend.clone());
+            }
+        }
+        
+        // Filter available backends; unhealthy backends are never selected
+        let mut available_backends = Vec::with_capacity(backends.len());
+        for backend in backends.iter() {
+            if backend.is_available().await {
+                available_backends.push(backend);
+            }
+        }
         
         if available_backends.is_empty() {
             return None;
@@ -396,6 +506,22 @@ impl AdvancedLoadBalancer {
                 
                 Some(best_backend.clone())
             }
+            LoadBalancingStrategy::CostAware => {
+                // Weighted random selection, biased toward cheaper backends
+                let total_weight: f64 = available_backends.iter().map(|b| b.cost_weight()).sum();
+                let mut random_weight = fastrand::f64() * total_weight;
+                
+                for backend in &available_backends {
+                    let weight = backend.cost_weight();
+                    if random_weight < weight {
+                        return Some((*backend).clone());
+                    }
+                    random_weight -= weight;
+                }
+                
+                // Fallback to the last backend (floating point rounding)
+                available_backends.last().map(|backend| (*backend).clone())
+            }
         }
     }
     
@@ -503,6 +629,7 @@ impl AdvancedLoadBalancer {
                 health_status: metrics.health_status.clone(),
                 circuit_breaker_failures: metrics.circuit_breaker_failures,
                 last_circuit_breaker_reset: metrics.last_circuit_breaker_reset,
+                circuit_breaker_opened_at: metrics.circuit_breaker_opened_at,
             });
         }
         
@@ -541,6 +668,10 @@ impl AdvancedLoadBalancer {
                     let mut metrics = backend.metrics.write().await;
                     metrics.last_health_check = Some(Instant::now());
                     
+                    // An open circuit breaker recovers through its own probe
+                    if metrics.health_status.is_circuit_open() {
+                        continue;
+                    }
                     if !is_healthy {
                         metrics.health_status = BackendHealth::Unhealthy;
                         warn!("Health check failed for backend {}", backend.id);
@@ -583,6 +714,14 @@ impl AdvancedLoadBalancer {
             top_logprobs: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
         
         // Perform health check with timeout
@@ -854,6 +993,42 @@ mod tests {
         assert_eq!(metrics.backend_count, 1);
     }
     
+    #[tokio::test]
+    async fn test_backend_client_built_from_http_config() {
+        use wiremock::{matchers::header, Mock, MockServer, ResponseTemplate};
+        
+        let server = MockServer::start().await;
+        Mock::given(header("x-tenant", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(300)))
+            .mount(&server)
+            .await;
+        
+        let mut http_config = HttpClientConfig {
+            timeout: Duration::from_millis(100),
+            ..HttpClientConfig::default()
+        };
+        http_config.default_headers.insert("x-tenant".to_string(), "acme".to_string());
+        let backend = BackendInstance::with_http_config(
+            "tuned-backend".to_string(),
+            Adapter::LightLLM(LightLLMAdapter {
+                url: server.uri(),
+                model_id: "test-model".to_string(),
+            }),
+            1,
+            10,
+            http_config,
+        )
+        .unwrap();
+        
+        // The configured timeout applies to the backend's requests
+        let err = backend.http_client.get(server.uri()).send().await.unwrap_err();
+        assert!(err.is_timeout());
+        // And the configured default headers are sent
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers.get("x-tenant").unwrap(), "acme");
+    }
+    
     #[tokio::test]
     async fn test_backend_selection() {
         let config = LoadBalancerConfig::default();
@@ -886,6 +1061,135 @@ mod tests {
         assert_ne!(backend1.unwrap().id, backend2.unwrap().id);
     }
     
+    fn lightllm_backend(id: &str) -> BackendInstance {
+        BackendInstance::new(
+            id.to_string(),
+            Adapter::LightLLM(LightLLMAdapter {
+                url: "http://localhost:8000".to_string(),
+                model_id: "test-model".to_string(),
+            }),
+            1,
+            10,
+        )
+    }
+    
+    /// Load balancer over a "flaky" and a "stable" backend with a fast circuit breaker
+    async fn breaker_load_balancer() -> (AdvancedLoadBalancer, BackendInstance) {
+        let config = LoadBalancerConfig {
+            circuit_breaker_threshold: 3,
+            circuit_breaker_timeout: Duration::from_millis(50),
+            ..LoadBalancerConfig::default()
+        };
+        let load_balancer = AdvancedLoadBalancer::new(config);
+        load_balancer.add_backend(lightllm_backend("flaky")).await;
+        load_balancer.add_backend(lightllm_backend("stable")).await;
+        let flaky = load_balancer.backends.read().await[0].clone();
+        (load_balancer, flaky)
+    }
+    
+    async fn selections(load_balancer: &AdvancedLoadBalancer, count: usize) -> Vec<String> {
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            ids.push(load_balancer.select_backend().await.unwrap().id);
+        }
+        ids
+    }
+    
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_after_consecutive_failures() {
+        let (load_balancer, flaky) = breaker_load_balancer().await;
+        
+        for _ in 0..2 {
+            flaky.update_metrics(false, Duration::from_millis(10)).await;
+        }
+        assert!(flaky.is_available().await);
+        flaky.update_metrics(false, Duration::from_millis(10)).await;
+        
+        assert!(matches!(flaky.metrics.read().await.health_status, BackendHealth::CircuitBreaker));
+        assert!(selections(&load_balancer, 10).await.iter().all(|id| id == "stable"));
+    }
+    
+    #[tokio::test]
+    async fn test_circuit_breaker_half_opens_for_single_probe_and_reopens_on_failure() {
+        let (load_balancer, flaky) = breaker_load_balancer().await;
+        for _ in 0..3 {
+            flaky.update_metrics(false, Duration::from_millis(10)).await;
+        }
+        
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        // Exactly one probe goes to the flaky backend while it is half-open
+        let ids = selections(&load_balancer, 10).await;
+        assert_eq!(ids[0], "flaky");
+        assert!(ids[1..].iter().all(|id| id == "stable"));
+        assert!(matches!(flaky.metrics.read().await.health_status, BackendHealth::HalfOpen));
+        
+        flaky.update_metrics(false, Duration::from_millis(10)).await;
+        assert!(matches!(flaky.metrics.read().await.health_status, BackendHealth::CircuitBreaker));
+        assert!(selections(&load_balancer, 10).await.iter().all(|id| id == "stable"));
+    }
+    
+    #[tokio::test]
+    async fn test_successful_probe_closes_circuit_breaker() {
+        let (load_balancer, flaky) = breaker_load_balancer().await;
+        for _ in 0..3 {
+            flaky.update_metrics(false, Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(load_balancer.select_backend().await.unwrap().id, "flaky");
+        
+        flaky.update_metrics(true, Duration::from_millis(10)).await;
+        
+        let metrics = flaky.metrics.read().await.clone();
+        assert!(!metrics.health_status.is_circuit_open());
+        assert_eq!(metrics.circuit_breaker_failures, 0);
+        assert!(metrics.last_circuit_breaker_reset.is_some());
+        assert!(selections(&load_balancer, 10).await.iter().any(|id| id == "flaky"));
+    }
+    
+    #[tokio::test]
+    async fn test_cost_aware_selection_prefers_cheaper_backend() {
+        let config = LoadBalancerConfig {
+            strategy: LoadBalancingStrategy::CostAware,
+            ..LoadBalancerConfig::default()
+        };
+        let load_balancer = AdvancedLoadBalancer::new(config);
+        
+        let mut cheap_metrics = None;
+        for (id, cost) in [("self-hosted", 1.0), ("cloud", 4.0)] {
+            let backend = BackendInstance::new(
+                id.to_string(),
+                Adapter::LightLLM(LightLLMAdapter {
+                    url: "http://localhost:8000".to_string(),
+                    model_id: "test-model".to_string(),
+                }),
+                1,
+                10,
+            )
+            .with_cost(cost);
+            if id == "self-hosted" {
+                cheap_metrics = Some(backend.metrics.clone());
+            }
+            load_balancer.add_backend(backend).await;
+        }
+        
+        let mut cheap_selections = 0;
+        for _ in 0..2000 {
+            if load_balancer.select_backend().await.unwrap().id == "self-hosted" {
+                cheap_selections += 1;
+            }
+        }
+        
+        // Expected share is 1/1 : 1/4, i.e. 80% to the cheaper backend
+        let cheap_share = cheap_selections as f64 / 2000.0;
+        assert!((0.75..=0.85).contains(&cheap_share), "cheap share was {}", cheap_share);
+        
+        // Cost never overrides health
+        cheap_metrics.unwrap().write().await.health_status = BackendHealth::Unhealthy;
+        for _ in 0..100 {
+            assert_eq!(load_balancer.select_backend().await.unwrap().id, "cloud");
+        }
+    }
+    
     #[tokio::test]
     async fn test_request_batching() {
         let batcher = RequestBatcher::new(5, Duration::from_secs(1));
@@ -908,6 +1212,14 @@ mod tests {
             presence_penalty: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
         
         // This will fail because batch processing is not fully implemented
@@ -18,7 +18,7 @@ use crate::{
     adapters::Adapter,
     config::Config,
     error::ProxyError,
-    schemas::{ChatCompletionRequest, Message},
+    schemas::{ChatCompletionRequest, ChatCompletionResponse, Message},
 };
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
@@ -176,6 +176,8 @@ impl PyMessage {
                 tool_calls: None,
                 function_call: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             },
         }
     }
@@ -221,7 +223,8 @@ impl PyNexusNitroLLMClient {
                 .map_err(|e| NexusNitroLLMError::new_err(format!("Failed to create async runtime: {}", e)))?
         );
 
-        let adapter = Adapter::from_config(&config.inner);
+        let adapter = Adapter::try_from_config(&config.inner)
+            .map_err(|e| NexusNitroLLMError::new_err(format!("Invalid configuration: {}", e)))?;
 
         Ok(Self { 
             adapter, 
@@ -315,64 +318,29 @@ impl PyNexusNitroLLMClient {
             seed: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
 
         debug!("Sending chat completion request with {} messages", request.messages.len());
 
         // CRITICAL: Release GIL for heavy async operations to prevent blocking Python
         let result = py.allow_threads(|| {
-            self.runtime.block_on(async {
-                // Call the trait method that returns ChatCompletionResponse
-                use crate::adapters::base::AdapterTrait;
-                match &self.adapter {
-                    Adapter::LightLLM(adapter) => adapter.chat_completions(request).await,
-                    Adapter::VLLM(adapter) => adapter.chat_completions(request).await,
-                    Adapter::OpenAI(adapter) => adapter.chat_completions(request).await,
-                    Adapter::AzureOpenAI(adapter) => adapter.chat_completions(request).await,
-                    Adapter::AWSBedrock(adapter) => adapter.chat_completions(request).await,
-                    Adapter::Custom(adapter) => adapter.chat_completions(request).await,
-                    Adapter::Direct(adapter) => adapter.chat_completions(request).await,
-                }
-            })
+            self.runtime.block_on(self.adapter.chat_completion_response(request))
         });
 
         match result {
             Ok(response) => {
                 debug!("Received successful response from adapter");
-                
-                Python::with_gil(|py| {
-                    // Convert ChatCompletionResponse to Python dict
-                    let choices: Vec<serde_json::Value> = response.choices.into_iter().map(|choice| {
-                        serde_json::json!({
-                            "index": choice.index,
-                            "message": {
-                                "role": choice.message.role,
-                                "content": choice.message.content.unwrap_or_default()
-                            },
-                            "finish_reason": choice.finish_reason
-                        })
-                    }).collect();
-
-                    let response_data = serde_json::json!({
-                        "id": response.id,
-                        "object": response.object,
-                        "created": response.created,
-                        "model": response.model,
-                        "choices": choices,
-                        "usage": response.usage
-                    });
-
-                    let response_str = serde_json::to_string(&response_data)
-                        .map_err(|e| {
-                            self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                            NexusNitroLLMError::new_err(
-                                format!("Failed to serialize response: {}", e)
-                            )
-                        })?;
-
-                    let json_module = py.import("json")?;
-                    let py_dict = json_module.call_method1("loads", (response_str,))?;
-                    Ok(py_dict.to_object(py))
+                completion_to_py(py, &response).map_err(|e| {
+                    self.error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    e
                 })
             }
             Err(e) => {
@@ -384,6 +352,18 @@ impl PyNexusNitroLLMClient {
                     ProxyError::Upstream(msg) => {
                         Err(ConnectionError::new_err(format!("Upstream error: {}", msg)))
                     }
+                    ProxyError::UpstreamStatus { status, body } => {
+                        Err(ConnectionError::new_err(format!("Upstream error: HTTP {}: {}", status, body)))
+                    }
+                    ProxyError::ModelLoading(msg) => {
+                        Err(ConnectionError::new_err(format!("Model is loading: {}", msg)))
+                    }
+                    ProxyError::Transport { kind, message } => {
+                        Err(ConnectionError::new_err(format!("Upstream {} error: {}", kind, message)))
+                    }
+                    error @ ProxyError::IncompleteResponse { .. } => {
+                        Err(ConnectionError::new_err(error.to_string()))
+                    }
                     ProxyError::BadRequest(msg) => {
                         Err(NexusNitroLLMError::new_err(format!("Bad request: {}", msg)))
                     }
@@ -414,6 +394,7 @@ impl PyNexusNitroLLMClient {
                 Adapter::AzureOpenAI(_) => "azure",
                 Adapter::AWSBedrock(_) => "aws",
                 Adapter::Custom(_) => "custom",
+                Adapter::Template(_) => "template",
                 Adapter::Direct(_) => "direct",
             })?;
             
@@ -498,6 +479,8 @@ impl PyNexusNitroLLMClient {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         }];
 
         let request = ChatCompletionRequest {
@@ -518,6 +501,14 @@ impl PyNexusNitroLLMClient {
             seed: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
 
         // CRITICAL: Release GIL for heavy async operations
@@ -531,6 +522,7 @@ impl PyNexusNitroLLMClient {
                     Adapter::AzureOpenAI(adapter) => adapter.chat_completions(request).await.is_ok(),
                     Adapter::AWSBedrock(adapter) => adapter.chat_completions(request).await.is_ok(),
                     Adapter::Custom(adapter) => adapter.chat_completions(request).await.is_ok(),
+                    Adapter::Template(adapter) => adapter.chat_completions(request).await.is_ok(),
                     Adapter::Direct(adapter) => adapter.chat_completions(request).await.is_ok(),
                 }
             })
@@ -555,7 +547,8 @@ impl PyAsyncNexusNitroLLMClient {
     /// Create a new async-compatible LightLLM client
     #[new]
     fn new(config: PyConfig) -> PyResult<Self> {
-        let adapter = Adapter::from_config(&config.inner);
+        let adapter = Adapter::try_from_config(&config.inner)
+            .map_err(|e| NexusNitroLLMError::new_err(format!("Invalid configuration: {}", e)))?;
 
         Ok(Self { 
             adapter, 
@@ -631,6 +624,14 @@ impl PyAsyncNexusNitroLLMClient {
             seed: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
 
         debug!("Sending async chat completion request with {} messages", request.messages.len());
@@ -641,68 +642,14 @@ impl PyAsyncNexusNitroLLMClient {
         let _request_count = self.request_count.clone();
         let error_count = self.error_count.clone();
 
-        // Convert Python messages to Rust messages before moving into async closure
-        let rust_messages: Result<Vec<crate::schemas::Message>, PyErr> = messages
-            .iter()
-            .map(|msg| {
-                Ok(crate::schemas::Message {
-                    role: msg.role().clone(),
-                    content: Some(msg.content().clone()),
-                    name: msg.inner.name.clone(),
-                    tool_calls: None,
-                    function_call: None,
-                    tool_call_id: None,
-                })
-            })
-            .collect();
-
-        let rust_messages = rust_messages?;
-        let messages_len = rust_messages.len();
-
         // Create a Python coroutine that will run the async operation
         pyo3_asyncio::tokio::future_into_py(py, async move {
-            let result = adapter.chat_completions(request).await;
+            let result = adapter.chat_completion_response(request).await;
 
             match result {
-                Ok(_response) => {
+                Ok(response) => {
                     debug!("Received successful async response from adapter");
-                    
-                    // Create a realistic response structure
-                    let current_time = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .map_err(|e| NexusNitroLLMError::new_err(format!("Time error: {}", e)))?
-                        .as_secs() as i64;
-
-                    let response_data = serde_json::json!({
-                        "id": format!("chatcmpl-async-{}-{}", current_time, uuid::Uuid::new_v4().to_string()[..8].to_string()),
-                        "object": "chat.completion",
-                        "created": current_time,
-                        "model": model_name.clone(),
-                        "choices": [{
-                            "index": 0,
-                            "message": {
-                                "role": "assistant",
-                                "content": format!("This is an async response from the {} model via LightLLM Rust bindings. The request contained {} messages.", model_name, messages_len)
-                            },
-                            "finish_reason": "stop"
-                        }],
-                        "usage": {
-                            "prompt_tokens": messages_len * 10,
-                            "completion_tokens": 25,
-                            "total_tokens": messages_len * 10 + 25
-                        }
-                    });
-
-                    let response_str = serde_json::to_string(&response_data)
-                        .map_err(|e| NexusNitroLLMError::new_err(
-                            format!("Failed to serialize async response: {}", e)
-                        ))?;
-
-                    return Python::with_gil(|py| -> PyResult<Py<PyAny>> {
-                        let json_module = py.import("json")?;
-                        let py_dict = json_module.call_method1("loads", (response_str,))?;
-                        Ok(py_dict.to_object(py))
-                    });
+                    Python::with_gil(|py| completion_to_py(py, &response))
                 }
                 Err(e) => {
                     error_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -713,6 +660,18 @@ impl PyAsyncNexusNitroLLMClient {
                         ProxyError::Upstream(msg) => {
                             Err(ConnectionError::new_err(msg))
                         }
+                        ProxyError::UpstreamStatus { status, body } => {
+                            Err(ConnectionError::new_err(format!("HTTP {}: {}", status, body)))
+                        }
+                        ProxyError::ModelLoading(msg) => {
+                            Err(ConnectionError::new_err(msg))
+                        }
+                        ProxyError::Transport { message, .. } => {
+                            Err(ConnectionError::new_err(message))
+                        }
+                        error @ ProxyError::IncompleteResponse { .. } => {
+                            Err(ConnectionError::new_err(error.to_string()))
+                        }
                         ProxyError::BadRequest(msg) => {
                             Err(NexusNitroLLMError::new_err(msg))
                         }
@@ -741,6 +700,7 @@ impl PyAsyncNexusNitroLLMClient {
                 Adapter::AzureOpenAI(_) => "azure",
                 Adapter::AWSBedrock(_) => "aws",
                 Adapter::Custom(_) => "custom",
+                Adapter::Template(_) => "template",
                 Adapter::Direct(_) => "direct",
             })?;
             
@@ -795,6 +755,8 @@ impl PyAsyncNexusNitroLLMClient {
                 tool_calls: None,
                 function_call: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }];
 
             let request = ChatCompletionRequest {
@@ -815,6 +777,14 @@ impl PyAsyncNexusNitroLLMClient {
                 seed: None,
                 tools: None,
                 tool_choice: None,
+                modalities: None,
+                audio: None,
+                suffix: None,
+                system_prompt_ref: None,
+                response_format: None,
+                top_k: None,
+                reasoning_effort: None,
+                conversation_id: None,
             };
 
             let result = adapter.chat_completions(request).await.is_ok();
@@ -869,6 +839,8 @@ impl PyStreamingClient {
                     tool_calls: None,
                     function_call: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 })
             })
             .collect();
@@ -898,6 +870,14 @@ impl PyStreamingClient {
             top_logprobs: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
 
         // For now, simulate streaming by returning a single chunk
@@ -970,6 +950,8 @@ impl PyAsyncStreamingClient {
                     tool_calls: None,
                     function_call: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 })
             })
             .collect();
@@ -999,6 +981,14 @@ impl PyAsyncStreamingClient {
             top_logprobs: None,
             tools: None,
             tool_choice: None,
+            modalities: None,
+            audio: None,
+            suffix: None,
+            system_prompt_ref: None,
+            response_format: None,
+            top_k: None,
+            reasoning_effort: None,
+            conversation_id: None,
         };
 
         // Create async streaming generator using actual backend streaming
@@ -1016,6 +1006,7 @@ impl PyAsyncStreamingClient {
                 Adapter::AzureOpenAI(adapter) => adapter.chat_completions(request_for_async).await,
                 Adapter::AWSBedrock(adapter) => adapter.chat_completions(request_for_async).await,
                 Adapter::Custom(adapter) => adapter.chat_completions(request_for_async).await,
+                Adapter::Template(adapter) => adapter.chat_completions(request_for_async).await,
                 Adapter::Direct(adapter) => adapter.chat_completions(request_for_async).await,
             }.map_err(|e| NexusNitroLLMError::new_err(
                 format!("Streaming request failed: {}", e)
@@ -1114,6 +1105,16 @@ streaming_gen = StreamingGenerator('{}', '{}', '{}', {})
     }
 }
 
+/// Convert a chat completion into the equivalent Python dict, keeping every
+/// field of the upstream response
+fn completion_to_py(py: Python, response: &ChatCompletionResponse) -> PyResult<PyObject> {
+    let response_str = serde_json::to_string(response)
+        .map_err(|e| NexusNitroLLMError::new_err(format!("Failed to serialize response: {}", e)))?;
+    let json_module = py.import("json")?;
+    let py_dict = json_module.call_method1("loads", (response_str,))?;
+    Ok(py_dict.to_object(py))
+}
+
 /// Python module definition
 #[pymodule]
 fn nexus_nitro_llm(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -13,21 +13,20 @@
 //! - Rate limit bypass for privileged users
 
 use crate::{
+    config::Config,
     schemas::ChatCompletionRequest,
 };
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use std::{
-    sync::{
-        atomic::{AtomicI64, Ordering},
-        Arc,
-    },
+    sync::Arc,
     time::{Duration, Instant},
 };
-use tokio::{
-};
 use tracing::debug;
 
+/// Keys tracked before buckets that have refilled completely are forgotten
+const MAX_TRACKED_KEYS: usize = 10_000;
+
 /// # Rate Limiting Configuration
 ///
 /// Configuration for rate limiting behavior.
@@ -39,6 +38,8 @@ pub struct RateLimitConfig {
     pub tokens_per_second: u32,
     /// Maximum tokens per minute
     pub tokens_per_minute: u32,
+    /// Maximum requests per minute for each user or API key
+    pub requests_per_minute: u32,
     /// Burst capacity (extra requests allowed in short bursts)
     pub burst_capacity: u32,
     /// Whether to enable distributed rate limiting
@@ -51,12 +52,24 @@ impl Default for RateLimitConfig {
             requests_per_second: 10,
             tokens_per_second: 1000,
             tokens_per_minute: 60000,
+            requests_per_minute: 600,
             burst_capacity: 20,
             distributed: false,
         }
     }
 }
 
+impl RateLimitConfig {
+    /// Per-key limits from `rate_limit_requests_per_minute` and `rate_limit_burst_size`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            requests_per_minute: config.rate_limit_requests_per_minute,
+            burst_capacity: config.rate_limit_burst_size,
+            ..Self::default()
+        }
+    }
+}
+
 /// # Token Priority
 ///
 /// Priority levels for token consumption.
@@ -77,70 +90,80 @@ pub enum TokenPriority {
 /// Implements the token bucket algorithm for rate limiting.
 #[derive(Debug)]
 pub struct TokenBucket {
-    /// Current number of tokens in the bucket
-    tokens: AtomicI64,
     /// Maximum capacity of the bucket
-    capacity: i64,
+    capacity: f64,
     /// Rate at which tokens are added (tokens per second)
     refill_rate: f64,
+    /// Tokens in the bucket as of the last refill
+    state: std::sync::Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// Current number of tokens in the bucket
+    tokens: f64,
     /// Last time the bucket was refilled
     last_refill: Instant,
-    /// Lock for thread-safe operations
-    _lock: std::sync::Mutex<()>,
 }
 
 impl TokenBucket {
     /// Create a new token bucket
     pub fn new(capacity: u32, refill_rate: f64) -> Self {
         Self {
-            tokens: AtomicI64::new(capacity as i64),
-            capacity: capacity as i64,
+            capacity: f64::from(capacity),
             refill_rate,
-            last_refill: Instant::now(),
-            _lock: std::sync::Mutex::new(()),
+            state: std::sync::Mutex::new(BucketState {
+                tokens: f64::from(capacity),
+                last_refill: Instant::now(),
+            }),
         }
     }
 
     /// Try to consume tokens from the bucket
     pub fn try_consume(&self, tokens: u32, priority: TokenPriority) -> bool {
-        let _lock = self._lock.lock().unwrap();
-        
-        // Refill tokens based on elapsed time
-        self.refill_tokens();
-        
-        let required_tokens = tokens as i64;
-        let current_tokens = self.tokens.load(Ordering::Relaxed);
-        
-        // Check if we have enough tokens
-        if current_tokens >= required_tokens {
-            self.tokens.fetch_sub(required_tokens, Ordering::Relaxed);
-            true
-        } else {
+        self.acquire(tokens, priority).is_ok()
+    }
+
+    /// Consume tokens, returning the whole tokens left afterwards, or how long
+    /// to wait until enough have refilled
+    pub fn acquire(&self, tokens: u32, priority: TokenPriority) -> Result<u32, Duration> {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+
+        let required = f64::from(tokens);
+        if state.tokens >= required {
+            state.tokens -= required;
+            Ok(state.tokens as u32)
+        } else if priority == TokenPriority::Critical {
             // Allow critical priority to bypass rate limits
-            if priority == TokenPriority::Critical {
-                true
-            } else {
-                false
-            }
+            Ok(state.tokens as u32)
+        } else if self.refill_rate > 0.0 {
+            Err(Duration::from_secs_f64((required - state.tokens) / self.refill_rate))
+        } else {
+            Err(Duration::MAX)
         }
     }
 
-    /// Refill tokens based on elapsed time
-    fn refill_tokens(&self) {
+    /// Add the tokens earned since the last refill
+    fn refill(&self, state: &mut BucketState) {
         let now = Instant::now();
-        let elapsed = now.duration_since(self.last_refill);
-        let tokens_to_add = (elapsed.as_secs_f64() * self.refill_rate) as i64;
-
-        if tokens_to_add > 0 {
-            let current_tokens = self.tokens.load(Ordering::Relaxed);
-            let new_tokens = (current_tokens + tokens_to_add).min(self.capacity);
-            self.tokens.store(new_tokens, Ordering::Relaxed);
-        }
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.refill_rate).min(self.capacity);
+        state.last_refill = now;
     }
 
     /// Get current token count
     pub fn get_tokens(&self) -> i64 {
-        self.tokens.load(Ordering::Relaxed)
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        state.tokens as i64
+    }
+
+    /// Whether the bucket has refilled to capacity
+    fn is_full(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        state.tokens >= self.capacity
     }
 }
 
@@ -194,28 +217,52 @@ impl AdvancedRateLimiter {
         }
 
         // Check per-user rate limits
-        if let Some(user_limiter) = self.user_limiters.get(user_id) {
-            if !user_limiter.try_consume(1, priority) {
-                debug!("Per-user rate limit exceeded for user: {}", user_id);
-                return false;
-            }
-        } else {
-            // Create new user limiter
-            let user_limiter = Arc::new(TokenBucket::new(
-                self.config.burst_capacity,
-                self.config.requests_per_second as f64,
-            ));
-            self.user_limiters.insert(user_id.to_string(), user_limiter.clone());
-            
-            if !user_limiter.try_consume(1, priority) {
-                debug!("Per-user rate limit exceeded for new user: {}", user_id);
-                return false;
-            }
+        if !self.key_bucket(user_id).try_consume(1, priority) {
+            debug!("Per-user rate limit exceeded for user: {}", user_id);
+            return false;
         }
 
         true
     }
 
+    /// Check one request against `key`'s own bucket of `requests_per_minute`
+    /// with `burst_capacity`, independently of the global limits
+    pub fn check_key(&self, key: &str) -> RateLimitResult {
+        match self.key_bucket(key).acquire(1, TokenPriority::Normal) {
+            Ok(remaining) => RateLimitResult {
+                allowed: true,
+                remaining_requests: i64::from(remaining),
+                remaining_tokens: self.token_bucket.get_tokens(),
+                retry_after: None,
+            },
+            Err(wait) => {
+                debug!("Per-key rate limit exceeded for: {}", key);
+                RateLimitResult::rate_limited(wait.as_secs_f64().ceil().max(1.0) as u64)
+            }
+        }
+    }
+
+    /// Bucket of `key`, created full on first use
+    fn key_bucket(&self, key: &str) -> Arc<TokenBucket> {
+        if let Some(bucket) = self.user_limiters.get(key) {
+            return bucket.clone();
+        }
+
+        // A full bucket behaves like a new one, so forgetting it loses nothing
+        if self.user_limiters.len() >= MAX_TRACKED_KEYS {
+            self.user_limiters.retain(|_, bucket| !bucket.is_full());
+        }
+        self.user_limiters
+            .entry(key.to_string())
+            .or_insert_with(|| {
+                Arc::new(TokenBucket::new(
+                    self.config.burst_capacity,
+                    f64::from(self.config.requests_per_minute) / 60.0,
+                ))
+            })
+            .clone()
+    }
+
     /// Estimate token count for a request
     fn estimate_tokens(&self, request: &ChatCompletionRequest) -> u32 {
         // Rough estimation: 4 characters per token
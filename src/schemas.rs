@@ -11,7 +11,7 @@
 //! - **HashMap<K, V>**: Similar to `std::unordered_map<K, V>` in C++
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// # Chat Completion Request
 /// 
@@ -25,37 +25,336 @@ pub struct ChatCompletionRequest {
     /// Maximum number of tokens to generate
     pub max_tokens: Option<u32>,
     /// Sampling temperature (0.0 to 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     /// Nucleus sampling parameter (0.0 to 1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
     /// Whether to stream the response (Server-Sent Events)
     pub stream: Option<bool>,
     /// Stop sequences to end generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
     /// Presence penalty (-2.0 to 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
     /// Frequency penalty (-2.0 to 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub frequency_penalty: Option<f32>,
     /// Logit bias map for token adjustment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub logit_bias: Option<HashMap<String, f32>>,
     /// User identifier for tracking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
     /// Number of completions to generate
     pub n: Option<u32>,
     /// Random seed for reproducible generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub seed: Option<u64>,
     /// Whether to return log probabilities
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub logprobs: Option<bool>,
     /// Number of top log probabilities to return
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub top_logprobs: Option<u32>,
     /// List of tools available to the model
     pub tools: Option<Vec<Tool>>,
     /// Tool choice configuration
     pub tool_choice: Option<ToolChoice>,
+    /// Output modalities to generate, e.g. `["text", "audio"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<String>>,
+    /// Audio output parameters, required when the `audio` modality is requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioOutputParams>,
+    /// Text following the completion, for fill-in-the-middle (FIM) code completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// ID of a system prompt registered with the proxy, expanded into a
+    /// leading system message before dispatch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt_ref: Option<String>,
+    /// Output format constraint, e.g. JSON mode or a JSON schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Top-k sampling: only the k most likely tokens are considered.
+    /// Not supported by OpenAI, so OpenAI-family adapters drop it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Reasoning effort for reasoning models (`low`, `medium` or `high`);
+    /// dropped for models that do not reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+    /// ID of a conversation stored by the proxy; its history is prepended to
+    /// `messages` and the new turn is appended to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+}
+
+/// # Response Format
+///
+/// Output format constraint (OpenAI `response_format` request field).
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free-form text, the default
+    Text,
+    /// JSON mode: any valid JSON object
+    JsonObject,
+    /// Structured output: JSON matching a schema
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+/// Named schema of a `json_schema` response format
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct JsonSchemaFormat {
+    /// Name of the response format
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON Schema the response must match
+    #[serde(default)]
+    pub schema: serde_json::Value,
+    /// Whether the backend should enforce the schema strictly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+/// # Audio Output Parameters
+///
+/// Voice and encoding for audio output (OpenAI `audio` request field).
+#[derive(Debug, Clone, Hash, PartialEq, Deserialize, Serialize)]
+pub struct AudioOutputParams {
+    /// Voice the model uses to respond, e.g. `alloy`
+    pub voice: String,
+    /// Output audio format, e.g. `wav`, `mp3`, `pcm16`
+    pub format: String,
+}
+
+/// Message roles accepted in chat completion requests
+pub const VALID_MESSAGE_ROLES: [&str; 6] = ["system", "developer", "user", "assistant", "tool", "function"];
+
+/// Values accepted for `reasoning_effort`
+pub const VALID_REASONING_EFFORTS: [&str; 3] = ["low", "medium", "high"];
+
+/// Optional sampling parameters that can be removed from a request for
+/// backends that reject them
+pub const STRIPPABLE_PARAMETERS: [&str; 12] = [
+    "frequency_penalty",
+    "presence_penalty",
+    "logit_bias",
+    "logprobs",
+    "top_logprobs",
+    "seed",
+    "stop",
+    "temperature",
+    "top_p",
+    "top_k",
+    "user",
+    "reasoning_effort",
+];
+
+/// # Validation Issue
+///
+/// A single problem found while validating a request, identified by a
+/// JSON-path-like field name such as `messages[0].role`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// Offending field
+    pub field: String,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl ChatCompletionRequest {
+    /// Whether the request asks the model for audio output
+    pub fn requests_audio(&self) -> bool {
+        self.audio.is_some()
+            || self
+                .modalities
+                .iter()
+                .flatten()
+                .any(|modality| modality == "audio")
+    }
+
+    /// Whether the request asks the model for JSON output
+    pub fn requests_json(&self) -> bool {
+        matches!(
+            self.response_format,
+            Some(ResponseFormat::JsonObject | ResponseFormat::JsonSchema { .. })
+        )
+    }
+
+    /// Schema the response must match, for `json_schema` requests
+    pub fn json_schema(&self) -> Option<&serde_json::Value> {
+        match &self.response_format {
+            Some(ResponseFormat::JsonSchema { json_schema }) => Some(&json_schema.schema),
+            _ => None,
+        }
+    }
+
+    /// Remove an optional parameter named in `STRIPPABLE_PARAMETERS`,
+    /// returning whether the request had set it
+    pub fn clear_parameter(&mut self, name: &str) -> bool {
+        match name {
+            "frequency_penalty" => self.frequency_penalty.take().is_some(),
+            "presence_penalty" => self.presence_penalty.take().is_some(),
+            "logit_bias" => self.logit_bias.take().is_some(),
+            "logprobs" => self.logprobs.take().is_some(),
+            "top_logprobs" => self.top_logprobs.take().is_some(),
+            "seed" => self.seed.take().is_some(),
+            "stop" => self.stop.take().is_some(),
+            "temperature" => self.temperature.take().is_some(),
+            "top_p" => self.top_p.take().is_some(),
+            "top_k" => self.top_k.take().is_some(),
+            "user" => self.user.take().is_some(),
+            "reasoning_effort" => self.reasoning_effort.take().is_some(),
+            _ => false,
+        }
+    }
+
+    /// # Validate request parameters
+    ///
+    /// Checks parameter ranges, message roles and tool definitions, collecting
+    /// every problem instead of stopping at the first one.
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let mut issues = Vec::new();
+
+        if self.messages.is_empty() {
+            issues.push(ValidationIssue::new("messages", "at least one message is required"));
+        }
+        for (index, message) in self.messages.iter().enumerate() {
+            let field = format!("messages[{}].role", index);
+            if message.role.is_empty() {
+                issues.push(ValidationIssue::new(field, "role is required"));
+            } else if !VALID_MESSAGE_ROLES.contains(&message.role.as_str()) {
+                issues.push(ValidationIssue::new(
+                    field,
+                    format!("unknown role '{}', expected one of: {}", message.role, VALID_MESSAGE_ROLES.join(", ")),
+                ));
+            }
+        }
+
+        let ranges = [
+            ("temperature", self.temperature, 0.0, 2.0),
+            ("top_p", self.top_p, 0.0, 1.0),
+            ("presence_penalty", self.presence_penalty, -2.0, 2.0),
+            ("frequency_penalty", self.frequency_penalty, -2.0, 2.0),
+        ];
+        for (field, value, min, max) in ranges {
+            if let Some(value) = value {
+                if !(min..=max).contains(&value) {
+                    issues.push(ValidationIssue::new(
+                        field,
+                        format!("{} is out of range, expected {} to {}", value, min, max),
+                    ));
+                }
+            }
+        }
+        if self.max_tokens == Some(0) {
+            issues.push(ValidationIssue::new("max_tokens", "must be greater than 0"));
+        }
+        if self.n == Some(0) {
+            issues.push(ValidationIssue::new("n", "must be greater than 0"));
+        }
+        if self.top_logprobs.is_some_and(|top_logprobs| top_logprobs > 20) {
+            issues.push(ValidationIssue::new("top_logprobs", "must be between 0 and 20"));
+        }
+        if let Some(effort) = &self.reasoning_effort {
+            if !VALID_REASONING_EFFORTS.contains(&effort.as_str()) {
+                issues.push(ValidationIssue::new(
+                    "reasoning_effort",
+                    format!("unknown reasoning effort '{}', expected one of: {}", effort, VALID_REASONING_EFFORTS.join(", ")),
+                ));
+            }
+        }
+
+        for (index, tool) in self.tools.iter().flatten().enumerate() {
+            if tool.tool_type != "function" {
+                issues.push(ValidationIssue::new(
+                    format!("tools[{}].type", index),
+                    format!("unsupported tool type '{}', expected 'function'", tool.tool_type),
+                ));
+            }
+            if tool.function.name.is_empty() {
+                issues.push(ValidationIssue::new(format!("tools[{}].function.name", index), "name is required"));
+            }
+            if let Some(parameters) = &tool.function.parameters {
+                let is_object_schema = parameters.as_object().is_some_and(|schema| {
+                    schema.get("type").is_none_or(|schema_type| schema_type == "object")
+                });
+                if !is_object_schema {
+                    issues.push(ValidationIssue::new(
+                        format!("tools[{}].function.parameters", index),
+                        "parameters must be a JSON Schema object with type 'object'",
+                    ));
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// # Validate tool message references
+    ///
+    /// Checks that every `tool` message carries a `tool_call_id` naming a tool
+    /// call made by an earlier assistant message. An orphaned tool result is
+    /// usually a client bug that backends reject with an opaque error.
+    pub fn validate_tool_call_ids(&self) -> Result<(), Vec<ValidationIssue>> {
+        let mut issued = HashSet::new();
+        let mut issues = Vec::new();
+
+        for (index, message) in self.messages.iter().enumerate() {
+            match message.role.as_str() {
+                "assistant" => issued.extend(message.tool_calls.iter().flatten().map(|call| call.id.as_str())),
+                "tool" => {
+                    let field = format!("messages[{}].tool_call_id", index);
+                    match message.tool_call_id.as_deref().filter(|id| !id.is_empty()) {
+                        None => issues.push(ValidationIssue::new(field, "tool messages require a tool_call_id")),
+                        Some(id) if !issued.contains(id) => issues.push(ValidationIssue::new(
+                            field,
+                            format!("'{}' does not match a tool call from a preceding assistant message", id),
+                        )),
+                        Some(_) => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Hash, Deserialize, Serialize)]
 pub struct Message {
+    /// Message role; a missing role is reported by `ChatCompletionRequest::validate`
+    #[serde(default)]
     pub role: String,
     pub content: Option<String>,
     pub name: Option<String>,
@@ -65,6 +364,32 @@ pub struct Message {
     pub function_call: Option<FunctionCall>,
     /// Tool call ID (for tool role messages)
     pub tool_call_id: Option<String>,
+    /// Audio output generated by the model, or a reference to a previous
+    /// audio response (only `id`) when sent back in the conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<MessageAudio>,
+    /// Reasoning ("thinking") text emitted separately from the answer by
+    /// reasoning models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
+}
+
+/// # Message Audio
+///
+/// Audio response data attached to an assistant message.
+#[derive(Debug, Clone, Hash, PartialEq, Deserialize, Serialize)]
+pub struct MessageAudio {
+    /// Identifier used to refer to this audio in follow-up turns
+    pub id: String,
+    /// Base64-encoded audio bytes in the requested format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// Unix timestamp after which the audio is no longer available upstream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// Transcript of the generated audio
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +400,9 @@ pub struct ChatCompletionResponse {
     pub model: String,
     pub choices: Vec<Choice>,
     pub usage: Option<Usage>,
+    /// Azure content-filter annotations for the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_filter_results: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +411,9 @@ pub struct Choice {
     pub message: Message,
     pub finish_reason: String,
     pub logprobs: Option<serde_json::Value>,
+    /// Azure content-filter annotations for the completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_filter_results: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -476,6 +807,8 @@ impl Message {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         }
     }
     
@@ -496,6 +829,8 @@ impl Message {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         }
     }
     
@@ -516,6 +851,8 @@ impl Message {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         }
     }
     
@@ -537,6 +874,8 @@ impl Message {
             tool_calls: None,
             function_call: None,
             tool_call_id: Some(tool_call_id),
+            audio: None,
+            reasoning_content: None,
         }
     }
     
@@ -567,4 +906,132 @@ impl Message {
         self.function_call = Some(function_call);
         self
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_response_round_trips() {
+        let body = serde_json::json!({
+            "id": "chatcmpl-audio",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o-audio-preview",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "name": null,
+                    "tool_calls": null,
+                    "function_call": null,
+                    "tool_call_id": null,
+                    "audio": {
+                        "id": "audio_abc123",
+                        "data": "UklGRiQAAABXQVZFZm10IBAAAAABAAEA",
+                        "expires_at": 1700003600,
+                        "transcript": "Hello there!"
+                    }
+                },
+                "finish_reason": "stop",
+                "logprobs": null
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 20, "total_tokens": 30}
+        });
+
+        let response: ChatCompletionResponse = serde_json::from_value(body.clone()).unwrap();
+        let audio = response.choices[0].message.audio.as_ref().unwrap();
+        assert_eq!(audio.transcript.as_deref(), Some("Hello there!"));
+        assert_eq!(serde_json::to_value(&response).unwrap(), body);
+    }
+
+    #[test]
+    fn test_tool_call_ids_must_match_preceding_assistant_tool_call() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [
+                {"role": "user", "content": "Weather in Paris?"},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\": \"Paris\"}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18C"}
+            ]
+        }))
+        .unwrap();
+        assert!(request.validate_tool_call_ids().is_ok());
+
+        let mut orphaned = request.clone();
+        orphaned.messages[2].tool_call_id = Some("call_9".to_string());
+        orphaned.messages.push(Message {
+            role: "tool".to_string(),
+            content: Some("18C".to_string()),
+            name: None,
+            tool_calls: None,
+            function_call: None,
+            tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
+        });
+        let issues = orphaned.validate_tool_call_ids().unwrap_err();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].field, "messages[2].tool_call_id");
+        assert!(issues[0].message.contains("'call_9'"));
+        assert_eq!(issues[1].to_string(), "messages[3].tool_call_id: tool messages require a tool_call_id");
+    }
+
+    #[test]
+    fn test_modalities_request_serialization() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}],
+            "modalities": ["text", "audio"],
+            "audio": {"voice": "alloy", "format": "wav"}
+        }))
+        .unwrap();
+        assert!(request.requests_audio());
+
+        let forwarded = serde_json::to_value(&request).unwrap();
+        assert_eq!(forwarded["modalities"], serde_json::json!(["text", "audio"]));
+        assert_eq!(forwarded["audio"]["voice"], "alloy");
+
+        let plain = ChatCompletionRequest::default();
+        assert!(!plain.requests_audio());
+        assert!(serde_json::to_value(&plain).unwrap().get("modalities").is_none());
+    }
+
+    #[test]
+    fn test_response_format_round_trips() {
+        for format in [
+            serde_json::json!({"type": "text"}),
+            serde_json::json!({"type": "json_object"}),
+            serde_json::json!({"type": "json_schema", "json_schema": {
+                "name": "weather",
+                "schema": {"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]},
+                "strict": true
+            }}),
+        ] {
+            let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+                "messages": [{"role": "user", "content": "Hi"}],
+                "response_format": format
+            }))
+            .unwrap();
+            assert_eq!(serde_json::to_value(&request).unwrap()["response_format"], format);
+        }
+
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}],
+            "response_format": {"type": "json_schema", "json_schema": {"name": "weather", "schema": {"type": "object"}}}
+        }))
+        .unwrap();
+        assert!(request.requests_json());
+        assert_eq!(request.json_schema(), Some(&serde_json::json!({"type": "object"})));
+
+        let text = ChatCompletionRequest {
+            response_format: Some(ResponseFormat::Text),
+            ..Default::default()
+        };
+        assert!(!text.requests_json());
+        assert!(text.json_schema().is_none());
+    }
+}
@@ -0,0 +1,92 @@
+//! # Cache Warming
+//!
+//! Pre-populates the response cache at startup from a JSONL file of chat
+//! completion requests (one `ChatCompletionRequest` per line), so common
+//! FAQ-style prompts are answered from cache from the first request on.
+
+use crate::{
+    adapters::Adapter,
+    caching::CacheManager,
+    error::ProxyError,
+    schemas::{ChatCompletionRequest, ChatCompletionResponse},
+};
+
+/// Parse the requests in a cache warm file.
+///
+/// Blank lines and lines starting with `#` are skipped. Streaming is disabled
+/// for every request since only buffered responses are cached.
+pub fn parse_warm_requests(contents: &str) -> Result<Vec<ChatCompletionRequest>, ProxyError> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            let mut request: ChatCompletionRequest = serde_json::from_str(line).map_err(|e| {
+                ProxyError::BadRequest(format!("Invalid cache warm request on line {}: {}", index + 1, e))
+            })?;
+            request.stream = Some(false);
+            Ok(request)
+        })
+        .collect()
+}
+
+/// Run every request in `path` through the backend and cache the responses.
+///
+/// Individual request failures are logged and skipped; the number of cached
+/// responses is returned.
+pub async fn warm_cache(cache: &CacheManager, adapter: &Adapter, path: &str) -> Result<usize, ProxyError> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read cache warm file '{}': {}", path, e)))?;
+    let requests = parse_warm_requests(&contents)?;
+
+    let mut warmed = 0;
+    for request in requests {
+        match fetch_completion(adapter, request.clone()).await {
+            Ok(response) => {
+                cache.put(&request, response).await?;
+                warmed += 1;
+            }
+            Err(error) => tracing::warn!("Cache warm request failed: {}", error),
+        }
+    }
+
+    Ok(warmed)
+}
+
+/// Send a request to the backend and decode the completion
+async fn fetch_completion(adapter: &Adapter, request: ChatCompletionRequest) -> Result<ChatCompletionResponse, ProxyError> {
+    let response = adapter.chat_completions(request).await?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+    serde_json::from_slice(&body)
+        .map_err(|e| ProxyError::Internal(format!("Failed to parse response JSON: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_warm_requests_skips_comments_and_disables_streaming() {
+        let contents = concat!(
+            "# FAQ prompts\n",
+            "{\"messages\": [{\"role\": \"user\", \"content\": \"What are your hours?\"}], \"stream\": true}\n",
+            "\n",
+            "{\"messages\": [{\"role\": \"user\", \"content\": \"Where are you?\"}]}\n",
+        );
+
+        let requests = parse_warm_requests(contents).unwrap();
+
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|request| request.stream == Some(false)));
+    }
+
+    #[test]
+    fn test_parse_warm_requests_reports_line_number() {
+        let error = parse_warm_requests("{\"messages\": []}\nnot json\n").unwrap_err();
+
+        assert!(error.to_string().contains("line 2"));
+    }
+}
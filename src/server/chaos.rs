@@ -0,0 +1,159 @@
+//! # Chaos Testing Middleware
+//!
+//! Injects artificial latency and random errors so client retry and backoff
+//! logic can be exercised against a real gateway. Chaos is only active when
+//! `chaos_enabled` is set and the environment is not `production`.
+
+use super::{is_probe_route, AppState};
+use crate::config::Config;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use std::time::Duration;
+
+/// Effective chaos settings for a running server
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// Delay added before each request is handled
+    pub delay: Duration,
+    /// Probability (0.0 to 1.0) of returning `error_status`
+    pub error_rate: f64,
+    /// Status returned for injected errors
+    pub error_status: StatusCode,
+}
+
+impl ChaosConfig {
+    /// Build chaos settings from configuration, or `None` when chaos is inactive
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if !config.chaos_enabled || config.environment == "production" {
+            return None;
+        }
+
+        Some(Self {
+            delay: Duration::from_millis(config.chaos_delay_ms),
+            error_rate: config.chaos_error_rate.clamp(0.0, 1.0),
+            error_status: StatusCode::from_u16(config.chaos_error_status)
+                .unwrap_or(StatusCode::SERVICE_UNAVAILABLE),
+        })
+    }
+
+    /// Decide whether the current request should fail
+    fn should_fail(&self) -> bool {
+        self.error_rate > 0.0 && fastrand::f64() < self.error_rate
+    }
+}
+
+/// Middleware applying the configured delay and random errors.
+///
+/// Health checks are exempt so orchestrators keep seeing the real status.
+pub async fn chaos_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let chaos = match ChaosConfig::from_config(state.config()) {
+        Some(chaos) if !is_probe_route(request.uri().path()) => chaos,
+        _ => return next.run(request).await,
+    };
+
+    if !chaos.delay.is_zero() {
+        tokio::time::sleep(chaos.delay).await;
+    }
+
+    if chaos.should_fail() {
+        tracing::debug!(status = chaos.error_status.as_u16(), "Injecting chaos error");
+        let body = Json(json!({
+            "error": {
+                "message": "Injected chaos error",
+                "type": "chaos_error",
+                "code": chaos.error_status.as_u16()
+            }
+        }));
+        return (chaos.error_status, body).into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::create_router;
+    use axum::body::Body;
+    use std::time::Instant;
+    use tower::ServiceExt;
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    fn chaos_config(backend_url: String) -> Config {
+        let mut config = Config::for_test();
+        config.backend_url = backend_url;
+        config.chaos_enabled = true;
+        config.chaos_error_status = 429;
+        config
+    }
+
+    async fn send_chat(config: Config) -> Response {
+        let app = create_router(AppState::new(config).await);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}).to_string(),
+            ))
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_rate_one_fails_every_request() {
+        let mut config = chaos_config("http://127.0.0.1:9/v1".to_string());
+        config.chaos_error_rate = 1.0;
+
+        for _ in 0..5 {
+            let response = send_chat(config.clone()).await;
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delay_is_applied() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hello!"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        let mut config = chaos_config(format!("{}/v1", server.uri()));
+        config.chaos_delay_ms = 150;
+
+        let started = Instant::now();
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_chaos_disabled_in_production() {
+        let mut config = chaos_config("http://localhost:8000".to_string());
+        config.chaos_error_rate = 1.0;
+        assert!(ChaosConfig::from_config(&config).is_some());
+
+        config.environment = "production".to_string();
+        assert!(ChaosConfig::from_config(&config).is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! # Connection Limiting
+//!
+//! Caps the number of client connections the accept loop serves at once so a
+//! connection flood cannot spawn an unbounded number of tasks. Connections
+//! beyond `max_concurrent_connections` either wait for a free slot or are
+//! dropped immediately, depending on `connection_limit_behavior`.
+
+use crate::config::Config;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// What happens to a connection accepted while the limit is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowBehavior {
+    /// Hold the connection until another one finishes
+    Wait,
+    /// Close the connection without serving it
+    Drop,
+}
+
+/// Semaphore-backed limit on concurrently served connections
+#[derive(Debug, Clone)]
+pub struct ConnectionLimiter {
+    /// `None` when connections are unlimited
+    semaphore: Option<Arc<Semaphore>>,
+    behavior: OverflowBehavior,
+}
+
+/// Slot held for the lifetime of a served connection
+#[derive(Debug)]
+pub struct ConnectionPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ConnectionLimiter {
+    /// Create a limiter allowing `max_connections` concurrent connections (0 = unlimited)
+    pub fn new(max_connections: usize, behavior: OverflowBehavior) -> Self {
+        Self {
+            semaphore: (max_connections > 0).then(|| Arc::new(Semaphore::new(max_connections))),
+            behavior,
+        }
+    }
+
+    /// Build the limiter from `max_concurrent_connections` and `connection_limit_behavior`
+    pub fn from_config(config: &Config) -> Self {
+        let behavior = match config.connection_limit_behavior.as_str() {
+            "drop" => OverflowBehavior::Drop,
+            _ => OverflowBehavior::Wait,
+        };
+        Self::new(config.max_concurrent_connections, behavior)
+    }
+
+    /// Reserve a slot for a newly accepted connection.
+    ///
+    /// Returns `None` when the connection should be dropped instead of served.
+    pub async fn acquire(&self) -> Option<ConnectionPermit> {
+        let Some(semaphore) = &self.semaphore else {
+            return Some(ConnectionPermit { _permit: None });
+        };
+
+        let permit = match self.behavior {
+            OverflowBehavior::Wait => semaphore.clone().acquire_owned().await.ok()?,
+            OverflowBehavior::Drop => semaphore.clone().try_acquire_owned().ok()?,
+        };
+        Some(ConnectionPermit { _permit: Some(permit) })
+    }
+
+    /// Number of free connection slots, or `None` when unlimited
+    pub fn available(&self) -> Option<usize> {
+        self.semaphore.as_ref().map(|semaphore| semaphore.available_permits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_wait_behavior_caps_concurrent_connections() {
+        let limiter = ConnectionLimiter::new(2, OverflowBehavior::Wait);
+
+        let first = limiter.acquire().await.unwrap();
+        let _second = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.available(), Some(0));
+
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(blocked.is_err(), "third connection must wait for a free slot");
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(third.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_drop_behavior_rejects_connections_over_limit() {
+        let limiter = ConnectionLimiter::new(1, OverflowBehavior::Drop);
+
+        let first = limiter.acquire().await;
+        assert!(first.is_some());
+        assert!(limiter.acquire().await.is_none());
+
+        drop(first);
+        assert!(limiter.acquire().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_zero_limit_is_unlimited() {
+        let limiter = ConnectionLimiter::new(0, OverflowBehavior::Drop);
+        let permits: Vec<_> = futures_util::future::join_all((0..100).map(|_| limiter.acquire())).await;
+
+        assert!(permits.iter().all(Option::is_some));
+        assert_eq!(limiter.available(), None);
+    }
+}
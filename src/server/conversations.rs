@@ -0,0 +1,506 @@
+//! # Conversation Storage
+//!
+//! Lets stateless clients continue a conversation without resending its
+//! history. A request carrying a `conversation_id` has the stored history
+//! prepended to its messages; once the response has been sent, the new turn
+//! and the assistant reply are appended to the stored conversation.
+//!
+//! Conversations live in a pluggable [`ConversationStore`], either in memory
+//! or in Redis (with the `redis` feature), and expire `conversation_ttl_secs`
+//! after their last turn. They are stored under the caller's API key and
+//! credential as well as the conversation ID, so one tenant cannot resume
+//! another's conversation. Concurrent turns on the same conversation are not
+//! merged: the turn that finishes last replaces the stored history. With `max_conversation_tokens` set, a
+//! [`ConversationBudget`] keeps a conversation within its token budget by
+//! dropping its oldest turns or refusing the new one.
+
+use crate::{
+    config::Config,
+    core::tokens::{self, TokenCounter},
+    error::ProxyError,
+    schemas::Message,
+};
+use axum::{body::Body, response::Response};
+use futures_util::{stream, StreamExt};
+use serde_json::Value;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Storage for conversation histories keyed by [`store_key`]
+#[async_trait::async_trait]
+pub trait ConversationStore: Send + Sync {
+    /// Messages of a conversation, empty when it is unknown or expired
+    async fn load(&self, key: &str) -> Result<Vec<Message>, ProxyError>;
+
+    /// Replace a conversation's messages and restart its expiry
+    async fn save(&self, key: &str, messages: &[Message]) -> Result<(), ProxyError>;
+}
+
+/// Store key of the conversation `conversation_id` belonging to `owner`.
+///
+/// The owner is hashed so API keys never appear in the store.
+pub fn store_key(owner: &impl Hash, conversation_id: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    owner.hash(&mut hasher);
+    format!("{:x}:{}", hasher.finish(), conversation_id)
+}
+
+/// Build the store selected by `conversation_store`, if any
+pub fn from_config(config: &Config) -> Option<Arc<dyn ConversationStore>> {
+    let ttl = Duration::from_secs(config.conversation_ttl_secs);
+    match config.conversation_store.as_str() {
+        "memory" => Some(Arc::new(InMemoryConversationStore::new(ttl))),
+        #[cfg(feature = "redis")]
+        "redis" => Some(Arc::new(RedisConversationStore::new(&config.conversation_redis_url, ttl))),
+        _ => None,
+    }
+}
+
+/// Conversations held in process memory
+#[derive(Debug)]
+pub struct InMemoryConversationStore {
+    ttl: Duration,
+    conversations: Mutex<Conversations>,
+}
+
+#[derive(Debug, Default)]
+struct Conversations {
+    /// Time of each conversation's last turn and its messages
+    stored: HashMap<String, (Instant, Vec<Message>)>,
+    /// Conversations in the order they were saved, so expired ones are dropped from the front
+    queue: VecDeque<(Instant, String)>,
+}
+
+impl InMemoryConversationStore {
+    /// Create a store whose conversations expire `ttl` after their last turn
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            conversations: Mutex::new(Conversations::default()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ConversationStore for InMemoryConversationStore {
+    async fn load(&self, key: &str) -> Result<Vec<Message>, ProxyError> {
+        let conversations = self.conversations.lock().unwrap();
+        Ok(conversations
+            .stored
+            .get(key)
+            .filter(|(updated, _)| updated.elapsed() < self.ttl)
+            .map(|(_, messages)| messages.clone())
+            .unwrap_or_default())
+    }
+
+    async fn save(&self, key: &str, messages: &[Message]) -> Result<(), ProxyError> {
+        let now = Instant::now();
+        let mut conversations = self.conversations.lock().unwrap();
+        let Conversations { stored, queue } = &mut *conversations;
+        while queue.front().is_some_and(|(updated, _)| now.duration_since(*updated) >= self.ttl) {
+            let (updated, old) = queue.pop_front().unwrap();
+            // Skip conversations saved again since this queue entry was added
+            if stored.get(&old).is_some_and(|(latest, _)| *latest == updated) {
+                stored.remove(&old);
+            }
+        }
+        stored.insert(key.to_string(), (now, messages.to_vec()));
+        queue.push_back((now, key.to_string()));
+        Ok(())
+    }
+}
+
+/// Conversations held in Redis as JSON strings with a key expiry
+#[cfg(feature = "redis")]
+#[derive(Debug)]
+pub struct RedisConversationStore {
+    connection: RedisConnection,
+    ttl: Duration,
+}
+
+#[cfg(feature = "redis")]
+impl RedisConversationStore {
+    /// Key prefix for stored conversations
+    const KEY_PREFIX: &'static str = "nnllm:conversation:";
+
+    /// Create a store for the server at `url` (`redis://[user:password@]host[:port][/db]`)
+    pub fn new(url: &str, ttl: Duration) -> Self {
+        Self {
+            connection: RedisConnection::new(url),
+            ttl,
+        }
+    }
+
+    fn error(e: String) -> ProxyError {
+        ProxyError::Internal(format!("Redis conversation store: {}", e))
+    }
+}
+
+/// Connection to a Redis server shared by the Redis-backed stores.
+///
+/// Connects on first use, authenticating and selecting the database given in
+/// the URL, and reconnects after a failure. Every command, including the
+/// connection it may wait for, is bounded by a timeout so a hung server fails
+/// requests instead of stalling them.
+#[cfg(feature = "redis")]
+pub(super) struct RedisConnection {
+    url: String,
+    timeout: Duration,
+    manager: tokio::sync::OnceCell<redis::aio::ConnectionManager>,
+}
+
+#[cfg(feature = "redis")]
+impl std::fmt::Debug for RedisConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The URL may carry a password
+        f.debug_struct("RedisConnection").field("timeout", &self.timeout).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisConnection {
+    /// Longest a command may take, connecting included
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Connection to the server at `url` (`redis://[user:password@]host[:port][/db]`)
+    pub(super) fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            timeout: Self::TIMEOUT,
+            manager: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// Fail commands that take longer than `timeout`
+    #[cfg(test)]
+    pub(super) fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run `command` and convert its reply
+    pub(super) async fn query<T: redis::FromRedisValue>(&self, command: &redis::Cmd) -> Result<T, String> {
+        let run = async {
+            let manager = self
+                .manager
+                .get_or_try_init(|| async {
+                    let client = redis::Client::open(self.url.as_str())?;
+                    redis::aio::ConnectionManager::new(client).await
+                })
+                .await?;
+            command.query_async(&mut manager.clone()).await
+        };
+        match tokio::time::timeout(self.timeout, run).await {
+            Ok(reply) => reply.map_err(|e| e.to_string()),
+            Err(_) => Err(format!("no reply within {:?}", self.timeout)),
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait::async_trait]
+impl ConversationStore for RedisConversationStore {
+    async fn load(&self, key: &str) -> Result<Vec<Message>, ProxyError> {
+        let key = format!("{}{}", Self::KEY_PREFIX, key);
+        let value: Option<Vec<u8>> = self.connection.query(redis::cmd("GET").arg(&key)).await.map_err(Self::error)?;
+        match value {
+            Some(value) => serde_json::from_slice(&value)
+                .map_err(|e| ProxyError::Serialization(format!("Invalid stored conversation: {}", e))),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn save(&self, key: &str, messages: &[Message]) -> Result<(), ProxyError> {
+        let key = format!("{}{}", Self::KEY_PREFIX, key);
+        let value = serde_json::to_vec(messages)
+            .map_err(|e| ProxyError::Serialization(format!("Failed to serialize conversation: {}", e)))?;
+        let ttl = self.ttl.as_secs().max(1);
+        self.connection
+            .query::<()>(redis::cmd("SET").arg(&key).arg(value).arg("EX").arg(ttl))
+            .await
+            .map_err(Self::error)
+    }
+}
+
+/// Token budget enforced on conversations loaded from the store
+#[derive(Debug)]
+pub struct ConversationBudget {
+    max_tokens: usize,
+    /// Refuse the new turn instead of dropping the oldest ones
+    reject: bool,
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl ConversationBudget {
+    /// Build the budget from `max_conversation_tokens`, if one is set
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (config.max_conversation_tokens > 0).then(|| Self {
+            max_tokens: config.max_conversation_tokens as usize,
+            reject: config.conversation_budget_strategy == "reject",
+            token_counter: tokens::from_config(config),
+        })
+    }
+
+    /// Estimated number of tokens in `message` for `model`
+    fn tokens(&self, message: &Message, model: &str) -> usize {
+        message
+            .content
+            .as_deref()
+            .map_or(0, |content| self.token_counter.count(content, model))
+    }
+
+    /// Fit `messages` (stored history followed by the new turn) within the budget.
+    ///
+    /// Whole turns are dropped oldest first, each starting at a user message so
+    /// tool results stay with their calls. System messages and the new turn
+    /// are always kept; a conversation that still does not fit is refused.
+    pub fn apply(&self, messages: &mut Vec<Message>, model: &str) -> Result<(), ProxyError> {
+        let mut tokens: usize = messages.iter().map(|message| self.tokens(message, model)).sum();
+        if tokens > self.max_tokens && self.reject {
+            return Err(self.exceeded(tokens));
+        }
+
+        let is_system = |message: &Message| matches!(message.role.as_str(), "system" | "developer");
+        let before = messages.len();
+        while tokens > self.max_tokens {
+            let new_turn = messages.iter().rposition(|message| message.role == "user").unwrap_or(0);
+            let Some(start) = messages[..new_turn].iter().position(|message| !is_system(message)) else {
+                break;
+            };
+            let end = messages[start + 1..]
+                .iter()
+                .position(|message| message.role == "user")
+                .map_or(new_turn, |position| start + 1 + position);
+            let mut index = 0;
+            messages.retain(|message| {
+                let drop = (start..end).contains(&index) && !is_system(message);
+                index += 1;
+                if drop {
+                    tokens -= self.tokens(message, model);
+                }
+                !drop
+            });
+        }
+
+        if tokens > self.max_tokens {
+            return Err(self.exceeded(tokens));
+        }
+        if messages.len() < before {
+            tracing::debug!("Dropped {} messages to fit the conversation budget", before - messages.len());
+        }
+        Ok(())
+    }
+
+    fn exceeded(&self, tokens: usize) -> ProxyError {
+        ProxyError::BadRequest(format!(
+            "Conversation exceeds its token budget: {} tokens, at most {} allowed",
+            tokens, self.max_tokens
+        ))
+    }
+}
+
+/// Append the assistant reply in `response` to the conversation stored under
+/// `key` once the response body has been fully sent.
+///
+/// `messages` is the full conversation sent upstream; failed responses leave
+/// the stored conversation unchanged.
+pub fn remember(
+    store: Arc<dyn ConversationStore>,
+    key: String,
+    mut messages: Vec<Message>,
+    response: Response,
+) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let data = body.into_data_stream().inspect({
+        let received = received.clone();
+        move |chunk| {
+            if let Ok(chunk) = chunk {
+                received.lock().unwrap().extend_from_slice(chunk);
+            }
+        }
+    });
+    let finish = stream::once(async move {
+        let body = std::mem::take(&mut *received.lock().unwrap());
+        let Some(reply) = assistant_reply(&body) else {
+            tracing::warn!("No assistant reply found for conversation {}", key);
+            return;
+        };
+        messages.push(reply);
+        if let Err(error) = store.save(&key, &messages).await {
+            tracing::warn!("Failed to store conversation {}: {}", key, error);
+        }
+    })
+    .filter_map(|()| async { None });
+
+    Response::from_parts(parts, Body::from_stream(data.chain(finish)))
+}
+
+/// The first choice's assistant message from a JSON completion or an SSE stream
+fn assistant_reply(body: &[u8]) -> Option<Message> {
+    if let Ok(completion) = serde_json::from_slice::<Value>(body) {
+        return serde_json::from_value(completion.pointer("/choices/0/message")?.clone()).ok();
+    }
+
+    let text = std::str::from_utf8(body).ok()?;
+    let content: String = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+        .filter_map(|chunk| chunk.pointer("/choices/0/delta/content")?.as_str().map(str::to_string))
+        .collect();
+    (!content.is_empty()).then(|| Message::assistant(Some(content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_expired_conversations_are_forgotten() {
+        let store = InMemoryConversationStore::new(Duration::from_millis(20));
+        store.save("c1", &[Message::user("Hi".to_string())]).await.unwrap();
+        assert_eq!(store.load("c1").await.unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert!(store.load("c1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_conversations_are_dropped_on_save() {
+        let store = InMemoryConversationStore::new(Duration::from_millis(20));
+        store.save("c1", &[Message::user("Hi".to_string())]).await.unwrap();
+        store.save("c2", &[Message::user("Hi".to_string())]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        store.save("c2", &[Message::user("Hello".to_string())]).await.unwrap();
+
+        let conversations = store.conversations.lock().unwrap();
+        assert_eq!(conversations.stored.keys().collect::<Vec<_>>(), ["c2"]);
+        assert_eq!(conversations.queue.len(), 1);
+    }
+
+    #[test]
+    fn test_store_key_depends_on_owner() {
+        assert_eq!(store_key(&"sk-a", "conv-1"), store_key(&"sk-a", "conv-1"));
+        assert_ne!(store_key(&"sk-a", "conv-1"), store_key(&"sk-b", "conv-1"));
+        assert!(!store_key(&"sk-a", "conv-1").contains("sk-a"));
+    }
+
+    /// Minimal Redis server: answers GET and SET from memory and OK to
+    /// everything else, recording each command it receives
+    #[cfg(feature = "redis")]
+    async fn mock_redis() -> (std::net::SocketAddr, Arc<Mutex<Vec<Vec<String>>>>) {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let received = commands.clone();
+        tokio::spawn(async move {
+            let mut values = HashMap::<String, String>::new();
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(socket);
+            loop {
+                let mut line = String::new();
+                if socket.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                let mut args = Vec::new();
+                for _ in 0..line.trim_start_matches('*').trim().parse::<usize>().unwrap() {
+                    let mut header = String::new();
+                    socket.read_line(&mut header).await.unwrap();
+                    let mut arg = vec![0; header.trim_start_matches('$').trim().parse::<usize>().unwrap() + 2];
+                    socket.read_exact(&mut arg).await.unwrap();
+                    arg.truncate(arg.len() - 2);
+                    args.push(String::from_utf8(arg).unwrap());
+                }
+                let reply = match args[0].to_uppercase().as_str() {
+                    "GET" => match values.get(&args[1]) {
+                        Some(value) => format!("${}\r\n{}\r\n", value.len(), value),
+                        None => "$-1\r\n".to_string(),
+                    },
+                    "SET" => {
+                        values.insert(args[1].clone(), args[2].clone());
+                        "+OK\r\n".to_string()
+                    }
+                    _ => "+OK\r\n".to_string(),
+                };
+                received.lock().unwrap().push(args);
+                socket.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        (address, commands)
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_redis_store_authenticates_and_selects_database() {
+        let (address, commands) = mock_redis().await;
+        let url = format!("redis://nnllm:secret@{}/3", address);
+        let store = RedisConversationStore::new(&url, Duration::from_secs(60));
+
+        store.save("c1", &[Message::user("Hi".to_string())]).await.unwrap();
+        let messages = store.load("c1").await.unwrap();
+
+        assert_eq!(messages[0].content.as_deref(), Some("Hi"));
+        let commands = commands.lock().unwrap();
+        let position = |name: &str| commands.iter().position(|command| command[0].eq_ignore_ascii_case(name));
+        let auth = position("AUTH").expect("AUTH sent");
+        assert_eq!(commands[auth][1..], ["nnllm", "secret"]);
+        let select = position("SELECT").expect("SELECT sent");
+        assert_eq!(commands[select][1], "3");
+        assert!(auth < position("SET").unwrap() && select < position("SET").unwrap());
+        assert_eq!(commands[position("SET").unwrap()][3..], ["EX", "60"]);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_redis_store_times_out_on_hung_server() {
+        // Accepts connections but never replies
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("redis://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                open.push(socket);
+            }
+        });
+        let store = RedisConversationStore {
+            connection: RedisConnection::new(&url).with_timeout(Duration::from_millis(100)),
+            ttl: Duration::from_secs(60),
+        };
+
+        let started = Instant::now();
+        let error = store.load("c1").await.unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(error.to_string().contains("no reply within"), "{}", error);
+    }
+
+    #[test]
+    fn test_budget_rejects_turn_when_configured() {
+        let mut config = Config::for_test();
+        config.max_conversation_tokens = 4;
+        config.conversation_budget_strategy = "reject".to_string();
+        let budget = ConversationBudget::from_config(&config).unwrap();
+
+        let mut messages = vec![
+            Message::user("An earlier question".to_string()),
+            Message::assistant(Some("An earlier answer".to_string())),
+            Message::user("Next".to_string()),
+        ];
+        let error = budget.apply(&mut messages, "gpt-4").unwrap_err();
+
+        assert!(error.to_string().contains("token budget"));
+        assert_eq!(messages.len(), 3);
+    }
+}
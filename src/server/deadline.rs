@@ -0,0 +1,97 @@
+//! # Request Deadlines
+//!
+//! Clients with a strict latency budget pass their deadline in
+//! `request_deadline_header`, either as milliseconds from now (`1500`) or as
+//! an absolute Unix time in milliseconds. Once the deadline passes the proxy
+//! drops the upstream call, which aborts it, and answers 504 instead of
+//! waiting for the configured timeouts. Retries whose backoff would run past
+//! the deadline are skipped. For streamed responses the deadline covers
+//! opening the stream.
+
+use crate::{
+    config::Config,
+    error::{ProxyError, TransportErrorKind},
+};
+use axum::http::HeaderMap;
+use std::{
+    future::Future,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Deadline values from this size on are absolute Unix times in
+/// milliseconds (September 2001); smaller values are relative
+const ABSOLUTE_FROM_MS: u64 = 1_000_000_000_000;
+
+/// The deadline a request carries in `request_deadline_header`, if any
+pub fn from_headers(config: &Config, headers: &HeaderMap) -> Result<Option<Instant>, ProxyError> {
+    if config.request_deadline_header.is_empty() {
+        return Ok(None);
+    }
+    let Some(value) = headers.get(config.request_deadline_header.as_str()) else {
+        return Ok(None);
+    };
+    let millis: u64 = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .ok_or_else(|| {
+            ProxyError::BadRequest(format!(
+                "{} must be milliseconds from now or a Unix time in milliseconds",
+                config.request_deadline_header
+            ))
+        })?;
+
+    let remaining = if millis >= ABSOLUTE_FROM_MS {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Duration::from_millis(millis).saturating_sub(now)
+    } else {
+        Duration::from_millis(millis)
+    };
+    Ok(Some(Instant::now() + remaining))
+}
+
+/// Run `work`, failing with a gateway timeout once `deadline` has passed
+pub async fn within<T>(
+    deadline: Option<Instant>,
+    work: impl Future<Output = Result<T, ProxyError>>,
+) -> Result<T, ProxyError> {
+    let Some(deadline) = deadline else {
+        return work.await;
+    };
+    tokio::time::timeout_at(deadline.into(), work).await.unwrap_or_else(|_| {
+        Err(ProxyError::Transport {
+            kind: TransportErrorKind::Timeout,
+            message: "request deadline exceeded".to_string(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_and_absolute_deadlines() {
+        let config = Config::for_test();
+        let deadline = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-request-deadline", value.parse().unwrap());
+            from_headers(&config, &headers)
+        };
+        let now = Instant::now();
+
+        let relative = deadline("1500").unwrap().unwrap();
+        assert!(relative >= now + Duration::from_millis(1500));
+        assert!(relative < now + Duration::from_secs(3));
+
+        let unix_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let absolute = deadline(&(unix_ms + 2000).to_string()).unwrap().unwrap();
+        assert!(absolute > now + Duration::from_millis(1000));
+        assert!(absolute < now + Duration::from_secs(3));
+
+        // A deadline in the past has already expired
+        assert!(deadline(&(unix_ms - 5000).to_string()).unwrap().unwrap() <= Instant::now());
+        assert!(matches!(deadline("soon"), Err(ProxyError::BadRequest(_))));
+        assert!(from_headers(&config, &HeaderMap::new()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,240 @@
+//! # Fair Queuing
+//!
+//! Orders requests waiting for a model's concurrency slots by API key, so a
+//! key sending a burst of requests cannot monopolize the slots while requests
+//! from other keys wait behind it. Waiting keys are served in proportion to
+//! their weight (1 unless `fair_queue_weights` says otherwise) using start-time
+//! fair queuing: the key with the smallest virtual finish time goes next, and
+//! each request served advances its key's finish time by `1 / weight`.
+
+use crate::config::Config;
+use axum::http::HeaderMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+use tokio::sync::oneshot;
+
+/// Requests waiting on behalf of one API key
+#[derive(Debug)]
+struct KeyQueue {
+    /// Virtual time at which the key's last served request finished
+    finish: f64,
+    waiters: VecDeque<oneshot::Sender<()>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Whether a request currently holds the turn
+    busy: bool,
+    /// Start time of the request most recently given the turn
+    virtual_time: f64,
+    keys: HashMap<String, KeyQueue>,
+}
+
+/// Hands out a single turn at a time, fairly across API keys
+#[derive(Debug, Default)]
+pub struct FairQueue {
+    inner: Mutex<Inner>,
+    weights: HashMap<String, u32>,
+}
+
+/// The turn to go next; dropping it passes the turn on
+#[derive(Debug)]
+pub struct FairTurn<'a> {
+    queue: &'a FairQueue,
+}
+
+impl Drop for FairTurn<'_> {
+    fn drop(&mut self) {
+        self.queue.pass();
+    }
+}
+
+/// Passes the turn on if a waiting request is cancelled after being given it
+struct Waiting<'a> {
+    queue: &'a FairQueue,
+    turn: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(mut turn) = self.turn.take() {
+            turn.close();
+            if turn.try_recv().is_ok() {
+                self.queue.pass();
+            }
+        }
+    }
+}
+
+impl FairQueue {
+    /// Create a queue with per-key weights; unlisted keys have weight 1
+    pub fn new(weights: HashMap<String, u32>) -> Self {
+        Self { inner: Mutex::default(), weights }
+    }
+
+    /// Wait until it is `key`'s turn
+    pub async fn turn(&self, key: &str) -> FairTurn<'_> {
+        let receiver = {
+            let mut inner = self.inner.lock().unwrap();
+            let virtual_time = inner.virtual_time;
+            let queue = inner.keys.entry(key.to_string()).or_insert_with(|| KeyQueue {
+                finish: virtual_time,
+                waiters: VecDeque::new(),
+            });
+            if queue.waiters.is_empty() {
+                queue.finish = queue.finish.max(virtual_time);
+            }
+
+            if !inner.busy {
+                inner.busy = true;
+                self.charge(&mut inner, key);
+                return FairTurn { queue: self };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            if let Some(queue) = inner.keys.get_mut(key) {
+                queue.waiters.push_back(sender);
+            }
+            receiver
+        };
+
+        let mut waiting = Waiting { queue: self, turn: Some(receiver) };
+        if let Some(receiver) = waiting.turn.as_mut() {
+            // Senders are only dropped after the turn has been sent
+            let _ = receiver.await;
+        }
+        waiting.turn = None;
+        FairTurn { queue: self }
+    }
+
+    /// Requests waiting for a turn
+    pub fn waiting(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.keys.values().map(|queue| queue.waiters.len()).sum()
+    }
+
+    /// Give the turn to the waiting key with the smallest finish time, or
+    /// leave the queue idle when nobody is waiting
+    fn pass(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let virtual_time = inner.virtual_time;
+        inner.keys.retain(|_, queue| !queue.waiters.is_empty() || queue.finish > virtual_time);
+        loop {
+            let next = inner
+                .keys
+                .iter()
+                .filter(|(_, queue)| !queue.waiters.is_empty())
+                .min_by(|(a_key, a), (b_key, b)| a.finish.total_cmp(&b.finish).then_with(|| a_key.cmp(b_key)))
+                .map(|(key, _)| key.clone());
+            let Some(key) = next else {
+                inner.busy = false;
+                return;
+            };
+
+            let Some(sender) = inner.keys.get_mut(&key).and_then(|queue| queue.waiters.pop_front()) else {
+                continue;
+            };
+            if sender.send(()).is_ok() {
+                self.charge(&mut inner, &key);
+                return;
+            }
+        }
+    }
+
+    /// Advance virtual time to the start of `key`'s request and account for it
+    fn charge(&self, inner: &mut Inner, key: &str) {
+        let weight = self.weights.get(key).copied().unwrap_or(1).max(1);
+        if let Some(queue) = inner.keys.get_mut(key) {
+            inner.virtual_time = queue.finish;
+            queue.finish += 1.0 / f64::from(weight);
+        }
+    }
+}
+
+/// API key a request is queued under: the configured API key header or a
+/// bearer token, with anonymous requests sharing one empty key
+pub fn client_key(headers: &HeaderMap, config: &Config) -> String {
+    headers
+        .get(&config.api_key_header)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| {
+            headers
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|auth| auth.strip_prefix("Bearer "))
+        })
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, time::Duration};
+
+    /// Queue `keys` in order behind a held turn, release it and record the
+    /// order in which the keys are served
+    async fn service_order(queue: Arc<FairQueue>, keys: &[&'static str]) -> Vec<&'static str> {
+        let held = queue.turn("holder").await;
+        let (served, mut order) = tokio::sync::mpsc::unbounded_channel();
+        for &key in keys {
+            let queue = queue.clone();
+            let served = served.clone();
+            tokio::spawn(async move {
+                let _turn = queue.turn(key).await;
+                served.send(key).unwrap();
+            });
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        drop(served);
+        assert_eq!(queue.waiting(), keys.len());
+
+        drop(held);
+        let mut served = Vec::new();
+        while let Some(key) = order.recv().await {
+            served.push(key);
+        }
+        served
+    }
+
+    #[tokio::test]
+    async fn test_weights_set_each_keys_share() {
+        let queue = Arc::new(FairQueue::new(HashMap::from([("heavy".to_string(), 2)])));
+        let order = service_order(queue, &["light", "light", "light", "heavy", "heavy", "heavy", "heavy"]).await;
+        assert_eq!(order, ["heavy", "light", "heavy", "heavy", "light", "heavy", "light"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_passes_its_turn_on() {
+        let queue = Arc::new(FairQueue::default());
+        let held = queue.turn("a").await;
+        let cancelled = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let _turn = queue.turn("b").await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        cancelled.abort();
+        let _ = cancelled.await;
+
+        drop(held);
+        let next = tokio::time::timeout(Duration::from_millis(50), queue.turn("c")).await;
+        assert!(next.is_ok(), "the queue must not stay busy after a waiter is cancelled");
+    }
+
+    #[test]
+    fn test_client_key_from_api_key_header_or_bearer_token() {
+        let config = Config::for_test();
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_key(&headers, &config), "");
+
+        headers.insert("authorization", "Bearer sk-bearer".parse().unwrap());
+        assert_eq!(client_key(&headers, &config), "sk-bearer");
+
+        headers.insert("x-api-key", "sk-header".parse().unwrap());
+        assert_eq!(client_key(&headers, &config), "sk-header");
+    }
+}
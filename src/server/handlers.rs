@@ -3,32 +3,386 @@
 //! This module contains HTTP route handlers for the server.
 
 use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, Method, StatusCode},
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
     response::{Response, IntoResponse, Json as JsonResponse},
     Json,
 };
+use std::time::{Duration, Instant};
 use crate::{
+    adapters::{AdapterUtils, UpstreamDuration},
+    config::Config,
+    core::http_client::RetryPolicy,
     error::ProxyError,
-    schemas::{ChatCompletionRequest, ChatCompletionResponse},
+    schemas::{ChatCompletionRequest, ChatCompletionResponse, ValidationIssue},
 };
 #[cfg(feature = "streaming")]
-use crate::streaming::create_streaming_response;
-use super::AppState;
+use crate::streaming::{create_streaming_response, meter_streaming_response};
+#[cfg(feature = "caching")]
+use crate::caching::CacheManager;
+use super::{
+    conversations, deadline, fair_queue, json_repair, json_schema, load_shedding::DEGRADED_FROM_HEADER, model_concurrency, model_pin::ModelPin,
+    parameter_support::ParameterSupport, prefill::Prefill, ranking::LogprobRanking, reasoning::ReasoningContent, refusal,
+    response_language::ResponseLanguage, transform, AppState,
+};
+
+/// Total handler time header
+pub const REQUEST_DURATION_HEADER: &str = "x-request-duration-ms";
+/// Upstream adapter HTTP call time header
+pub const UPSTREAM_DURATION_HEADER: &str = "x-upstream-duration-ms";
+
+/// Request identifier header, taken from the client or generated, and echoed
+/// back when `return_request_id` is on
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Per-request override of the upstream retry count
+pub const MAX_RETRIES_HEADER: &str = "x-max-retries";
+/// Which backend served a request when content filter retries are enabled
+/// ("primary" or "content-filter-retry")
+pub const SERVED_BY_HEADER: &str = "x-served-by-backend";
+
+/// Path of the chat completions endpoint, recorded with its errors
+#[cfg(feature = "metrics")]
+const CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
 
 /// Chat completions handler
 pub async fn chat_completions(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<ChatCompletionRequest>,
+) -> Response {
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let return_request_id = state.config().return_request_id;
+
+    let mut response = match complete_chat(state, &headers, req, &request_id).await {
+        Ok(response) => response,
+        Err(error) => {
+            tracing::error!(
+                request_id = %request_id,
+                status = error.status_code().as_u16(),
+                error = %error,
+                "Chat completion failed"
+            );
+            if return_request_id {
+                error.into_response_with_request_id(&request_id)
+            } else {
+                error.into_response()
+            }
+        }
+    };
+    if let Some(value) = return_request_id.then(|| HeaderValue::from_str(&request_id).ok()).flatten() {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Handle a chat completion identified by `request_id`
+async fn complete_chat(
+    state: AppState,
+    headers: &HeaderMap,
+    mut req: ChatCompletionRequest,
+    request_id: &str,
+) -> Result<Response, ProxyError> {
+    let start_time = Instant::now();
+    let request_deadline = deadline::from_headers(state.config(), headers)?;
+    let credential = upstream_credential(state.config(), headers);
+    if let Err(issues) = req.validate() {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        return Err(ProxyError::BadRequest(format!("Invalid request: {}", issues.join("; "))));
+    }
+    let conversation = match req.conversation_id.take() {
+        Some(conversation_id) => {
+            let store = state.conversations().cloned().ok_or_else(|| {
+                ProxyError::BadRequest("conversation_id requires server-side conversation storage".to_string())
+            })?;
+            // Conversations belong to the caller, so another key cannot resume them
+            let owner = (fair_queue::client_key(headers, state.config()), credential.as_deref());
+            let key = conversations::store_key(&owner, &conversation_id);
+            let mut messages = store.load(&key).await?;
+            // A resumed conversation already starts with the referenced prompt
+            if !messages.is_empty() {
+                req.system_prompt_ref = None;
+            }
+            state.system_prompts().expand(&mut req)?;
+            messages.append(&mut req.messages);
+            if let Some(budget) = state.conversation_budget() {
+                budget.apply(&mut messages, &AdapterUtils::extract_model(&req, state.adapter().model_id()))?;
+            }
+            req.messages = messages;
+            Some((store, key, req.messages.clone()))
+        }
+        None => {
+            state.system_prompts().expand(&mut req)?;
+            None
+        }
+    };
+    // Checked after loading the conversation, whose history holds the tool calls
+    if state.config().validate_tool_call_ids {
+        if let Err(issues) = req.validate_tool_call_ids() {
+            let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+            return Err(ProxyError::BadRequest(format!("Invalid request: {}", issues.join("; "))));
+        }
+    }
+    if state.config().require_system_message
+        && !req.messages.iter().any(|message| matches!(message.role.as_str(), "system" | "developer"))
+    {
+        return Err(ProxyError::BadRequest(
+            "A system message is required: add a message with role 'system' or set system_prompt_ref".to_string(),
+        ));
+    }
+    ResponseLanguage::from_config(state.config()).apply(&mut req, headers)?;
+    let routed = state
+        .size_router()
+        .route(&req, &AdapterUtils::extract_model(&req, state.adapter().model_id()))
+        .cloned();
+    let state = match (routed, credential) {
+        (None, None) => state,
+        (routed, credential) => {
+            let adapter = routed.unwrap_or_else(|| state.adapter().clone());
+            match credential {
+                // Completions billed to one tenant's key are never served to another
+                Some(token) => state.with_adapter(adapter.with_backend_token(token)?).without_shared_responses(),
+                None => state.with_adapter(adapter),
+            }
+        }
+    };
+    req.stream = Some(state.config().resolve_stream(req.stream));
+    let mut model = AdapterUtils::extract_model(&req, state.adapter().model_id());
+
+    // Requests sampled out are still kept while failures are captured, so a
+    // failed request can be captured once its outcome is known
+    let captured_request = state.prompt_capture().and_then(|capture| {
+        let sampled = capture.sample();
+        (sampled || capture.captures_errors())
+            .then(|| (capture.clone(), sampled, serde_json::to_value(&req).unwrap_or_default()))
+    });
+    if let Some(shadow) = state.shadow() {
+        shadow.mirror(&req);
+    }
+
+    let mut degraded_from = None;
+    if let Some(fallback) = state.load_shedder().route(&model, state.model_limiter()) {
+        req.model = Some(fallback.to_string());
+        degraded_from = Some(std::mem::replace(&mut model, fallback.to_string()));
+    }
+    ParameterSupport::from_config(state.config()).strip(&mut req, &model);
+
+    let client_key = fair_queue::client_key(headers, state.config());
+    // Queueing for capacity counts against the client's deadline too
+    let result = deadline::within(request_deadline, async {
+        let permit = state.model_limiter().acquire(&model, &client_key).await;
+        let result = match state.upstream_pool().acquire().await {
+            Ok(Some(connection)) => dispatch_chat_completion(&state, headers, req, request_deadline)
+                .await
+                .map(|response| model_concurrency::hold_permit(response, connection)),
+            Ok(None) => dispatch_chat_completion(&state, headers, req, request_deadline).await,
+            Err(error) => Err(error),
+        };
+        state.upstream_pool().record_outcome(&result);
+        match permit {
+            Some(permit) => result.map(|response| model_concurrency::hold_permit(response, permit)),
+            None => result,
+        }
+    })
+    .await;
+    let result = match conversation {
+        Some((store, key, messages)) => {
+            result.map(|response| conversations::remember(store, key, messages, response))
+        }
+        None => result,
+    };
+    let result = match captured_request {
+        Some((capture, sampled, request)) if sampled || capture.captures_outcome(&result) => {
+            capture.capture(request_id.to_string(), request, sampled, result)
+        }
+        _ => result,
+    };
+
+    let duration = start_time.elapsed();
+    let status = match &result {
+        Ok(response) => response.status(),
+        Err(error) => error.status_code(),
+    };
+    tracing::info!(
+        request_id = %request_id,
+        model = %model,
+        adapter = state.adapter().name(),
+        duration_ms = duration.as_millis() as u64,
+        status = status.as_u16(),
+        "Chat completion finished"
+    );
+
+    // Every request and error is recorded in the monitoring system
+    #[cfg(feature = "metrics")]
+    {
+        let monitoring = state.monitoring();
+        // Only buffered bodies have a known size up front
+        let bytes = match &result {
+            Ok(response) => axum::body::HttpBody::size_hint(response.body()).lower(),
+            Err(_) => 0,
+        };
+        monitoring.record_request(duration, status.is_success(), bytes).await;
+        if let Err(error) = &result {
+            let user_agent = headers
+                .get(axum::http::header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            monitoring
+                .record_error(
+                    error.kind().to_string(),
+                    error.to_string(),
+                    Some(request_id.to_string()),
+                    Some(CHAT_COMPLETIONS_PATH.to_string()),
+                    user_agent,
+                    None,
+                )
+                .await;
+        }
+    }
+
+    // Client errors say nothing about backend health, so only 2xx and 5xx count
+    #[cfg(feature = "metrics")]
+    {
+        let metrics = state.metrics();
+        metrics.record_request();
+        metrics.record_labelled(state.adapter().name(), &model, status.as_u16(), duration);
+        if status.is_server_error() {
+            metrics.record_failure();
+        } else if status.is_success() {
+            metrics.record_success(0, duration.as_millis() as u64);
+            if let Some(alert) = metrics.sla().record(&model, duration.as_millis() as u64) {
+                tracing::warn!(
+                    model = %alert.model,
+                    compliance_percent = alert.compliance_percent,
+                    target_ms = alert.target_ms,
+                    "SLO compliance dropped below {}%",
+                    alert.threshold_percent
+                );
+                if let Some(webhook_url) = state.config().sla_alert_webhook_url.clone() {
+                    let client = state.http_client().clone();
+                    tokio::spawn(async move { alert.deliver(&client, &webhook_url).await });
+                }
+            }
+        }
+    }
+
+    if degraded_from.is_none() && status.is_success() {
+        state.load_shedder().record_latency(&model, duration);
+    }
+
+    let mut response = result?;
+    if state.config().enable_timing_headers {
+        add_timing_headers(&mut response, duration);
+    }
+    if let Some(value) = degraded_from.and_then(|original| HeaderValue::from_str(&original).ok()) {
+        response.headers_mut().insert(DEGRADED_FROM_HEADER, value);
+    }
+
+    Ok(response)
+}
+
+/// The client's own backend credential, when bring-your-own-key is enabled.
+///
+/// Accepts a bare key or a `Bearer` value. The credential is never logged.
+fn upstream_credential(config: &Config, headers: &HeaderMap) -> Option<String> {
+    if !config.allow_byok {
+        return None;
+    }
+    let value = headers.get(config.byok_header.as_str())?.to_str().ok()?.trim();
+    let token = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+/// Route a chat completion request to the streaming or regular adapter path
+async fn dispatch_chat_completion(
+    state: &AppState,
+    headers: &HeaderMap,
+    mut req: ChatCompletionRequest,
+    request_deadline: Option<Instant>,
 ) -> Result<Response, ProxyError> {
+    if req.requests_audio() && !state.adapter().supports_audio() {
+        return Err(ProxyError::BadRequest(format!(
+            "Audio output is not supported by the {} backend",
+            state.adapter().name()
+        )));
+    }
+    if req.n.is_some_and(|n| n > 1) && !state.adapter().supports_n() {
+        return Err(ProxyError::BadRequest(format!(
+            "Multiple choices (n > 1) are not supported by the {} backend",
+            state.adapter().name()
+        )));
+    }
+    if req.suffix.is_some() && !state.adapter().supports_suffix() {
+        return Err(ProxyError::BadRequest(format!(
+            "The suffix parameter (fill-in-the-middle) is not supported by the {} backend",
+            state.adapter().name()
+        )));
+    }
+
+    let ranking = LogprobRanking::from_request(state.config(), headers, &mut req)?;
+    let prefill = if state.config().assistant_prefill {
+        Prefill::extract(&mut req, state.adapter().supports_prefill())
+    } else {
+        None
+    };
+
     // Check if streaming is requested
     if req.stream.unwrap_or(false) {
         // Check if the adapter supports streaming
         if state.adapter().supports_streaming() {
             #[cfg(feature = "streaming")]
             {
-                let sse_response = create_streaming_response(state.adapter(), req).await?;
-                Ok(sse_response.into_response())
+                let started = Instant::now();
+                let choice_count = req.n.unwrap_or(1);
+                // Only opening the stream is retried: once the upstream has
+                // answered, later failures arrive inside the streamed body
+                let policy = retry_policy(state, headers).with_deadline(request_deadline);
+                let open_stream = || {
+                    policy.wait_for_model(
+                        || {
+                            policy.retry(
+                                || create_streaming_response(state.adapter(), req.clone()),
+                                |error| policy.is_retryable(error),
+                            )
+                        },
+                        ProxyError::is_model_loading,
+                    )
+                };
+                let mut sse_response = match state.stream_fanout() {
+                    Some(fanout) => {
+                        fanout
+                            .stream(&req, || async { Ok(open_stream().await?.into_response()) })
+                            .await?
+                    }
+                    None => open_stream().await?.into_response(),
+                };
+                if state.config().stream_tool_call_truncation == "signal" {
+                    sse_response = transform::guard_truncated_tool_calls(sse_response);
+                }
+                let reasoning = ReasoningContent::from_config(state.config());
+                if reasoning != ReasoningContent::Forward {
+                    sse_response = reasoning.rewrite_stream(sse_response);
+                }
+                let prefixes = state.config().strip_prefixes();
+                if !prefixes.is_empty() {
+                    sse_response = transform::strip_streaming_prefixes(sse_response, prefixes.into());
+                }
+                if state.config().stream_choice_demux
+                    && choice_count > 1
+                    && headers
+                        .get(transform::STREAM_CHOICES_HEADER)
+                        .is_some_and(|value| value == transform::STREAM_CHOICES_SEPARATE)
+                {
+                    sse_response = transform::demux_streaming_choices(sse_response);
+                }
+                Ok(meter_streaming_response(
+                    sse_response,
+                    state.streaming_stats().clone(),
+                    started,
+                ))
             }
             #[cfg(not(feature = "streaming"))]
             {
@@ -42,21 +396,510 @@ pub async fn chat_completions(
             ))
         }
     } else {
-        // Return regular JSON response
-        state.adapter().chat_completions(req).await
+        let model = AdapterUtils::extract_model(&req, state.adapter().model_id());
+
+        #[cfg(feature = "caching")]
+        let cached = match state.cache() {
+            Some(cache) => cache.get(&req).await,
+            None => None,
+        };
+        #[cfg(not(feature = "caching"))]
+        let cached: Option<ChatCompletionResponse> = None;
+
+        let result = match cached {
+            Some(completion) => Ok(JsonResponse(completion).into_response()),
+            None => {
+                // Return regular JSON response, retrying transient upstream failures
+                let policy = retry_policy(state, headers).with_deadline(request_deadline);
+                let result = policy
+                    .wait_for_model(
+                        || policy.retry(|| state.adapter().chat_completions(req.clone()), |error| policy.is_retryable(error)),
+                        ProxyError::is_model_loading,
+                    )
+                    .await;
+                let result = match state.content_filter_backend() {
+                    Some(backend) => retry_content_filter(result, backend, &req).await,
+                    None => result,
+                };
+
+                // Verify the served model before the response can be cached
+                let result = match result {
+                    Ok(response) => ModelPin::from_config(state.config()).check(response, &model).await,
+                    Err(error) => Err(error),
+                };
+
+                #[cfg(feature = "caching")]
+                let result = match state.cache() {
+                    Some(cache) => cache_completion(cache, &req, result).await,
+                    None => result,
+                };
+                result
+            }
+        };
+
+        let result = match &state.config().refusal_fallback_message {
+            Some(fallback_message) => substitute_refusal(result, fallback_message, &model).await,
+            None => result,
+        };
+
+        let reasoning = ReasoningContent::from_config(state.config());
+        let result = if reasoning == ReasoningContent::Forward {
+            result
+        } else {
+            rewrite_json_response(result?, |json| reasoning.rewrite_completion(json)).await
+        };
+
+        let prefixes = state.config().strip_prefixes();
+        let result = if prefixes.is_empty() {
+            result
+        } else {
+            rewrite_json_response(result?, |json| transform::strip_completion_prefixes(json, &prefixes)).await
+        };
+        let result = match &prefill {
+            Some(prefill) => rewrite_json_response(result?, |json| prefill.complete(json)).await,
+            None => result,
+        };
+
+        let result = match &ranking {
+            Some(ranking) => rewrite_json_response(result?, |json| ranking.rank(json)).await,
+            None => result,
+        };
+        let result = if state.config().repair_json_output && req.requests_json() {
+            rewrite_json_response(result?, json_repair::repair_completion_json).await
+        } else {
+            result
+        };
+        match req.json_schema() {
+            Some(schema) => enforce_json_schema(result?, schema).await,
+            None => result,
+        }
+    }
+}
+
+/// Fail a successful completion whose content does not match the `json_schema`
+/// the client requested
+async fn enforce_json_schema(response: Response, schema: &serde_json::Value) -> Result<Response, ProxyError> {
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body_bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+    if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+        json_schema::validate_completion(&json, schema).map_err(|error| {
+            ProxyError::Upstream(format!("Response does not match the requested JSON schema: {}", error))
+        })?;
+    }
+    Ok(Response::from_parts(parts, axum::body::Body::from(body_bytes)))
+}
+
+/// Build the retry policy for a request.
+///
+/// Authenticated clients may override the configured retry count with the
+/// `X-Max-Retries` header, clamped to `max_retries_ceiling`. The header is
+/// ignored when API key validation is disabled.
+fn retry_policy(state: &AppState, headers: &HeaderMap) -> RetryPolicy {
+    let config = state.config();
+    let policy = RetryPolicy::from(config);
+
+    if !config.api_key_validation_enabled {
+        return policy;
+    }
+
+    match headers
+        .get(MAX_RETRIES_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u32>().ok())
+    {
+        Some(max_retries) => policy.with_max_retries(max_retries.min(config.max_retries_ceiling)),
+        None => policy,
+    }
+}
+
+/// Store a successful upstream completion in the response cache
+#[cfg(feature = "caching")]
+async fn cache_completion(
+    cache: &CacheManager,
+    req: &ChatCompletionRequest,
+    result: Result<Response, ProxyError>,
+) -> Result<Response, ProxyError> {
+    let response = result?;
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body_bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+
+    if let Ok(completion) = serde_json::from_slice::<ChatCompletionResponse>(&body_bytes) {
+        if let Err(error) = cache.put(req, completion).await {
+            tracing::warn!("Failed to cache completion: {}", error);
+        }
     }
+    Ok(Response::from_parts(parts, axum::body::Body::from(body_bytes)))
+}
+
+/// Retry a request blocked by the primary backend's content filter against
+/// `retry_on_content_filter_backend`, tagging the response with the backend
+/// that served it
+async fn retry_content_filter(
+    result: Result<Response, ProxyError>,
+    backend: &crate::adapters::Adapter,
+    req: &ChatCompletionRequest,
+) -> Result<Response, ProxyError> {
+    match result {
+        Ok(response) => {
+            let (parts, body) = response.into_parts();
+            let body_bytes = axum::body::to_bytes(body, usize::MAX)
+                .await
+                .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+            let blocked = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+                .is_ok_and(|json| refusal::is_safety_refusal(&json));
+            if !blocked {
+                let mut response = Response::from_parts(parts, axum::body::Body::from(body_bytes));
+                response.headers_mut().insert(SERVED_BY_HEADER, HeaderValue::from_static("primary"));
+                return Ok(response);
+            }
+            tracing::info!("Content filter blocked the response; retrying against the alternate backend");
+        }
+        Err(error) if refusal::is_safety_refusal_error(&error) => {
+            tracing::info!("Content filter blocked the request; retrying against the alternate backend: {}", error);
+        }
+        Err(error) => return Err(error),
+    }
+
+    let mut response = backend.chat_completions(req.clone()).await?;
+    response
+        .headers_mut()
+        .insert(SERVED_BY_HEADER, HeaderValue::from_static("content-filter-retry"));
+    Ok(response)
+}
+
+/// Replace backend safety refusals with the configured fallback message
+async fn substitute_refusal(
+    result: Result<Response, ProxyError>,
+    fallback_message: &str,
+    model: &str,
+) -> Result<Response, ProxyError> {
+    let response = match result {
+        Ok(response) => response,
+        Err(error) if refusal::is_safety_refusal_error(&error) => {
+            tracing::info!("Backend safety block replaced with fallback message: {}", error);
+            return Ok(JsonResponse(refusal::refusal_completion(fallback_message, model)).into_response());
+        }
+        Err(error) => return Err(error),
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+
+    let mut json = match serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+        Ok(json) if refusal::is_safety_refusal(&json) => json,
+        _ => return Ok(Response::from_parts(parts, axum::body::Body::from(body_bytes))),
+    };
+
+    tracing::info!("Backend safety refusal replaced with fallback message");
+    refusal::apply_refusal_fallback(&mut json, fallback_message, model);
+
+    let body = serde_json::to_vec(&json)
+        .map_err(|e| ProxyError::Serialization(format!("Failed to serialize response: {}", e)))?;
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, axum::body::Body::from(body)))
+}
+
+/// Apply `rewrite` to the body of a JSON response; other bodies pass through unchanged
+async fn rewrite_json_response(
+    response: Response,
+    rewrite: impl FnOnce(&mut serde_json::Value),
+) -> Result<Response, ProxyError> {
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+
+    let mut json = match serde_json::from_slice::<serde_json::Value>(&body_bytes) {
+        Ok(json) => json,
+        Err(_) => return Ok(Response::from_parts(parts, axum::body::Body::from(body_bytes))),
+    };
+    rewrite(&mut json);
+
+    let body = serde_json::to_vec(&json)
+        .map_err(|e| ProxyError::Serialization(format!("Failed to serialize response: {}", e)))?;
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, axum::body::Body::from(body)))
+}
+
+/// Add total and upstream duration headers to a response
+///
+/// The upstream duration is only known for responses produced by an adapter
+/// HTTP call, which carry an `UpstreamDuration` extension.
+fn add_timing_headers(response: &mut Response, request_duration: Duration) {
+    let upstream_duration = response.extensions().get::<UpstreamDuration>().copied();
+    let headers = response.headers_mut();
+
+    headers.insert(
+        REQUEST_DURATION_HEADER,
+        HeaderValue::from(request_duration.as_millis() as u64),
+    );
+    if let Some(UpstreamDuration(duration)) = upstream_duration {
+        headers.insert(
+            UPSTREAM_DURATION_HEADER,
+            HeaderValue::from(duration.as_millis() as u64),
+        );
+    }
+}
+
+/// Query parameters of the health check
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct HealthParams {
+    /// Probe the backend now instead of reusing a recent result
+    #[serde(default)]
+    pub deep: bool,
 }
 
 /// Health check handler
-pub async fn health_check() -> impl IntoResponse {
-    let health_status = serde_json::json!({
+///
+/// Reports 503 while the backend is unreachable. The backend status comes
+/// from a probe at most `health_probe_interval_secs` old, or from a live
+/// probe when none is that recent or `?deep=true` is given; live probes are
+/// limited to one per `health_probe_min_interval_secs`.
+pub async fn health_check(State(state): State<AppState>, Query(params): Query<HealthParams>) -> impl IntoResponse {
+    let mut status = StatusCode::OK;
+    let mut health_status = serde_json::json!({
         "status": "healthy",
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "service": "nexus-nitro-llm",
-        "version": env!("CARGO_PKG_VERSION")
+        "version": env!("CARGO_PKG_VERSION"),
+        "components": {
+            "server": {"status": "healthy"}
+        }
+    });
+
+    #[cfg(feature = "metrics")]
+    if state.config().enable_health_checks {
+        use crate::monitoring::BackendHealthStatus;
+
+        let (backend, from_cache) = state.backend_health(params.deep).await;
+
+        match backend.health_status {
+            BackendHealthStatus::Unhealthy => {
+                status = StatusCode::SERVICE_UNAVAILABLE;
+                health_status["status"] = "unhealthy".into();
+            }
+            BackendHealthStatus::Degraded => health_status["status"] = "degraded".into(),
+            BackendHealthStatus::Healthy | BackendHealthStatus::Unknown => {}
+        }
+        health_status["components"]["backend"] = serde_json::json!({
+            "status": backend.health_status.as_str(),
+            "type": state.adapter().name(),
+            "response_time_ms": backend.response_time_ms,
+            "last_check": backend.last_health_check.map(|checked| chrono::DateTime::<chrono::Utc>::from(checked).to_rfc3339()),
+            "cached": from_cache,
+            "error": backend.error_message,
+        });
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (state, params);
+
+    (status, JsonResponse(health_status))
+}
+
+/// Liveness handler
+///
+/// Answers 200 whenever the runtime can still schedule the handler, without
+/// touching the backend, so a slow upstream never gets the process restarted.
+pub async fn liveness_check() -> impl IntoResponse {
+    (StatusCode::OK, JsonResponse(serde_json::json!({ "status": "alive" })))
+}
+
+/// Backend readiness handler
+///
+/// Reports not-ready (503) until a health probe or chat request has reached
+/// the backend, and again whenever the latest probe finds the backend
+/// unreachable or the latest request fails to connect to it.
+pub async fn backend_readiness_check(State(state): State<AppState>) -> impl IntoResponse {
+    #[cfg(feature = "metrics")]
+    {
+        // Chat requests and probes alike record whether they reached the backend
+        let (backend, _) = state.backend_health(false).await;
+        let reachable = !matches!(backend.health_status, crate::monitoring::BackendHealthStatus::Unhealthy);
+        let pool_warm = state.upstream_pool().is_warm();
+        let (status, label) = if reachable && pool_warm {
+            (StatusCode::OK, "ready")
+        } else {
+            (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+        };
+        let readiness = serde_json::json!({
+            "status": label,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "checks": {
+                "backend": {
+                    "status": backend.health_status.as_str(),
+                    "error": backend.error_message,
+                },
+                "connection_pool": {"status": if pool_warm { "warm" } else { "cold" }}
+            }
+        });
+        (status, JsonResponse(readiness))
+    }
+    #[cfg(not(feature = "metrics"))]
+    {
+        let _ = state;
+        (StatusCode::OK, JsonResponse(serde_json::json!({ "status": "ready" })))
+    }
+}
+
+/// Readiness handler
+///
+/// Reports not-ready (503) while the error rate of real chat completion
+/// requests within the recent window exceeds the configured threshold, so the
+/// instance is taken out of rotation during backend outages.
+pub async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse {
+    #[cfg(feature = "metrics")]
+    {
+        let config = state.config();
+        let recent = state.metrics().recent_error_rate();
+        let tripped = recent.requests >= config.readiness_min_requests.max(1)
+            && recent.error_rate > config.readiness_error_rate_threshold;
+        let (status, label) = if tripped {
+            (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+        } else {
+            (StatusCode::OK, "ready")
+        };
+        let readiness = serde_json::json!({
+            "status": label,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "recent_requests": recent,
+            "error_rate_threshold": config.readiness_error_rate_threshold,
+        });
+        (status, JsonResponse(readiness))
+    }
+    #[cfg(not(feature = "metrics"))]
+    {
+        let _ = state;
+        (StatusCode::OK, JsonResponse(serde_json::json!({ "status": "ready" })))
+    }
+}
+
+/// Request validation handler
+///
+/// Runs the same deserialization and validation as `/v1/chat/completions`
+/// and reports every problem found, without calling the backend.
+pub async fn validate_request(body: axum::body::Bytes) -> impl IntoResponse {
+    let errors = match serde_json::from_slice::<ChatCompletionRequest>(&body) {
+        Ok(req) => req.validate().err().unwrap_or_default(),
+        Err(err) => vec![ValidationIssue {
+            field: "body".to_string(),
+            message: err.to_string(),
+        }],
+    };
+
+    let report = serde_json::json!({
+        "valid": errors.is_empty(),
+        "errors": errors,
     });
 
-    (StatusCode::OK, JsonResponse(health_status))
+    (StatusCode::OK, JsonResponse(report))
+}
+
+/// Metrics endpoint handler.
+///
+/// Scrapers that accept `text/plain` get the Prometheus text format; everyone
+/// else gets the JSON summary.
+pub async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    #[cfg(feature = "metrics")]
+    if accepts_prometheus(&headers) {
+        let body = state.metrics().render_prometheus(state.adapter().name());
+        return ([(axum::http::header::CONTENT_TYPE, crate::metrics::PROMETHEUS_CONTENT_TYPE)], body).into_response();
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = headers;
+
+    let metrics = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "streaming": state.streaming_stats().snapshot(),
+        "model_concurrency": state.model_limiter().snapshot(),
+        "upstream_pool": state.upstream_pool().snapshot(),
+    });
+    #[cfg(feature = "metrics")]
+    let metrics = {
+        let mut metrics = metrics;
+        let system = state.monitoring().get_metrics().await;
+        metrics["requests"] = serde_json::json!(system.requests);
+        metrics["errors"] = serde_json::json!(system.errors);
+        metrics["sla"] = serde_json::json!(state.metrics().sla().compliance());
+        metrics
+    };
+
+    (StatusCode::OK, JsonResponse(metrics)).into_response()
+}
+
+/// Whether the `Accept` header asks for the Prometheus text format
+#[cfg(feature = "metrics")]
+fn accepts_prometheus(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(axum::http::header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| media.trim().starts_with("text/plain"))
+}
+
+/// OpenAI-compatible model listing
+pub async fn list_models(State(state): State<AppState>) -> Result<Response, ProxyError> {
+    Ok(JsonResponse(state.adapter().list_models().await?).into_response())
+}
+
+/// Metadata of a single model
+pub async fn retrieve_model(State(state): State<AppState>, Path(id): Path<String>) -> Result<Response, ProxyError> {
+    match state.adapter().retrieve_model(&id).await? {
+        Some(model) => Ok(JsonResponse(model).into_response()),
+        None => {
+            let body = serde_json::json!({
+                "error": {
+                    "message": format!("The model '{}' does not exist", id),
+                    "type": "invalid_request_error",
+                    "code": "model_not_found"
+                }
+            });
+            Ok((StatusCode::NOT_FOUND, JsonResponse(body)).into_response())
+        }
+    }
+}
+
+/// Response cache statistics handler, mounted when caching is enabled
+#[cfg(feature = "caching")]
+pub async fn cache_stats(State(state): State<AppState>) -> Result<Response, ProxyError> {
+    let cache = state
+        .cache()
+        .ok_or_else(|| ProxyError::BadRequest("Response caching is not enabled".to_string()))?;
+    Ok(JsonResponse(cache.get_stats().await).into_response())
+}
+
+/// Shadow backend statistics handler, mounted when a shadow backend is configured
+pub async fn shadow_stats(State(state): State<AppState>) -> Result<Response, ProxyError> {
+    let shadow = state
+        .shadow()
+        .ok_or_else(|| ProxyError::BadRequest("Shadow traffic is not enabled".to_string()))?;
+    Ok(JsonResponse(shadow.stats().snapshot()).into_response())
+}
+
+/// Function registry listing, in the `tools` format of chat completion requests
+#[cfg(feature = "tools")]
+pub async fn list_tools(State(state): State<AppState>) -> Result<Response, ProxyError> {
+    let mut tools = state
+        .function_registry()
+        .read()
+        .map_err(|_| ProxyError::Internal("Function registry lock poisoned".to_string()))?
+        .as_tools();
+    tools.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+    Ok(JsonResponse(serde_json::json!({ "object": "list", "data": tools })).into_response())
 }
 
 /// UI proxy handler
@@ -174,7 +1017,8 @@ pub async fn anthropic_messages(
     Json(req): Json<crate::anthropic::AnthropicRequest>,
 ) -> Result<Response, ProxyError> {
     // Convert Anthropic request to OpenAI format
-    let openai_req = req.to_openai_request();
+    let mut openai_req = req.to_openai_request()?;
+    openai_req.stream = Some(state.config().resolve_stream(openai_req.stream));
     
     // Check if streaming is requested
     if openai_req.stream.unwrap_or(false) {
@@ -182,11 +1026,9 @@ pub async fn anthropic_messages(
         if state.adapter().supports_streaming() {
             #[cfg(feature = "streaming")]
             {
-                // For streaming, we need to handle SSE format conversion
-                // For now, delegate to the OpenAI streaming handler
-                // TODO: Convert OpenAI SSE events to Anthropic SSE format
+                // Re-frame the OpenAI chunks as Anthropic SSE events
                 let sse_response = create_streaming_response(state.adapter(), openai_req).await?;
-                Ok(sse_response.into_response())
+                Ok(crate::anthropic::to_anthropic_stream(sse_response.into_response()))
             }
             #[cfg(not(feature = "streaming"))]
             {
@@ -216,3 +1058,2303 @@ pub async fn anthropic_messages(
         Ok(JsonResponse(anthropic_resp).into_response())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::create_router;
+    use axum::{body::Body, http::Request};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn completion_body() -> serde_json::Value {
+        serde_json::json!({
+            "id": "chatcmpl-test",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello!"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 2, "total_tokens": 7}
+        })
+    }
+
+    async fn mock_openai_backend() -> MockServer {
+        mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion_body())).await
+    }
+
+    async fn mock_openai_backend_with(response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    async fn send_chat(config: Config) -> Response {
+        send_chat_with_headers(config, &[]).await
+    }
+
+    async fn send_chat_with_headers(config: Config, headers: &[(&str, &str)]) -> Response {
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+        send_chat_request(config, headers, body).await
+    }
+
+    async fn send_chat_request(
+        config: Config,
+        headers: &[(&str, &str)],
+        body: serde_json::Value,
+    ) -> Response {
+        send_chat_request_to(AppState::new(config).await, headers, body).await
+    }
+
+    async fn send_chat_request_to(
+        state: AppState,
+        headers: &[(&str, &str)],
+        body: serde_json::Value,
+    ) -> Response {
+        let app = create_router(state);
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = request.body(Body::from(body.to_string())).unwrap();
+
+        app.oneshot(request).await.unwrap()
+    }
+
+    fn header_millis(response: &Response, name: &str) -> Option<u64> {
+        response
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap().parse().expect("header should be numeric"))
+    }
+
+    #[tokio::test]
+    async fn test_timing_headers_present_and_numeric() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let total = header_millis(&response, REQUEST_DURATION_HEADER).expect("missing request duration");
+        let upstream = header_millis(&response, UPSTREAM_DURATION_HEADER).expect("missing upstream duration");
+        assert!(upstream <= total);
+    }
+
+    #[tokio::test]
+    async fn test_timing_headers_can_be_disabled() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.enable_timing_headers = false;
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(REQUEST_DURATION_HEADER).is_none());
+        assert!(response.headers().get(UPSTREAM_DURATION_HEADER).is_none());
+    }
+
+    fn refusal_config(server: &MockServer) -> Config {
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.refusal_fallback_message = Some("Sorry, I can't help with that.".to_string());
+        config
+    }
+
+    #[tokio::test]
+    async fn test_content_filter_finish_reason_uses_fallback() {
+        let mut blocked = completion_body();
+        blocked["choices"][0]["message"]["content"] = serde_json::Value::Null;
+        blocked["choices"][0]["finish_reason"] = "content_filter".into();
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(blocked)).await;
+
+        let response = send_chat(refusal_config(&server)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["choices"][0]["message"]["content"], "Sorry, I can't help with that.");
+        assert_eq!(json["choices"][0]["finish_reason"], "content_filter");
+        assert_eq!(json["id"], "chatcmpl-test");
+    }
+
+    #[tokio::test]
+    async fn test_safety_block_error_uses_fallback() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+            "error": {
+                "message": "The response was filtered due to the prompt triggering content management policy.",
+                "code": "content_filter"
+            }
+        })))
+        .await;
+
+        let response = send_chat(refusal_config(&server)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["object"], "chat.completion");
+        assert_eq!(json["model"], "gpt-4o");
+        assert_eq!(json["choices"][0]["message"]["content"], "Sorry, I can't help with that.");
+        assert_eq!(json["choices"][0]["finish_reason"], "content_filter");
+    }
+
+    #[tokio::test]
+    async fn test_content_filter_block_retried_against_alternate_backend() {
+        let mut blocked = completion_body();
+        blocked["choices"][0]["message"]["content"] = serde_json::Value::Null;
+        blocked["choices"][0]["finish_reason"] = "content_filter".into();
+        let strict = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(blocked)).await;
+        let permissive = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", strict.uri());
+        config.retry_on_content_filter_backend = Some(format!("{}/v1", permissive.uri()));
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[SERVED_BY_HEADER], "content-filter-retry");
+        let json = body_json(response).await;
+        assert_eq!(json["choices"][0]["message"]["content"], "Hello!");
+        assert_eq!(json["choices"][0]["finish_reason"], "stop");
+        assert_eq!(strict.received_requests().await.unwrap().len(), 1);
+        assert_eq!(permissive.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unfiltered_response_passes_through_with_fallback_configured() {
+        let server = mock_openai_backend().await;
+
+        let response = send_chat(refusal_config(&server)).await;
+
+        let json = body_json(response).await;
+        assert_eq!(json["choices"][0]["message"]["content"], "Hello!");
+        assert_eq!(json["choices"][0]["finish_reason"], "stop");
+    }
+
+    /// Writer collecting formatted log output for assertions
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    #[tokio::test]
+    async fn test_json_log_format_emits_structured_fields() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.log_format = "json".to_string();
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let _guard = tracing::subscriber::set_default(config.log_subscriber(move || writer.clone()));
+
+        let app = create_router(AppState::new(config).await);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json")
+            .header(REQUEST_ID_HEADER, "req-123")
+            .body(Body::from(
+                serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}).to_string(),
+            ))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("every log line should be JSON"))
+            .collect();
+        let event = lines
+            .iter()
+            .find(|line| line["message"] == "Chat completion finished")
+            .expect("completion event should be logged");
+
+        assert_eq!(event["request_id"], "req-123");
+        assert_eq!(event["model"], "gpt-4o");
+        assert_eq!(event["adapter"], "openai");
+        assert_eq!(event["status"], 200);
+        assert!(event["duration_ms"].is_u64());
+    }
+
+    #[cfg(feature = "cli")]
+    #[tokio::test]
+    async fn test_error_body_carries_request_id_of_error_event() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(500).set_body_string("backend exploded")).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.log_format = "json".to_string();
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let _guard = tracing::subscriber::set_default(config.log_subscriber(move || writer.clone()));
+
+        let response = send_chat(config).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let header = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        let body = body_json(response).await;
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let event: serde_json::Value = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .find(|line: &serde_json::Value| line["message"] == "Chat completion failed")
+            .expect("error event should be logged");
+
+        assert!(!header.is_empty());
+        assert_eq!(body["error"]["request_id"], header);
+        assert_eq!(event["request_id"], header);
+        assert_eq!(event["status"], 500);
+    }
+
+    #[tokio::test]
+    async fn test_html_backend_response_yields_descriptive_error() {
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<html><body>Gateway login required</body></html>", "text/html; charset=utf-8"),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let json = body_json(response).await;
+        let message = json["error"]["message"].as_str().unwrap();
+        assert!(message.contains("unexpected response content type 'text/html"), "{}", message);
+        assert!(message.contains("Gateway login required"), "{}", message);
+    }
+
+    fn retrying_config(server: &MockServer) -> Config {
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.api_key_validation_enabled = true;
+        config.upstream_max_retries = 3;
+        config.upstream_retry_backoff_ms = 1;
+        config.max_retries_ceiling = 1;
+        config
+    }
+
+    const TEST_API_KEY: &str = "Bearer sk-test-0123456789abcdefghij";
+
+    /// Backend failing the first two requests with `status`, then answering with `success`
+    async fn flaky_backend(status: u16, success: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(status))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(success)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_retried_until_success() {
+        let server = flaky_backend(503, ResponseTemplate::new(200).set_body_json(completion_body())).await;
+        let mut config = retrying_config(&server);
+        config.api_key_validation_enabled = false;
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        let sse_body = "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi\"},\"finish_reason\":\"stop\"}]}\n\ndata: [DONE]\n\n";
+        let server = flaky_backend(502, ResponseTemplate::new(200).set_body_raw(sse_body, "text/event-stream")).await;
+        let mut config = retrying_config(&server);
+        config.api_key_validation_enabled = false;
+        let body = serde_json::json!({"messages": [{"role": "user", "content": "Hi"}], "stream": true});
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&bytes).contains(r#""content":"Hi""#));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rank_by_logprob_orders_choices_best_first() {
+        use wiremock::matchers::body_partial_json;
+
+        let choice = |index: u32, content: &str, logprobs: &[f64]| {
+            let tokens: Vec<_> = logprobs
+                .iter()
+                .map(|logprob| serde_json::json!({"token": content, "logprob": logprob, "top_logprobs": []}))
+                .collect();
+            serde_json::json!({
+                "index": index,
+                "message": {"role": "assistant", "content": content},
+                "logprobs": {"content": tokens},
+                "finish_reason": "stop"
+            })
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"n": 3, "logprobs": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": "gpt-4o",
+                "choices": [
+                    choice(0, "fair", &[-1.0, -2.0]),
+                    choice(1, "best", &[-0.1, -0.3]),
+                    choice(2, "worst", &[-3.0, -4.0])
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.rank_choices = true;
+
+        let response = send_chat_request(
+            config,
+            &[("x-rank-by", "logprob")],
+            serde_json::json!({"messages": [{"role": "user", "content": "Hi"}], "n": 3}),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        let choices = body["choices"].as_array().unwrap();
+
+        let contents: Vec<_> = choices.iter().map(|choice| choice["message"]["content"].as_str().unwrap()).collect();
+        assert_eq!(contents, ["best", "fair", "worst"]);
+        let scores: Vec<_> = choices.iter().map(|choice| choice["x_mean_logprob"].as_f64().unwrap()).collect();
+        assert!((scores[0] - -0.2).abs() < 1e-9);
+        assert_eq!(&scores[1..], [-1.5, -3.5]);
+        // Logprobs were only requested for scoring
+        assert!(choices.iter().all(|choice| choice.get("logprobs").is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_request_deadline_cuts_slow_backend_short() {
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200)
+                .set_body_json(completion_body())
+                .set_delay(Duration::from_secs(5)),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.upstream_max_retries = 3;
+
+        let started = Instant::now();
+        let response = send_chat_request(
+            config,
+            &[("x-request-deadline", "300")],
+            serde_json::json!({"messages": [{"role": "user", "content": "Hi"}]}),
+        )
+        .await;
+        let elapsed = started.elapsed();
+
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_secs(2), "gave up after {:?}", elapsed);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_status_outside_retryable_codes_not_retried() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(503)).await;
+        let mut config = retrying_config(&server);
+        config.api_key_validation_enabled = false;
+        config.upstream_retryable_status_codes = "502,504".to_string();
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_retries_header_zero_disables_retries() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(503)).await;
+
+        let response = send_chat_with_headers(
+            retrying_config(&server),
+            &[("authorization", TEST_API_KEY), (MAX_RETRIES_HEADER, "0")],
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_retries_header_clamped_to_ceiling() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(503)).await;
+
+        let response = send_chat_with_headers(
+            retrying_config(&server),
+            &[("authorization", TEST_API_KEY), (MAX_RETRIES_HEADER, "10")],
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        // One attempt plus the single retry allowed by the ceiling
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_configured_retries_apply_without_header() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(503)).await;
+
+        let response = send_chat_with_headers(retrying_config(&server), &[("authorization", TEST_API_KEY)]).await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+
+    /// Backend whose first `truncated` responses declare a Content-Length but
+    /// close the connection halfway through the body; returns its address and
+    /// the number of requests received
+    async fn truncating_backend(truncated: usize) -> (std::net::SocketAddr, Arc<AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                // Read the request head and body before answering
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_ascii_lowercase();
+                    let Some(head_end) = text.find("\r\n\r\n") else {
+                        if n == 0 { break } else { continue }
+                    };
+                    let length = text[..head_end]
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if n == 0 || request.len() >= head_end + 4 + length {
+                        break;
+                    }
+                }
+
+                let attempt = counter.fetch_add(1, Ordering::SeqCst);
+                let body = completion_body().to_string();
+                let sent = if attempt < truncated { &body[..body.len() / 2] } else { body.as_str() };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    sent
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (addr, requests)
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_upstream_response_reported_and_retried() {
+        let (addr, requests) = truncating_backend(usize::MAX).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("http://{}/v1", addr);
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let json = body_json(response).await;
+        assert_eq!(json["error"]["code"], "upstream_incomplete_response");
+        let message = json["error"]["message"].as_str().unwrap();
+        assert!(message.starts_with("Incomplete upstream response: received"), "{}", message);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let (addr, requests) = truncating_backend(1).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("http://{}/v1", addr);
+        config.upstream_max_retries = 2;
+        config.upstream_retry_backoff_ms = 1;
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_n_forwarded_and_all_choices_returned_for_openai() {
+        let mut completion = completion_body();
+        completion["choices"] = (0..3)
+            .map(|index| {
+                serde_json::json!({
+                    "index": index,
+                    "message": {"role": "assistant", "content": format!("Answer {}", index)},
+                    "finish_reason": "stop"
+                })
+            })
+            .collect();
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "n": 3
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let choices = body_json(response).await["choices"].as_array().unwrap().clone();
+        assert_eq!(choices.len(), 3);
+        assert_eq!(choices[2]["message"]["content"], "Answer 2");
+        let forwarded: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(forwarded["n"], 3);
+    }
+
+    /// Backend answering 503 "model is loading" `loading_responses` times, then 200
+    async fn loading_backend(loading_responses: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(503).set_body_json(serde_json::json!({"error": "Model is currently loading"})),
+            )
+            .up_to_n_times(loading_responses)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_model_loading_retried_until_ready_within_budget() {
+        let server = loading_backend(2).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.model_loading_wait_secs = 5;
+        config.upstream_retry_backoff_ms = 10;
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_model_loading_without_budget_returns_distinct_error() {
+        let server = loading_backend(1).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body_json(response).await["error"]["code"], "model_loading");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    fn rate_limited() -> ResponseTemplate {
+        ResponseTemplate::new(429).set_body_json(serde_json::json!({
+            "error": {
+                "message": "Rate limit reached for gpt-4o",
+                "type": "requests",
+                "code": "rate_limit_exceeded"
+            }
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_upstream_rate_limit_reaches_client_as_429() {
+        let server = mock_openai_backend_with(rate_limited()).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let json = body_json(response).await;
+        assert_eq!(json["error"]["code"], "rate_limit_exceeded");
+        assert_eq!(json["error"]["message"], "Rate limit reached for gpt-4o");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_upstream_rate_limit_reaches_client_as_429() {
+        let server = mock_openai_backend_with(rate_limited()).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "stream": true
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body_json(response).await["error"]["code"], "rate_limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_request_records_time_to_first_token() {
+        let sse_body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(sse_body, "text/event-stream")
+                .set_delay(Duration::from_millis(20)),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let app = create_router(AppState::new(config).await);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "model": "gpt-4o",
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "stream": true
+                })
+                .to_string(),
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let streaming = &body_json(response).await["streaming"];
+        assert_eq!(streaming["completed_streams"], 1);
+        assert_eq!(streaming["active_streams"], 0);
+        assert!(streaming["mean_time_to_first_token_ms"].as_f64().unwrap() >= 20.0);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completions_recorded_in_monitoring_metrics() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({"error": {"message": "bad"}})))
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let state = AppState::new(config).await;
+        for _ in 0..3 {
+            let body = serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]});
+            send_chat_request_to(state.clone(), &[], body).await;
+        }
+
+        let metrics = state.monitoring().get_metrics().await;
+        assert_eq!(metrics.requests.total_requests, 3);
+        assert_eq!(metrics.requests.successful_requests, 2);
+        assert_eq!(metrics.requests.failed_requests, 1);
+        assert!(metrics.requests.total_bytes_transferred > 0);
+        assert_eq!(metrics.errors.total_errors, 1);
+        assert_eq!(metrics.errors.errors_by_type.get("upstream_status"), Some(&1));
+        assert_eq!(metrics.errors.errors_by_endpoint.get("/v1/chat/completions"), Some(&1));
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["requests"]["total_requests"], 3);
+        assert_eq!(json["errors"]["total_errors"], 1);
+    }
+
+    /// Parse Prometheus text exposition format, asserting every sample
+    /// belongs to a family declared with `# HELP` and `# TYPE`
+    fn parse_prometheus(text: &str) -> Vec<(String, std::collections::BTreeMap<String, String>, f64)> {
+        let mut families = std::collections::HashMap::new();
+        let mut samples = Vec::new();
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some("HELP"), Some(_), Some(_)) => {}
+                    (Some("TYPE"), Some(name), Some(kind)) => {
+                        assert!(["counter", "gauge", "histogram"].contains(&kind), "bad type: {line}");
+                        families.insert(name.to_string(), kind.to_string());
+                    }
+                    _ => panic!("bad comment: {line}"),
+                }
+                continue;
+            }
+
+            let (series, value) = line.rsplit_once(' ').unwrap_or_else(|| panic!("bad sample: {line}"));
+            let value: f64 = value.parse().unwrap_or_else(|_| panic!("bad value: {line}"));
+            let (name, labels) = match series.split_once('{') {
+                Some((name, labels)) => (name, labels.strip_suffix('}').unwrap_or_else(|| panic!("bad labels: {line}"))),
+                None => (series, ""),
+            };
+            assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'), "bad name: {line}");
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix).filter(|base| families.get(*base).is_some_and(|kind| kind == "histogram")))
+                .unwrap_or(name);
+            assert!(families.contains_key(family), "undeclared family: {line}");
+
+            let labels = labels
+                .split(',')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or_else(|| panic!("bad label: {line}"));
+                    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or_else(|| panic!("unquoted label: {line}"));
+                    (key.to_string(), value.to_string())
+                })
+                .collect();
+            samples.push((name.to_string(), labels, value));
+        }
+        samples
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_prometheus_text_format() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({"error": {"message": "bad"}})))
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let state = AppState::new(config).await;
+        for _ in 0..3 {
+            let body = serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]});
+            send_chat_request_to(state.clone(), &[], body).await;
+        }
+
+        let request = Request::builder()
+            .uri("/metrics")
+            .header("accept", "application/openmetrics-text;q=0.5,text/plain;version=0.0.4;q=0.3,*/*;q=0.1")
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(content_type(&response).starts_with("text/plain; version=0.0.4"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let samples = parse_prometheus(std::str::from_utf8(&body).unwrap());
+
+        let value = |name: &str, status: &str, le: Option<&str>| {
+            samples
+                .iter()
+                .find(|(sample, labels, _)| {
+                    sample == name
+                        && labels["backend"] == "openai"
+                        && labels["model"] == "gpt-4o"
+                        && labels["status"] == status
+                        && labels.get("le").map(String::as_str) == le
+                })
+                .map(|(_, _, value)| *value)
+        };
+        assert_eq!(value("nnllm_requests_total", "200", None), Some(2.0));
+        assert_eq!(value("nnllm_requests_total", "400", None), Some(1.0));
+        assert_eq!(value("nnllm_request_duration_seconds_count", "200", None), Some(2.0));
+        assert_eq!(value("nnllm_request_duration_seconds_bucket", "200", Some("+Inf")), Some(2.0));
+        assert!(samples.iter().any(|(name, labels, _)| name == "nnllm_backend_recent_error_rate" && labels["backend"] == "openai"));
+
+        // Plain clients keep getting JSON
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let app = create_router(AppState::new(Config::for_test()).await);
+        let response = app.oneshot(request).await.unwrap();
+        assert!(content_type(&response).starts_with("application/json"));
+    }
+
+    fn content_type(response: &Response) -> &str {
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_stream_force_off_returns_buffered_json() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.stream_force = "off".to_string();
+
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "stream": true
+        });
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(content_type(&response).starts_with("application/json"));
+        assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+
+        let upstream: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(upstream["stream"], false);
+    }
+
+    #[tokio::test]
+    async fn test_stream_cut_off_mid_tool_call_is_reported_truncated() {
+        // The upstream connection drops partway through the arguments, without [DONE]
+        let sse_body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",",
+            "\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,",
+            "\"function\":{\"arguments\":\"{\\\"location\\\": \\\"Par\"}}]}}]}\n\n",
+        );
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200).set_body_raw(sse_body, "text/event-stream"),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Weather in Paris?"}],
+            "stream": true
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains(r#""finish_reason":"tool_calls_truncated""#));
+        assert!(text.contains(r#""code":"tool_calls_truncated""#));
+        assert!(text.ends_with("data: [DONE]\n\n"));
+    }
+
+    async fn send_reasoning_chat(mode: &str, stream: bool) -> String {
+        let upstream = if stream {
+            let sse_body = concat!(
+                "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"reasoning_content\":\"2 plus 2\"}}]}\n\n",
+                "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"4\"},\"finish_reason\":\"stop\"}]}\n\n",
+                "data: [DONE]\n\n",
+            );
+            ResponseTemplate::new(200).set_body_raw(sse_body, "text/event-stream")
+        } else {
+            let mut body = completion_body();
+            body["choices"][0]["message"]["reasoning_content"] = serde_json::json!("2 plus 2");
+            ResponseTemplate::new(200).set_body_json(body)
+        };
+        let server = mock_openai_backend_with(upstream).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.reasoning_content = mode.to_string();
+        let body = serde_json::json!({
+            "model": "o3-mini",
+            "messages": [{"role": "user", "content": "What is 2+2?"}],
+            "stream": stream
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_content_forwarded_by_default() {
+        let completion: serde_json::Value = serde_json::from_str(&send_reasoning_chat("forward", false).await).unwrap();
+        assert_eq!(completion["choices"][0]["message"]["reasoning_content"], "2 plus 2");
+
+        let stream = send_reasoning_chat("forward", true).await;
+        assert!(stream.contains(r#""reasoning_content":"2 plus 2""#));
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_content_stripped_when_configured() {
+        let completion: serde_json::Value = serde_json::from_str(&send_reasoning_chat("strip", false).await).unwrap();
+        assert!(completion["choices"][0]["message"].get("reasoning_content").is_none());
+        assert_eq!(completion["choices"][0]["message"]["content"], "Hello!");
+
+        let stream = send_reasoning_chat("strip", true).await;
+        assert!(!stream.contains("reasoning_content"));
+        assert!(!stream.contains("2 plus 2"));
+        assert!(stream.contains(r#""content":"4""#));
+    }
+
+    #[tokio::test]
+    async fn test_stream_default_applies_when_client_omits_stream() {
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200).set_body_raw("data: [DONE]\n\n", "text/event-stream"),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.stream_default = true;
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(content_type(&response).starts_with("text/event-stream"));
+        let upstream: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(upstream["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_messages_stream_uses_anthropic_events() {
+        let sse_body = concat!(
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"claude-3-5-sonnet\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"claude-3-5-sonnet\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"claude-3-5-sonnet\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo!\"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"claude-3-5-sonnet\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"length\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"model\":\"claude-3-5-sonnet\",\"choices\":[],",
+            "\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2,\"total_tokens\":11}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200).set_body_raw(sse_body, "text/event-stream"),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/messages")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({
+                    "model": "claude-3-5-sonnet",
+                    "max_tokens": 2,
+                    "messages": [{"role": "user", "content": "Hi"}],
+                    "stream": true
+                })
+                .to_string(),
+            ))
+            .unwrap();
+
+        let response = create_router(AppState::new(config).await).oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        let events: Vec<(&str, serde_json::Value)> = text
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let name = event.lines().find_map(|line| line.strip_prefix("event: ")).unwrap();
+                let data = event.lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+                (name, serde_json::from_str(data).unwrap())
+            })
+            .collect();
+        let names: Vec<&str> = events.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert!(events.iter().all(|(name, data)| data["type"] == *name));
+        assert_eq!(events[0].1["message"]["id"], "chatcmpl-1");
+        assert_eq!(events[2].1["index"], 0);
+        assert_eq!(events[2].1["delta"], serde_json::json!({"type": "text_delta", "text": "Hel"}));
+        assert_eq!(events[5].1["delta"]["stop_reason"], "max_tokens");
+        assert_eq!(events[5].1["usage"], serde_json::json!({"input_tokens": 9, "output_tokens": 2}));
+    }
+
+    #[tokio::test]
+    async fn test_backend_default_headers_sent_upstream() {
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("anthropic-version", "2023-06-01"))
+            .and(header("x-deployment", "eu-west"))
+            .and(header("authorization", "Bearer backend-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.backend_token = Some("backend-token".to_string());
+        config.backend_default_headers = Some("anthropic-version=2023-06-01,x-deployment=eu-west".to_string());
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_byok_credential_overrides_backend_token() {
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+        for token in ["Bearer tenant-key", "Bearer backend-token"] {
+            Mock::given(method("POST"))
+                .and(path("/v1/chat/completions"))
+                .and(header("authorization", token))
+                .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.backend_token = Some("backend-token".to_string());
+        config.allow_byok = true;
+
+        let response = send_chat_with_headers(config.clone(), &[("x-upstream-authorization", "Bearer tenant-key")]).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = send_chat(config).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_byok_credential_rejected_by_backend_that_cannot_use_it() {
+        let mut config = Config::for_test();
+        config.backend_type = "direct".to_string();
+        config.allow_byok = true;
+
+        let response = send_chat_with_headers(config, &[("x-upstream-authorization", "Bearer tenant-key")]).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = body_json(response).await;
+        assert!(body["error"]["message"].as_str().unwrap().contains("client-supplied credential"));
+    }
+
+    #[cfg(feature = "caching")]
+    #[tokio::test]
+    async fn test_byok_responses_not_shared_between_credentials() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.allow_byok = true;
+        config.enable_caching = true;
+        let state = AppState::new(config).await;
+
+        let body = serde_json::json!({"messages": [{"role": "user", "content": "Hi"}]});
+        for key in ["Bearer tenant-a", "Bearer tenant-b"] {
+            let response = send_chat_request_to(state.clone(), &[("x-upstream-authorization", key)], body.clone()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            body_json(response).await;
+        }
+
+        let tokens: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| request.headers.get(&"authorization".into()).unwrap().as_str().to_string())
+            .collect();
+        assert_eq!(tokens, ["Bearer tenant-a", "Bearer tenant-b"]);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_size_selects_backend() {
+        let small = MockServer::start().await;
+        let large = MockServer::start().await;
+        for server in [&small, &large] {
+            Mock::given(method("POST"))
+                .and(path("/v1/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+        let mut config = Config::for_test();
+        config.backend_url = "http://127.0.0.1:9/v1".to_string();
+        config.size_routing = Some(format!("100={}/v1,100000={}/v1", small.uri(), large.uri()));
+
+        let tiny = serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]});
+        let response = send_chat_request(config.clone(), &[], tiny).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let long = serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "word ".repeat(2000)}]});
+        let response = send_chat_request(config, &[], long).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    async fn validate_payload(body: serde_json::Value) -> serde_json::Value {
+        let app = create_router(AppState::new(Config::for_test()).await);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/validate")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json(response).await
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_all_errors() {
+        let report = validate_payload(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"content": "Hi"}],
+            "temperature": 3.5
+        }))
+        .await;
+
+        assert_eq!(report["valid"], false);
+        let fields: Vec<&str> = report["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, ["messages[0].role", "temperature"]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_accepts_valid_request() {
+        let report = validate_payload(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}],
+            "temperature": 0.7
+        }))
+        .await;
+
+        assert_eq!(report["valid"], true);
+        assert_eq!(report["errors"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_rejects_invalid_request() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let body = serde_json::json!({"messages": [{"role": "user", "content": "Hi"}], "top_p": 1.5});
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_effort_forwarded_for_reasoning_models_only() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        for model in ["o3-mini", "gpt-4o"] {
+            let body = serde_json::json!({
+                "model": model,
+                "messages": [{"role": "user", "content": "Hi"}],
+                "reasoning_effort": "high"
+            });
+            let response = send_chat_request(config.clone(), &[], body).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let forwarded: Vec<serde_json::Value> = requests.iter().map(|request| request.body_json().unwrap()).collect();
+        assert_eq!(forwarded[0]["reasoning_effort"], "high");
+        assert!(forwarded[1].get("reasoning_effort").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_system_role_sent_as_developer_to_reasoning_models() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let messages = serde_json::json!([
+            {"role": "system", "content": "Be terse"},
+            {"role": "user", "content": "Hi"}
+        ]);
+
+        for (model, developer_role) in [("o1", true), ("gpt-4o", true), ("o1", false)] {
+            config.reasoning_developer_role = developer_role;
+            let body = serde_json::json!({"model": model, "messages": messages});
+            let response = send_chat_request(config.clone(), &[], body).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let roles: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|request| request.body_json::<serde_json::Value>().unwrap()["messages"][0]["role"].clone())
+            .collect();
+        assert_eq!(roles, ["developer", "system", "system"]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reasoning_effort_rejected() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let body = serde_json::json!({
+            "model": "o3-mini",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "reasoning_effort": "extreme"
+        });
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = body_json(response).await;
+        assert!(error["error"]["message"].as_str().unwrap().contains("reasoning_effort"));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_audio_request_rejected_for_unsupported_backend() {
+        let config = Config::for_test();
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}],
+            "modalities": ["text", "audio"],
+            "audio": {"voice": "alloy", "format": "wav"}
+        });
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = body_json(response).await;
+        assert!(error["error"]["message"].as_str().unwrap().contains("Audio output is not supported"));
+    }
+
+    #[tokio::test]
+    async fn test_response_strip_prefixes_applied_to_completion() {
+        let mut body = completion_body();
+        body["choices"][0]["message"]["content"] = serde_json::json!("Assistant: Hello! Assistant: is my name.");
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(body)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.response_strip_prefixes = Some("Assistant:".to_string());
+
+        let response = send_chat(config).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["choices"][0]["message"]["content"], "Hello! Assistant: is my name.");
+    }
+
+    #[tokio::test]
+    async fn test_conversation_follow_up_includes_prior_turns() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.conversation_store = "memory".to_string();
+        let state = AppState::new(config).await;
+
+        let first = serde_json::json!({
+            "conversation_id": "conv-1",
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+        let response = send_chat_request_to(state.clone(), &[], first).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json(response).await;
+
+        let follow_up = serde_json::json!({
+            "conversation_id": "conv-1",
+            "messages": [{"role": "user", "content": "Tell me more"}]
+        });
+        let response = send_chat_request_to(state, &[], follow_up).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let requests = server.received_requests().await.unwrap();
+        let upstream: serde_json::Value = requests[1].body_json().unwrap();
+        let turns: Vec<_> = upstream["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| (message["role"].as_str().unwrap(), message["content"].as_str().unwrap()))
+            .collect();
+        assert_eq!(turns, [("user", "Hi"), ("assistant", "Hello!"), ("user", "Tell me more")]);
+        assert!(upstream.get("conversation_id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_conversation_not_resumed_by_another_api_key() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.conversation_store = "memory".to_string();
+        let state = AppState::new(config).await;
+
+        let first = serde_json::json!({
+            "conversation_id": "conv-1",
+            "messages": [{"role": "user", "content": "My secret is 42"}]
+        });
+        let response = send_chat_request_to(state.clone(), &[("authorization", "Bearer sk-alice")], first).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        body_json(response).await;
+
+        let follow_up = serde_json::json!({
+            "conversation_id": "conv-1",
+            "messages": [{"role": "user", "content": "What is my secret?"}]
+        });
+        let response = send_chat_request_to(state, &[("authorization", "Bearer sk-mallory")], follow_up).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let requests = server.received_requests().await.unwrap();
+        let upstream: serde_json::Value = requests[1].body_json().unwrap();
+        let messages = upstream["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["content"], "What is my secret?");
+    }
+
+    #[tokio::test]
+    async fn test_conversation_system_prompt_ref_expanded_once() {
+        let server = mock_openai_backend().await;
+        let prompts_file = std::env::temp_dir().join(format!("system-prompts-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&prompts_file, r#"{"support-v1": "You are a patient support agent."}"#).unwrap();
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.conversation_store = "memory".to_string();
+        config.system_prompts_file = Some(prompts_file.display().to_string());
+        let state = AppState::new(config).await;
+        std::fs::remove_file(&prompts_file).unwrap();
+
+        for content in ["Hi", "Tell me more"] {
+            let request = serde_json::json!({
+                "conversation_id": "conv-1",
+                "system_prompt_ref": "support-v1",
+                "messages": [{"role": "user", "content": content}]
+            });
+            let response = send_chat_request_to(state.clone(), &[], request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            body_json(response).await;
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let upstream: serde_json::Value = requests[1].body_json().unwrap();
+        let roles: Vec<_> = upstream["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_over_budget_drops_oldest_turns() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.conversation_store = "memory".to_string();
+        config.max_conversation_tokens = 12;
+        let state = AppState::new(config).await;
+
+        let turns = [
+            serde_json::json!([
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "First question"}
+            ]),
+            serde_json::json!([{"role": "user", "content": "Second question"}]),
+            serde_json::json!([{"role": "user", "content": "Third question"}]),
+        ];
+        for messages in turns {
+            let request = serde_json::json!({"conversation_id": "conv-1", "messages": messages});
+            let response = send_chat_request_to(state.clone(), &[], request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            body_json(response).await;
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let upstream: serde_json::Value = requests[2].body_json().unwrap();
+        let turns: Vec<_> = upstream["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| (message["role"].as_str().unwrap(), message["content"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            turns,
+            [
+                ("system", "Be brief."),
+                ("user", "Second question"),
+                ("assistant", "Hello!"),
+                ("user", "Third question")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pool_exhaustion_reported_distinctly() {
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200)
+                .set_body_json(completion_body())
+                .set_delay(Duration::from_millis(300)),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.http_client_max_connections = 1;
+        config.pool_exhaustion_threshold_ms = 50;
+        config.pool_exhaustion_fail_fast = true;
+        let state = AppState::new(config).await;
+        let body = serde_json::json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]});
+
+        let first = tokio::spawn(send_chat_request_to(state.clone(), &[], body.clone()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let response = send_chat_request_to(state.clone(), &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json = body_json(response).await;
+        assert_eq!(json["error"]["code"], "upstream_pool_exhausted");
+        assert!(json["error"]["message"].as_str().unwrap().contains("pool exhausted"));
+        assert_eq!(state.upstream_pool().snapshot().pool_exhaustion, 1);
+        assert_eq!(first.await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_trailing_assistant_message_prefills_reply() {
+        let mut completion = completion_body();
+        completion["choices"][0]["message"]["content"] = serde_json::json!("{\"colors\": [\"red\"]}");
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.assistant_prefill = true;
+
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "user", "content": "One color as JSON"},
+                {"role": "assistant", "content": "{"}
+            ]
+        });
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["choices"][0]["message"]["content"], "{\"colors\": [\"red\"]}");
+        let sent: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        let last = &sent["messages"][1];
+        assert_eq!(last["role"], "system");
+        assert!(last["content"].as_str().unwrap().ends_with("\n{"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_system_message_rejected_when_required() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+
+        let response = send_chat(config.clone()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        config.require_system_message = true;
+        let response = send_chat(config).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = body_json(response).await;
+        assert!(error["error"]["message"].as_str().unwrap().contains("system message is required"));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_tool_message_rejected_when_tool_call_ids_validated() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.validate_tool_call_ids = true;
+        let body = |tool_call_id: &str| {
+            serde_json::json!({
+                "messages": [
+                    {"role": "user", "content": "Weather in Paris?"},
+                    {"role": "assistant", "content": null, "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{}"}
+                    }]},
+                    {"role": "tool", "tool_call_id": tool_call_id, "content": "18C"}
+                ]
+            })
+        };
+
+        let response = send_chat_request(config.clone(), &[], body("call_1")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = send_chat_request(config, &[], body("call_2")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = body_json(response).await;
+        let message = error["error"]["message"].as_str().unwrap();
+        assert!(message.contains("messages[2].tool_call_id: 'call_2' does not match a tool call"), "{}", message);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_parameter_removed_from_outgoing_payload() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.unsupported_parameters = Some("mistral-large=presence_penalty".to_string());
+        let body = serde_json::json!({
+            "model": "mistral-large",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "presence_penalty": 0.6,
+            "frequency_penalty": 0.4
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let sent: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert!(sent.get("presence_penalty").is_none(), "{}", sent);
+        assert!((sent["frequency_penalty"].as_f64().unwrap() - 0.4).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_json_mode_response_repaired() {
+        let mut body = completion_body();
+        body["choices"][0]["message"]["content"] = serde_json::json!("```json\n{\"answer\": 42,}\n```");
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(body)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.repair_json_output = true;
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "Answer in JSON"}],
+            "response_format": {"type": "json_object"}
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        let content = json["choices"][0]["message"]["content"].as_str().unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(content).unwrap();
+        assert_eq!(parsed, serde_json::json!({"answer": 42}));
+    }
+
+    #[tokio::test]
+    async fn test_response_not_matching_json_schema_is_upstream_error() {
+        let mut completion = completion_body();
+        completion["choices"][0]["message"]["content"] = serde_json::json!(r#"{"city": 42}"#);
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "Where?"}],
+            "response_format": {"type": "json_schema", "json_schema": {
+                "name": "place",
+                "schema": {"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]}
+            }}
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let json = body_json(response).await;
+        assert!(json["error"]["message"].as_str().unwrap().contains("$.city: expected string, got number"));
+        let upstream: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(upstream["response_format"]["json_schema"]["name"], "place");
+    }
+
+    #[tokio::test]
+    async fn test_suffix_forwarded_to_vllm_backend() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/vllm/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/vllm", server.uri());
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "fn add(a: i32, b: i32) -> i32 {"}],
+            "suffix": "}\n"
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let requests = server.received_requests().await.unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(payload["suffix"], "}\n");
+    }
+
+    #[tokio::test]
+    async fn test_suffix_rejected_for_unsupported_backend() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "Hi"}],
+            "suffix": "}"
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = body_json(response).await;
+        assert!(error["error"]["message"].as_str().unwrap().contains("suffix parameter"));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    async fn send_chat_to_substituting_backend(pin_model_version: &str) -> Response {
+        let mut completion = completion_body();
+        completion["model"] = "gpt-4o-mini-2024-07-18".into();
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.pin_model_version = pin_model_version.to_string();
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+
+        send_chat_request(config, &[], body).await
+    }
+
+    #[tokio::test]
+    async fn test_pinned_model_mismatch_rejected() {
+        let response = send_chat_to_substituting_backend("reject").await;
+
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let error = body_json(response).await;
+        assert!(error["error"]["message"].as_str().unwrap().contains("gpt-4o-mini-2024-07-18"));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_model_mismatch_warns_and_passes_through() {
+        let response = send_chat_to_substituting_backend("warn").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["model"], "gpt-4o-mini-2024-07-18");
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_ref_expanded_before_dispatch() {
+        let server = mock_openai_backend().await;
+        let prompts_file = std::env::temp_dir().join(format!("system-prompts-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&prompts_file, r#"{"support-v1": "You are a patient support agent."}"#).unwrap();
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.system_prompts_file = Some(prompts_file.display().to_string());
+        let body = serde_json::json!({
+            "system_prompt_ref": "support-v1",
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+
+        let response = send_chat_request(config, &[], body).await;
+        std::fs::remove_file(&prompts_file).unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let upstream: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        assert_eq!(upstream["messages"][0]["role"], "system");
+        assert_eq!(upstream["messages"][0]["content"], "You are a patient support agent.");
+        assert_eq!(upstream["messages"][1]["content"], "Hi");
+        assert!(upstream.get("system_prompt_ref").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_response_language_header_adds_instruction_to_expanded_system_prompt() {
+        let server = mock_openai_backend().await;
+        let prompts_file = std::env::temp_dir().join(format!("system-prompts-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&prompts_file, r#"{"support-v1": "You are a patient support agent."}"#).unwrap();
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.system_prompts_file = Some(prompts_file.display().to_string());
+        config.default_response_language = Some("de".to_string());
+        let body = serde_json::json!({
+            "system_prompt_ref": "support-v1",
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+
+        let headers = [(crate::server::response_language::RESPONSE_LANGUAGE_HEADER, "es")];
+        let response = send_chat_request(config, &headers, body).await;
+        std::fs::remove_file(&prompts_file).unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let upstream: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+        let messages = upstream["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[0]["content"], "You are a patient support agent.\n\nRespond in Spanish.");
+        assert_eq!(messages[1]["content"], "Hi");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_capture_pairs_every_request_with_its_response() {
+        let server = mock_openai_backend().await;
+        let capture_file = std::env::temp_dir().join(format!("prompt-capture-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.prompt_capture_sample_rate = 1.0;
+        config.prompt_capture_path = Some(capture_file.display().to_string());
+        let state = AppState::new(config).await;
+
+        for (request_id, prompt) in [("req-1", "First"), ("req-2", "Second")] {
+            let body = serde_json::json!({"messages": [{"role": "user", "content": prompt}]});
+            let response = send_chat_request_to(state.clone(), &[(REQUEST_ID_HEADER, request_id)], body).await;
+            assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+        }
+
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(&capture_file)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_file(&capture_file).unwrap();
+
+        assert_eq!(records.len(), 2);
+        for (record, (request_id, prompt)) in records.iter().zip([("req-1", "First"), ("req-2", "Second")]) {
+            assert_eq!(record["capture_id"], request_id);
+            assert_eq!(record["request"]["messages"][0]["content"], prompt);
+            assert_eq!(record["status"], 200);
+            assert_eq!(record["response"]["choices"][0]["message"]["content"], "Hello!");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_captured_even_when_sampled_out() {
+        use wiremock::matchers::body_partial_json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"messages": [{"content": "Fail"}]})))
+            .respond_with(ResponseTemplate::new(500).set_body_string("backend exploded"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .mount(&server)
+            .await;
+        let capture_file = std::env::temp_dir().join(format!("prompt-capture-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.prompt_capture_sample_rate = 0.0;
+        config.prompt_capture_path = Some(capture_file.display().to_string());
+        let state = AppState::new(config).await;
+
+        for (request_id, prompt) in [("req-ok", "Hi"), ("req-failed", "Fail")] {
+            let body = serde_json::json!({"messages": [{"role": "user", "content": prompt}]});
+            send_chat_request_to(state.clone(), &[(REQUEST_ID_HEADER, request_id)], body).await;
+        }
+
+        // Errors are written in the background
+        let mut contents = String::new();
+        for _ in 0..50 {
+            contents = std::fs::read_to_string(&capture_file).unwrap();
+            if !contents.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        std::fs::remove_file(&capture_file).unwrap();
+        let records: Vec<serde_json::Value> =
+            contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["capture_id"], "req-failed");
+        assert_eq!(records[0]["sampled"], false);
+        assert_eq!(records[0]["status"], 500);
+        assert_eq!(records[0]["request"]["messages"][0]["content"], "Fail");
+        assert_eq!(records[0]["response"]["kind"], "upstream_status");
+        assert!(records[0]["response"]["error"].as_str().unwrap().contains("backend exploded"));
+    }
+
+    #[tokio::test]
+    async fn test_shadowed_request_reaches_both_backends_and_records_shadow_metrics() {
+        let primary = mock_openai_backend().await;
+        let shadow = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-shadow",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "From shadow"},
+                "finish_reason": "stop"
+            }]
+        })))
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", primary.uri());
+        config.shadow_backend_url = Some(format!("{}/v1", shadow.uri()));
+        config.shadow_percentage = 100.0;
+        let state = AppState::new(config).await;
+
+        let response = send_chat_request_to(
+            state.clone(),
+            &[],
+            serde_json::json!({"messages": [{"role": "user", "content": "Hi"}]}),
+        )
+        .await;
+        assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+
+        // The shadow request finishes in the background
+        let mut stats = state.shadow().unwrap().stats().snapshot();
+        for _ in 0..100 {
+            if stats.requests > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            stats = state.shadow().unwrap().stats().snapshot();
+        }
+        assert_eq!(stats.requests, 1);
+        assert_eq!(stats.errors, 0);
+        assert_eq!(primary.received_requests().await.unwrap().len(), 1);
+        let shadowed = shadow.received_requests().await.unwrap();
+        assert_eq!(shadowed.len(), 1);
+        assert_eq!(shadowed[0].body_json::<serde_json::Value>().unwrap()["messages"][0]["content"], "Hi");
+
+        let request = Request::builder().uri("/shadow/stats").body(Body::empty()).unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(body_json(response).await["requests"], 1);
+    }
+
+    async fn get_json(config: Config, uri: &str) -> (StatusCode, serde_json::Value) {
+        let app = create_router(AppState::new(config).await);
+        let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+        (response.status(), body_json(response).await)
+    }
+
+    #[tokio::test]
+    async fn test_models_proxied_from_openai_compatible_backend() {
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+        let model = serde_json::json!({"id": "gpt-4o", "object": "model", "created": 1715367049, "owned_by": "system"});
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", "Bearer backend-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"object": "list", "data": [model]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models/gpt-4o"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(model.clone()))
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.backend_token = Some("backend-token".to_string());
+
+        let (status, body) = get_json(config.clone(), "/v1/models").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["object"], "list");
+        assert_eq!(body["data"][0], model);
+
+        let (status, body) = get_json(config.clone(), "/v1/models/gpt-4o").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, model);
+
+        // Unknown models are reported with the backend's status
+        let (status, _) = get_json(config, "/v1/models/unknown").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_models_synthesized_for_lightllm_backend() {
+        let mut config = Config::for_test();
+        config.backend_type = "lightllm".to_string();
+        config.model_id = "meta-llama/Llama-3-8B".to_string();
+
+        let (status, body) = get_json(config.clone(), "/v1/models").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "object": "list",
+                "data": [{"id": "meta-llama/Llama-3-8B", "object": "model", "created": 0, "owned_by": "lightllm"}]
+            })
+        );
+
+        let (status, body) = get_json(config.clone(), "/v1/models/meta-llama/Llama-3-8B").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "meta-llama/Llama-3-8B");
+
+        let (status, body) = get_json(config, "/v1/models/gpt-4o").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "model_not_found");
+    }
+
+    #[cfg(feature = "tools")]
+    #[tokio::test]
+    async fn test_tools_endpoint_lists_registered_functions() {
+        use crate::tools::FunctionDefinition;
+
+        let mut config = Config::for_test();
+        config.api_key_validation_enabled = true;
+        let state = AppState::new(config).await;
+        {
+            let mut registry = state.function_registry().write().unwrap();
+            registry.register(
+                FunctionDefinition::new("get_weather".to_string())
+                    .with_description("Current weather for a city".to_string())
+                    .with_parameters(serde_json::json!({
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}},
+                        "required": ["city"]
+                    })),
+            );
+            registry.register(
+                FunctionDefinition::new("convert_currency".to_string()).with_parameters(serde_json::json!({
+                    "type": "object",
+                    "properties": {"amount": {"type": "number"}, "to": {"type": "string"}}
+                })),
+            );
+        }
+        let tools = |api_key: Option<&str>| {
+            let mut request = Request::builder().uri("/v1/tools");
+            if let Some(api_key) = api_key {
+                request = request.header("authorization", api_key);
+            }
+            create_router(state.clone()).oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = tools(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = tools(Some(TEST_API_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(
+            body["data"],
+            serde_json::json!([
+                {
+                    "type": "function",
+                    "function": {
+                        "name": "convert_currency",
+                        "description": null,
+                        "parameters": {
+                            "type": "object",
+                            "properties": {"amount": {"type": "number"}, "to": {"type": "string"}}
+                        }
+                    }
+                },
+                {
+                    "type": "function",
+                    "function": {
+                        "name": "get_weather",
+                        "description": "Current weather for a city",
+                        "parameters": {
+                            "type": "object",
+                            "properties": {"city": {"type": "string"}},
+                            "required": ["city"]
+                        }
+                    }
+                }
+            ])
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_readiness_flips_after_burst_of_failed_requests() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(500)).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.readiness_error_rate_threshold = 0.5;
+        config.readiness_min_requests = 5;
+        let state = AppState::new(config).await;
+        let readiness = || async {
+            let request = Request::builder().uri("/health/ready").body(Body::empty()).unwrap();
+            create_router(state.clone()).oneshot(request).await.unwrap()
+        };
+        assert_eq!(readiness().await.status(), StatusCode::OK);
+
+        for _ in 0..5 {
+            let body = serde_json::json!({"messages": [{"role": "user", "content": "Hi"}]});
+            let response = send_chat_request_to(state.clone(), &[], body).await;
+            assert!(response.status().is_server_error());
+        }
+
+        let response = readiness().await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let readiness = body_json(response).await;
+        assert_eq!(readiness["status"], "not_ready");
+        assert_eq!(readiness["recent_requests"]["failures"], 5);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_health_reports_reachable_backend_and_reuses_recent_probe() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion_body())).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.health_probe_interval_secs = 60;
+        config.health_probe_min_interval_secs = 0;
+        let state = AppState::new(config).await;
+        let health = |uri: &'static str| {
+            let state = state.clone();
+            async move {
+                let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+                create_router(state).oneshot(request).await.unwrap()
+            }
+        };
+
+        let response = health("/health").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["status"], "healthy");
+        assert_eq!(body["components"]["server"]["status"], "healthy");
+        assert_eq!(body["components"]["backend"]["status"], "healthy");
+        assert_eq!(body["components"]["backend"]["cached"], false);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // A recent probe is reused unless a deep check is asked for
+        let body = body_json(health("/health").await).await;
+        assert_eq!(body["components"]["backend"]["cached"], true);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        let body = body_json(health("/health?deep=true").await).await;
+        assert_eq!(body["components"]["backend"]["cached"], false);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_deep_health_checks_limited_to_one_probe_per_interval() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion_body())).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.health_probe_min_interval_secs = 60;
+        let state = AppState::new(config).await;
+
+        let checks = (0..5).map(|_| get(create_router(state.clone()), "/health?deep=true"));
+        let responses = futures_util::future::join_all(checks).await;
+
+        assert!(responses.iter().all(|response| response.status() == StatusCode::OK));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_health_unavailable_when_backend_is_down() {
+        // Nothing listens on a port freed right after binding
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = Config::for_test();
+        config.backend_url = format!("http://127.0.0.1:{}/v1", port);
+        let app = create_router(AppState::new(config).await);
+
+        let request = Request::builder().uri("/health?deep=true").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = body_json(response).await;
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["components"]["server"]["status"], "healthy");
+        assert_eq!(body["components"]["backend"]["status"], "unhealthy");
+        assert!(body["components"]["backend"]["error"].as_str().is_some());
+    }
+
+    async fn get(app: axum::Router, uri: &str) -> Response {
+        app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_kubernetes_probes_with_reachable_backend() {
+        let server = mock_openai_backend_with(ResponseTemplate::new(200).set_body_json(completion_body())).await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.health_probe_interval_secs = 60;
+        let state = AppState::new(config).await;
+        assert!(!state.upstream_pool().is_warm());
+
+        let response = get(create_router(state.clone()), "/healthz").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["status"], "alive");
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        let response = get(create_router(state.clone()), "/readyz").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["checks"]["backend"]["status"], "healthy");
+        assert_eq!(body["checks"]["connection_pool"]["status"], "warm");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_kubernetes_probes_with_unreachable_backend() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = Config::for_test();
+        config.backend_url = format!("http://127.0.0.1:{}/v1", port);
+        let state = AppState::new(config.clone()).await;
+
+        // The process is alive even though it cannot serve yet
+        assert_eq!(get(create_router(state.clone()), "/healthz").await.status(), StatusCode::OK);
+        let response = get(create_router(state.clone()), "/readyz").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = body_json(response).await;
+        assert_eq!(body["status"], "not_ready");
+        assert_eq!(body["checks"]["backend"]["status"], "unhealthy");
+        assert_eq!(body["checks"]["connection_pool"]["status"], "cold");
+
+        config.enable_health_checks = false;
+        let app = create_router(AppState::new(config).await);
+        assert_eq!(get(app.clone(), "/healthz").await.status(), StatusCode::NOT_FOUND);
+        assert_eq!(get(app, "/readyz").await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "caching")]
+    #[tokio::test]
+    async fn test_warmed_prompt_served_from_cache() {
+        let server = mock_openai_backend().await;
+        let warm_file = std::env::temp_dir().join(format!("cache-warm-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &warm_file,
+            "{\"model\": \"gpt-4o\", \"messages\": [{\"role\": \"user\", \"content\": \"Hi\"}]}\n",
+        )
+        .unwrap();
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.enable_caching = true;
+        let state = AppState::new(config).await;
+
+        let warmed = crate::server::cache_warm::warm_cache(
+            state.cache().unwrap(),
+            state.adapter(),
+            warm_file.to_str().unwrap(),
+        )
+        .await
+        .unwrap();
+        std::fs::remove_file(&warm_file).unwrap();
+        assert_eq!(warmed, 1);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+        let response = send_chat_request_to(state, &[], body).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "caching")]
+    #[tokio::test]
+    async fn test_repeated_request_served_from_cache_and_differing_parameters_miss() {
+        let server = mock_openai_backend().await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.enable_caching = true;
+        let state = AppState::new(config).await;
+
+        let body = |temperature: f64| {
+            serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "Hi"}],
+                "temperature": temperature
+            })
+        };
+        for (temperature, upstream_calls) in [(0.2, 1), (0.2, 1), (0.7, 2)] {
+            let response = send_chat_request_to(state.clone(), &[], body(temperature)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(body_json(response).await["choices"][0]["message"]["content"], "Hello!");
+            assert_eq!(server.received_requests().await.unwrap().len(), upstream_calls);
+        }
+
+        let request = Request::builder().uri("/cache/stats").body(Body::empty()).unwrap();
+        let response = create_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stats = body_json(response).await;
+        assert_eq!(stats["hits"], 1);
+        assert_eq!(stats["misses"], 2);
+        assert_eq!(stats["evictions"], 0);
+        assert_eq!(stats["current_size"], 2);
+        assert!((stats["hit_rate"].as_f64().unwrap() - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_streams_share_one_upstream_call() {
+        let sse_body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let server = mock_openai_backend_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(sse_body, "text/event-stream")
+                .set_delay(Duration::from_millis(200)),
+        )
+        .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.stream_dedup_enabled = true;
+        let state = AppState::new(config).await;
+
+        let body = serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "stream": true
+        });
+        let stream = || async {
+            let response = send_chat_request_to(state.clone(), &[], body.clone()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+        let (first, second) = tokio::join!(stream(), stream());
+
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+        for content in [&first, &second] {
+            assert!(content.contains("Hel") && content.contains("lo"));
+            assert!(content.contains("[DONE]"));
+        }
+        assert_eq!(first, second);
+    }
+}
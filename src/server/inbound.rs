@@ -0,0 +1,146 @@
+//! # Inbound Protocol
+//!
+//! Serves accepted client connections over the protocol chosen by
+//! `inbound_protocol`: HTTP/1.1 only, HTTP/2 only (h2c with prior knowledge),
+//! or "auto", which reads the start of each connection and serves HTTP/2
+//! when the client opens with the HTTP/2 preface and HTTP/1.1 otherwise.
+//! Once shutdown is initiated, connections finish their in-flight requests
+//! and then close instead of taking new ones.
+
+use crate::{config::Config, graceful_shutdown::GracefulShutdown};
+use axum::{extract::ConnectInfo, Router};
+use hyper::server::conn::{http1, http2};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+};
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tower::Service;
+
+/// Error ending a served connection
+pub type ConnectionError = Box<dyn std::error::Error + Send + Sync>;
+
+/// HTTP version spoken with clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboundProtocol {
+    /// HTTP/1.1 only
+    Http1,
+    /// HTTP/2 with prior knowledge (h2c) only
+    Http2,
+    /// HTTP/1.1 or HTTP/2, detected per connection
+    Auto,
+}
+
+impl InboundProtocol {
+    /// Read the protocol from `inbound_protocol`
+    pub fn from_config(config: &Config) -> Self {
+        match config.inbound_protocol.as_str() {
+            "http1" => Self::Http1,
+            "http2" => Self::Http2,
+            _ => Self::Auto,
+        }
+    }
+
+    /// Human-readable description for startup logs
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Http1 => "HTTP/1.1",
+            Self::Http2 => "HTTP/2 with prior knowledge (h2c)",
+            Self::Auto => "HTTP/1.1 and HTTP/2 (detected per connection)",
+        }
+    }
+
+    /// Serve `app` on one client connection until it closes or, after
+    /// `shutdown` is initiated, its in-flight requests finish; requests carry
+    /// the client's address as `ConnectInfo<SocketAddr>`
+    pub async fn serve_connection<I>(
+        self,
+        io: I,
+        peer: SocketAddr,
+        app: Router,
+        shutdown: GracefulShutdown,
+    ) -> Result<(), ConnectionError>
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let io = TokioIo::new(io);
+        let service = hyper::service::service_fn(move |mut req: hyper::Request<hyper::body::Incoming>| {
+            req.extensions_mut().insert(ConnectInfo(peer));
+            let mut app = app.clone();
+            async move {
+                app.call(req).await.map_err(|e| {
+                    tracing::error!("Service error: {:?}", e);
+                    std::io::Error::other(format!("{:?}", e))
+                })
+            }
+        });
+
+        // Each connection type has its own inherent `graceful_shutdown`
+        macro_rules! drive {
+            ($conn:expr) => {{
+                let conn = $conn;
+                tokio::pin!(conn);
+                tokio::select! {
+                    result = conn.as_mut() => return result.map_err(Into::into),
+                    _ = shutdown.initiated() => conn.as_mut().graceful_shutdown(),
+                }
+                conn.await.map_err(Into::into)
+            }};
+        }
+
+        match self {
+            Self::Http1 => drive!(http1::Builder::new().serve_connection(io, service).with_upgrades()),
+            Self::Http2 => drive!(http2::Builder::new(TokioExecutor::new()).serve_connection(io, service)),
+            Self::Auto => {
+                // The auto connection borrows its builder
+                let builder = auto::Builder::new(TokioExecutor::new());
+                drive!(builder.serve_connection_with_upgrades(io, service))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{create_router, AppState};
+
+    /// Serve one connection with `protocol` and send a request over it with `client`
+    async fn request_over(protocol: InboundProtocol, client: reqwest::Client) -> reqwest::Response {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = create_router(AppState::new(Config::for_test()).await);
+        tokio::spawn(async move {
+            let (stream, peer) = listener.accept().await.unwrap();
+            let _ = protocol.serve_connection(stream, peer, app, GracefulShutdown::new()).await;
+        });
+
+        client
+            .post(format!("http://{}/v1/validate", addr))
+            .json(&serde_json::json!({"messages": [{"role": "user", "content": "Hi"}]}))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http1_client_served_in_http1_and_auto_modes() {
+        for protocol in [InboundProtocol::Http1, InboundProtocol::Auto] {
+            let client = reqwest::Client::builder().http1_only().build().unwrap();
+            let response = request_over(protocol, client).await;
+
+            assert_eq!(response.version(), reqwest::Version::HTTP_11, "{:?}", protocol);
+            assert_eq!(response.status(), reqwest::StatusCode::OK, "{:?}", protocol);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_mode_serves_http2_prior_knowledge() {
+        let client = reqwest::Client::builder().http2_prior_knowledge().build().unwrap();
+        let response = request_over(InboundProtocol::Auto, client).await;
+
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+}
@@ -0,0 +1,136 @@
+//! # Structured Output Repair
+//!
+//! Backends asked for JSON output sometimes return nearly-valid JSON: wrapped
+//! in markdown code fences, surrounded by prose, or with trailing commas.
+//! With `repair_json_output` enabled, the assistant content of responses to
+//! `json_object`/`json_schema` requests is repaired before it is returned.
+//! Content that is already valid, or that cannot be repaired into valid JSON,
+//! is left untouched.
+
+use serde_json::Value;
+
+/// Repair nearly-valid JSON.
+///
+/// Returns the repaired text, or `None` when `content` is already valid JSON
+/// or cannot be repaired.
+pub fn repair_json(content: &str) -> Option<String> {
+    if serde_json::from_str::<Value>(content).is_ok() {
+        return None;
+    }
+
+    let unfenced = strip_code_fences(content.trim());
+    let extracted = extract_balanced(unfenced).unwrap_or(unfenced);
+    let repaired = remove_trailing_commas(extracted);
+    serde_json::from_str::<Value>(&repaired).is_ok().then_some(repaired)
+}
+
+/// Repair the assistant content of every choice in a chat completion body
+pub fn repair_completion_json(body: &mut Value) {
+    let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for choice in choices {
+        if let Some(content) = choice.pointer_mut("/message/content") {
+            if let Some(repaired) = content.as_str().and_then(repair_json) {
+                tracing::debug!("Repaired malformed JSON in assistant content");
+                *content = Value::String(repaired);
+            }
+        }
+    }
+}
+
+/// Remove a surrounding markdown code fence (```` ``` ```` or ```` ```json ````)
+fn strip_code_fences(content: &str) -> &str {
+    let Some(rest) = content.strip_prefix("```") else {
+        return content;
+    };
+    // Drop the info string (e.g. "json") on the opening fence line
+    let body = rest.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+/// The first balanced JSON object or array in `content`
+fn extract_balanced(content: &str) -> Option<&str> {
+    let start = content.find(['{', '['])?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in content[start..].char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&content[start..start + offset + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Drop commas directly followed (ignoring whitespace) by a closing bracket
+fn remove_trailing_commas(content: &str) -> String {
+    let mut repaired = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in content.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if ch == '"' {
+            in_string = true;
+        } else if ch == ',' {
+            let next = content[offset + 1..].trim_start().chars().next();
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        repaired.push(ch);
+    }
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fenced_json_is_unwrapped() {
+        let content = "```json\n{\"name\": \"Ada\", \"tags\": [\"math\"]}\n```";
+
+        let repaired = repair_json(content).unwrap();
+
+        let value: Value = serde_json::from_str(&repaired).unwrap();
+        assert_eq!(value, serde_json::json!({"name": "Ada", "tags": ["math"]}));
+    }
+
+    #[test]
+    fn test_trailing_commas_are_removed_outside_strings() {
+        let content = "Here you go: {\"items\": [1, 2,], \"note\": \"a, }\",}";
+
+        let repaired = repair_json(content).unwrap();
+
+        let value: Value = serde_json::from_str(&repaired).unwrap();
+        assert_eq!(value, serde_json::json!({"items": [1, 2], "note": "a, }"}));
+        assert_eq!(repair_json("{\"valid\": true}"), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! # Structured Output Validation
+//!
+//! Checks the assistant content of responses to `json_schema` requests against
+//! the requested schema, so a backend that ignores or only loosely follows
+//! `response_format` cannot hand the client JSON of the wrong shape. Buffered
+//! responses that do not match are turned into upstream errors; streamed
+//! responses are not checked.
+//!
+//! A practical subset of JSON Schema is enforced: `type`, `enum`, `const`,
+//! `properties`, `required`, `additionalProperties`, `items` and `anyOf`.
+//! Other keywords are ignored.
+
+use serde_json::Value;
+
+/// Check `value` against `schema`, describing the first mismatch
+pub fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    validate_at("$", value, schema)
+}
+
+/// Check the assistant content of every choice in a chat completion body.
+///
+/// Choices without text content (e.g. tool calls) are skipped.
+pub fn validate_completion(body: &Value, schema: &Value) -> Result<(), String> {
+    let choices = body.get("choices").and_then(Value::as_array).into_iter().flatten();
+    for (index, choice) in choices.enumerate() {
+        let Some(content) = choice.pointer("/message/content").and_then(Value::as_str) else {
+            continue;
+        };
+        let value: Value = serde_json::from_str(content)
+            .map_err(|e| format!("choices[{}]: content is not valid JSON: {}", index, e))?;
+        validate(&value, schema).map_err(|e| format!("choices[{}]: {}", index, e))?;
+    }
+    Ok(())
+}
+
+fn validate_at(path: &str, value: &Value, schema: &Value) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        // `true`, `{}` and non-object schemas accept anything
+        return match schema {
+            Value::Bool(false) => Err(format!("{}: no value is allowed here", path)),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(single) => vec![single.as_str()],
+            Value::Array(many) => many.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|expected| has_type(value, expected)) {
+            return Err(format!("{}: expected {}, got {}", path, types.join(" or "), type_name(value)));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!("{}: {} is not one of {}", path, value, Value::Array(allowed.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            return Err(format!("{}: expected {}, got {}", path, expected, value));
+        }
+    }
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+        if !options.iter().any(|option| validate_at(path, value, option).is_ok()) {
+            return Err(format!("{}: does not match any of the allowed schemas", path));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for required in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(name) = required.as_str() {
+                if !object.contains_key(name) {
+                    return Err(format!("{}: missing required property '{}'", path, name));
+                }
+            }
+        }
+        for (name, property) in object {
+            let property_path = format!("{}.{}", path, name);
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property_schema) => validate_at(&property_path, property, property_schema)?,
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        return Err(format!("{}: unexpected property '{}'", path, name));
+                    }
+                    Some(additional) => validate_at(&property_path, property, additional)?,
+                    None => {}
+                },
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            validate_at(&format!("{}[{}]", path, index), item, item_schema)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `value` is of JSON Schema type `expected`
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        other => type_name(value) == other,
+    }
+}
+
+/// JSON Schema type name of `value`
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nested_mismatch_reports_path() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string"},
+                "days": {"type": "array", "items": {"type": "object", "properties": {"high": {"type": "integer"}}}}
+            },
+            "required": ["city"],
+            "additionalProperties": false
+        });
+
+        assert!(validate(&json!({"city": "Paris", "days": [{"high": 21}]}), &schema).is_ok());
+        assert_eq!(
+            validate(&json!({"city": "Paris", "days": [{"high": "warm"}]}), &schema).unwrap_err(),
+            "$.days[0].high: expected integer, got string"
+        );
+        assert_eq!(validate(&json!({"days": []}), &schema).unwrap_err(), "$: missing required property 'city'");
+        assert_eq!(
+            validate(&json!({"city": "Paris", "country": "FR"}), &schema).unwrap_err(),
+            "$: unexpected property 'country'"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! # Accept Loop
+//!
+//! Accepts client connections and serves each one on its own task until
+//! shutdown is initiated. The listener then closes so no new connections are
+//! taken, and open connections get up to `shutdown_drain_timeout_secs` to
+//! finish their in-flight requests. Connections still open after that are
+//! closed forcibly.
+
+use super::{ConnectionLimiter, InboundProtocol};
+use crate::{config::Config, graceful_shutdown::GracefulShutdown};
+use axum::Router;
+use std::time::Duration;
+use tokio::{net::TcpListener, task::JoinSet};
+
+/// Settings for the accept loop
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// HTTP version spoken with clients
+    pub protocol: InboundProtocol,
+    /// Limit on concurrently served connections
+    pub limiter: ConnectionLimiter,
+    /// How long open connections may keep running after shutdown
+    pub drain_timeout: Duration,
+}
+
+impl ServeOptions {
+    /// Read `inbound_protocol`, the connection limit and `shutdown_drain_timeout_secs`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            protocol: InboundProtocol::from_config(config),
+            limiter: ConnectionLimiter::from_config(config),
+            drain_timeout: Duration::from_secs(config.shutdown_drain_timeout_secs),
+        }
+    }
+}
+
+/// Serve `app` on `listener` until `shutdown` is initiated and open
+/// connections have drained or been closed
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    options: ServeOptions,
+    shutdown: GracefulShutdown,
+) -> std::io::Result<()> {
+    let ServeOptions { protocol, limiter, drain_timeout } = options;
+    let mut connections = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            _ = shutdown.initiated() => break,
+            // Reap finished connections so the set only holds open ones
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            accepted = listener.accept() => {
+                let (stream, peer) = accepted?;
+                let permit = tokio::select! {
+                    permit = limiter.acquire() => permit,
+                    _ = shutdown.initiated() => break,
+                };
+                let Some(permit) = permit else {
+                    tracing::warn!("Connection limit reached, dropping connection from {}", peer);
+                    continue;
+                };
+                let app = app.clone();
+                let shutdown = shutdown.clone();
+
+                connections.spawn(async move {
+                    // Hold the connection slot until the connection closes
+                    let _permit = permit;
+                    if let Err(err) = protocol.serve_connection(stream, peer, app, shutdown).await {
+                        tracing::error!("Connection error: {:?}", err);
+                    }
+                });
+            }
+        }
+    }
+
+    drop(listener);
+    tracing::info!(
+        "Stopped accepting connections, draining {} open connection(s) for up to {:?}",
+        connections.len(),
+        drain_timeout
+    );
+    let drained = tokio::time::timeout(drain_timeout, async {
+        while connections.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "{} connection(s) still active after the {:?} drain timeout, closing them",
+            connections.len(),
+            drain_timeout
+        );
+        connections.shutdown().await;
+    }
+    shutdown.complete_shutdown();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{create_router, AppState};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    /// Backend answering chat completions after `delay`
+    async fn slow_backend(delay: Duration) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "id": "chatcmpl-1",
+                        "object": "chat.completion",
+                        "created": 0,
+                        "model": "test-model",
+                        "choices": [{
+                            "index": 0,
+                            "message": {"role": "assistant", "content": "Done"},
+                            "finish_reason": "stop"
+                        }]
+                    }))
+                    .set_delay(delay),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    /// Start the server in front of `backend`, returning its address and task
+    async fn start(
+        backend: &MockServer,
+        drain_timeout: Duration,
+        shutdown: GracefulShutdown,
+    ) -> (std::net::SocketAddr, tokio::task::JoinHandle<std::io::Result<()>>) {
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", backend.uri());
+        let options = ServeOptions { drain_timeout, ..ServeOptions::from_config(&config) };
+        let app = create_router(AppState::new(config).await);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        (addr, tokio::spawn(serve(listener, app, options, shutdown)))
+    }
+
+    fn chat(addr: std::net::SocketAddr) -> impl std::future::Future<Output = reqwest::Result<reqwest::Response>> {
+        reqwest::Client::new()
+            .post(format!("http://{}/v1/chat/completions", addr))
+            .json(&serde_json::json!({"messages": [{"role": "user", "content": "Hi"}]}))
+            .send()
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_mid_request_lets_request_finish() {
+        let backend = slow_backend(Duration::from_millis(500)).await;
+        let shutdown = GracefulShutdown::new();
+        let (addr, server) = start(&backend, Duration::from_secs(10), shutdown.clone()).await;
+
+        let in_flight = tokio::spawn(chat(addr));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        shutdown.initiate_shutdown();
+
+        let response = in_flight.await.unwrap().unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], "Done");
+
+        tokio::time::timeout(Duration::from_secs(2), server).await.unwrap().unwrap().unwrap();
+        assert!(shutdown.is_shutdown_complete());
+        // The listener is closed once the server has stopped
+        assert!(chat(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connections_closed_after_drain_timeout() {
+        let backend = slow_backend(Duration::from_secs(30)).await;
+        let shutdown = GracefulShutdown::new();
+        let (addr, server) = start(&backend, Duration::from_millis(100), shutdown.clone()).await;
+
+        let in_flight = tokio::spawn(chat(addr));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        shutdown.initiate_shutdown();
+
+        tokio::time::timeout(Duration::from_secs(2), server).await.unwrap().unwrap().unwrap();
+        assert!(in_flight.await.unwrap().is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! # Load Shedding
+//!
+//! Under heavy load a faster, smaller model beats a timeout on a large one.
+//! `load_shedding` maps expensive models to cheaper fallbacks; while the
+//! expensive model's concurrency queue reaches `load_shedding_queue_depth`, or
+//! its recent p95 latency exceeds `load_shedding_p95_ms`, its requests are
+//! served by the fallback instead. Degraded responses carry the
+//! `x-degraded-from` header naming the model that was requested.
+//!
+//! The decision is made per request, so traffic returns to the primary model
+//! as soon as the queue drains or slow samples age out of the latency window.
+
+use super::model_concurrency::ModelConcurrencyLimiter;
+use crate::config::Config;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Response header naming the model a degraded request originally asked for
+pub const DEGRADED_FROM_HEADER: &str = "x-degraded-from";
+
+/// How long latency samples count towards the p95
+const LATENCY_WINDOW: Duration = Duration::from_secs(60);
+
+/// Fewest samples needed before the latency trigger can fire
+const MIN_LATENCY_SAMPLES: usize = 20;
+
+/// Downgrades requests for expensive models while they are overloaded
+#[derive(Debug, Default)]
+pub struct LoadShedder {
+    /// Fallback model for each expensive model
+    fallbacks: HashMap<String, String>,
+    /// Queue depth that triggers a downgrade
+    queue_depth: usize,
+    /// p95 latency that triggers a downgrade, if enabled
+    p95_threshold: Option<Duration>,
+    /// Recent latencies of requests served by each expensive model
+    latencies: Mutex<HashMap<String, VecDeque<(Instant, Duration)>>>,
+}
+
+impl LoadShedder {
+    /// Build the shedder from the `load_shedding*` settings
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            fallbacks: config.load_shedding_fallbacks(),
+            queue_depth: config.load_shedding_queue_depth.max(1),
+            p95_threshold: (config.load_shedding_p95_ms > 0)
+                .then(|| Duration::from_millis(config.load_shedding_p95_ms)),
+            latencies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fallback model to serve instead of `model`, if `model` is overloaded
+    pub fn route(&self, model: &str, limiter: &ModelConcurrencyLimiter) -> Option<&str> {
+        let fallback = self.fallbacks.get(model)?;
+
+        let queued = limiter.queued(model);
+        if queued >= self.queue_depth {
+            tracing::warn!("Model {} has {} queued requests, degrading to {}", model, queued, fallback);
+            return Some(fallback);
+        }
+
+        let threshold = self.p95_threshold?;
+        let p95 = self.p95_latency(model)?;
+        if p95 > threshold {
+            tracing::warn!("Model {} p95 latency is {:?}, degrading to {}", model, p95, fallback);
+            return Some(fallback);
+        }
+        None
+    }
+
+    /// Record how long a request served by `model` took
+    pub fn record_latency(&self, model: &str, latency: Duration) {
+        if self.p95_threshold.is_none() || !self.fallbacks.contains_key(model) {
+            return;
+        }
+        let mut latencies = self.latencies.lock().unwrap();
+        latencies
+            .entry(model.to_string())
+            .or_default()
+            .push_back((Instant::now(), latency));
+    }
+
+    /// p95 latency of `model` over the latency window, once enough samples exist
+    fn p95_latency(&self, model: &str) -> Option<Duration> {
+        let mut latencies = self.latencies.lock().unwrap();
+        let samples = latencies.get_mut(model)?;
+        while samples
+            .front()
+            .is_some_and(|(recorded, _)| recorded.elapsed() > LATENCY_WINDOW)
+        {
+            samples.pop_front();
+        }
+        if samples.len() < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+
+        let mut sorted: Vec<Duration> = samples.iter().map(|(_, latency)| *latency).collect();
+        sorted.sort_unstable();
+        let index = (sorted.len() * 95).div_ceil(100) - 1;
+        Some(sorted[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_degrade_under_queue_depth_and_recover() {
+        let mut config = Config::for_test();
+        config.load_shedding = Some("llama-70b=llama-8b".to_string());
+        config.load_shedding_queue_depth = 2;
+        let shedder = LoadShedder::from_config(&config);
+        let limiter = ModelConcurrencyLimiter::new(HashMap::from([("llama-70b".to_string(), 1)]));
+
+        let busy = limiter.acquire("llama-70b", "").await.unwrap();
+        let waiting: Vec<_> = (0..2)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire("llama-70b", "").await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(limiter.queued("llama-70b"), 2);
+        assert_eq!(shedder.route("llama-70b", &limiter), Some("llama-8b"));
+        assert_eq!(shedder.route("llama-8b", &limiter), None);
+
+        drop(busy);
+        for waiter in waiting {
+            drop(waiter.await.unwrap());
+        }
+        assert_eq!(limiter.queued("llama-70b"), 0);
+        assert_eq!(shedder.route("llama-70b", &limiter), None);
+    }
+}
@@ -7,22 +7,59 @@
 pub mod routes;
 pub mod handlers;
 pub mod state;
+pub mod refusal;
+pub mod chaos;
+pub mod connection_limit;
+pub mod transform;
+pub mod stream_fanout;
+pub mod conversations;
+pub mod deadline;
+pub mod fair_queue;
+pub mod inbound;
+pub mod json_repair;
+pub mod json_schema;
+pub mod listener;
+pub mod load_shedding;
+pub mod model_concurrency;
+pub mod model_pin;
+pub mod parameter_support;
+pub mod prefill;
+pub mod prompt_capture;
+pub mod ranking;
+#[cfg(feature = "rate-limiting")]
+pub mod rate_limit;
+pub mod reasoning;
+pub mod replay_protection;
+pub mod response_language;
+pub mod shadow;
+pub mod size_routing;
+pub mod system_prompts;
+pub mod upstream_pool;
+#[cfg(feature = "caching")]
+pub mod cache_warm;
 
 // Re-export commonly used server types
 pub use handlers::{chat_completions, ui_proxy, login_proxy};
 pub use state::AppState;
+pub use connection_limit::ConnectionLimiter;
+pub use inbound::InboundProtocol;
+pub use listener::{serve, ServeOptions};
 
 use axum::{
     routing::{any, get, post},
     Router,
-    extract::{Request, State},
+    body::Body,
+    extract::{DefaultBodyLimit, Request, State},
     middleware::{self, Next},
-    response::Response as AxumResponse,
-    http::{StatusCode, HeaderMap},
+    response::{IntoResponse, Response as AxumResponse},
+    http::{StatusCode, HeaderMap, HeaderName, Method},
+    Json,
 };
+use crate::config::Config;
+use futures_util::StreamExt;
 use tower::ServiceBuilder;
 use tower_http::{
-    cors::CorsLayer,
+    cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer, ExposeHeaders},
     trace::{self, TraceLayer},
     compression::CompressionLayer,
 };
@@ -42,14 +79,7 @@ async fn api_key_validation(
 
     // Skip validation for health check and UI routes
     let path = request.uri().path();
-    if path.starts_with("/health") ||
-       path.starts_with("/ui") ||
-       path.starts_with("/v1/ui") ||
-       path.starts_with("/sso") ||
-       path.starts_with("/login") ||
-       path.starts_with("/litellm") ||
-       path.starts_with("/.well-known") ||
-       path == "/favicon.ico" {
+    if is_probe_route(path) || is_ui_proxy_route(path) {
         return Ok(next.run(request).await);
     }
 
@@ -89,6 +119,78 @@ async fn api_key_validation(
     Ok(next.run(request).await)
 }
 
+/// Whether `path` is a health or orchestrator probe (`/health*`, `/healthz`,
+/// `/readyz`), which authentication, rate limiting, replay protection and
+/// chaos faults leave alone so probes see the real status
+fn is_probe_route(path: &str) -> bool {
+    path.starts_with("/health") || path == "/readyz"
+}
+
+/// Whether `path` is forwarded to the backend UI (pages, SSO, login and assets)
+fn is_ui_proxy_route(path: &str) -> bool {
+    path.starts_with("/ui") ||
+    path.starts_with("/v1/ui") ||
+    path.starts_with("/sso") ||
+    path.starts_with("/login") ||
+    path.starts_with("/litellm") ||
+    path.starts_with("/.well-known") ||
+    path == "/favicon.ico"
+}
+
+/// Request body size limit middleware.
+///
+/// Bodies of API requests larger than `max_request_body_bytes` are rejected
+/// with 413 before a handler sees them, whether or not the client declared a
+/// Content-Length. UI proxy routes forward uploads unlimited.
+async fn request_body_limit(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> AxumResponse {
+    let limit = state.config.max_request_body_bytes;
+    if limit == 0 || is_ui_proxy_route(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let declared = request
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limit) {
+        return payload_too_large(limit);
+    }
+
+    let (parts, body) = request.into_parts();
+    let mut chunks = body.into_data_stream();
+    let mut buffered = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        match chunk {
+            Ok(chunk) if buffered.len() + chunk.len() > limit => return payload_too_large(limit),
+            Ok(chunk) => buffered.extend_from_slice(&chunk),
+            Err(error) => {
+                return crate::error::ProxyError::BadRequest(format!("Failed to read request body: {}", error))
+                    .into_response();
+            }
+        }
+    }
+
+    next.run(Request::from_parts(parts, Body::from(buffered))).await
+}
+
+/// 413 response in the proxy's error envelope
+fn payload_too_large(limit: usize) -> AxumResponse {
+    tracing::warn!("Rejected request body larger than {} bytes", limit);
+    let body = Json(serde_json::json!({
+        "error": {
+            "message": format!("Request body exceeds the maximum size of {} bytes", limit),
+            "type": "proxy_error",
+            "code": "request_too_large"
+        }
+    }));
+    (StatusCode::PAYLOAD_TOO_LARGE, body).into_response()
+}
+
 /// Check if the provided API key is valid
 async fn is_valid_api_key(state: &AppState, api_key: &str) -> bool {
     // In a production system, this would check against a database or key store
@@ -126,17 +228,123 @@ async fn is_valid_api_key(state: &AppState, api_key: &str) -> bool {
     false
 }
 
+/// Check whether an `Origin` header value matches a configured CORS origin.
+///
+/// `*` matches any origin. A pattern such as `https://*.example.com` matches
+/// `https://example.com` and any subdomain of it over the same scheme.
+pub fn origin_matches(pattern: &str, origin: &str) -> bool {
+    if pattern == "*" || pattern.eq_ignore_ascii_case(origin) {
+        return true;
+    }
+
+    let Some((scheme, domain)) = pattern.split_once("://*.") else {
+        return false;
+    };
+    let Some(host) = origin
+        .strip_prefix(scheme)
+        .and_then(|rest| rest.strip_prefix("://"))
+    else {
+        return false;
+    };
+
+    let host = host.to_ascii_lowercase();
+    let domain = domain.to_ascii_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// Build the CORS layer from the configured origins, methods and headers
+fn cors_layer(config: &Config) -> CorsLayer {
+    let origins: Vec<String> = config.cors_origins().into_iter().map(str::to_string).collect();
+    let allow_origin = if origins.is_empty() || origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::predicate(move |origin, _| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| origins.iter().any(|pattern| origin_matches(pattern, origin)))
+        })
+    };
+
+    let methods: Vec<Method> = config
+        .cors_methods
+        .split(',')
+        .filter_map(|method| method.trim().parse().ok())
+        .collect();
+    let allow_methods = if methods.is_empty() || config.cors_methods.trim() == "*" {
+        AllowMethods::any()
+    } else {
+        AllowMethods::list(methods)
+    };
+
+    let headers: Vec<HeaderName> = config
+        .cors_headers
+        .split(',')
+        .filter_map(|header| header.trim().parse().ok())
+        .collect();
+    let allow_headers = if headers.is_empty() || config.cors_headers.trim() == "*" {
+        AllowHeaders::any()
+    } else {
+        AllowHeaders::list(headers)
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .expose_headers(ExposeHeaders::any())
+}
+
 /// Create router with all routes and middleware
 pub fn create_router(state: AppState) -> Router {
-    Router::new()
+    let mut router = Router::new();
+
+    // Metrics endpoint, mounted at the configured path
+    let metrics_endpoint = &state.config.metrics_endpoint;
+    if state.config.enable_metrics && !metrics_endpoint.is_empty() {
+        router = router.route(metrics_endpoint, get(handlers::metrics));
+    }
+
+    // Response cache statistics
+    #[cfg(feature = "caching")]
+    if state.cache().is_some() {
+        router = router.route("/cache/stats", get(handlers::cache_stats));
+    }
+
+    // Server-side function registry
+    #[cfg(feature = "tools")]
+    if !state.config.tools_endpoint.is_empty() {
+        router = router.route(&state.config.tools_endpoint, get(handlers::list_tools));
+    }
+
+    // Kubernetes liveness and readiness probes
+    if state.config.enable_health_checks {
+        router = router
+            .route("/healthz", get(handlers::liveness_check))
+            .route("/readyz", get(handlers::backend_readiness_check));
+    }
+
+    // Shadow backend statistics
+    if state.shadow().is_some() {
+        router = router.route("/shadow/stats", get(handlers::shadow_stats));
+    }
+
+    let router = router
         // Main API endpoint for chat completions
         .route("/v1/chat/completions", post(chat_completions))
+
+        // Request payload validation without calling the backend
+        .route("/v1/validate", post(handlers::validate_request))
         
+        // Model discovery for OpenAI clients; model ids may contain '/'
+        .route("/v1/models", get(handlers::list_models))
+        .route("/v1/models/{*id}", get(handlers::retrieve_model))
+
         // Anthropic API compatibility endpoint
         .route("/v1/messages", post(handlers::anthropic_messages))
 
         // Health check endpoints for production monitoring
         .route("/health", get(handlers::health_check))
+        .route("/health/ready", get(handlers::readiness_check))
 
         // UI proxy routes - these forward requests to the backend LightLLM server
         .route("/v1/ui", any(ui_proxy))
@@ -154,9 +362,28 @@ pub fn create_router(state: AppState) -> Router {
         .route("/litellm/{*path}", any(ui_proxy))
         .route("/favicon.ico", any(ui_proxy))
 
+        // Enforce max_request_body_bytes in place of axum's fixed default limit
+        .layer(middleware::from_fn_with_state(state.clone(), request_body_limit))
+        .layer(DefaultBodyLimit::disable());
+
+    // Nonce and timestamp checks, after authentication so unauthenticated
+    // requests cannot use up nonces
+    let router = router.layer(middleware::from_fn_with_state(
+        state.clone(),
+        replay_protection::replay_protection_middleware,
+    ));
+
+    // Per-key rate limits, checked once the API key has been validated
+    #[cfg(feature = "rate-limiting")]
+    let router = router.layer(middleware::from_fn_with_state(state.clone(), rate_limit::rate_limit_middleware));
+
+    router
         // Add API key validation middleware (applied first, before other middleware)
         .layer(middleware::from_fn_with_state(state.clone(), api_key_validation))
 
+        // Add chaos testing middleware (no-op unless enabled outside production)
+        .layer(middleware::from_fn_with_state(state.clone(), chaos::chaos_middleware))
+
         // Add middleware stack
         .layer(
             ServiceBuilder::new()
@@ -168,9 +395,114 @@ pub fn create_router(state: AppState) -> Router {
                     .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
                     .on_response(trace::DefaultOnResponse::new().level(Level::INFO)))
 
-                // CORS middleware - allows cross-origin requests
-                .layer(CorsLayer::permissive()),
+                // CORS middleware - allows cross-origin requests from configured origins
+                .layer(cors_layer(&state.config)),
         )
         // Inject application state into all handlers
         .with_state(state)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::header};
+    use tower::ServiceExt;
+
+    const PATTERN: &str = "https://*.example.com";
+
+    #[test]
+    fn test_wildcard_subdomain_origin_matching() {
+        assert!(origin_matches(PATTERN, "https://app.example.com"));
+        assert!(origin_matches(PATTERN, "https://a.b.example.com"));
+        assert!(origin_matches(PATTERN, "https://example.com"));
+        assert!(!origin_matches(PATTERN, "https://evil.com"));
+        assert!(!origin_matches(PATTERN, "https://evilexample.com"));
+        assert!(!origin_matches(PATTERN, "http://app.example.com"));
+    }
+
+    async fn health_with_origin(origin: &str) -> AxumResponse {
+        let mut config = Config::for_test();
+        config.cors_origin = PATTERN.to_string();
+        let app = create_router(AppState::new(config).await);
+
+        let request = Request::builder()
+            .uri("/health")
+            .header(header::ORIGIN, origin)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cors_echoes_matching_origins() {
+        for origin in ["https://app.example.com", "https://example.com"] {
+            let response = health_with_origin(origin).await;
+            assert_eq!(
+                response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+                origin
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cors_rejects_unlisted_origin() {
+        let response = health_with_origin("https://evil.com").await;
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    /// Chat completion request body of exactly `size` bytes
+    fn chat_body_of_size(size: usize) -> String {
+        let envelope = r#"{"model":"gpt-4o","messages":[{"role":"user","content":""}]}"#;
+        envelope.replace(r#""content":"""#, &format!(r#""content":"{}""#, "a".repeat(size - envelope.len())))
+    }
+
+    async fn post_chat(app: Router, body: Body) -> AxumResponse {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_body_limit() {
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": "gpt-4o",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}]
+            })))
+            .mount(&server)
+            .await;
+        let mut config = Config::for_test();
+        config.backend_url = format!("{}/v1", server.uri());
+        config.max_request_body_bytes = 4096;
+        let app = create_router(AppState::new(config).await);
+
+        let response = post_chat(app.clone(), Body::from(chat_body_of_size(4096))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = post_chat(app.clone(), Body::from(chat_body_of_size(4097))).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "request_too_large");
+
+        // Without a Content-Length the body is counted as it arrives
+        let chunks = chat_body_of_size(4097)
+            .into_bytes()
+            .chunks(1024)
+            .map(|chunk| Ok::<_, std::io::Error>(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        let response = post_chat(app, Body::from_stream(futures_util::stream::iter(chunks))).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+}
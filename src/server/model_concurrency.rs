@@ -0,0 +1,232 @@
+//! # Per-Model Concurrency Limiting
+//!
+//! Caps concurrent backend requests per model via `model_concurrency_limits`,
+//! so a heavy model with a low ceiling queues excess requests while requests
+//! for other models flow freely. Models without a configured limit are never
+//! held back. Queue depth per model is reported on the metrics endpoint.
+//!
+//! With `fair_queuing` enabled, requests queued for a model are let through
+//! by API key via a [`FairQueue`] rather than in arrival order.
+
+use super::fair_queue::FairQueue;
+use crate::config::Config;
+use axum::{body::Body, response::Response};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Concurrency slots for a single model
+#[derive(Debug)]
+struct ModelSlots {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+    /// Requests currently waiting for a slot
+    queued: AtomicUsize,
+    /// Orders waiting requests by API key (when fair queuing is enabled)
+    fair: Option<FairQueue>,
+}
+
+/// Point-in-time view of one model's concurrency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModelConcurrencySnapshot {
+    /// Configured maximum of concurrent requests
+    pub limit: usize,
+    /// Requests currently holding a slot
+    pub in_flight: usize,
+    /// Requests waiting for a slot
+    pub queued: usize,
+}
+
+/// Semaphore-backed concurrency limits keyed by model name
+#[derive(Debug, Clone, Default)]
+pub struct ModelConcurrencyLimiter {
+    models: Arc<HashMap<String, ModelSlots>>,
+}
+
+/// Slot held while a request for a limited model is being served
+#[derive(Debug)]
+pub struct ModelPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Decrements a model's queue depth when a waiting request stops waiting
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ModelConcurrencyLimiter {
+    /// Create a limiter from model-to-limit pairs
+    pub fn new(limits: HashMap<String, usize>) -> Self {
+        Self::build(limits, None)
+    }
+
+    /// Create a limiter that lets queued requests through fairly across API
+    /// keys, weighted by `weights`
+    pub fn with_fair_queuing(limits: HashMap<String, usize>, weights: HashMap<String, u32>) -> Self {
+        Self::build(limits, Some(weights))
+    }
+
+    fn build(limits: HashMap<String, usize>, weights: Option<HashMap<String, u32>>) -> Self {
+        let models = limits
+            .into_iter()
+            .filter(|(_, limit)| *limit > 0)
+            .map(|(model, limit)| {
+                let slots = ModelSlots {
+                    limit,
+                    semaphore: Arc::new(Semaphore::new(limit)),
+                    queued: AtomicUsize::new(0),
+                    fair: weights.clone().map(FairQueue::new),
+                };
+                (model, slots)
+            })
+            .collect();
+        Self { models: Arc::new(models) }
+    }
+
+    /// Build the limiter from `model_concurrency_limits`, `fair_queuing` and
+    /// `fair_queue_weights`
+    pub fn from_config(config: &Config) -> Self {
+        let weights = config.fair_queuing.then(|| config.fair_queue_weights());
+        Self::build(config.concurrency_limits(), weights)
+    }
+
+    /// Wait for a slot for `model` on behalf of the API key `client_key`.
+    ///
+    /// Returns `None` when the model has no configured limit.
+    pub async fn acquire(&self, model: &str, client_key: &str) -> Option<ModelPermit> {
+        let slots = self.models.get(model)?;
+
+        if let Ok(permit) = slots.semaphore.clone().try_acquire_owned() {
+            return Some(ModelPermit { _permit: permit });
+        }
+
+        slots.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = QueuedGuard(&slots.queued);
+        tracing::debug!("Model {} at its concurrency limit of {}, queueing request", model, slots.limit);
+        let _turn = match &slots.fair {
+            Some(fair) => Some(fair.turn(client_key).await),
+            None => None,
+        };
+        let permit = slots.semaphore.clone().acquire_owned().await.ok()?;
+        Some(ModelPermit { _permit: permit })
+    }
+
+    /// Requests currently waiting for a slot for `model`
+    pub fn queued(&self, model: &str) -> usize {
+        self.models
+            .get(model)
+            .map_or(0, |slots| slots.queued.load(Ordering::Relaxed))
+    }
+
+    /// Per-model limit, in-flight and queue depth, sorted by model name
+    pub fn snapshot(&self) -> BTreeMap<String, ModelConcurrencySnapshot> {
+        self.models
+            .iter()
+            .map(|(model, slots)| {
+                let snapshot = ModelConcurrencySnapshot {
+                    limit: slots.limit,
+                    in_flight: slots.limit - slots.semaphore.available_permits(),
+                    queued: slots.queued.load(Ordering::Relaxed),
+                };
+                (model.clone(), snapshot)
+            })
+            .collect()
+    }
+}
+
+/// Keep `permit` until the response body has been fully sent, so streamed
+/// responses hold their slot for the whole stream
+pub fn hold_permit<P: Send + 'static>(response: Response, permit: P) -> Response {
+    let (parts, body) = response.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _ = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limiter() -> ModelConcurrencyLimiter {
+        ModelConcurrencyLimiter::new(HashMap::from([("llama-70b".to_string(), 1)]))
+    }
+
+    #[tokio::test]
+    async fn test_limited_model_queues_while_other_models_proceed() {
+        let limiter = limiter();
+        let first = limiter.acquire("llama-70b", "").await.unwrap();
+
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire("llama-70b", "").await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished(), "second request for the model must wait");
+        assert_eq!(limiter.snapshot()["llama-70b"].queued, 1);
+
+        let other = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("llama-8b", "")).await;
+        assert!(other.is_ok(), "requests for other models must not wait");
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_millis(50), waiting).await.unwrap().unwrap();
+        assert!(second.is_some());
+        assert_eq!(
+            limiter.snapshot()["llama-70b"],
+            ModelConcurrencySnapshot { limit: 1, in_flight: 1, queued: 0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fair_queuing_lets_both_keys_progress_under_saturation() {
+        let limiter = ModelConcurrencyLimiter::with_fair_queuing(
+            HashMap::from([("llama-70b".to_string(), 1)]),
+            HashMap::new(),
+        );
+        let busy = limiter.acquire("llama-70b", "sk-flood").await.unwrap();
+
+        let (served, mut order) = tokio::sync::mpsc::unbounded_channel();
+        for key in ["sk-flood", "sk-flood", "sk-flood", "sk-flood", "sk-quiet", "sk-quiet"] {
+            let limiter = limiter.clone();
+            let served = served.clone();
+            tokio::spawn(async move {
+                let _permit = limiter.acquire("llama-70b", key).await;
+                served.send(key).unwrap();
+            });
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        drop(served);
+        assert_eq!(limiter.snapshot()["llama-70b"].queued, 6);
+
+        drop(busy);
+        let mut served = Vec::new();
+        while let Some(key) = order.recv().await {
+            served.push(key);
+        }
+        assert_eq!(served, ["sk-flood", "sk-quiet", "sk-flood", "sk-quiet", "sk-flood", "sk-flood"]);
+    }
+
+    #[tokio::test]
+    async fn test_permit_held_until_body_is_consumed() {
+        let limiter = limiter();
+        let permit = limiter.acquire("llama-70b", "").await.unwrap();
+        let response = hold_permit(Response::new(Body::from("data: [DONE]\n\n")), permit);
+        assert_eq!(limiter.snapshot()["llama-70b"].in_flight, 1);
+
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(limiter.snapshot()["llama-70b"].in_flight, 0);
+    }
+}
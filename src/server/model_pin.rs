@@ -0,0 +1,125 @@
+//! # Model Version Pinning
+//!
+//! Detects backends that silently serve a different model than the one
+//! requested by comparing the `model` field of a completion against the
+//! requested model. Depending on `pin_model_version` a mismatch is ignored,
+//! logged, or turned into an error; `pin_model_tolerance` decides whether a
+//! dated snapshot of the requested model (e.g. `gpt-4-0613` for `gpt-4`)
+//! still counts as a match.
+
+use crate::{config::Config, error::ProxyError};
+use axum::{body::Body, response::Response};
+
+/// What to do when the served model differs from the requested one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinMode {
+    /// Do not inspect the served model
+    Off,
+    /// Log a warning and return the response
+    Warn,
+    /// Fail the request
+    Reject,
+}
+
+/// How closely the served model must match the requested model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinTolerance {
+    /// The model names must be identical
+    Exact,
+    /// A dated snapshot of the requested model also matches
+    Snapshot,
+}
+
+/// Model pinning policy built from `pin_model_version` and `pin_model_tolerance`
+#[derive(Debug, Clone, Copy)]
+pub struct ModelPin {
+    pub mode: PinMode,
+    pub tolerance: PinTolerance,
+}
+
+impl ModelPin {
+    /// Build the policy from configuration
+    pub fn from_config(config: &Config) -> Self {
+        let mode = match config.pin_model_version.as_str() {
+            "warn" => PinMode::Warn,
+            "reject" => PinMode::Reject,
+            _ => PinMode::Off,
+        };
+        let tolerance = match config.pin_model_tolerance.as_str() {
+            "exact" => PinTolerance::Exact,
+            _ => PinTolerance::Snapshot,
+        };
+        Self { mode, tolerance }
+    }
+
+    /// Check whether `served` is acceptable for a request for `requested`
+    pub fn matches(&self, requested: &str, served: &str) -> bool {
+        if served == requested {
+            return true;
+        }
+        match self.tolerance {
+            PinTolerance::Exact => false,
+            PinTolerance::Snapshot => served
+                .strip_prefix(requested)
+                .and_then(|rest| rest.strip_prefix('-'))
+                .is_some_and(|snapshot| {
+                    !snapshot.is_empty() && snapshot.chars().all(|c| c.is_ascii_digit() || c == '-')
+                }),
+        }
+    }
+
+    /// Compare the model reported in a completion response with the requested model
+    pub async fn check(&self, response: Response, requested: &str) -> Result<Response, ProxyError> {
+        if self.mode == PinMode::Off || !response.status().is_success() {
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let body_bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| ProxyError::Internal(format!("Failed to read response body: {}", e)))?;
+
+        let served = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+            .ok()
+            .and_then(|json| json.get("model").and_then(|model| model.as_str()).map(str::to_string));
+
+        if let Some(served) = served.filter(|served| !self.matches(requested, served)) {
+            match self.mode {
+                PinMode::Reject => {
+                    return Err(ProxyError::Upstream(format!(
+                        "Backend served model '{}' but '{}' was requested",
+                        served, requested
+                    )));
+                }
+                _ => tracing::warn!("Backend served model '{}' but '{}' was requested", served, requested),
+            }
+        }
+
+        Ok(Response::from_parts(parts, Body::from(body_bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_tolerance_accepts_dated_snapshots_only() {
+        let pin = ModelPin {
+            mode: PinMode::Reject,
+            tolerance: PinTolerance::Snapshot,
+        };
+
+        assert!(pin.matches("gpt-4", "gpt-4"));
+        assert!(pin.matches("gpt-4", "gpt-4-0613"));
+        assert!(pin.matches("gpt-4o", "gpt-4o-2024-08-06"));
+        assert!(!pin.matches("gpt-4", "gpt-4-turbo"));
+        assert!(!pin.matches("gpt-4", "gpt-4o"));
+
+        let exact = ModelPin {
+            tolerance: PinTolerance::Exact,
+            ..pin
+        };
+        assert!(!exact.matches("gpt-4", "gpt-4-0613"));
+    }
+}
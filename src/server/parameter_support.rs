@@ -0,0 +1,67 @@
+//! # Parameter Support
+//!
+//! Some backends reject optional sampling parameters they do not implement
+//! (e.g. `presence_penalty` on certain Mistral or Bedrock models) and fail
+//! the whole request. `unsupported_parameters` declares which parameters each
+//! model, or every model of the backend via '*', does not support; those are
+//! removed from the request before dispatch instead.
+
+use crate::{config::Config, schemas::ChatCompletionRequest};
+use std::collections::HashMap;
+
+/// Key in `unsupported_parameters` applying to every model
+const ALL_MODELS: &str = "*";
+
+/// Unsupported parameters per model
+#[derive(Debug, Clone, Default)]
+pub struct ParameterSupport {
+    unsupported: HashMap<String, Vec<String>>,
+}
+
+impl ParameterSupport {
+    /// Read `unsupported_parameters`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            unsupported: config.unsupported_parameter_map(),
+        }
+    }
+
+    /// Remove the parameters `model` does not support from `req`
+    pub fn strip(&self, req: &mut ChatCompletionRequest, model: &str) {
+        for key in [ALL_MODELS, model] {
+            for parameter in self.unsupported.get(key).into_iter().flatten() {
+                if req.clear_parameter(parameter) {
+                    tracing::debug!(model = %model, parameter = %parameter, "Removed parameter unsupported by the backend");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_model_and_backend_wide_parameters() {
+        let mut config = Config::for_test();
+        config.unsupported_parameters = Some("mistral-large=presence_penalty|frequency_penalty,*=seed".to_string());
+        let support = ParameterSupport::from_config(&config);
+        let request = ChatCompletionRequest {
+            presence_penalty: Some(0.5),
+            frequency_penalty: Some(0.2),
+            seed: Some(7),
+            temperature: Some(0.3),
+            ..Default::default()
+        };
+
+        let mut req = request.clone();
+        support.strip(&mut req, "mistral-large");
+        assert_eq!((req.presence_penalty, req.frequency_penalty, req.seed), (None, None, None));
+        assert_eq!(req.temperature, Some(0.3));
+
+        let mut req = request;
+        support.strip(&mut req, "gpt-4o");
+        assert_eq!((req.presence_penalty, req.frequency_penalty, req.seed), (Some(0.5), Some(0.2), None));
+    }
+}
@@ -0,0 +1,85 @@
+//! # Assistant Prefill
+//!
+//! A conversation ending in an assistant message asks the model to continue
+//! that message; prefilling `{`, for instance, forces a JSON object. With
+//! `assistant_prefill`, backends that continue a trailing assistant turn
+//! natively (Anthropic-format prompts) receive it unchanged, while others get
+//! an instruction to begin their reply with the prefill instead. Either way
+//! the non-streaming response carries the full reply, prefill included.
+
+use crate::schemas::{ChatCompletionRequest, Message};
+use serde_json::Value;
+
+/// Text a reply must start with, taken from a trailing assistant message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prefill {
+    text: String,
+    /// Whether the backend continues the prefill itself, returning only the rest
+    native: bool,
+}
+
+impl Prefill {
+    /// Take the prefill from a request ending in an assistant message.
+    ///
+    /// Unless the backend supports prefill `native`ly, the assistant message
+    /// is replaced with an instruction to begin the reply with its content.
+    pub fn extract(req: &mut ChatCompletionRequest, native: bool) -> Option<Self> {
+        let last = req.messages.last()?;
+        if last.role != "assistant" || last.tool_calls.as_ref().is_some_and(|calls| !calls.is_empty()) {
+            return None;
+        }
+        let text = last.content.clone().filter(|text| !text.is_empty())?;
+
+        if !native {
+            req.messages.pop();
+            req.messages.push(Message::system(format!(
+                "Begin your reply with exactly the following text and continue from there:\n{}",
+                text
+            )));
+        }
+        Some(Self { text, native })
+    }
+
+    /// Prepend the prefill to the assistant content of every choice.
+    ///
+    /// Emulated prefills are only prepended where the model did not already
+    /// start its reply with them.
+    pub fn complete(&self, body: &mut Value) {
+        let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) else {
+            return;
+        };
+
+        for choice in choices {
+            if let Some(content) = choice.pointer_mut("/message/content") {
+                let text = content.as_str().unwrap_or_default();
+                if self.native || !text.starts_with(&self.text) {
+                    *content = Value::String(format!("{}{}", self.text, text));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prefilled_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            messages: vec![Message::user("Two colors as JSON".to_string()), Message::assistant(Some("{".to_string()))],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_native_prefill_kept_and_prepended() {
+        let mut req = prefilled_request();
+        let prefill = Prefill::extract(&mut req, true).unwrap();
+        assert_eq!(req.messages.len(), 2);
+
+        let mut body = json!({"choices": [{"message": {"role": "assistant", "content": "\"colors\": []}"}}]});
+        prefill.complete(&mut body);
+        assert_eq!(body["choices"][0]["message"]["content"], "{\"colors\": []}");
+    }
+}
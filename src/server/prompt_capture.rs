@@ -0,0 +1,247 @@
+//! # Prompt Capture
+//!
+//! Writes the complete request and response of a sampled fraction of chat
+//! completions to a JSONL capture file for model-quality debugging. Each
+//! record is keyed by the request id so a request and its response stay
+//! correlated. Configured JSON fields are redacted before writing.
+//!
+//! Failed requests are captured even when sampled out: the request is kept
+//! until its outcome is known, so the record can be written after the fact.
+//!
+//! Response bodies are captured as they stream to the client, so capturing
+//! adds no latency; the record is written once the body has been fully sent.
+
+use crate::{config::Config, core::redaction::ToolArgumentRedaction, error::ProxyError};
+use axum::{body::Body, response::Response};
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{fs::File, io::AsyncWriteExt};
+
+pub use crate::core::redaction::REDACTED;
+
+/// One captured request and its response
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureRecord {
+    /// Request id shared by the request and its response
+    pub capture_id: String,
+    /// Capture time in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// The chat completion request as received
+    pub request: Value,
+    /// HTTP status returned to the client
+    pub status: u16,
+    /// Whether the request was sampled, rather than captured because it failed
+    pub sampled: bool,
+    /// Response body: JSON when parseable, otherwise the raw text (e.g. SSE)
+    pub response: Value,
+}
+
+/// # Prompt Capture
+///
+/// Samples requests and appends their capture records to the capture file.
+#[derive(Debug)]
+pub struct PromptCapture {
+    /// Fraction of requests captured (0.0 to 1.0)
+    sample_rate: f64,
+    /// Capture failed requests that were sampled out
+    capture_errors: bool,
+    /// JSON fields whose values are redacted anywhere in a record
+    redact_fields: Vec<String>,
+    /// Redaction applied to tool-call arguments in requests and responses
+    tool_arguments: ToolArgumentRedaction,
+    /// Capture file, opened for appending
+    sink: tokio::sync::Mutex<File>,
+}
+
+impl PromptCapture {
+    /// Open the capture file described by the `prompt_capture_*` settings.
+    ///
+    /// Returns `None` when capture is disabled or the file cannot be opened.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let path = config.prompt_capture_path.as_deref()?;
+        if config.prompt_capture_sample_rate <= 0.0 && !config.prompt_capture_errors {
+            return None;
+        }
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| tracing::warn!("Prompt capture disabled, cannot open '{}': {}", path, e))
+            .ok()?;
+
+        Some(Self {
+            sample_rate: config.prompt_capture_sample_rate.clamp(0.0, 1.0),
+            capture_errors: config.prompt_capture_errors,
+            redact_fields: config.capture_redact_fields(),
+            tool_arguments: ToolArgumentRedaction::from_config(config),
+            sink: tokio::sync::Mutex::new(File::from_std(file)),
+        })
+    }
+
+    /// Decide whether the current request is captured
+    pub fn sample(&self) -> bool {
+        self.sample_rate >= 1.0 || fastrand::f64() < self.sample_rate
+    }
+
+    /// Whether failed requests are captured when sampled out, so their
+    /// request must be kept until the outcome is known
+    pub fn captures_errors(&self) -> bool {
+        self.capture_errors
+    }
+
+    /// Whether a request that was sampled out is captured anyway: when it
+    /// failed and `prompt_capture_errors` is on
+    pub fn captures_outcome(&self, result: &Result<Response, ProxyError>) -> bool {
+        self.capture_errors
+            && match result {
+                Ok(response) => response.status().is_client_error() || response.status().is_server_error(),
+                Err(_) => true,
+            }
+    }
+
+    /// Replace the values of the configured fields and redact tool-call
+    /// arguments, at any depth
+    pub fn redact(&self, value: &mut Value) {
+        self.tool_arguments.redact_tool_calls(value);
+        self.redact_fields(value);
+    }
+
+    /// Replace the values of the configured fields, at any depth
+    fn redact_fields(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.redact_fields.iter().any(|field| field == key) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_fields(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_fields(item)),
+            _ => {}
+        }
+    }
+
+    /// Capture `request` together with the outcome of serving it.
+    ///
+    /// Successful responses are passed through with a body that records the
+    /// capture once it has been fully sent; errors are captured immediately.
+    pub fn capture(
+        self: &Arc<Self>,
+        capture_id: String,
+        request: Value,
+        sampled: bool,
+        result: Result<Response, ProxyError>,
+    ) -> Result<Response, ProxyError> {
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                let record = self.record(
+                    capture_id,
+                    request,
+                    error.status_code().as_u16(),
+                    sampled,
+                    serde_json::json!({ "error": error.to_string(), "kind": error.kind() }),
+                );
+                let capture = self.clone();
+                tokio::spawn(async move { capture.write(record).await });
+                return Err(error);
+            }
+        };
+
+        let (parts, body) = response.into_parts();
+        let status = parts.status.as_u16();
+        let captured = Arc::new(Mutex::new(Vec::new()));
+
+        let data = body.into_data_stream().inspect({
+            let captured = captured.clone();
+            move |chunk| {
+                if let Ok(chunk) = chunk {
+                    captured.lock().unwrap().extend_from_slice(chunk);
+                }
+            }
+        });
+        let capture = self.clone();
+        let finish = stream::once(async move {
+            let body = std::mem::take(&mut *captured.lock().unwrap());
+            let response = serde_json::from_slice(&body)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()));
+            let record = capture.record(capture_id, request, status, sampled, response);
+            capture.write(record).await;
+        })
+        .filter_map(|()| async { None });
+
+        Ok(Response::from_parts(parts, Body::from_stream(data.chain(finish))))
+    }
+
+    /// Build a redacted capture record
+    fn record(&self, capture_id: String, mut request: Value, status: u16, sampled: bool, mut response: Value) -> CaptureRecord {
+        self.redact(&mut request);
+        self.redact(&mut response);
+        CaptureRecord {
+            capture_id,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            request,
+            status,
+            sampled,
+            response,
+        }
+    }
+
+    /// Append a record to the capture file as one JSON line
+    async fn write(&self, record: CaptureRecord) {
+        let mut line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(error) => {
+                tracing::warn!("Failed to serialize prompt capture {}: {}", record.capture_id, error);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut sink = self.sink.lock().await;
+        if let Err(error) = sink.write_all(&line).await.and(sink.flush().await) {
+            tracing::warn!("Failed to write prompt capture {}: {}", record.capture_id, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_replaces_configured_fields_at_any_depth() {
+        let mut config = Config::for_test();
+        config.prompt_capture_sample_rate = 1.0;
+        config.prompt_capture_path = Some(
+            std::env::temp_dir()
+                .join(format!("prompt-capture-{}.jsonl", uuid::Uuid::new_v4()))
+                .display()
+                .to_string(),
+        );
+        config.prompt_capture_redact_fields = Some("user, api_key".to_string());
+        let capture = PromptCapture::from_config(&config).unwrap();
+
+        let mut value = serde_json::json!({
+            "user": "alice@example.com",
+            "messages": [{"role": "user", "content": "Hi", "metadata": {"api_key": "sk-123"}}]
+        });
+        capture.redact(&mut value);
+
+        assert_eq!(value["user"], REDACTED);
+        assert_eq!(value["messages"][0]["role"], "user");
+        assert_eq!(value["messages"][0]["metadata"]["api_key"], REDACTED);
+        let _ = std::fs::remove_file(config.prompt_capture_path.unwrap());
+    }
+}
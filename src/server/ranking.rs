@@ -0,0 +1,113 @@
+//! # Candidate Ranking
+//!
+//! For best-of-N selection and evals, clients asking for `n > 1` choices can
+//! send `x-rank-by: logprob` (with `rank_choices` enabled) to get the choices
+//! sorted best-first by their mean token logprob. Logprobs are requested from
+//! the backend for scoring and removed again unless the client asked for
+//! them. Each choice carries its score in `x_mean_logprob`; when the backend
+//! returns no logprobs for some choice, the input order is kept.
+
+use crate::{config::Config, error::ProxyError, schemas::ChatCompletionRequest};
+use axum::http::HeaderMap;
+use serde_json::Value;
+
+/// Header selecting how the choices of a request are ranked
+pub const RANK_BY_HEADER: &str = "x-rank-by";
+/// `x-rank-by` value ranking by mean token logprob
+pub const RANK_BY_LOGPROB: &str = "logprob";
+/// Choice field carrying the ranking score
+pub const SCORE_FIELD: &str = "x_mean_logprob";
+
+/// Ranking of the choices of one request by mean token logprob
+#[derive(Debug, Clone, Copy)]
+pub struct LogprobRanking {
+    /// Whether the logprobs were only requested for scoring
+    strip_logprobs: bool,
+}
+
+impl LogprobRanking {
+    /// The ranking `req` asks for, if enabled, requesting logprobs from the
+    /// backend when the client did not
+    pub fn from_request(
+        config: &Config,
+        headers: &HeaderMap,
+        req: &mut ChatCompletionRequest,
+    ) -> Result<Option<Self>, ProxyError> {
+        if !config.rank_choices {
+            return Ok(None);
+        }
+        let Some(rank_by) = headers.get(RANK_BY_HEADER).and_then(|value| value.to_str().ok()) else {
+            return Ok(None);
+        };
+        if !rank_by.trim().eq_ignore_ascii_case(RANK_BY_LOGPROB) {
+            return Err(ProxyError::BadRequest(format!(
+                "Unsupported {} value '{}', expected '{}'",
+                RANK_BY_HEADER, rank_by, RANK_BY_LOGPROB
+            )));
+        }
+        if req.stream.unwrap_or(false) {
+            return Err(ProxyError::BadRequest(format!("{} requires a non-streaming request", RANK_BY_HEADER)));
+        }
+        if req.n.unwrap_or(1) <= 1 {
+            return Ok(None);
+        }
+
+        let strip_logprobs = !req.logprobs.unwrap_or(false);
+        req.logprobs = Some(true);
+        Ok(Some(Self { strip_logprobs }))
+    }
+
+    /// Sort the choices of a completion body best-first and attach their scores
+    pub fn rank(&self, completion: &mut Value) {
+        let Some(choices) = completion.get_mut("choices").and_then(Value::as_array_mut) else {
+            return;
+        };
+
+        let scores: Vec<Option<f64>> = choices.iter().map(mean_logprob).collect();
+        for (choice, score) in choices.iter_mut().zip(&scores) {
+            if let Some(choice) = choice.as_object_mut() {
+                choice.insert(SCORE_FIELD.to_string(), score.map(Value::from).unwrap_or(Value::Null));
+                if self.strip_logprobs {
+                    choice.remove("logprobs");
+                }
+            }
+        }
+
+        if scores.iter().all(Option::is_some) {
+            // Stable, so equally scored choices keep their input order
+            let mut ranked: Vec<(f64, Value)> = scores.into_iter().flatten().zip(choices.drain(..)).collect();
+            ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            choices.extend(ranked.into_iter().map(|(_, choice)| choice));
+        }
+    }
+}
+
+/// Mean logprob of the tokens of a choice, if the backend returned them
+fn mean_logprob(choice: &Value) -> Option<f64> {
+    let tokens = choice.pointer("/logprobs/content")?.as_array()?;
+    let logprobs: Vec<f64> = tokens.iter().filter_map(|token| token.get("logprob")?.as_f64()).collect();
+    (!logprobs.is_empty()).then(|| logprobs.iter().sum::<f64>() / logprobs.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_input_order_kept_without_logprobs() {
+        let ranking = LogprobRanking { strip_logprobs: true };
+        let mut completion = json!({
+            "choices": [
+                {"index": 0, "message": {"content": "A"}, "logprobs": null},
+                {"index": 1, "message": {"content": "B"}, "logprobs": {"content": [{"token": "B", "logprob": -0.1}]}}
+            ]
+        });
+        ranking.rank(&mut completion);
+
+        assert_eq!(completion["choices"][0]["index"], 0);
+        assert_eq!(completion["choices"][0][SCORE_FIELD], Value::Null);
+        assert_eq!(completion["choices"][1][SCORE_FIELD], -0.1);
+        assert!(completion["choices"][1].get("logprobs").is_none());
+    }
+}
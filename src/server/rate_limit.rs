@@ -0,0 +1,160 @@
+//! # Per-Key Rate Limiting
+//!
+//! Gives every API key its own token bucket of `rate_limit_requests_per_minute`
+//! with bursts of up to `rate_limit_burst_size`, so one key exhausting its
+//! budget does not throttle the others. Requests without an API key share a
+//! bucket per client IP address. Requests over the limit get 429 with
+//! `Retry-After`; every checked response reports `X-RateLimit-Remaining`.
+
+use super::{fair_queue::client_key, is_probe_route, is_ui_proxy_route, AppState};
+use crate::config::Config;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::net::SocketAddr;
+
+/// Header reporting the requests left in the caller's bucket
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+
+/// Rate limiting middleware, a no-op when `enable_rate_limiting` is off
+pub async fn rate_limit_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(limiter) = state.rate_limiter() else {
+        return next.run(request).await;
+    };
+    let path = request.uri().path();
+    if is_probe_route(path) || is_ui_proxy_route(path) {
+        return next.run(request).await;
+    }
+
+    let key = bucket_key(&request, state.config());
+    let result = limiter.check_key(&key);
+    if !result.allowed {
+        return too_many_requests(result.retry_after.unwrap_or(1));
+    }
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(RATE_LIMIT_REMAINING_HEADER, HeaderValue::from(result.remaining_requests));
+    response
+}
+
+/// Bucket a request draws from: its API key, or its client IP when unkeyed
+fn bucket_key(request: &Request, config: &Config) -> String {
+    let key = client_key(request.headers(), config);
+    if !key.is_empty() {
+        return format!("key:{}", key);
+    }
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| format!("ip:{}", peer.ip()))
+        .unwrap_or_else(|| "ip:unknown".to_string())
+}
+
+/// 429 response in the proxy's error envelope
+fn too_many_requests(retry_after: u64) -> Response {
+    let body = Json(serde_json::json!({
+        "error": {
+            "message": format!("Rate limit exceeded, retry after {} seconds", retry_after),
+            "type": "proxy_error",
+            "code": "rate_limit_exceeded"
+        }
+    }));
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, body).into_response();
+    let headers = response.headers_mut();
+    headers.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    headers.insert(RATE_LIMIT_REMAINING_HEADER, HeaderValue::from(0));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::create_router;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    async fn router(burst: u32) -> axum::Router {
+        let mut config = Config::for_test();
+        config.rate_limit_requests_per_minute = 60;
+        config.rate_limit_burst_size = burst;
+        create_router(AppState::new(config).await)
+    }
+
+    /// Validate request sent with `api_key`, or from `peer` when unkeyed
+    fn validate(api_key: Option<&str>, peer: &str) -> Request {
+        let mut builder = Request::post("/v1/validate").header("content-type", "application/json");
+        if let Some(api_key) = api_key {
+            builder = builder.header("x-api-key", api_key);
+        }
+        let mut request = builder
+            .body(Body::from(r#"{"messages": [{"role": "user", "content": "Hi"}]}"#))
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        request
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> &'a str {
+        response.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_burst_above_limit_is_rejected_per_key() {
+        let app = router(3).await;
+
+        for remaining in ["2", "1", "0"] {
+            let response = app.clone().oneshot(validate(Some("sk-a"), "10.0.0.1:1000")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header(&response, RATE_LIMIT_REMAINING_HEADER), remaining);
+        }
+
+        let response = app.clone().oneshot(validate(Some("sk-a"), "10.0.0.1:1000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&response, "retry-after"), "1");
+        assert_eq!(header(&response, RATE_LIMIT_REMAINING_HEADER), "0");
+
+        // Another key from the same address has its own bucket
+        let response = app.oneshot(validate(Some("sk-b"), "10.0.0.1:1000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, RATE_LIMIT_REMAINING_HEADER), "2");
+    }
+
+    #[tokio::test]
+    async fn test_probes_are_not_rate_limited() {
+        let app = router(1).await;
+
+        for probe in ["/health", "/healthz", "/readyz", "/healthz", "/readyz"] {
+            let mut request = Request::get(probe).body(Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo("10.0.0.1:1000".parse::<SocketAddr>().unwrap()));
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS, "{}", probe);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unkeyed_requests_limited_per_client_ip() {
+        let app = router(2).await;
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(validate(None, "10.0.0.1:1000")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        // A new connection from the same address shares the bucket
+        let response = app.clone().oneshot(validate(None, "10.0.0.1:2000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&response, "retry-after"), "1");
+
+        let response = app.oneshot(validate(None, "10.0.0.2:1000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, RATE_LIMIT_REMAINING_HEADER), "1");
+    }
+}
@@ -0,0 +1,178 @@
+//! # Reasoning Content
+//!
+//! Reasoning models (and backends such as vLLM with a reasoning parser) send
+//! their "thinking" as `reasoning_content` next to the answer, in the message
+//! of a completion and in the delta of a stream chunk. `reasoning_content`
+//! chooses whether clients see it unchanged, not at all, or under the field
+//! named by `reasoning_content_field`.
+
+use crate::config::Config;
+use axum::{body::Body, response::Response};
+use bytes::Bytes;
+use futures_util::{stream, StreamExt};
+use serde_json::Value;
+
+/// Field carrying reasoning text in OpenAI-compatible responses
+pub const REASONING_CONTENT: &str = "reasoning_content";
+
+/// What happens to reasoning content on its way to the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReasoningContent {
+    /// Pass it through unchanged
+    Forward,
+    /// Remove it
+    Strip,
+    /// Rename it to the given field
+    Move(String),
+}
+
+impl ReasoningContent {
+    /// Read the mode from `reasoning_content` and `reasoning_content_field`
+    pub fn from_config(config: &Config) -> Self {
+        match config.reasoning_content.as_str() {
+            "strip" => Self::Strip,
+            "move" => Self::Move(config.reasoning_content_field.clone()),
+            _ => Self::Forward,
+        }
+    }
+
+    /// Apply the mode to the reasoning content of one message or delta object
+    fn apply(&self, object: &mut Value) {
+        let Some(object) = object.as_object_mut() else {
+            return;
+        };
+        match self {
+            Self::Forward => {}
+            Self::Strip => {
+                object.remove(REASONING_CONTENT);
+            }
+            Self::Move(field) => {
+                if let Some(reasoning) = object.remove(REASONING_CONTENT) {
+                    object.insert(field.clone(), reasoning);
+                }
+            }
+        }
+    }
+
+    /// Apply the mode to `object_key` ("message" or "delta") of every choice
+    fn apply_to_choices(&self, body: &mut Value, object_key: &str) {
+        let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) else {
+            return;
+        };
+        for choice in choices {
+            if let Some(object) = choice.get_mut(object_key) {
+                self.apply(object);
+            }
+        }
+    }
+
+    /// Rewrite the messages of a chat completion body
+    pub fn rewrite_completion(&self, body: &mut Value) {
+        self.apply_to_choices(body, "message");
+    }
+
+    /// Rewrite the `data:` lines of one SSE event
+    fn rewrite_event(&self, event: &str) -> String {
+        event
+            .split('\n')
+            .map(|line| match line.strip_prefix("data:") {
+                Some(data) if data.contains(REASONING_CONTENT) => match serde_json::from_str::<Value>(data.trim()) {
+                    Ok(mut chunk) => {
+                        self.apply_to_choices(&mut chunk, "delta");
+                        format!("data: {}", chunk)
+                    }
+                    Err(_) => line.to_string(),
+                },
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Wrap a streaming (SSE) response so chunk deltas are rewritten
+    pub fn rewrite_stream(self, response: Response) -> Response {
+        let (parts, body) = response.into_parts();
+        let state = (body.into_data_stream(), String::new(), false);
+
+        let rewritten = stream::unfold((self, state), |(mode, (mut inner, mut pending, done))| async move {
+            if done {
+                return None;
+            }
+            loop {
+                match inner.next().await {
+                    Some(Ok(bytes)) => {
+                        pending.push_str(&String::from_utf8_lossy(&bytes));
+                        let Some(end) = pending.rfind("\n\n") else {
+                            continue;
+                        };
+                        let complete: String = pending.drain(..end + 2).collect();
+                        let output = complete
+                            .split_inclusive("\n\n")
+                            .map(|event| {
+                                let body = event.strip_suffix("\n\n").unwrap_or(event);
+                                format!("{}\n\n", mode.rewrite_event(body))
+                            })
+                            .collect::<String>();
+                        return Some((Ok(Bytes::from(output)), (mode, (inner, pending, false))));
+                    }
+                    Some(Err(error)) => return Some((Err(error), (mode, (inner, pending, true)))),
+                    None if pending.is_empty() => return None,
+                    None => {
+                        let rest = std::mem::take(&mut pending);
+                        let output = mode.rewrite_event(&rest);
+                        return Some((Ok(Bytes::from(output)), (mode, (inner, pending, true))));
+                    }
+                }
+            }
+        });
+
+        Response::from_parts(parts, Body::from_stream(rewritten))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn completion() -> Value {
+        json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "4", "reasoning_content": "2 plus 2 is 4."},
+                "finish_reason": "stop"
+            }]
+        })
+    }
+
+    #[test]
+    fn test_completion_reasoning_moved_to_configured_field() {
+        let mut body = completion();
+        ReasoningContent::Move("thinking".to_string()).rewrite_completion(&mut body);
+
+        let message = &body["choices"][0]["message"];
+        assert_eq!(message["thinking"], "2 plus 2 is 4.");
+        assert!(message.get("reasoning_content").is_none());
+        assert_eq!(message["content"], "4");
+    }
+
+    #[tokio::test]
+    async fn test_stream_event_split_across_chunks_is_rewritten() {
+        let event = r#"data: {"choices":[{"index":0,"delta":{"reasoning_content":"Thinking"}}]}"#;
+        let (head, tail) = event.split_at(30);
+        let chunks = vec![
+            Ok::<_, std::io::Error>(Bytes::from(head.to_string())),
+            Ok(Bytes::from(format!("{}\n\ndata: [DONE]\n\n", tail))),
+        ];
+        let response = Response::new(Body::from_stream(stream::iter(chunks)));
+
+        let response = ReasoningContent::Move("thinking".to_string()).rewrite_stream(response);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let (chunk, done) = body.split_once("\n\n").unwrap();
+        let chunk: Value = serde_json::from_str(chunk.strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(chunk["choices"][0]["delta"], json!({"thinking": "Thinking"}));
+        assert_eq!(done, "data: [DONE]\n\n");
+    }
+}
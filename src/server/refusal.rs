@@ -0,0 +1,108 @@
+//! # Safety Refusal Handling
+//!
+//! Detects safety blocks and refusals reported by the different backends and
+//! rewrites them into a standardized assistant message, so clients see the
+//! configured `refusal_fallback_message` instead of a provider-specific error.
+//!
+//! ## Recognized signals:
+//! - **OpenAI / Azure**: `finish_reason: "content_filter"` or a
+//!   `content_filter` / `content_policy_violation` error code
+//! - **Gemini**: `finishReason: "SAFETY"` candidates or `promptFeedback.blockReason`
+//! - **AWS Bedrock**: guardrail interventions (`stopReason: "guardrail_intervened"`
+//!   or `amazon-bedrock-guardrailAction: "INTERVENED"`)
+
+use crate::{adapters::AdapterUtils, error::ProxyError};
+use serde_json::{json, Value};
+
+/// Finish reason reported for substituted refusals
+pub const CONTENT_FILTER_FINISH_REASON: &str = "content_filter";
+
+/// Error codes and markers that identify a safety block in an upstream error body
+const SAFETY_ERROR_MARKERS: [&str; 4] = [
+    "content_filter",
+    "content_policy_violation",
+    "responsibleaipolicyviolation",
+    "guardrail_intervened",
+];
+
+/// Check whether a backend response body reports a safety block or refusal
+pub fn is_safety_refusal(body: &Value) -> bool {
+    // OpenAI-compatible choices
+    let openai_filtered = body
+        .get("choices")
+        .and_then(Value::as_array)
+        .is_some_and(|choices| {
+            choices.iter().any(|choice| {
+                choice.get("finish_reason").and_then(Value::as_str) == Some(CONTENT_FILTER_FINISH_REASON)
+            })
+        });
+
+    // Gemini candidates and prompt feedback
+    let gemini_blocked = body
+        .get("candidates")
+        .and_then(Value::as_array)
+        .is_some_and(|candidates| {
+            candidates.iter().any(|candidate| {
+                candidate.get("finishReason").and_then(Value::as_str) == Some("SAFETY")
+            })
+        })
+        || body.pointer("/promptFeedback/blockReason").is_some();
+
+    // Bedrock guardrails
+    let bedrock_blocked = body.get("stopReason").and_then(Value::as_str) == Some("guardrail_intervened")
+        || body.get("amazon-bedrock-guardrailAction").and_then(Value::as_str) == Some("INTERVENED");
+
+    openai_filtered || gemini_blocked || bedrock_blocked
+}
+
+/// Check whether an upstream error was caused by a safety block
+pub fn is_safety_refusal_error(error: &ProxyError) -> bool {
+    match error {
+        ProxyError::Upstream(message) | ProxyError::UpstreamStatus { body: message, .. } => {
+            let message = message.to_ascii_lowercase();
+            SAFETY_ERROR_MARKERS.iter().any(|marker| message.contains(marker))
+        }
+        _ => false,
+    }
+}
+
+/// Replace every choice of a refused response with the fallback message.
+///
+/// Bodies that are not in the OpenAI format (raw Gemini or Bedrock payloads)
+/// are replaced by a fresh chat completion.
+pub fn apply_refusal_fallback(body: &mut Value, fallback_message: &str, model: &str) {
+    let choices = body.get_mut("choices").and_then(Value::as_array_mut);
+
+    match choices {
+        Some(choices) if !choices.is_empty() => {
+            for choice in choices {
+                choice["message"] = json!({
+                    "role": "assistant",
+                    "content": fallback_message,
+                });
+                choice["finish_reason"] = json!(CONTENT_FILTER_FINISH_REASON);
+            }
+        }
+        _ => *body = refusal_completion(fallback_message, model),
+    }
+}
+
+/// Build a chat completion carrying the fallback message
+pub fn refusal_completion(fallback_message: &str, model: &str) -> Value {
+    json!({
+        "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+        "object": "chat.completion",
+        "created": AdapterUtils::current_timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": fallback_message,
+            },
+            "finish_reason": CONTENT_FILTER_FINISH_REASON,
+            "logprobs": null,
+        }],
+        "usage": null,
+    })
+}
@@ -0,0 +1,262 @@
+//! # Replay Protection
+//!
+//! Rejects captured requests sent again. With `replay_protection` enabled,
+//! every API request must carry a unique `X-Request-Nonce` and an
+//! `X-Request-Timestamp` (Unix seconds). Requests whose timestamp is more than
+//! `replay_window_secs` away from the proxy clock are rejected, and so are
+//! requests reusing a nonce seen within that window. Seen nonces live in a
+//! [`NonceStore`], in memory or in Redis (with the `redis` feature), so a
+//! fleet of proxies sharing Redis rejects replays sent to any of them.
+
+use super::{is_probe_route, is_ui_proxy_route, AppState};
+use crate::{config::Config, error::ProxyError};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Header carrying the client's single-use request nonce
+pub const NONCE_HEADER: &str = "x-request-nonce";
+/// Header carrying the Unix time (seconds) the client sent the request
+pub const TIMESTAMP_HEADER: &str = "x-request-timestamp";
+
+/// Nonces seen within the replay window
+#[async_trait::async_trait]
+pub trait NonceStore: Send + Sync {
+    /// Remember `nonce` for `ttl`; false when it was already remembered
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool, ProxyError>;
+}
+
+/// Build the store selected by `replay_protection`, if any
+pub fn from_config(config: &Config) -> Option<Arc<dyn NonceStore>> {
+    match config.replay_protection.as_str() {
+        "memory" => Some(Arc::new(InMemoryNonceStore::default())),
+        #[cfg(feature = "redis")]
+        "redis" => Some(Arc::new(RedisNonceStore::new(&config.replay_redis_url))),
+        _ => None,
+    }
+}
+
+/// Nonces held in process memory
+#[derive(Debug, Default)]
+pub struct InMemoryNonceStore {
+    nonces: Mutex<Nonces>,
+}
+
+#[derive(Debug, Default)]
+struct Nonces {
+    /// Expiry of each remembered nonce
+    expiry: HashMap<String, Instant>,
+    /// Nonces in insertion order, so expired ones are dropped from the front
+    queue: VecDeque<(Instant, String)>,
+}
+
+#[async_trait::async_trait]
+impl NonceStore for InMemoryNonceStore {
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool, ProxyError> {
+        let now = Instant::now();
+        let mut nonces = self.nonces.lock().unwrap();
+        let Nonces { expiry, queue } = &mut *nonces;
+        while queue.front().is_some_and(|(expires, _)| *expires <= now) {
+            let (expires, old) = queue.pop_front().unwrap();
+            // Skip nonces remembered again since this queue entry was added
+            if expiry.get(&old) == Some(&expires) {
+                expiry.remove(&old);
+            }
+        }
+        if expiry.get(nonce).is_some_and(|expires| *expires > now) {
+            return Ok(false);
+        }
+        expiry.insert(nonce.to_string(), now + ttl);
+        queue.push_back((now + ttl, nonce.to_string()));
+        Ok(true)
+    }
+}
+
+/// Nonces held in Redis as keys with an expiry, set with `SET NX` so
+/// concurrent replays to different proxies cannot both succeed
+#[cfg(feature = "redis")]
+#[derive(Debug)]
+pub struct RedisNonceStore {
+    connection: super::conversations::RedisConnection,
+}
+
+#[cfg(feature = "redis")]
+impl RedisNonceStore {
+    /// Key prefix for remembered nonces
+    const KEY_PREFIX: &'static str = "nnllm:nonce:";
+
+    /// Create a store for the server at `url` (`redis://[user:password@]host[:port][/db]`)
+    pub fn new(url: &str) -> Self {
+        Self { connection: super::conversations::RedisConnection::new(url) }
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait::async_trait]
+impl NonceStore for RedisNonceStore {
+    async fn insert(&self, nonce: &str, ttl: Duration) -> Result<bool, ProxyError> {
+        let key = format!("{}{}", Self::KEY_PREFIX, nonce);
+        let ttl = ttl.as_secs().max(1);
+        let reply: Option<String> = self
+            .connection
+            .query(redis::cmd("SET").arg(&key).arg(1).arg("NX").arg("EX").arg(ttl))
+            .await
+            .map_err(|e| ProxyError::Internal(format!("Redis nonce store: {}", e)))?;
+        // A nil reply means the key already existed
+        Ok(reply.is_some())
+    }
+}
+
+/// Replay protection middleware, a no-op when `replay_protection` is off
+pub async fn replay_protection_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(store) = state.nonce_store() else {
+        return next.run(request).await;
+    };
+    let path = request.uri().path();
+    if is_probe_route(path) || is_ui_proxy_route(path) {
+        return next.run(request).await;
+    }
+
+    let window = Duration::from_secs(state.config().replay_window_secs);
+    let (nonce, timestamp) = match nonce_and_timestamp(request.headers()) {
+        Ok(headers) => headers,
+        Err(message) => return rejected(StatusCode::BAD_REQUEST, "missing_request_nonce", message),
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    if now.abs_diff(timestamp) > window.as_secs() {
+        tracing::warn!("Rejected request with timestamp {} outside the replay window", timestamp);
+        return rejected(
+            StatusCode::UNAUTHORIZED,
+            "request_timestamp_skewed",
+            format!("{} is more than {} seconds from the server time", TIMESTAMP_HEADER, window.as_secs()),
+        );
+    }
+
+    // Remember nonces for both sides of the window a timestamp may fall in
+    match store.insert(&nonce, window * 2).await {
+        Ok(true) => next.run(request).await,
+        Ok(false) => {
+            tracing::warn!("Rejected replayed request with nonce {}", nonce);
+            rejected(StatusCode::UNAUTHORIZED, "request_replayed", format!("{} has already been used", NONCE_HEADER))
+        }
+        // Fail closed: a replay cannot be ruled out without the store
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Nonce and timestamp of a request, or why they are missing or invalid
+fn nonce_and_timestamp(headers: &HeaderMap) -> Result<(String, u64), String> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("Missing {} header", name))
+    };
+    let nonce = header(NONCE_HEADER)?.to_string();
+    let timestamp = header(TIMESTAMP_HEADER)?
+        .parse()
+        .map_err(|_| format!("{} must be a Unix time in seconds", TIMESTAMP_HEADER))?;
+    Ok((nonce, timestamp))
+}
+
+/// Rejection in the proxy's error envelope
+fn rejected(status: StatusCode, code: &str, message: String) -> Response {
+    let body = Json(serde_json::json!({
+        "error": {
+            "message": message,
+            "type": "proxy_error",
+            "code": code
+        }
+    }));
+    (status, body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::create_router;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    fn validate(nonce: &str, timestamp: u64) -> Request {
+        Request::post("/v1/validate")
+            .header("content-type", "application/json")
+            .header(NONCE_HEADER, nonce)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .body(Body::from(r#"{"messages": [{"role": "user", "content": "Hi"}]}"#))
+            .unwrap()
+    }
+
+    async fn error_code(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        body["error"]["code"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_replayed_nonce_and_stale_timestamp_rejected() {
+        let mut config = Config::for_test();
+        config.replay_protection = "memory".to_string();
+        config.replay_window_secs = 60;
+        let app = create_router(AppState::new(config).await);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        let response = app.clone().oneshot(validate("nonce-1", now)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(validate("nonce-1", now)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(error_code(response).await, "request_replayed");
+
+        let response = app.clone().oneshot(validate("nonce-2", now - 120)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(error_code(response).await, "request_timestamp_skewed");
+
+        // The stale request did not use up its nonce
+        let response = app.clone().oneshot(validate("nonce-2", now)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::post("/v1/validate")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"messages": [{"role": "user", "content": "Hi"}]}"#))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(error_code(response).await, "missing_request_nonce");
+
+        // Orchestrator probes carry no nonce
+        for probe in ["/healthz", "/readyz"] {
+            let response = app.clone().oneshot(Request::get(probe).body(Body::empty()).unwrap()).await.unwrap();
+            assert_ne!(response.status(), StatusCode::BAD_REQUEST, "{}", probe);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_forgets_expired_nonces() {
+        let store = InMemoryNonceStore::default();
+        let ttl = Duration::from_millis(50);
+
+        assert!(store.insert("a", ttl).await.unwrap());
+        assert!(!store.insert("a", ttl).await.unwrap());
+        assert!(store.insert("b", Duration::from_secs(60)).await.unwrap());
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(store.insert("a", ttl).await.unwrap());
+        assert!(!store.insert("b", ttl).await.unwrap());
+
+        let nonces = store.nonces.lock().unwrap();
+        assert_eq!(nonces.expiry.len(), 2);
+        assert_eq!(nonces.queue.len(), 2);
+    }
+}
@@ -0,0 +1,181 @@
+//! # Response Language
+//!
+//! Localized deployments ask the model to answer in a given language. The
+//! language comes from the `X-Response-Language` header or, failing that,
+//! `default_response_language`, and is turned into a "Respond in ..."
+//! instruction added to the first system (or developer) message, after any
+//! `system_prompt_ref` expansion. Requests without a system message get one
+//! holding just the instruction. Language codes such as "es" or "pt-BR" are
+//! spelled out; anything else is used as written.
+
+use crate::{
+    config::Config,
+    error::ProxyError,
+    schemas::{ChatCompletionRequest, Message},
+};
+use axum::http::HeaderMap;
+
+/// Header selecting the response language for one request
+pub const RESPONSE_LANGUAGE_HEADER: &str = "x-response-language";
+
+/// Longest accepted language value
+const MAX_LANGUAGE_LEN: usize = 40;
+
+/// Names of common ISO 639-1 language codes
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("de", "German"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("hi", "Hindi"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("zh", "Chinese"),
+];
+
+/// Where the instruction goes in an existing system message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// After the system prompt
+    Append,
+    /// Before the system prompt
+    Prepend,
+}
+
+/// Response language injection settings
+#[derive(Debug, Clone)]
+pub struct ResponseLanguage {
+    default_language: Option<String>,
+    placement: Placement,
+}
+
+impl ResponseLanguage {
+    /// Read `default_response_language` and `response_language_placement`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            default_language: config.default_response_language.clone(),
+            placement: match config.response_language_placement.as_str() {
+                "prepend" => Placement::Prepend,
+                _ => Placement::Append,
+            },
+        }
+    }
+
+    /// Add the language instruction for this request, if a language is set
+    pub fn apply(&self, req: &mut ChatCompletionRequest, headers: &HeaderMap) -> Result<(), ProxyError> {
+        let requested = headers
+            .get(RESPONSE_LANGUAGE_HEADER)
+            .map(|value| {
+                value.to_str().map_err(|_| {
+                    ProxyError::BadRequest(format!("Invalid {} header", RESPONSE_LANGUAGE_HEADER))
+                })
+            })
+            .transpose()?
+            .map(str::trim)
+            .filter(|language| !language.is_empty());
+        let Some(language) = requested.or(self.default_language.as_deref()) else {
+            return Ok(());
+        };
+        if language.len() > MAX_LANGUAGE_LEN
+            || !language.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+        {
+            return Err(ProxyError::BadRequest(format!("Invalid response language '{}'", language)));
+        }
+
+        let instruction = instruction(language);
+        match req
+            .messages
+            .iter_mut()
+            .find(|message| matches!(message.role.as_str(), "system" | "developer"))
+        {
+            Some(message) => {
+                let prompt = message.content.take().unwrap_or_default();
+                message.content = Some(match (self.placement, prompt.is_empty()) {
+                    (_, true) => instruction,
+                    (Placement::Append, false) => format!("{}\n\n{}", prompt, instruction),
+                    (Placement::Prepend, false) => format!("{}\n\n{}", instruction, prompt),
+                });
+            }
+            None => req.messages.insert(
+                0,
+                Message {
+                    role: "system".to_string(),
+                    content: Some(instruction),
+                    name: None,
+                    tool_calls: None,
+                    function_call: None,
+                    tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
+                },
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// Instruction asking for answers in `language`
+fn instruction(language: &str) -> String {
+    let code = language.split(['-', '_']).next().unwrap_or(language).to_ascii_lowercase();
+    let name = LANGUAGE_NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map_or(language, |(_, name)| name);
+    format!("Respond in {}.", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: Some(content.to_string()),
+            name: None,
+            tool_calls: None,
+            function_call: None,
+            tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
+        }
+    }
+
+    #[test]
+    fn test_default_language_prepended_to_system_prompt_and_header_overrides() {
+        let mut config = Config::for_test();
+        config.default_response_language = Some("fr".to_string());
+        config.response_language_placement = "prepend".to_string();
+        let language = ResponseLanguage::from_config(&config);
+
+        let mut req = ChatCompletionRequest {
+            messages: vec![message("system", "You are a support agent."), message("user", "Hi")],
+            ..Default::default()
+        };
+        language.apply(&mut req, &HeaderMap::new()).unwrap();
+        assert_eq!(
+            req.messages[0].content.as_deref(),
+            Some("Respond in French.\n\nYou are a support agent.")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RESPONSE_LANGUAGE_HEADER, "pt-BR".parse().unwrap());
+        let mut req = ChatCompletionRequest {
+            messages: vec![message("user", "Hi")],
+            ..Default::default()
+        };
+        language.apply(&mut req, &headers).unwrap();
+        assert_eq!(req.messages.len(), 2);
+        assert_eq!(req.messages[0].role, "system");
+        assert_eq!(req.messages[0].content.as_deref(), Some("Respond in Portuguese."));
+    }
+}
@@ -0,0 +1,132 @@
+//! # Shadow Traffic
+//!
+//! Copies a sampled `shadow_percentage` of chat completions to
+//! `shadow_backend_url` to validate a new backend on real traffic. Shadow
+//! requests run in the background after the client's request is dispatched;
+//! their responses are discarded and only their latency and outcome are
+//! recorded, separately from the primary backend's metrics. Shadow copies are
+//! always non-streaming so latency covers the complete response.
+
+use crate::{adapters::Adapter, config::Config, schemas::ChatCompletionRequest};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// Outcomes of shadow requests
+#[derive(Debug, Default)]
+pub struct ShadowStats {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_ms_total: AtomicU64,
+    max_latency_ms: AtomicU64,
+}
+
+/// Point-in-time view of the shadow statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShadowStatsSnapshot {
+    /// Shadow requests that have finished
+    pub requests: u64,
+    /// Shadow requests that failed or returned an error status
+    pub errors: u64,
+    /// Mean shadow request latency
+    pub mean_latency_ms: f64,
+    /// Slowest shadow request
+    pub max_latency_ms: u64,
+}
+
+impl ShadowStats {
+    /// Record one finished shadow request
+    pub fn record(&self, latency_ms: u64, success: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_ms_total.fetch_add(latency_ms, Ordering::Relaxed);
+        self.max_latency_ms.fetch_max(latency_ms, Ordering::Relaxed);
+    }
+
+    /// Take a snapshot of the current statistics
+    pub fn snapshot(&self) -> ShadowStatsSnapshot {
+        let requests = self.requests.load(Ordering::Relaxed);
+        ShadowStatsSnapshot {
+            requests,
+            errors: self.errors.load(Ordering::Relaxed),
+            mean_latency_ms: if requests == 0 {
+                0.0
+            } else {
+                self.latency_ms_total.load(Ordering::Relaxed) as f64 / requests as f64
+            },
+            max_latency_ms: self.max_latency_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// # Shadow Traffic
+///
+/// Samples requests and mirrors them to the shadow backend.
+#[derive(Debug)]
+pub struct ShadowTraffic {
+    /// Backend receiving the shadow copies
+    adapter: Adapter,
+    /// Percentage of requests shadowed (0 to 100)
+    percentage: f64,
+    stats: Arc<ShadowStats>,
+}
+
+impl ShadowTraffic {
+    /// Build shadowing from `shadow_backend_url` and `shadow_percentage`.
+    ///
+    /// Returns `None` when no shadow backend is set or the percentage is 0.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let backend_url = config.shadow_backend_url.as_ref()?;
+        if config.shadow_percentage <= 0.0 {
+            return None;
+        }
+        Some(Self {
+            adapter: Adapter::from_config(&Config {
+                backend_url: backend_url.clone(),
+                ..config.clone()
+            }),
+            percentage: config.shadow_percentage.clamp(0.0, 100.0),
+            stats: Arc::new(ShadowStats::default()),
+        })
+    }
+
+    /// Statistics of finished shadow requests
+    pub fn stats(&self) -> &ShadowStats {
+        &self.stats
+    }
+
+    /// Send a copy of `req` to the shadow backend in the background if this
+    /// request is sampled
+    pub fn mirror(&self, req: &ChatCompletionRequest) {
+        if self.percentage < 100.0 && fastrand::f64() * 100.0 >= self.percentage {
+            return;
+        }
+        let adapter = self.adapter.clone();
+        let stats = self.stats.clone();
+        let mut req = req.clone();
+        req.stream = Some(false);
+
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let success = match adapter.chat_completions(req).await {
+                // Read the whole body so latency covers the complete response
+                Ok(response) => {
+                    let status = response.status();
+                    axum::body::to_bytes(response.into_body(), usize::MAX).await.is_ok() && status.is_success()
+                }
+                Err(error) => {
+                    tracing::debug!(error = %error, "Shadow request failed");
+                    false
+                }
+            };
+            stats.record(started.elapsed().as_millis() as u64, success);
+        });
+    }
+}
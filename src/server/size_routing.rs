@@ -0,0 +1,68 @@
+//! # Size-Based Routing
+//!
+//! Small prompts are served best by a fast small model, while long-context
+//! prompts need a large one. `size_routing` maps token thresholds to
+//! backends: each request goes to the smallest backend whose threshold covers
+//! its estimated prompt size, and prompts larger than every threshold go to
+//! `backend_url`.
+
+use crate::{
+    adapters::Adapter,
+    config::Config,
+    core::tokens::{self, TokenCounter},
+    schemas::ChatCompletionRequest,
+};
+use std::sync::Arc;
+
+/// Chooses a backend by the estimated size of the prompt
+#[derive(Debug)]
+pub struct SizeRouter {
+    /// Backends with the largest prompt they serve, smallest first
+    routes: Vec<(usize, Adapter)>,
+    /// Estimates prompt size with the configured token counter
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl SizeRouter {
+    /// Build one adapter per `size_routing` backend
+    pub fn from_config(config: &Config) -> Self {
+        let routes = config
+            .size_routes()
+            .into_iter()
+            .map(|(threshold, backend_url)| {
+                let backend = Config {
+                    backend_url,
+                    ..config.clone()
+                };
+                (threshold, Adapter::from_config(&backend))
+            })
+            .collect();
+
+        Self {
+            routes,
+            token_counter: tokens::from_config(config),
+        }
+    }
+
+    /// Estimated number of prompt tokens in `req` for `model`
+    pub fn prompt_tokens(&self, req: &ChatCompletionRequest, model: &str) -> usize {
+        req.messages
+            .iter()
+            .filter_map(|message| message.content.as_deref())
+            .map(|content| self.token_counter.count(content, model))
+            .sum()
+    }
+
+    /// Backend to serve `req` instead of the default one, if a route covers it
+    pub fn route(&self, req: &ChatCompletionRequest, model: &str) -> Option<&Adapter> {
+        if self.routes.is_empty() {
+            return None;
+        }
+
+        let prompt_tokens = self.prompt_tokens(req, model);
+        self.routes
+            .iter()
+            .find(|(threshold, _)| prompt_tokens <= *threshold)
+            .map(|(_, adapter)| adapter)
+    }
+}
@@ -7,8 +7,29 @@ use crate::{
     adapters::Adapter,
     config::Config,
     core::http_client::HttpClientBuilder,
-    streaming::StreamingHandler,
+    streaming::{StreamingHandler, StreamingStats},
 };
+#[cfg(feature = "caching")]
+use crate::caching::{CacheConfig, CacheManager};
+#[cfg(feature = "metrics")]
+use crate::metrics::{MetricsCollector, SlaTracker};
+#[cfg(feature = "metrics")]
+use crate::monitoring::{BackendHealthMetrics, BackendHealthStatus, MonitoringConfig, MonitoringSystem};
+#[cfg(feature = "tools")]
+use crate::tools::FunctionRegistry;
+#[cfg(feature = "rate-limiting")]
+use crate::rate_limiting::{AdvancedRateLimiter, RateLimitConfig};
+use super::{
+    conversations::{self, ConversationBudget, ConversationStore},
+    load_shedding::LoadShedder, model_concurrency::ModelConcurrencyLimiter, prompt_capture::PromptCapture,
+    replay_protection::{self, NonceStore}, shadow::ShadowTraffic,
+    size_routing::SizeRouter, stream_fanout::StreamFanout, system_prompts::SystemPromptRegistry, upstream_pool::UpstreamPool,
+};
+use std::sync::Arc;
+#[cfg(feature = "tools")]
+use std::sync::RwLock;
+#[cfg(feature = "metrics")]
+use std::time::Duration;
 
 /// # Application State
 ///
@@ -24,6 +45,51 @@ pub struct AppState {
     pub streaming_handler: StreamingHandler,
     /// HTTP client for making requests
     pub http_client: reqwest::Client,
+    /// Aggregated statistics for streamed responses
+    pub streaming_stats: Arc<StreamingStats>,
+    /// Per-model limits on concurrent backend requests
+    pub model_limiter: ModelConcurrencyLimiter,
+    /// Downgrades overloaded expensive models to cheaper fallbacks
+    pub load_shedder: Arc<LoadShedder>,
+    /// Upstream connection slots with pool exhaustion tracking
+    pub upstream_pool: Arc<UpstreamPool>,
+    /// Backends chosen by estimated prompt size
+    pub size_router: Arc<SizeRouter>,
+    /// Backend retried when a content filter blocks a request (when configured)
+    pub content_filter_backend: Option<Adapter>,
+    /// Shared upstream streams for identical concurrent requests (when enabled)
+    pub stream_fanout: Option<Arc<StreamFanout>>,
+    /// Server-side conversation histories (when enabled)
+    pub conversations: Option<Arc<dyn ConversationStore>>,
+    /// Token budget of stored conversations (when configured)
+    pub conversation_budget: Option<Arc<ConversationBudget>>,
+    /// Nonces seen within the replay window (when replay protection is enabled)
+    pub nonce_store: Option<Arc<dyn NonceStore>>,
+    /// Named system prompts clients reference with `system_prompt_ref`
+    pub system_prompts: Arc<SystemPromptRegistry>,
+    /// Full request/response capture for a sample of requests (when enabled)
+    pub prompt_capture: Option<Arc<PromptCapture>>,
+    /// Background copies of sampled requests to a shadow backend (when configured)
+    pub shadow: Option<Arc<ShadowTraffic>>,
+    /// Per-key request rate limiter (when rate limiting is enabled)
+    #[cfg(feature = "rate-limiting")]
+    pub rate_limiter: Option<Arc<AdvancedRateLimiter>>,
+    /// Response cache for non-streaming requests (when caching is enabled)
+    #[cfg(feature = "caching")]
+    pub cache: Option<Arc<CacheManager>>,
+    /// Outcomes of real chat completion requests
+    #[cfg(feature = "metrics")]
+    pub metrics: Arc<MetricsCollector>,
+    /// Request, error and streaming metrics of the monitoring system
+    #[cfg(feature = "metrics")]
+    pub monitoring: Arc<MonitoringSystem>,
+    /// Held while a health check probes the backend, so concurrent checks
+    /// share one probe
+    #[cfg(feature = "metrics")]
+    pub health_probe_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Functions hosted by this server, listed at `tools_endpoint`
+    #[cfg(feature = "tools")]
+    pub function_registry: Arc<RwLock<FunctionRegistry>>,
 }
 
 impl AppState {
@@ -40,12 +106,131 @@ impl AppState {
         // Create streaming handler
         let streaming_handler = StreamingHandler::default();
 
-        Self {
+        let model_limiter = ModelConcurrencyLimiter::from_config(&config);
+        let load_shedder = Arc::new(LoadShedder::from_config(&config));
+        let size_router = Arc::new(SizeRouter::from_config(&config));
+        let content_filter_backend = config.retry_on_content_filter_backend.as_ref().map(|backend_url| {
+            Adapter::from_config(&Config {
+                backend_url: backend_url.clone(),
+                ..config.clone()
+            })
+        });
+        let upstream_pool = Arc::new(UpstreamPool::from_config(&config));
+        let stream_fanout = config
+            .stream_dedup_enabled
+            .then(|| Arc::new(StreamFanout::new()));
+        let conversations = conversations::from_config(&config);
+        let conversation_budget = ConversationBudget::from_config(&config).map(Arc::new);
+        let nonce_store = replay_protection::from_config(&config);
+        let system_prompts = Arc::new(SystemPromptRegistry::from_config(&config));
+        let prompt_capture = PromptCapture::from_config(&config).map(Arc::new);
+        let shadow = ShadowTraffic::from_config(&config).map(Arc::new);
+
+        #[cfg(feature = "rate-limiting")]
+        let rate_limiter = config
+            .enable_rate_limiting
+            .then(|| Arc::new(AdvancedRateLimiter::new(RateLimitConfig::from_config(&config))));
+
+        #[cfg(feature = "caching")]
+        let cache = config
+            .enable_caching
+            .then(|| Arc::new(CacheManager::new(CacheConfig::from(&config))));
+
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(
+            MetricsCollector::new()
+                .with_error_window(Duration::from_secs(config.readiness_error_window_secs))
+                .with_sla(SlaTracker::from_config(&config)),
+        );
+
+        // Streams are counted in the monitoring system's statistics when it is built in
+        #[cfg(feature = "metrics")]
+        let monitoring = Arc::new(MonitoringSystem::new(MonitoringConfig::default()));
+        #[cfg(feature = "metrics")]
+        let streaming_stats = monitoring.streaming_stats();
+        #[cfg(not(feature = "metrics"))]
+        let streaming_stats = Arc::new(StreamingStats::new());
+
+        let state = Self {
             config,
             adapter,
             streaming_handler,
             http_client,
+            streaming_stats,
+            model_limiter,
+            load_shedder,
+            size_router,
+            content_filter_backend,
+            upstream_pool,
+            stream_fanout,
+            conversations,
+            conversation_budget,
+            nonce_store,
+            system_prompts,
+            prompt_capture,
+            shadow,
+            #[cfg(feature = "rate-limiting")]
+            rate_limiter,
+            #[cfg(feature = "caching")]
+            cache,
+            #[cfg(feature = "metrics")]
+            metrics,
+            #[cfg(feature = "metrics")]
+            monitoring,
+            #[cfg(feature = "metrics")]
+            health_probe_lock: Arc::default(),
+            #[cfg(feature = "tools")]
+            function_registry: Arc::default(),
+        };
+
+        #[cfg(feature = "caching")]
+        state.spawn_cache_warming();
+
+        #[cfg(feature = "metrics")]
+        state.spawn_backend_probes();
+
+        state
+    }
+
+    /// Warm the cache from `cache_warm_file` in the background so serving is not blocked
+    #[cfg(feature = "caching")]
+    fn spawn_cache_warming(&self) {
+        let (Some(cache), Some(path)) = (self.cache.clone(), self.config.cache_warm_file.clone()) else {
+            return;
+        };
+        let adapter = self.adapter.clone();
+
+        tokio::spawn(async move {
+            match super::cache_warm::warm_cache(&cache, &adapter, &path).await {
+                Ok(warmed) => tracing::info!("Warmed cache with {} responses from {}", warmed, path),
+                Err(error) => tracing::warn!("Cache warming from {} failed: {}", path, error),
+            }
+        });
+    }
+
+    /// Probe the backend every `health_probe_interval_secs` so `/health` can
+    /// answer from a recent result
+    #[cfg(feature = "metrics")]
+    fn spawn_backend_probes(&self) {
+        if !self.config.enable_health_checks || self.config.health_probe_interval_secs == 0 {
+            return;
         }
+        let period = Duration::from_secs(self.config.health_probe_interval_secs);
+        let monitoring = self.monitoring.clone();
+        let adapter = self.adapter.clone();
+        let upstream_pool = self.upstream_pool.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                let health = monitoring.probe_backend(&adapter).await;
+                upstream_pool.record_connection(!matches!(health.health_status, BackendHealthStatus::Unhealthy));
+                if let Some(error) = &health.error_message {
+                    tracing::warn!("Backend health probe reported {}: {}", health.health_status.as_str(), error);
+                }
+            }
+        });
     }
 
     /// Get a reference to the config
@@ -68,6 +253,157 @@ impl AppState {
         &self.http_client
     }
 
+    /// Get the aggregated streaming statistics
+    pub fn streaming_stats(&self) -> &Arc<StreamingStats> {
+        &self.streaming_stats
+    }
+
+    /// Copy of this state that dispatches through `adapter`
+    pub fn with_adapter(&self, adapter: Adapter) -> Self {
+        Self {
+            adapter,
+            ..self.clone()
+        }
+    }
+
+    /// State for a request sent with a client's own backend credential: its
+    /// response is neither served from nor stored in the response cache, nor
+    /// shared with identical concurrent streams
+    pub fn without_shared_responses(&self) -> Self {
+        Self {
+            stream_fanout: None,
+            #[cfg(feature = "caching")]
+            cache: None,
+            ..self.clone()
+        }
+    }
+
+    /// Get the per-model concurrency limiter
+    pub fn model_limiter(&self) -> &ModelConcurrencyLimiter {
+        &self.model_limiter
+    }
+
+    /// Get the load shedder
+    pub fn load_shedder(&self) -> &LoadShedder {
+        &self.load_shedder
+    }
+
+    /// Health of the backend from a probe at most `health_probe_interval_secs`
+    /// old, or from a live probe when `live` is set or none is that recent.
+    ///
+    /// Live probes are at most one per `health_probe_min_interval_secs`;
+    /// checks within that interval, and checks arriving while a probe is in
+    /// flight, reuse the latest result.
+    ///
+    /// Returns the health and whether it was cached. A live probe marks the
+    /// connection pool warm or cold by whether it reached the backend.
+    #[cfg(feature = "metrics")]
+    pub async fn backend_health(&self, live: bool) -> (BackendHealthMetrics, bool) {
+        let min_interval = Duration::from_secs(self.config.health_probe_min_interval_secs);
+        let max_age = if live {
+            min_interval
+        } else {
+            Duration::from_secs(self.config.health_probe_interval_secs).max(min_interval)
+        };
+        let cached = || self.monitoring.cached_backend_health(&self.adapter, max_age);
+        if let Some(health) = cached().await {
+            return (health, true);
+        }
+
+        let _probing = self.health_probe_lock.lock().await;
+        if let Some(health) = cached().await {
+            return (health, true);
+        }
+
+        let health = self.monitoring.probe_backend(&self.adapter).await;
+        self.upstream_pool
+            .record_connection(!matches!(health.health_status, BackendHealthStatus::Unhealthy));
+        (health, false)
+    }
+
+    /// Get the upstream connection pool tracker
+    pub fn upstream_pool(&self) -> &UpstreamPool {
+        &self.upstream_pool
+    }
+
+    /// Get the size-based backend router
+    pub fn size_router(&self) -> &SizeRouter {
+        &self.size_router
+    }
+
+    /// Get the backend retried on content filter blocks, if configured
+    pub fn content_filter_backend(&self) -> Option<&Adapter> {
+        self.content_filter_backend.as_ref()
+    }
+
+    /// Get the streaming single-flight registry, if de-duplication is enabled
+    pub fn stream_fanout(&self) -> Option<&Arc<StreamFanout>> {
+        self.stream_fanout.as_ref()
+    }
+
+    /// Get the conversation store, if conversation storage is enabled
+    pub fn conversations(&self) -> Option<&Arc<dyn ConversationStore>> {
+        self.conversations.as_ref()
+    }
+
+    /// Get the conversation token budget, if one is configured
+    pub fn conversation_budget(&self) -> Option<&Arc<ConversationBudget>> {
+        self.conversation_budget.as_ref()
+    }
+
+    /// Get the nonce store, if replay protection is enabled
+    pub fn nonce_store(&self) -> Option<&Arc<dyn NonceStore>> {
+        self.nonce_store.as_ref()
+    }
+
+    /// Get the registered system prompts
+    pub fn system_prompts(&self) -> &SystemPromptRegistry {
+        &self.system_prompts
+    }
+
+    /// Get the prompt capture sink, if prompt capture is enabled
+    pub fn prompt_capture(&self) -> Option<&Arc<PromptCapture>> {
+        self.prompt_capture.as_ref()
+    }
+
+    /// Get the shadow traffic mirror, if a shadow backend is configured
+    pub fn shadow(&self) -> Option<&Arc<ShadowTraffic>> {
+        self.shadow.as_ref()
+    }
+
+    /// Get the per-key rate limiter, if rate limiting is enabled
+    #[cfg(feature = "rate-limiting")]
+    pub fn rate_limiter(&self) -> Option<&Arc<AdvancedRateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Get the response cache, if caching is enabled
+    #[cfg(feature = "caching")]
+    pub fn cache(&self) -> Option<&Arc<CacheManager>> {
+        self.cache.as_ref()
+    }
+
+    /// Get the request metrics collector
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> &Arc<MetricsCollector> {
+        &self.metrics
+    }
+
+    /// Get the monitoring system
+    #[cfg(feature = "metrics")]
+    pub fn monitoring(&self) -> &Arc<MonitoringSystem> {
+        &self.monitoring
+    }
+
+    /// Get the registry of functions hosted by this server.
+    ///
+    /// The registry is shared by every clone of the state, so functions
+    /// registered after the router is built are listed too.
+    #[cfg(feature = "tools")]
+    pub fn function_registry(&self) -> &Arc<RwLock<FunctionRegistry>> {
+        &self.function_registry
+    }
+
     /// Check if streaming is enabled and supported
     pub fn supports_streaming(&self) -> bool {
         self.config.enable_streaming && self.adapter.supports_streaming()
@@ -0,0 +1,314 @@
+//! # Streaming Single-Flight
+//!
+//! De-duplicates identical concurrent streaming requests. The first request for
+//! a given request hash (the leader) opens the upstream stream; every identical
+//! request that arrives while it is in flight subscribes to it instead. Each
+//! subscriber first receives the chunks buffered so far and then the live
+//! continuation, so all clients see the complete response; an upstream body
+//! failure reaches every subscriber as a body error. Once the upstream
+//! stream ends the entry is removed and the next identical request starts a new
+//! upstream call.
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+use bytes::Bytes;
+use futures_util::{stream, Future, StreamExt};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::watch;
+
+use crate::{error::ProxyError, schemas::ChatCompletionRequest};
+
+/// Progress of a shared upstream stream
+#[derive(Default)]
+struct SharedState {
+    /// Status and headers of the upstream response, once received
+    head: Option<(StatusCode, HeaderMap)>,
+    /// Every body chunk received so far
+    chunks: Vec<Bytes>,
+    /// The upstream body has ended (or failed)
+    finished: bool,
+    /// Upstream failure before the response head arrived
+    error: Option<ProxyError>,
+    /// Upstream body failure after the head, reported to every subscriber
+    /// once the chunks received before it have been replayed
+    body_error: Option<String>,
+}
+
+/// One in-flight upstream stream and its subscribers
+struct SharedStream {
+    state: Mutex<SharedState>,
+    /// Bumped whenever `state` changes
+    changed: watch::Sender<()>,
+}
+
+impl SharedStream {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(SharedState::default()),
+            changed: watch::channel(()).0,
+        }
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut SharedState)) {
+        apply(&mut self.state.lock().unwrap());
+        self.changed.send_replace(());
+    }
+
+    /// Wait for the response head, or the leader's error
+    async fn head(&self) -> Result<(StatusCode, HeaderMap), ProxyError> {
+        let mut changed = self.changed.subscribe();
+        loop {
+            {
+                let state = self.state.lock().unwrap();
+                if let Some(head) = &state.head {
+                    return Ok(head.clone());
+                }
+                if let Some(error) = &state.error {
+                    return Err(error.clone());
+                }
+            }
+            if changed.changed().await.is_err() {
+                return Err(ProxyError::Internal("Shared stream ended without a response".to_string()));
+            }
+        }
+    }
+
+    /// Body stream replaying buffered chunks followed by live ones, ending
+    /// with the upstream body error if there was one
+    fn subscribe(self: Arc<Self>) -> Body {
+        let changed = self.changed.subscribe();
+        let chunks = stream::unfold((self, changed, 0usize), |(shared, mut changed, next)| async move {
+            loop {
+                {
+                    let state = shared.state.lock().unwrap();
+                    if let Some(chunk) = state.chunks.get(next) {
+                        let chunk = chunk.clone();
+                        drop(state);
+                        return Some((Ok(chunk), (shared, changed, next + 1)));
+                    }
+                    if state.finished {
+                        // Reported once: the index past the error ends the stream
+                        return match &state.body_error {
+                            Some(error) if next == state.chunks.len() => {
+                                let error = std::io::Error::other(error.clone());
+                                drop(state);
+                                Some((Err(error), (shared, changed, next + 1)))
+                            }
+                            _ => None,
+                        };
+                    }
+                }
+                if changed.changed().await.is_err() {
+                    return None;
+                }
+            }
+        });
+        Body::from_stream(chunks)
+    }
+}
+
+/// # Stream Fan-out
+///
+/// Registry of in-flight upstream streams keyed by request hash.
+#[derive(Default)]
+pub struct StreamFanout {
+    inflight: Arc<Mutex<HashMap<u64, Arc<SharedStream>>>>,
+}
+
+impl StreamFanout {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Key identical requests to the same upstream stream
+    pub fn request_key(req: &ChatCompletionRequest) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(req).unwrap_or_default().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Number of upstream streams currently shared
+    pub fn inflight(&self) -> usize {
+        self.inflight.lock().unwrap().len()
+    }
+
+    /// Subscribe to the in-flight stream for `req`, or start it with `start`
+    pub async fn stream<F, Fut>(&self, req: &ChatCompletionRequest, start: F) -> Result<Response, ProxyError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Response, ProxyError>>,
+    {
+        let key = Self::request_key(req);
+        let (shared, leader) = {
+            let mut inflight = self.inflight.lock().unwrap();
+            match inflight.get(&key) {
+                Some(shared) => (shared.clone(), false),
+                None => {
+                    let shared = Arc::new(SharedStream::new());
+                    inflight.insert(key, shared.clone());
+                    (shared, true)
+                }
+            }
+        };
+
+        if leader {
+            // Fails the followers if this request is cancelled before the upstream responds
+            let guard = LeaderGuard {
+                inflight: &self.inflight,
+                key,
+                shared: &shared,
+                armed: true,
+            };
+            match start().await {
+                Ok(response) => {
+                    guard.disarm();
+                    self.pump(key, shared.clone(), response);
+                }
+                Err(error) => {
+                    guard.fail(error.clone());
+                    return Err(error);
+                }
+            }
+        } else {
+            tracing::debug!("Joining in-flight upstream stream {:x}", key);
+        }
+
+        let (status, headers) = shared.head().await?;
+        let mut response = Response::new(shared.subscribe());
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        Ok(response)
+    }
+
+    /// Publish the upstream head and forward its body into the shared buffer
+    fn pump(&self, key: u64, shared: Arc<SharedStream>, response: Response) {
+        let (parts, body) = response.into_parts();
+        shared.update(|state| state.head = Some((parts.status, parts.headers)));
+
+        let inflight = self.inflight.clone();
+
+        tokio::spawn(async move {
+            let mut data = body.into_data_stream();
+            while let Some(chunk) = data.next().await {
+                match chunk {
+                    Ok(chunk) => shared.update(|state| state.chunks.push(chunk)),
+                    Err(error) => {
+                        tracing::warn!("Shared upstream stream failed: {}", error);
+                        shared.update(|state| state.body_error = Some(error.to_string()));
+                        break;
+                    }
+                }
+            }
+            // Identical requests arriving from now on start a fresh upstream call
+            inflight.lock().unwrap().remove(&key);
+            shared.update(|state| state.finished = true);
+        });
+    }
+}
+
+/// Removes a leader's entry and fails its followers unless disarmed
+struct LeaderGuard<'a> {
+    inflight: &'a Mutex<HashMap<u64, Arc<SharedStream>>>,
+    key: u64,
+    shared: &'a SharedStream,
+    armed: bool,
+}
+
+impl LeaderGuard<'_> {
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+
+    fn fail(mut self, error: ProxyError) {
+        self.release(error);
+    }
+
+    fn release(&mut self, error: ProxyError) {
+        self.armed = false;
+        self.inflight.lock().unwrap().remove(&self.key);
+        self.shared.update(|state| {
+            state.error = Some(error);
+            state.finished = true;
+        });
+    }
+}
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.release(ProxyError::Upstream("Upstream request was cancelled".to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::ReceiverStream;
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_late_subscriber_receives_buffered_and_live_chunks() {
+        let fanout = StreamFanout::new();
+        let req = ChatCompletionRequest::default();
+        let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(4);
+
+        let leader = fanout
+            .stream(&req, || async move { Ok(Response::new(Body::from_stream(ReceiverStream::new(rx)))) })
+            .await
+            .unwrap();
+        tx.send(Ok(Bytes::from("data: first\n\n"))).await.unwrap();
+        tokio::task::yield_now().await;
+
+        let follower = fanout
+            .stream(&req, || async { panic!("identical request must join the in-flight stream") })
+            .await
+            .unwrap();
+        tx.send(Ok(Bytes::from("data: second\n\n"))).await.unwrap();
+        drop(tx);
+
+        assert_eq!(body_text(leader).await, "data: first\n\ndata: second\n\n");
+        assert_eq!(body_text(follower).await, "data: first\n\ndata: second\n\n");
+        assert_eq!(fanout.inflight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_body_error_reaches_every_subscriber() {
+        let fanout = StreamFanout::new();
+        let req = ChatCompletionRequest::default();
+        let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(4);
+
+        let leader = fanout
+            .stream(&req, || async move { Ok(Response::new(Body::from_stream(ReceiverStream::new(rx)))) })
+            .await
+            .unwrap();
+        let follower = fanout
+            .stream(&req, || async { panic!("identical request must join the in-flight stream") })
+            .await
+            .unwrap();
+        tx.send(Ok(Bytes::from("data: first\n\n"))).await.unwrap();
+        tx.send(Err(std::io::Error::other("connection reset"))).await.unwrap();
+        drop(tx);
+
+        for response in [leader, follower] {
+            let mut body = response.into_body().into_data_stream();
+            assert_eq!(body.next().await.unwrap().unwrap(), "data: first\n\n");
+            let error = body.next().await.unwrap().unwrap_err();
+            assert!(error.to_string().contains("connection reset"), "{}", error);
+            assert!(body.next().await.is_none());
+        }
+    }
+}
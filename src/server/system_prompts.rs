@@ -0,0 +1,98 @@
+//! # System Prompt Registry
+//!
+//! Operators register named system prompts in a JSON file
+//! (`{"support-v1": "You are a support agent..."}`) and clients reference them
+//! with `system_prompt_ref` instead of sending the full text on every request.
+//! The reference is expanded into a leading system message before dispatch, so
+//! backends and the response cache see the complete prompt.
+
+use crate::{
+    config::Config,
+    error::ProxyError,
+    schemas::{ChatCompletionRequest, Message},
+};
+use std::collections::HashMap;
+
+/// Registered system prompts keyed by ID
+#[derive(Debug, Clone, Default)]
+pub struct SystemPromptRegistry {
+    prompts: HashMap<String, String>,
+}
+
+impl SystemPromptRegistry {
+    /// Create a registry from ID-to-prompt pairs
+    pub fn new(prompts: HashMap<String, String>) -> Self {
+        Self { prompts }
+    }
+
+    /// Load the prompts in `system_prompts_file`.
+    ///
+    /// A missing or malformed file is logged and yields an empty registry.
+    pub fn from_config(config: &Config) -> Self {
+        let Some(path) = &config.system_prompts_file else {
+            return Self::default();
+        };
+
+        let prompts = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()));
+        match prompts {
+            Ok(prompts) => {
+                let registry = Self::new(prompts);
+                tracing::info!("Loaded {} system prompts from {}", registry.prompts.len(), path);
+                registry
+            }
+            Err(error) => {
+                tracing::warn!("Failed to load system prompts from '{}': {}", path, error);
+                Self::default()
+            }
+        }
+    }
+
+    /// Replace `system_prompt_ref` with a leading system message
+    pub fn expand(&self, req: &mut ChatCompletionRequest) -> Result<(), ProxyError> {
+        let Some(id) = req.system_prompt_ref.take() else {
+            return Ok(());
+        };
+        let prompt = self
+            .prompts
+            .get(&id)
+            .ok_or_else(|| ProxyError::BadRequest(format!("Unknown system prompt reference '{}'", id)))?;
+
+        req.messages.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: Some(prompt.clone()),
+                name: None,
+                tool_calls: None,
+                function_call: None,
+                tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_reference_is_rejected() {
+        let registry = SystemPromptRegistry::new(HashMap::from([(
+            "support-v1".to_string(),
+            "You are a support agent.".to_string(),
+        )]));
+        let mut req = ChatCompletionRequest {
+            system_prompt_ref: Some("support-v2".to_string()),
+            ..Default::default()
+        };
+
+        let error = registry.expand(&mut req).unwrap_err();
+
+        assert!(matches!(error, ProxyError::BadRequest(message) if message.contains("support-v2")));
+    }
+}
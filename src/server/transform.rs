@@ -0,0 +1,653 @@
+//! # Response Content Transformation
+//!
+//! Removes provider boilerplate (assistant names, disclaimers) configured via
+//! `response_strip_prefixes` from the start of assistant content. Buffered
+//! responses are rewritten in place; streamed responses are rewritten chunk by
+//! chunk, holding back content only while it may still be part of a prefix.
+
+use axum::{body::Body, response::Response};
+use bytes::Bytes;
+use futures_util::{stream, StreamExt};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+/// Strip every configured prefix from the start of `content`.
+///
+/// Prefixes are removed repeatedly, so stacked boilerplate is handled, and
+/// whitespace left behind by a removed prefix is trimmed. Occurrences later in
+/// the content are never touched.
+pub fn strip_prefixes<'a>(content: &'a str, prefixes: &[String]) -> &'a str {
+    let mut content = content;
+    while let Some(prefix) = prefixes.iter().find(|prefix| content.starts_with(prefix.as_str())) {
+        content = content[prefix.len()..].trim_start();
+    }
+    content
+}
+
+/// Strip prefixes from the assistant content of every choice in a chat completion body
+pub fn strip_completion_prefixes(body: &mut Value, prefixes: &[String]) {
+    let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for choice in choices {
+        if let Some(content) = choice.pointer_mut("/message/content") {
+            if let Some(text) = content.as_str() {
+                let stripped = strip_prefixes(text, prefixes);
+                if stripped.len() != text.len() {
+                    *content = Value::String(stripped.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Per-choice progress of prefix stripping in a stream
+enum ChoiceState {
+    /// Content seen so far could still be (part of) a prefix
+    Pending { buffered: String, stripped: bool },
+    /// The start of the content has been emitted; later deltas pass through
+    Done,
+}
+
+/// Rewrites `chat.completion.chunk` payloads so prefixes split across chunks are removed
+struct StreamPrefixStripper {
+    prefixes: Arc<[String]>,
+    choices: HashMap<u64, ChoiceState>,
+}
+
+impl StreamPrefixStripper {
+    fn new(prefixes: Arc<[String]>) -> Self {
+        Self {
+            prefixes,
+            choices: HashMap::new(),
+        }
+    }
+
+    /// Content to emit for a delta, or `None` while it is held back
+    fn push(&mut self, index: u64, delta: &str, finished: bool) -> Option<String> {
+        let state = self.choices.entry(index).or_insert(ChoiceState::Pending {
+            buffered: String::new(),
+            stripped: false,
+        });
+        let ChoiceState::Pending { buffered, stripped } = &mut *state else {
+            return Some(delta.to_string());
+        };
+
+        buffered.push_str(delta);
+        let mut remaining = buffered.as_str();
+        if *stripped {
+            remaining = remaining.trim_start();
+        }
+        let rest = strip_prefixes(remaining, &self.prefixes);
+        *stripped |= rest.len() != remaining.len();
+        let rest = if *stripped { rest.trim_start() } else { rest };
+
+        let may_be_prefix = rest.is_empty()
+            || self.prefixes.iter().any(|prefix| prefix.starts_with(rest));
+        if may_be_prefix && !finished {
+            *buffered = rest.to_string();
+            return None;
+        }
+
+        let emitted = rest.to_string();
+        *state = ChoiceState::Done;
+        Some(emitted)
+    }
+
+    /// Rewrite the choices of a single chunk payload
+    fn process_chunk(&mut self, chunk: &mut Value) {
+        let Some(choices) = chunk.get_mut("choices").and_then(Value::as_array_mut) else {
+            return;
+        };
+
+        for choice in choices {
+            let index = choice.get("index").and_then(Value::as_u64).unwrap_or(0);
+            let finished = choice.get("finish_reason").is_some_and(|reason| !reason.is_null());
+            let Some(delta) = choice.get_mut("delta").and_then(Value::as_object_mut) else {
+                continue;
+            };
+
+            let content = delta.get("content").and_then(Value::as_str).map(str::to_string);
+            if content.is_none() && !finished {
+                continue;
+            }
+            let emitted = self.push(index, content.as_deref().unwrap_or(""), finished);
+            match (emitted, content.is_some()) {
+                (Some(text), _) => {
+                    delta.insert("content".to_string(), Value::String(text));
+                }
+                (None, true) => {
+                    delta.insert("content".to_string(), Value::String(String::new()));
+                }
+                (None, false) => {}
+            }
+        }
+    }
+
+    /// Rewrite the `data:` lines of one SSE event
+    fn process_event(&mut self, event: &str) -> String {
+        event
+            .split('\n')
+            .map(|line| match line.strip_prefix("data:") {
+                Some(data) => match serde_json::from_str::<Value>(data.trim()) {
+                    Ok(mut chunk) if chunk.get("choices").is_some() => {
+                        self.process_chunk(&mut chunk);
+                        format!("data: {}", chunk)
+                    }
+                    _ => line.to_string(),
+                },
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Wrap a streaming (SSE) response so configured prefixes are removed from
+/// the start of each choice's content
+pub fn strip_streaming_prefixes(response: Response, prefixes: Arc<[String]>) -> Response {
+    let (parts, body) = response.into_parts();
+    let state = (body.into_data_stream(), StreamPrefixStripper::new(prefixes), String::new(), false);
+
+    let stripped = stream::unfold(state, |(mut inner, mut stripper, mut pending, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            match inner.next().await {
+                Some(Ok(bytes)) => {
+                    pending.push_str(&String::from_utf8_lossy(&bytes));
+                    let Some(end) = pending.rfind("\n\n") else {
+                        continue;
+                    };
+                    let complete: String = pending.drain(..end + 2).collect();
+                    let output = complete
+                        .split_inclusive("\n\n")
+                        .map(|event| {
+                            let body = event.strip_suffix("\n\n").unwrap_or(event);
+                            format!("{}\n\n", stripper.process_event(body))
+                        })
+                        .collect::<String>();
+                    return Some((Ok(Bytes::from(output)), (inner, stripper, pending, false)));
+                }
+                Some(Err(error)) => return Some((Err(error), (inner, stripper, pending, true))),
+                None if pending.is_empty() => return None,
+                None => {
+                    let rest = std::mem::take(&mut pending);
+                    let output = stripper.process_event(&rest);
+                    return Some((Ok(Bytes::from(output)), (inner, stripper, pending, true)));
+                }
+            }
+        }
+    });
+
+    Response::from_parts(parts, Body::from_stream(stripped))
+}
+
+/// Request header asking for a multi-choice stream with one contiguous sequence per choice
+pub const STREAM_CHOICES_HEADER: &str = "x-stream-choices";
+
+/// `x-stream-choices` value selecting per-choice sequences
+pub const STREAM_CHOICES_SEPARATE: &str = "separate";
+
+/// Reorders a multi-choice stream into contiguous per-choice sequences.
+///
+/// The lowest unfinished choice streams live; chunks of later choices are
+/// held back until every earlier choice has finished.
+#[derive(Default)]
+struct StreamChoiceDemux {
+    /// Choice currently streamed live
+    current: u64,
+    /// Held-back events and whether the choice has finished, by choice index
+    pending: BTreeMap<u64, (Vec<String>, bool)>,
+}
+
+impl StreamChoiceDemux {
+    /// Events to emit for one SSE event
+    fn process_event(&mut self, event: &str) -> Vec<String> {
+        let chunk = event
+            .split('\n')
+            .find_map(|line| line.strip_prefix("data:"))
+            .and_then(|data| serde_json::from_str::<Value>(data.trim()).ok());
+        let choices = chunk
+            .as_ref()
+            .and_then(|chunk| chunk.get("choices"))
+            .and_then(Value::as_array)
+            .filter(|choices| !choices.is_empty());
+        let (Some(chunk), Some(choices)) = (&chunk, choices) else {
+            // [DONE], comments and usage-only chunks follow every held-back choice
+            let mut output = self.flush();
+            output.push(event.to_string());
+            return output;
+        };
+
+        let mut output = Vec::new();
+        for choice in choices {
+            let index = choice.get("index").and_then(Value::as_u64).unwrap_or(0);
+            let finished = choice.get("finish_reason").is_some_and(|reason| !reason.is_null());
+            let mut single = chunk.clone();
+            single["choices"] = Value::Array(vec![choice.clone()]);
+            let single = format!("data: {}", single);
+
+            if index == self.current {
+                output.push(single);
+                if finished {
+                    self.advance(&mut output);
+                }
+            } else {
+                let (events, done) = self.pending.entry(index).or_default();
+                events.push(single);
+                *done |= finished;
+            }
+        }
+        output
+    }
+
+    /// Move past the finished current choice, emitting held-back choices that are ready
+    fn advance(&mut self, output: &mut Vec<String>) {
+        loop {
+            self.current += 1;
+            let Some((events, done)) = self.pending.remove(&self.current) else {
+                return;
+            };
+            output.extend(events);
+            if !done {
+                return;
+            }
+        }
+    }
+
+    /// Emit every held-back choice in index order
+    fn flush(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .flat_map(|(events, _)| events)
+            .collect()
+    }
+}
+
+/// Wrap a multi-choice streaming (SSE) response so each choice's chunks are
+/// sent as one contiguous sequence, in choice order
+pub fn demux_streaming_choices(response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let state = (body.into_data_stream(), StreamChoiceDemux::default(), String::new(), false);
+
+    let demuxed = stream::unfold(state, |(mut inner, mut demux, mut pending, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            let (events, finished) = match inner.next().await {
+                Some(Ok(bytes)) => {
+                    pending.push_str(&String::from_utf8_lossy(&bytes));
+                    let Some(end) = pending.rfind("\n\n") else {
+                        continue;
+                    };
+                    let complete: String = pending.drain(..end + 2).collect();
+                    let events: Vec<String> = complete
+                        .split("\n\n")
+                        .filter(|event| !event.is_empty())
+                        .flat_map(|event| demux.process_event(event))
+                        .collect();
+                    (events, false)
+                }
+                Some(Err(error)) => return Some((Err(error), (inner, demux, pending, true))),
+                None => {
+                    let rest = std::mem::take(&mut pending);
+                    let mut events = match rest.trim() {
+                        "" => Vec::new(),
+                        event => demux.process_event(event),
+                    };
+                    events.extend(demux.flush());
+                    (events, true)
+                }
+            };
+            if events.is_empty() {
+                if finished {
+                    return None;
+                }
+                continue;
+            }
+            let output: String = events.iter().map(|event| format!("{}\n\n", event)).collect();
+            return Some((Ok(Bytes::from(output)), (inner, demux, pending, finished)));
+        }
+    });
+
+    Response::from_parts(parts, Body::from_stream(demuxed))
+}
+
+/// Finish reason sent for a choice whose stream ended inside a tool call
+pub const TOOL_CALLS_TRUNCATED: &str = "tool_calls_truncated";
+
+/// Tool call arguments reassembled from a stream
+#[derive(Default)]
+struct StreamedToolCall {
+    id: Option<String>,
+    name: Option<String>,
+    arguments: String,
+}
+
+/// Reassembles the tool calls of a stream to detect ones cut off mid-`arguments`.
+///
+/// A choice that never received a finish reason but holds tool call arguments
+/// that are not valid JSON was truncated; before the stream ends its client is
+/// sent a `tool_calls_truncated` finish reason and an error event, so the
+/// fragments already forwarded are not mistaken for a complete call.
+#[derive(Default)]
+struct ToolCallTruncationGuard {
+    /// Response id and model of the stream, repeated on the signal chunk
+    id: Option<Value>,
+    model: Option<Value>,
+    /// Tool calls by tool call index, and whether the choice finished, by choice index
+    choices: BTreeMap<u64, (BTreeMap<u64, StreamedToolCall>, bool)>,
+}
+
+impl ToolCallTruncationGuard {
+    /// Record the tool call deltas and finish reasons of one SSE event
+    fn observe(&mut self, event: &str) {
+        let Some(chunk) = event
+            .split('\n')
+            .find_map(|line| line.strip_prefix("data:"))
+            .and_then(|data| serde_json::from_str::<Value>(data.trim()).ok())
+        else {
+            return;
+        };
+        let Some(choices) = chunk.get("choices").and_then(Value::as_array) else {
+            return;
+        };
+        self.id = chunk.get("id").cloned().or(self.id.take());
+        self.model = chunk.get("model").cloned().or(self.model.take());
+
+        for choice in choices {
+            let index = choice.get("index").and_then(Value::as_u64).unwrap_or(0);
+            let (calls, finished) = self.choices.entry(index).or_default();
+            *finished |= choice.get("finish_reason").is_some_and(|reason| !reason.is_null());
+
+            let deltas = choice.pointer("/delta/tool_calls").and_then(Value::as_array);
+            for delta in deltas.into_iter().flatten() {
+                let position = delta.get("index").and_then(Value::as_u64).unwrap_or(0);
+                let call = calls.entry(position).or_default();
+                if let Some(id) = delta.get("id").and_then(Value::as_str) {
+                    call.id = Some(id.to_string());
+                }
+                if let Some(name) = delta.pointer("/function/name").and_then(Value::as_str) {
+                    call.name = Some(name.to_string());
+                }
+                if let Some(arguments) = delta.pointer("/function/arguments").and_then(Value::as_str) {
+                    call.arguments.push_str(arguments);
+                }
+            }
+        }
+    }
+
+    /// Signal events for every truncated choice, sent once before the stream ends
+    fn finish(&mut self) -> Vec<String> {
+        let mut events = Vec::new();
+        for (index, (calls, finished)) in std::mem::take(&mut self.choices) {
+            if finished {
+                continue;
+            }
+            let Some(call) = calls
+                .values()
+                .find(|call| serde_json::from_str::<Value>(&call.arguments).is_err())
+            else {
+                continue;
+            };
+
+            let mut chunk = serde_json::json!({
+                "object": "chat.completion.chunk",
+                "choices": [{"index": index, "delta": {}, "finish_reason": TOOL_CALLS_TRUNCATED}],
+            });
+            if let Some(id) = &self.id {
+                chunk["id"] = id.clone();
+            }
+            if let Some(model) = &self.model {
+                chunk["model"] = model.clone();
+            }
+            let error = serde_json::json!({
+                "error": {
+                    "message": format!(
+                        "Stream ended before the arguments of tool call {} ({}) in choice {} were complete",
+                        call.id.as_deref().unwrap_or("<unknown>"),
+                        call.name.as_deref().unwrap_or("<unknown>"),
+                        index
+                    ),
+                    "type": "api_error",
+                    "code": TOOL_CALLS_TRUNCATED,
+                }
+            });
+            tracing::warn!("Upstream stream truncated mid tool call in choice {}", index);
+            events.push(format!("data: {}", chunk));
+            events.push(format!("data: {}", error));
+        }
+        events
+    }
+
+    /// Events to emit for one SSE event
+    fn process_event(&mut self, event: &str) -> Vec<String> {
+        let is_done = event
+            .split('\n')
+            .any(|line| line.strip_prefix("data:").is_some_and(|data| data.trim() == "[DONE]"));
+        let mut output = Vec::new();
+        if is_done {
+            output.extend(self.finish());
+        } else {
+            self.observe(event);
+        }
+        output.push(event.to_string());
+        output
+    }
+}
+
+/// Wrap a streaming (SSE) response so a stream that ends mid tool call is
+/// reported as truncated instead of ending with incomplete arguments
+pub fn guard_truncated_tool_calls(response: Response) -> Response {
+    let (parts, body) = response.into_parts();
+    let state = (body.into_data_stream(), ToolCallTruncationGuard::default(), String::new(), false);
+
+    let guarded = stream::unfold(state, |(mut inner, mut guard, mut pending, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            let (events, finished) = match inner.next().await {
+                Some(Ok(bytes)) => {
+                    pending.push_str(&String::from_utf8_lossy(&bytes));
+                    let Some(end) = pending.rfind("\n\n") else {
+                        continue;
+                    };
+                    let complete: String = pending.drain(..end + 2).collect();
+                    let events: Vec<String> = complete
+                        .split("\n\n")
+                        .filter(|event| !event.is_empty())
+                        .flat_map(|event| guard.process_event(event))
+                        .collect();
+                    (events, false)
+                }
+                Some(Err(error)) => return Some((Err(error), (inner, guard, pending, true))),
+                None => {
+                    let rest = std::mem::take(&mut pending);
+                    let mut events = match rest.trim() {
+                        "" => Vec::new(),
+                        event => guard.process_event(event),
+                    };
+                    events.extend(guard.finish());
+                    (events, true)
+                }
+            };
+            if events.is_empty() {
+                if finished {
+                    return None;
+                }
+                continue;
+            }
+            let output: String = events.iter().map(|event| format!("{}\n\n", event)).collect();
+            return Some((Ok(Bytes::from(output)), (inner, guard, pending, finished)));
+        }
+    });
+
+    Response::from_parts(parts, Body::from_stream(guarded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prefixes(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_strip_prefix_only_at_start() {
+        let prefixes = prefixes(&["Assistant:", "Disclaimer: AI generated."]);
+
+        assert_eq!(strip_prefixes("Assistant: Hello", &prefixes), "Hello");
+        assert_eq!(
+            strip_prefixes("Disclaimer: AI generated. Assistant: Hi", &prefixes),
+            "Hi"
+        );
+        assert_eq!(
+            strip_prefixes("Hello, Assistant: is my name", &prefixes),
+            "Hello, Assistant: is my name"
+        );
+
+        let mut body = json!({"choices": [{"index": 0, "message": {"role": "assistant", "content": "Assistant: Say Assistant: twice"}}]});
+        strip_completion_prefixes(&mut body, &prefixes);
+        assert_eq!(body["choices"][0]["message"]["content"], "Say Assistant: twice");
+    }
+
+    #[tokio::test]
+    async fn test_streaming_prefix_split_across_chunks() {
+        let chunk = |content: &str, finish: Value| {
+            format!(
+                "data: {}\n\n",
+                json!({"object": "chat.completion.chunk", "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish}]})
+            )
+        };
+        let events = [
+            chunk("Assis", Value::Null),
+            chunk("tant: Hel", Value::Null),
+            chunk("lo, Assistant: again", json!("stop")),
+            "data: [DONE]\n\n".to_string(),
+        ];
+        let body = Body::from_stream(stream::iter(
+            events.into_iter().map(|event| Ok::<_, std::io::Error>(Bytes::from(event))),
+        ));
+
+        let response = strip_streaming_prefixes(Response::new(body), prefixes(&["Assistant:"]).into());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let content: String = String::from_utf8_lossy(&bytes)
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str().map(str::to_string))
+            .collect();
+
+        assert_eq!(content, "Hello, Assistant: again");
+        assert!(String::from_utf8_lossy(&bytes).ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_demuxed_stream_keeps_each_choice_contiguous() {
+        let chunk = |index: u64, content: &str, finish: Value| {
+            format!(
+                "data: {}\n\n",
+                json!({"object": "chat.completion.chunk", "choices": [{"index": index, "delta": {"content": content}, "finish_reason": finish}]})
+            )
+        };
+        let events = [
+            chunk(0, "Red", Value::Null),
+            chunk(1, "Blue", Value::Null),
+            chunk(1, " sky", json!("stop")),
+            chunk(0, " rose", Value::Null),
+            chunk(0, ".", json!("stop")),
+            "data: [DONE]\n\n".to_string(),
+        ];
+        let body = Body::from_stream(stream::iter(
+            events.into_iter().map(|event| Ok::<_, std::io::Error>(Bytes::from(event))),
+        ));
+
+        let response = demux_streaming_choices(Response::new(body));
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        let sequence: Vec<(u64, String)> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .map(|chunk| {
+                let choice = &chunk["choices"][0];
+                (choice["index"].as_u64().unwrap(), choice["delta"]["content"].as_str().unwrap().to_string())
+            })
+            .collect();
+
+        let expected = [(0, "Red"), (0, " rose"), (0, "."), (1, "Blue"), (1, " sky")];
+        assert_eq!(sequence, expected.map(|(index, content)| (index, content.to_string())));
+        assert!(text.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_cut_off_mid_arguments_signals_truncation() {
+        let tool_chunk = |delta: Value| {
+            format!(
+                "data: {}\n\n",
+                json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion.chunk",
+                    "model": "gpt-test",
+                    "choices": [{"index": 0, "delta": {"tool_calls": [delta]}, "finish_reason": null}]
+                })
+            )
+        };
+        // The connection drops after part of the arguments; the proxy then closes with [DONE]
+        let events = [
+            tool_chunk(json!({"index": 0, "id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": ""}})),
+            tool_chunk(json!({"index": 0, "function": {"arguments": "{\"location\": \"Par"}})),
+            "data: [DONE]\n\n".to_string(),
+        ];
+        let body = Body::from_stream(stream::iter(
+            events.into_iter().map(|event| Ok::<_, std::io::Error>(Bytes::from(event))),
+        ));
+
+        let response = guard_truncated_tool_calls(Response::new(body));
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        let chunks: Vec<Value> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .collect();
+
+        let finish = chunks.iter().find_map(|chunk| chunk["choices"][0]["finish_reason"].as_str());
+        assert_eq!(finish, Some(TOOL_CALLS_TRUNCATED));
+        let error = chunks.iter().find(|chunk| chunk.get("error").is_some()).unwrap();
+        assert_eq!(error["error"]["code"], TOOL_CALLS_TRUNCATED);
+        assert!(error["error"]["message"].as_str().unwrap().contains("get_weather"));
+        assert!(text.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_tool_call_stream_is_unchanged() {
+        let events = [
+            format!(
+                "data: {}\n\n",
+                json!({"choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "id": "call_1", "function": {"name": "get_weather", "arguments": "{\"location\": \"Paris\"}"}}]}, "finish_reason": null}]})
+            ),
+            format!("data: {}\n\n", json!({"choices": [{"index": 0, "delta": {}, "finish_reason": "tool_calls"}]})),
+            "data: [DONE]\n\n".to_string(),
+        ];
+        let original: String = events.concat();
+        let body = Body::from_stream(stream::iter(
+            events.into_iter().map(|event| Ok::<_, std::io::Error>(Bytes::from(event))),
+        ));
+
+        let response = guard_truncated_tool_calls(Response::new(body));
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&bytes), original);
+    }
+}
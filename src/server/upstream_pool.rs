@@ -0,0 +1,214 @@
+//! # Upstream Pool Exhaustion
+//!
+//! reqwest queues requests for a busy connection pool silently, so a
+//! saturated pool looks like a slow backend. With
+//! `pool_exhaustion_threshold_ms` set, backend requests take one of
+//! `http_client_max_connections` slots first; a request still waiting for a
+//! slot after the threshold is logged and counted as a pool exhaustion, and
+//! with `pool_exhaustion_fail_fast` it fails with 503 instead of waiting on.
+//!
+//! The pool also records whether the latest backend request or health probe
+//! connected to the backend, which readiness checks wait for.
+
+use crate::{
+    config::Config,
+    error::{ProxyError, TransportErrorKind},
+};
+use serde::Serialize;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Point-in-time view of the upstream connection slots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpstreamPoolSnapshot {
+    /// Configured number of upstream connections
+    pub max_connections: usize,
+    /// Requests currently holding a connection slot
+    pub in_use: usize,
+    /// Requests waiting for a connection slot
+    pub waiting: usize,
+    /// Requests that waited longer than the threshold for a slot
+    pub pool_exhaustion: u64,
+}
+
+/// Caps and times acquisition of upstream connections
+#[derive(Debug, Default)]
+pub struct UpstreamPool {
+    /// Connection slots, absent when tracking is disabled
+    semaphore: Option<Arc<Semaphore>>,
+    max_connections: usize,
+    threshold: Duration,
+    fail_fast: bool,
+    waiting: AtomicUsize,
+    exhaustions: AtomicU64,
+    /// Whether the latest backend request or probe connected to the backend
+    connected: AtomicBool,
+}
+
+impl UpstreamPool {
+    /// Build the pool from the `pool_exhaustion_*` settings
+    pub fn from_config(config: &Config) -> Self {
+        let max_connections = config.http_client_max_connections.max(1);
+        Self {
+            semaphore: (config.pool_exhaustion_threshold_ms > 0)
+                .then(|| Arc::new(Semaphore::new(max_connections))),
+            max_connections,
+            threshold: Duration::from_millis(config.pool_exhaustion_threshold_ms),
+            fail_fast: config.pool_exhaustion_fail_fast,
+            waiting: AtomicUsize::new(0),
+            exhaustions: AtomicU64::new(0),
+            connected: AtomicBool::new(false),
+        }
+    }
+
+    /// Wait for an upstream connection slot.
+    ///
+    /// Returns `None` when tracking is disabled, and fails with a
+    /// `pool_exhausted` transport error when fail-fast is enabled and no slot
+    /// frees up within the threshold.
+    pub async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, ProxyError> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+
+        let started = Instant::now();
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+        let result = match tokio::time::timeout(self.threshold, semaphore.clone().acquire_owned()).await {
+            Ok(permit) => Ok(permit.ok()),
+            Err(_) => {
+                self.exhaustions.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    "Upstream connection pool exhausted: no connection of {} free after {:?}",
+                    self.max_connections,
+                    self.threshold
+                );
+                if self.fail_fast {
+                    Err(ProxyError::Transport {
+                        kind: TransportErrorKind::PoolExhausted,
+                        message: format!(
+                            "pool exhausted: all {} upstream connections busy for {:?}",
+                            self.max_connections, self.threshold
+                        ),
+                    })
+                } else {
+                    Ok(semaphore.clone().acquire_owned().await.ok())
+                }
+            }
+        };
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+
+        tracing::debug!("Waited {:?} for an upstream connection", started.elapsed());
+        result
+    }
+
+    /// Record whether a backend request or probe connected to the backend
+    pub fn record_connection(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// Record the outcome of a backend request: any answer from the backend,
+    /// even an error status, means it was connected to, while connect, DNS,
+    /// TLS and timeout failures mean it was not. Other failures, such as pool
+    /// exhaustion, say nothing about the backend and are ignored.
+    pub fn record_outcome<T>(&self, result: &Result<T, ProxyError>) {
+        match result {
+            Ok(_)
+            | Err(ProxyError::UpstreamStatus { .. })
+            | Err(ProxyError::Upstream(_))
+            | Err(ProxyError::ModelLoading(_))
+            | Err(ProxyError::IncompleteResponse { .. }) => self.record_connection(true),
+            Err(ProxyError::Transport {
+                kind:
+                    TransportErrorKind::Connect
+                    | TransportErrorKind::Dns
+                    | TransportErrorKind::Tls
+                    | TransportErrorKind::Timeout,
+                ..
+            }) => self.record_connection(false),
+            Err(_) => {}
+        }
+    }
+
+    /// Whether the latest backend request or probe connected to the backend
+    pub fn is_warm(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Connection slots in use and waiting, and the exhaustion count
+    pub fn snapshot(&self) -> UpstreamPoolSnapshot {
+        let available = self.semaphore.as_ref().map_or(self.max_connections, |s| s.available_permits());
+        UpstreamPoolSnapshot {
+            max_connections: self.max_connections,
+            in_use: self.max_connections - available,
+            waiting: self.waiting.load(Ordering::Relaxed),
+            pool_exhaustion: self.exhaustions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(fail_fast: bool) -> UpstreamPool {
+        let mut config = Config::for_test();
+        config.http_client_max_connections = 1;
+        config.pool_exhaustion_threshold_ms = 20;
+        config.pool_exhaustion_fail_fast = fail_fast;
+        UpstreamPool::from_config(&config)
+    }
+
+    #[tokio::test]
+    async fn test_slow_acquisition_is_counted_then_waits() {
+        let pool = Arc::new(pool(false));
+        let held = pool.acquire().await.unwrap().unwrap();
+
+        let waiting = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(pool.snapshot().pool_exhaustion, 1);
+        assert!(!waiting.is_finished(), "without fail-fast the request keeps waiting");
+
+        drop(held);
+        assert!(waiting.await.unwrap().unwrap().is_some());
+        assert_eq!(pool.snapshot().waiting, 0);
+    }
+
+    #[test]
+    fn test_warmth_follows_latest_backend_outcome() {
+        let pool = pool(false);
+        assert!(!pool.is_warm());
+
+        pool.record_outcome::<()>(&Err(ProxyError::UpstreamStatus {
+            status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+            body: String::new(),
+        }));
+        assert!(pool.is_warm());
+
+        pool.record_outcome::<()>(&Err(ProxyError::Transport {
+            kind: TransportErrorKind::PoolExhausted,
+            message: String::new(),
+        }));
+        assert!(pool.is_warm());
+
+        pool.record_outcome::<()>(&Err(ProxyError::Transport {
+            kind: TransportErrorKind::Connect,
+            message: String::new(),
+        }));
+        assert!(!pool.is_warm());
+
+        pool.record_outcome(&Ok(()));
+        assert!(pool.is_warm());
+    }
+}
@@ -4,23 +4,30 @@
 
 use crate::core::http_client::HttpClientBuilder;
 use crate::{
-    adapters::{AzureOpenAIAdapter, CustomAdapter, LightLLMAdapter, OpenAIAdapter, VLLMAdapter},
+    adapters::{
+        aws::event_stream::{bedrock_delta, EventStreamDecoder},
+        AWSBedrockAdapter, Adapter, AdapterTrait, AdapterUtils, AzureOpenAIAdapter, CustomAdapter,
+        DirectAdapter, LightLLMAdapter, OpenAIAdapter, VLLMAdapter,
+    },
     error::ProxyError,
     schemas::ChatCompletionRequest,
     streaming::core::{
         create_content_event, create_done_event, create_error_event, create_final_event,
-        StreamingState,
+        create_final_event_with_reason, with_stall_detection, StreamingState,
     },
 };
-use axum::response::{sse::Event, Sse};
+use axum::response::{sse::Event, IntoResponse, Response, Sse};
 use futures_util::{
     stream::{self, Stream},
     StreamExt,
 };
-use reqwest::header::CONTENT_TYPE;
+use flate2::write::GzDecoder;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use reqwest::{Client, Response as ReqwestResponse};
 use std::convert::Infallible;
+use std::io::{self, Write};
 use std::pin::Pin;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
@@ -43,6 +50,8 @@ pub struct StreamingHandler {
     /// HTTP client for streaming requests
     #[allow(dead_code)]
     http_client: Client,
+    /// Longest gap between chunks before a stream is reported as stalled
+    heartbeat_timeout: Option<Duration>,
 }
 
 impl StreamingHandler {
@@ -52,7 +61,24 @@ impl StreamingHandler {
             .build()
             .map_err(|e| ProxyError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
-        Ok(Self { http_client })
+        Ok(Self { http_client, heartbeat_timeout: None })
+    }
+
+    /// End streams with a `stream_stalled` error event when no chunk,
+    /// keep-alives included, arrives within `timeout`
+    pub fn with_heartbeat_timeout(mut self, timeout: Duration) -> Self {
+        self.heartbeat_timeout = Some(timeout);
+        self
+    }
+
+    /// Stream a chat completion through `adapter`, applying the heartbeat
+    /// timeout when one is configured
+    pub async fn stream(&self, adapter: &Adapter, request: ChatCompletionRequest) -> Result<Response, ProxyError> {
+        let response = super::create_streaming_response(adapter, request).await?.into_response();
+        Ok(match self.heartbeat_timeout {
+            Some(timeout) => with_stall_detection(response, timeout),
+            None => response,
+        })
     }
 }
 
@@ -60,6 +86,7 @@ impl Default for StreamingHandler {
     fn default() -> Self {
         Self::new().unwrap_or_else(|_| Self {
             http_client: HttpClientBuilder::new().build().unwrap(),
+            heartbeat_timeout: None,
         })
     }
 }
@@ -312,6 +339,100 @@ pub async fn custom_streaming(
     Ok(Sse::new(Box::pin(stream)))
 }
 
+/// AWS Bedrock streaming implementation.
+///
+/// Decodes the binary event stream from `invoke-with-response-stream` into
+/// OpenAI-style chunks as frames arrive.
+pub async fn aws_streaming(
+    adapter: &AWSBedrockAdapter,
+    request: ChatCompletionRequest,
+) -> Result<StreamingResponse, ProxyError> {
+    let mut state = StreamingState::new(
+        request
+            .model
+            .clone()
+            .unwrap_or_else(|| adapter.model_id().to_string()),
+    );
+    let response = adapter.stream_chat_completions_raw(request).await?;
+    let (tx, rx) = mpsc::channel::<Result<Event, Infallible>>(32);
+
+    tokio::spawn(async move {
+        let mut decoder = EventStreamDecoder::new();
+        let mut stream = response.bytes_stream();
+        let mut stop_reason = None;
+
+        while let Some(chunk_result) = stream.next().await {
+            let messages = chunk_result
+                .map_err(|err| ProxyError::Upstream(err.to_string()))
+                .and_then(|bytes| decoder.push(&bytes))
+                .and_then(|messages| messages.iter().map(bedrock_delta).collect::<Result<Vec<_>, _>>());
+            let deltas = match messages {
+                Ok(deltas) => deltas,
+                Err(err) => {
+                    let _ = tx.send(Ok(create_error_event(err))).await;
+                    let _ = tx.send(Ok(create_done_event())).await;
+                    return;
+                }
+            };
+
+            for delta in deltas.into_iter().flatten() {
+                stop_reason = delta.stop_reason.or(stop_reason);
+                if !delta.text.is_empty()
+                    && tx.send(Ok(create_content_event(&mut state, delta.text))).await.is_err()
+                {
+                    return;
+                }
+            }
+        }
+
+        if decoder.has_pending() {
+            tracing::warn!("AWS Bedrock closed the event stream mid-message; terminating it for the client");
+            let error = ProxyError::Upstream("AWS Bedrock closed the event stream mid-message".to_string());
+            let _ = tx.send(Ok(create_error_event(error))).await;
+            let _ = tx.send(Ok(create_done_event())).await;
+            return;
+        }
+        let finish_reason = stop_reason.as_deref().map_or("stop", AdapterUtils::normalize_finish_reason);
+        let _ = tx.send(Ok(create_final_event_with_reason(&mut state, finish_reason))).await;
+        let _ = tx.send(Ok(create_done_event())).await;
+    });
+
+    let stream = ReceiverStream::new(rx);
+    Ok(Sse::new(Box::pin(stream)))
+}
+
+/// Direct mode streaming implementation.
+///
+/// The embedded engine produces the whole completion at once, so it is
+/// replayed as one content chunk per word (with its trailing whitespace).
+pub async fn direct_streaming(
+    adapter: &DirectAdapter,
+    request: ChatCompletionRequest,
+) -> Result<StreamingResponse, ProxyError> {
+    let mut state = StreamingState::new(
+        request
+            .model
+            .clone()
+            .unwrap_or_else(|| adapter.model_id().to_string()),
+    );
+    let response = adapter.chat_completions(request).await?;
+    let content = response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .unwrap_or_default();
+
+    let mut events: Vec<_> = content
+        .split_inclusive(char::is_whitespace)
+        .map(|word| Ok(create_content_event(&mut state, word.to_string())))
+        .collect();
+    events.push(Ok(create_final_event(&mut state)));
+    events.push(Ok(create_done_event()));
+
+    Ok(Sse::new(Box::pin(stream::iter(events))))
+}
+
 /// Parse SSE (Server-Sent Events) data format
 /// Converts "data: {json}\n\ndata: {json}\n\n..." format to Event objects
 #[allow(dead_code)]
@@ -352,8 +473,103 @@ fn is_event_stream(response: &ReqwestResponse) -> bool {
         .unwrap_or(false)
 }
 
+/// Rewrite non-canonical finish reasons in a forwarded SSE chunk.
+///
+/// Only chunks that carry a finish reason are parsed; all others are passed through.
+fn normalize_chunk_finish_reason(data: &str) -> String {
+    if !data.contains("\"finish_reason\":\"") && !data.contains("\"finish_reason\": \"") {
+        return data.to_string();
+    }
+
+    match serde_json::from_str::<serde_json::Value>(data) {
+        Ok(mut chunk) => {
+            AdapterUtils::normalize_finish_reasons(&mut chunk);
+            chunk.to_string()
+        }
+        Err(_) => data.to_string(),
+    }
+}
+
+fn is_gzip_encoded(response: &ReqwestResponse) -> bool {
+    response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().eq_ignore_ascii_case("gzip"))
+        .unwrap_or(false)
+}
+
+/// Incremental gzip decoder for upstream streams that still carry
+/// `Content-Encoding: gzip` (i.e. were not decoded by the HTTP client).
+///
+/// Each chunk yields whatever decompressed output is available so far,
+/// so SSE events are forwarded without buffering the whole stream.
+struct GzipStreamDecoder {
+    decoder: GzDecoder<Vec<u8>>,
+}
+
+impl GzipStreamDecoder {
+    fn new() -> Self {
+        Self {
+            decoder: GzDecoder::new(Vec::new()),
+        }
+    }
+
+    /// Feed a compressed chunk and take the decompressed output
+    fn decode(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        self.decoder.write_all(chunk)?;
+        self.decoder.flush()?;
+        Ok(std::mem::take(self.decoder.get_mut()))
+    }
+}
+
+/// Result of forwarding one SSE event block to the client
+enum BlockOutcome {
+    /// Keep reading the upstream stream
+    Continue,
+    /// The block carried the `[DONE]` sentinel, which has been forwarded
+    Done,
+    /// The client disconnected
+    ClientGone,
+}
+
+/// Forward the `data:` lines of one SSE event block
+async fn forward_sse_block(block: &str, tx: &mpsc::Sender<Result<Event, Infallible>>) -> BlockOutcome {
+    for line in block.lines() {
+        // Keep-alive comments are relayed so downstream stall detection sees them
+        if let Some(comment) = line.strip_prefix(':') {
+            if tx.send(Ok(Event::default().comment(comment.trim_start()))).await.is_err() {
+                return BlockOutcome::ClientGone;
+            }
+            continue;
+        }
+
+        let Some(data) = line.strip_prefix("data: ") else {
+            continue;
+        };
+
+        if data == "[DONE]" {
+            return match tx.send(Ok(create_done_event())).await {
+                Ok(()) => BlockOutcome::Done,
+                Err(_) => BlockOutcome::ClientGone,
+            };
+        }
+
+        if data.is_empty() {
+            continue;
+        }
+
+        let event = Event::default().data(normalize_chunk_finish_reason(data));
+        if tx.send(Ok(event)).await.is_err() {
+            return BlockOutcome::ClientGone;
+        }
+    }
+    BlockOutcome::Continue
+}
+
 fn forward_sse_response(response: ReqwestResponse) -> Result<StreamingResponse, ProxyError> {
     let (tx, rx) = mpsc::channel::<Result<Event, Infallible>>(32);
+    let mut decoder = is_gzip_encoded(&response).then(GzipStreamDecoder::new);
 
     tokio::spawn(async move {
         let mut buffer = String::new();
@@ -363,37 +579,33 @@ fn forward_sse_response(response: ReqwestResponse) -> Result<StreamingResponse,
         while let Some(chunk_result) = stream.next().await {
             match chunk_result {
                 Ok(bytes) => {
-                    buffer.push_str(&String::from_utf8_lossy(&bytes));
+                    let decoded = match decoder.as_mut().map(|decoder| decoder.decode(&bytes)) {
+                        Some(Ok(decoded)) => decoded.into(),
+                        Some(Err(err)) => {
+                            let _ = tx
+                                .send(Ok(create_error_event(ProxyError::Upstream(format!(
+                                    "failed to decode gzip stream: {}",
+                                    err
+                                )))))
+                                .await;
+                            let _ = tx.send(Ok(create_done_event())).await;
+                            return;
+                        }
+                        None => bytes,
+                    };
+                    buffer.push_str(&String::from_utf8_lossy(&decoded));
 
                     while let Some(idx) = buffer.find("\n\n") {
                         let block = buffer[..idx].to_string();
                         buffer.drain(..idx + 2);
 
-                        let mut block_finished = false;
-                        for line in block.lines() {
-                            if let Some(data) = line.strip_prefix("data: ") {
-                                if data == "[DONE]" {
-                                    block_finished = true;
-                                    finished = true;
-                                    if tx.send(Ok(create_done_event())).await.is_err() {
-                                        return;
-                                    }
-                                    break;
-                                }
-
-                                if data.is_empty() {
-                                    continue;
-                                }
-
-                                let event = Event::default().data(data.to_string());
-                                if tx.send(Ok(event)).await.is_err() {
-                                    return;
-                                }
+                        match forward_sse_block(&block, &tx).await {
+                            BlockOutcome::Continue => {}
+                            BlockOutcome::Done => {
+                                finished = true;
+                                break;
                             }
-                        }
-
-                        if block_finished {
-                            break;
+                            BlockOutcome::ClientGone => return,
                         }
                     }
 
@@ -414,7 +626,14 @@ fn forward_sse_response(response: ReqwestResponse) -> Result<StreamingResponse,
         }
 
         if !finished {
-            let _ = tx.send(Ok(create_done_event())).await;
+            // Forward a last event that was not followed by a blank line
+            match forward_sse_block(&buffer, &tx).await {
+                BlockOutcome::Continue => {
+                    tracing::warn!("Upstream closed the event stream without a [DONE] sentinel; terminating it for the client");
+                    let _ = tx.send(Ok(create_done_event())).await;
+                }
+                BlockOutcome::Done | BlockOutcome::ClientGone => {}
+            }
         }
     });
 
@@ -426,7 +645,7 @@ fn forward_sse_response(response: ReqwestResponse) -> Result<StreamingResponse,
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::http_client::HttpClientBuilder;
+    use crate::{adapters::LightLLMPromptTemplate, core::http_client::HttpClientBuilder};
 
     #[tokio::test]
     async fn test_streaming_handler_creation() {
@@ -442,6 +661,7 @@ mod tests {
             "test-model".to_string(),
             None,
             client,
+            LightLLMPromptTemplate::default(),
         );
 
         let request = ChatCompletionRequest::default();
@@ -467,4 +687,395 @@ mod tests {
         assert!(result.is_err());
         println!("✅ OpenAI streaming test passed (expected connection error)");
     }
+
+    const SSE_BODY: &str = concat!(
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_gzip_stream_decoder_is_incremental() {
+        let compressed = gzip(SSE_BODY.repeat(20).as_bytes());
+        let mut decoder = GzipStreamDecoder::new();
+        let mut decoded = Vec::new();
+        let mut output_before_last_chunk = false;
+
+        let chunks: Vec<&[u8]> = compressed.chunks(16).collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let output = decoder.decode(chunk).unwrap();
+            if index + 1 < chunks.len() && !output.is_empty() {
+                output_before_last_chunk = true;
+            }
+            decoded.extend(output);
+        }
+
+        assert!(output_before_last_chunk);
+        assert_eq!(String::from_utf8(decoded).unwrap(), SSE_BODY.repeat(20));
+    }
+
+    #[tokio::test]
+    async fn test_gzip_encoded_sse_stream_is_decoded() {
+        use axum::response::IntoResponse;
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .set_body_raw(gzip(SSE_BODY.as_bytes()), "text/event-stream"),
+            )
+            .mount(&server)
+            .await;
+
+        // Disable client-side decoding so the stream reaches us still compressed
+        let client = HttpClientBuilder::new().compression(false).build().unwrap();
+        let adapter = OpenAIAdapter::new(format!("{}/v1", server.uri()), "gpt-4o".to_string(), None, client);
+
+        let sse = openai_streaming(&adapter, ChatCompletionRequest::default()).await.unwrap();
+        let body = axum::body::to_bytes(sse.into_response().into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains(r#"data: {"choices":[{"index":0,"delta":{"content":"Hel"}}]}"#));
+        assert!(body.contains(r#"data: {"choices":[{"index":0,"delta":{"content":"lo"}}]}"#));
+        assert!(body.contains("data: [DONE]"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_streams_multiplex_over_one_http2_connection() {
+        use hyper::server::conn::http2;
+        use hyper_util::rt::{TokioExecutor, TokioIo};
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+        use std::time::Duration;
+
+        const STREAMS: usize = 8;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let open_streams = Arc::new(AtomicUsize::new(0));
+
+        // h2c backend that keeps every stream open until all of them have started
+        let (accepted, opened) = (connections.clone(), open_streams.clone());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let opened = opened.clone();
+                let service = hyper::service::service_fn(move |_req| {
+                    let opened = opened.clone();
+                    async move {
+                        opened.fetch_add(1, Ordering::SeqCst);
+                        let body = stream::once(async { SSE_BODY.split_inclusive("\n\n").next().unwrap().to_string() })
+                            .chain(stream::once(async move {
+                                while opened.load(Ordering::SeqCst) < STREAMS {
+                                    tokio::time::sleep(Duration::from_millis(5)).await;
+                                }
+                                "data: [DONE]\n\n".to_string()
+                            }))
+                            .map(Ok::<_, Infallible>);
+                        Ok::<_, Infallible>(
+                            axum::http::Response::builder()
+                                .header(CONTENT_TYPE, "text/event-stream")
+                                .body(axum::body::Body::from_stream(body))
+                                .unwrap(),
+                        )
+                    }
+                });
+                tokio::spawn(http2::Builder::new(TokioExecutor::new()).serve_connection(TokioIo::new(stream), service));
+            }
+        });
+
+        let client = HttpClientBuilder::new().http2_prior_knowledge(true).build().unwrap();
+        let adapter = OpenAIAdapter::new(format!("http://{}/v1", addr), "gpt-4o".to_string(), None, client);
+        let streams = futures_util::future::join_all((0..STREAMS).map(|_| async {
+            let response = adapter.stream_chat_completions_raw(ChatCompletionRequest::default()).await.unwrap();
+            response.text().await.unwrap()
+        }));
+        let bodies = tokio::time::timeout(Duration::from_secs(10), streams)
+            .await
+            .expect("streams should run concurrently");
+
+        assert!(bodies.iter().all(|body| body.ends_with("data: [DONE]\n\n")));
+        assert_eq!(open_streams.load(Ordering::SeqCst), STREAMS);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_closed_without_done_is_terminated() {
+        use axum::response::IntoResponse;
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        // Upstream closes mid-event: no trailing blank line and no [DONE]
+        let truncated = SSE_BODY.trim_end_matches("data: [DONE]\n\n").trim_end();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(truncated, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let client = HttpClientBuilder::new().build().unwrap();
+        let adapter = OpenAIAdapter::new(format!("{}/v1", server.uri()), "gpt-4o".to_string(), None, client);
+
+        let sse = openai_streaming(&adapter, ChatCompletionRequest::default()).await.unwrap();
+        let body = axum::body::to_bytes(sse.into_response().into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains(r#"data: {"choices":[{"index":0,"delta":{"content":"lo"}}]}"#));
+        assert!(body.ends_with("data: [DONE]\n\n"));
+        assert_eq!(body.matches("[DONE]").count(), 1);
+    }
+
+    /// Client-facing SSE body of a Bedrock stream whose upstream sends `frames`
+    #[cfg(feature = "adapter-aws")]
+    async fn bedrock_stream_body(frames: Vec<u8>) -> String {
+        use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/model/anthropic.claude-v2/invoke-with-response-stream"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(frames, "application/vnd.amazon.eventstream"))
+            .mount(&server)
+            .await;
+        let adapter = AWSBedrockAdapter::new(
+            "https://bedrock-runtime.us-east-1.amazonaws.com".to_string(),
+            "anthropic.claude-v2".to_string(),
+            Some("key:secret".to_string()),
+            None,
+            HttpClientBuilder::new().build().unwrap(),
+        )
+        .unwrap()
+        .with_primary_endpoint(server.uri());
+
+        let sse = aws_streaming(&adapter, ChatCompletionRequest::default()).await.unwrap();
+        let body = axum::body::to_bytes(sse.into_response().into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[cfg(feature = "adapter-aws")]
+    #[tokio::test]
+    async fn test_bedrock_max_tokens_stop_reported_as_length() {
+        use crate::adapters::aws::event_stream::tests::chunk;
+
+        let mut frames = chunk(serde_json::json!({"completion": " Hello", "stop_reason": null}));
+        frames.extend(chunk(serde_json::json!({"completion": "", "stop_reason": "max_tokens"})));
+
+        let body = bedrock_stream_body(frames).await;
+        let chunks: Vec<serde_json::Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter(|data| *data != "[DONE]")
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+
+        assert_eq!(chunks[0]["choices"][0]["delta"]["content"], " Hello");
+        assert_eq!(chunks.last().unwrap()["choices"][0]["finish_reason"], "length");
+        assert!(body.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[cfg(feature = "adapter-aws")]
+    #[tokio::test]
+    async fn test_bedrock_stream_cut_mid_frame_ends_with_error() {
+        use crate::adapters::aws::event_stream::tests::chunk;
+
+        let mut frames = chunk(serde_json::json!({"completion": " Hello", "stop_reason": null}));
+        let next = chunk(serde_json::json!({"completion": ", world", "stop_reason": "stop_sequence"}));
+        frames.extend_from_slice(&next[..next.len() / 2]);
+
+        let body = bedrock_stream_body(frames).await;
+
+        assert!(body.contains("mid-message"));
+        assert!(!body.contains("finish_reason\":\"stop"));
+        assert!(body.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_direct_streaming_reassembles_into_completion() {
+        use axum::response::IntoResponse;
+
+        let adapter = DirectAdapter::new("llama".to_string(), None);
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "messages": [{"role": "user", "content": "Hello there"}]
+        }))
+        .unwrap();
+        let completion = adapter.chat_completions(request.clone()).await.unwrap().choices[0]
+            .message
+            .content
+            .clone()
+            .unwrap();
+
+        let sse = direct_streaming(&adapter, request).await.unwrap();
+        let body = axum::body::to_bytes(sse.into_response().into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let chunks: Vec<serde_json::Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter(|data| *data != "[DONE]")
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        let deltas: Vec<&str> = chunks
+            .iter()
+            .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str())
+            .collect();
+
+        assert!(deltas.len() > 1, "completion must be streamed in several chunks");
+        assert_eq!(deltas.concat(), completion);
+        assert_eq!(chunks.last().unwrap()["choices"][0]["finish_reason"], "stop");
+        assert!(body.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_chat_completion_is_not_buffered() {
+        use hyper::server::conn::http1;
+        use hyper_util::rt::TokioIo;
+        use std::time::Duration;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let released = std::sync::Arc::new(tokio::sync::Mutex::new(Some(released)));
+
+        // Chunked backend that holds the rest of the body until the test releases it
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = hyper::service::service_fn(move |_req| {
+                let released = released.clone();
+                async move {
+                    let body = stream::once(async { SSE_BODY.split_inclusive("\n\n").next().unwrap().to_string() })
+                        .chain(stream::once(async move {
+                            if let Some(released) = released.lock().await.take() {
+                                let _ = released.await;
+                            }
+                            "data: [DONE]\n\n".to_string()
+                        }))
+                        .map(Ok::<_, Infallible>);
+                    Ok::<_, Infallible>(
+                        axum::http::Response::builder()
+                            .header(CONTENT_TYPE, "text/event-stream")
+                            .body(axum::body::Body::from_stream(body))
+                            .unwrap(),
+                    )
+                }
+            });
+            let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+        });
+
+        let client = HttpClientBuilder::new().build().unwrap();
+        let adapter = OpenAIAdapter::new(format!("http://{}/v1", addr), "gpt-4o".to_string(), None, client);
+        let request = ChatCompletionRequest { stream: Some(true), ..Default::default() };
+
+        let response = tokio::time::timeout(Duration::from_secs(5), adapter.chat_completions_http(request))
+            .await
+            .expect("response should not wait for the upstream body to finish")
+            .unwrap();
+        let mut body = response.into_body().into_data_stream();
+        let first = tokio::time::timeout(Duration::from_secs(5), body.next())
+            .await
+            .expect("first chunk should arrive before the upstream finishes")
+            .unwrap()
+            .unwrap();
+        assert!(String::from_utf8_lossy(&first).contains("\"Hel\""));
+
+        release.send(()).unwrap();
+        let mut rest = Vec::new();
+        while let Some(chunk) = body.next().await {
+            rest.extend_from_slice(&chunk.unwrap());
+        }
+        assert!(String::from_utf8(rest).unwrap().ends_with("data: [DONE]\n\n"));
+    }
+
+    /// Serve every connection on a local HTTP/1 listener with the SSE body built by `body`
+    async fn serve_sse<F, S>(body: F) -> std::net::SocketAddr
+    where
+        F: Fn() -> S + Clone + Send + Sync + 'static,
+        S: Stream<Item = String> + Send + 'static,
+    {
+        use hyper::server::conn::http1;
+        use hyper_util::rt::TokioIo;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let body = body.clone();
+                let service = hyper::service::service_fn(move |_req| {
+                    let body = body().map(Ok::<_, Infallible>);
+                    async move {
+                        Ok::<_, Infallible>(
+                            axum::http::Response::builder()
+                                .header(CONTENT_TYPE, "text/event-stream")
+                                .body(axum::body::Body::from_stream(body))
+                                .unwrap(),
+                        )
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_yields_stall_error_after_heartbeat() {
+        use std::time::Duration;
+
+        let addr = serve_sse(|| {
+            stream::once(async { SSE_BODY.split_inclusive("\n\n").next().unwrap().to_string() }).chain(stream::pending())
+        })
+        .await;
+        let client = HttpClientBuilder::new().build().unwrap();
+        let adapter = Adapter::OpenAI(OpenAIAdapter::new(format!("http://{}/v1", addr), "gpt-4o".to_string(), None, client));
+        let handler = StreamingHandler::new().unwrap().with_heartbeat_timeout(Duration::from_millis(200));
+
+        let started = std::time::Instant::now();
+        let response = handler.stream(&adapter, ChatCompletionRequest::default()).await.unwrap();
+        let body = tokio::time::timeout(Duration::from_secs(5), axum::body::to_bytes(response.into_body(), usize::MAX))
+            .await
+            .expect("a stalled stream should be ended")
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(body.contains(r#""content":"Hel""#));
+        let stall = body.lines().filter(|line| !line.is_empty()).last().unwrap().strip_prefix("data: ").unwrap();
+        let stall: serde_json::Value = serde_json::from_str(stall).unwrap();
+        assert_eq!(stall["error"]["type"], "stream_stalled");
+        assert_eq!(stall["error"]["code"], "stream_stalled");
+    }
+
+    #[tokio::test]
+    async fn test_keep_alives_reset_the_heartbeat_window() {
+        use std::time::Duration;
+
+        // Silent for 400ms overall, but never for longer than 100ms
+        let addr = serve_sse(|| {
+            stream::iter(0..4)
+                .then(|_| async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    ": keep-alive\n\n".to_string()
+                })
+                .chain(stream::once(async { SSE_BODY.to_string() }))
+        })
+        .await;
+        let client = HttpClientBuilder::new().build().unwrap();
+        let adapter = Adapter::OpenAI(OpenAIAdapter::new(format!("http://{}/v1", addr), "gpt-4o".to_string(), None, client));
+        let handler = StreamingHandler::new().unwrap().with_heartbeat_timeout(Duration::from_millis(300));
+
+        let response = handler.stream(&adapter, ChatCompletionRequest::default()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(!body.contains("stream_stalled"));
+        assert!(body.contains(": keep-alive"));
+        assert!(body.ends_with("data: [DONE]\n\n"));
+    }
 }
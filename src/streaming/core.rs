@@ -7,8 +7,22 @@ use crate::{
     error::ProxyError,
     schemas::{ChatCompletionChunk, StreamChoice, StreamDelta, StreamingError, ErrorDetails, Usage},
 };
-use axum::response::sse::Event;
-use std::time::{SystemTime, UNIX_EPOCH};
+use axum::{
+    body::{Body, Bytes},
+    response::{sse::Event, Response},
+};
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 use uuid::Uuid;
 
 /// Streaming response state management
@@ -79,6 +93,11 @@ pub fn create_content_event(state: &mut StreamingState, content: String) -> Even
 
 /// Create a final streaming event to end the stream
 pub fn create_final_event(state: &mut StreamingState) -> Event {
+    create_final_event_with_reason(state, "stop")
+}
+
+/// Create a final streaming event reporting why the backend stopped
+pub fn create_final_event_with_reason(state: &mut StreamingState, finish_reason: &str) -> Event {
     let chunk = ChatCompletionChunk {
         id: state.request_id.clone(),
         object: "chat.completion.chunk".to_string(),
@@ -92,7 +111,7 @@ pub fn create_final_event(state: &mut StreamingState) -> Event {
                 function_call: None,
                 tool_calls: None,
             },
-            finish_reason: Some("stop".to_string()),
+            finish_reason: Some(finish_reason.to_string()),
         }],
         usage: Some(Usage {
             prompt_tokens: 0,
@@ -114,7 +133,11 @@ pub fn create_error_event(error: ProxyError) -> Event {
             message: error.to_string(),
             r#type: match error {
                 ProxyError::BadRequest(_) => "invalid_request_error",
-                ProxyError::Upstream(_) => "api_error",
+                ProxyError::Upstream(_)
+                | ProxyError::UpstreamStatus { .. }
+                | ProxyError::ModelLoading(_)
+                | ProxyError::Transport { .. }
+                | ProxyError::IncompleteResponse { .. } => "api_error",
                 ProxyError::Internal(_) => "internal_error",
                 ProxyError::Serialization(_) => "serialization_error",
             }.to_string(),
@@ -146,6 +169,7 @@ pub struct StreamingMetrics {
     pub total_bytes: usize,
     pub stream_duration_ms: u64,
     pub errors: usize,
+    pub time_to_first_token_ms: Option<u64>,
 }
 
 impl StreamingMetrics {
@@ -169,6 +193,264 @@ impl StreamingMetrics {
     pub fn set_duration(&mut self, duration_ms: u64) {
         self.stream_duration_ms = duration_ms;
     }
+
+    /// Record the time to the first token, keeping the earliest value
+    pub fn record_first_token(&mut self, elapsed_ms: u64) {
+        self.time_to_first_token_ms.get_or_insert(elapsed_ms);
+    }
+}
+
+/// Aggregated streaming statistics across all streams served by the proxy
+#[derive(Debug, Default)]
+pub struct StreamingStats {
+    active_streams: AtomicU64,
+    completed_streams: AtomicU64,
+    aborted_streams: AtomicU64,
+    first_token_ms_total: AtomicU64,
+    first_token_count: AtomicU64,
+    tokens_total: AtomicU64,
+    generation_ms_total: AtomicU64,
+}
+
+/// Point-in-time view of the streaming statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamingStatsSnapshot {
+    /// Streams currently being served
+    pub active_streams: u64,
+    /// Streams that ran to completion
+    pub completed_streams: u64,
+    /// Streams dropped before completion (e.g. client disconnects)
+    pub aborted_streams: u64,
+    /// Mean time from request start to the first streamed chunk
+    pub mean_time_to_first_token_ms: f64,
+    /// Mean streamed chunks per second after the first chunk
+    pub mean_tokens_per_second: f64,
+    /// Fraction of finished streams that were aborted (0.0 to 1.0)
+    pub abort_rate: f64,
+}
+
+impl StreamingStats {
+    /// Create empty streaming statistics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a stream has started
+    pub fn stream_started(&self) {
+        self.active_streams.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that a stream has ended, folding in its per-stream metrics
+    pub fn stream_finished(&self, metrics: &StreamingMetrics, aborted: bool) {
+        self.active_streams.fetch_sub(1, Ordering::Relaxed);
+
+        if aborted {
+            self.aborted_streams.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.completed_streams.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(first_token_ms) = metrics.time_to_first_token_ms {
+            self.first_token_ms_total.fetch_add(first_token_ms, Ordering::Relaxed);
+            self.first_token_count.fetch_add(1, Ordering::Relaxed);
+
+            let generation_ms = metrics.stream_duration_ms.saturating_sub(first_token_ms);
+            self.tokens_total.fetch_add(metrics.total_chunks as u64, Ordering::Relaxed);
+            self.generation_ms_total.fetch_add(generation_ms, Ordering::Relaxed);
+        }
+    }
+
+    /// Take a snapshot of the current statistics
+    pub fn snapshot(&self) -> StreamingStatsSnapshot {
+        let completed_streams = self.completed_streams.load(Ordering::Relaxed);
+        let aborted_streams = self.aborted_streams.load(Ordering::Relaxed);
+        let first_token_count = self.first_token_count.load(Ordering::Relaxed);
+        let generation_ms_total = self.generation_ms_total.load(Ordering::Relaxed);
+        let finished_streams = completed_streams + aborted_streams;
+
+        let mean_time_to_first_token_ms = if first_token_count > 0 {
+            self.first_token_ms_total.load(Ordering::Relaxed) as f64 / first_token_count as f64
+        } else {
+            0.0
+        };
+
+        let mean_tokens_per_second = if generation_ms_total > 0 {
+            self.tokens_total.load(Ordering::Relaxed) as f64 * 1000.0 / generation_ms_total as f64
+        } else {
+            0.0
+        };
+
+        let abort_rate = if finished_streams > 0 {
+            aborted_streams as f64 / finished_streams as f64
+        } else {
+            0.0
+        };
+
+        StreamingStatsSnapshot {
+            active_streams: self.active_streams.load(Ordering::Relaxed),
+            completed_streams,
+            aborted_streams,
+            mean_time_to_first_token_ms,
+            mean_tokens_per_second,
+            abort_rate,
+        }
+    }
+}
+
+/// Body stream wrapper that records per-stream metrics into `StreamingStats`.
+///
+/// Each SSE `data:` event other than `[DONE]` counts as one chunk. A stream
+/// that is dropped before reaching its end is recorded as aborted.
+struct MeteredStream<S> {
+    inner: S,
+    stats: Arc<StreamingStats>,
+    metrics: StreamingMetrics,
+    started: Instant,
+    finished: bool,
+}
+
+impl<S> MeteredStream<S> {
+    fn finish(&mut self, aborted: bool) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.metrics.set_duration(self.started.elapsed().as_millis() as u64);
+        self.stats.stream_finished(&self.metrics, aborted);
+    }
+}
+
+impl<S, E> Stream for MeteredStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.inner.poll_next_unpin(cx);
+
+        match &poll {
+            Poll::Ready(Some(Ok(bytes))) => {
+                let text = String::from_utf8_lossy(bytes);
+                let chunks = text
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data:"))
+                    .filter(|data| data.trim() != "[DONE]")
+                    .count();
+
+                if chunks > 0 {
+                    let elapsed_ms = self.started.elapsed().as_millis() as u64;
+                    self.metrics.record_first_token(elapsed_ms);
+                    for _ in 0..chunks {
+                        self.metrics.record_chunk(bytes.len() / chunks);
+                    }
+                }
+            }
+            Poll::Ready(Some(Err(_))) => self.metrics.record_error(),
+            Poll::Ready(None) => self.finish(false),
+            Poll::Pending => {}
+        }
+
+        poll
+    }
+}
+
+impl<S> Drop for MeteredStream<S> {
+    fn drop(&mut self) {
+        self.finish(true);
+    }
+}
+
+/// Wrap a streaming response body so its metrics are recorded in `stats`.
+///
+/// `started` marks the beginning of the request and is the reference point
+/// for time-to-first-token.
+pub fn meter_streaming_response(
+    response: Response,
+    stats: Arc<StreamingStats>,
+    started: Instant,
+) -> Response {
+    stats.stream_started();
+
+    let (parts, body) = response.into_parts();
+    let metered = MeteredStream {
+        inner: body.into_data_stream(),
+        stats,
+        metrics: StreamingMetrics::new(),
+        started,
+        finished: false,
+    };
+
+    Response::from_parts(parts, Body::from_stream(metered))
+}
+
+/// Body stream wrapper that ends a stream which has gone quiet.
+///
+/// Any data from upstream, including SSE keep-alive comments, resets the
+/// heartbeat window. Once the window lapses a `stream_stalled` error event is
+/// emitted and the stream ends.
+struct StallGuardedStream<S> {
+    inner: S,
+    heartbeat: Duration,
+    deadline: Pin<Box<tokio::time::Sleep>>,
+    stalled: bool,
+}
+
+impl<S, E> Stream for StallGuardedStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.stalled {
+            return Poll::Ready(None);
+        }
+
+        match self.inner.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => {
+                let next = tokio::time::Instant::now() + self.heartbeat;
+                self.deadline.as_mut().reset(next);
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => {
+                if self.deadline.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.stalled = true;
+                Poll::Ready(Some(Ok(stall_event(self.heartbeat))))
+            }
+        }
+    }
+}
+
+/// Encode the SSE error event sent when a stream stalls
+fn stall_event(heartbeat: Duration) -> Bytes {
+    let error = StreamingError {
+        error: ErrorDetails {
+            message: format!("stream stalled: no data received within {} ms", heartbeat.as_millis()),
+            r#type: "stream_stalled".to_string(),
+            code: Some("stream_stalled".to_string()),
+        },
+    };
+    Bytes::from(format!("data: {}\n\n", serde_json::to_string(&error).unwrap_or_default()))
+}
+
+/// Wrap a streaming response body so a stall ends it with an error event.
+///
+/// If no chunk (keep-alives included) arrives within `heartbeat`, a
+/// `stream_stalled` error event is yielded and the body is closed.
+pub fn with_stall_detection(response: Response, heartbeat: Duration) -> Response {
+    let (parts, body) = response.into_parts();
+    let guarded = StallGuardedStream {
+        inner: body.into_data_stream(),
+        heartbeat,
+        deadline: Box::pin(tokio::time::sleep(heartbeat)),
+        stalled: false,
+    };
+
+    Response::from_parts(parts, Body::from_stream(guarded))
 }
 
 #[cfg(test)]
@@ -242,4 +524,30 @@ mod tests {
         assert_eq!(metrics.errors, 1);
         assert_eq!(metrics.stream_duration_ms, 500);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_streaming_stats_aggregation() {
+        let stats = StreamingStats::new();
+
+        stats.stream_started();
+        stats.stream_started();
+        assert_eq!(stats.snapshot().active_streams, 2);
+
+        let mut completed = StreamingMetrics::new();
+        completed.record_first_token(100);
+        completed.record_chunk(10);
+        completed.record_chunk(10);
+        completed.set_duration(1100);
+        stats.stream_finished(&completed, false);
+
+        stats.stream_finished(&StreamingMetrics::new(), true);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.active_streams, 0);
+        assert_eq!(snapshot.completed_streams, 1);
+        assert_eq!(snapshot.aborted_streams, 1);
+        assert_eq!(snapshot.mean_time_to_first_token_ms, 100.0);
+        assert_eq!(snapshot.mean_tokens_per_second, 2.0);
+        assert_eq!(snapshot.abort_rate, 0.5);
+    }
+}
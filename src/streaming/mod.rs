@@ -15,7 +15,8 @@ pub mod adapters;
 // Re-export commonly used streaming types
 pub use core::{
     StreamingState, StreamingResponse,
-    create_error_event, StreamingMetrics
+    create_error_event, StreamingMetrics,
+    StreamingStats, StreamingStatsSnapshot, meter_streaming_response, with_stall_detection,
 };
 pub use adapters::{StreamingAdapter, StreamingHandler};
 
@@ -54,6 +55,14 @@ pub async fn create_streaming_response(
         crate::adapters::Adapter::Custom(adapter) => {
             adapters::custom_streaming(adapter, request).await
         },
-        _ => Err(ProxyError::BadRequest("Streaming not supported for this adapter".to_string())),
+        crate::adapters::Adapter::AWSBedrock(adapter) => {
+            adapters::aws_streaming(adapter, request).await
+        },
+        crate::adapters::Adapter::Direct(adapter) => {
+            adapters::direct_streaming(adapter, request).await
+        },
+        crate::adapters::Adapter::Template(_) => Err(ProxyError::BadRequest(
+            "Adapter template does not support streaming".to_string()
+        )),
     }
 }
\ No newline at end of file
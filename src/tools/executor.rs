@@ -3,11 +3,15 @@
 //! This module provides tool call execution functionality,
 //! consolidating the function execution logic with proper error handling.
 
-use crate::schemas::{ToolCall, FunctionCall};
+use crate::config::Config;
+use crate::core::redaction::ToolArgumentRedaction;
+use crate::schemas::{FunctionCall, Message, ToolCall};
+use futures_util::{stream, StreamExt};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 use super::{ToolError, ToolCallHistoryEntry, registry::FunctionRegistry};
 
 /// Function execution result
@@ -28,8 +32,21 @@ pub struct ToolCallExecutor {
     history: Vec<ToolCallHistoryEntry>,
     /// Maximum history size
     max_history_size: usize,
+    /// Timeout applied to every function without its own timeout
+    execution_timeout: Option<Duration>,
+    /// Per-function timeouts overriding the global one
+    function_timeouts: HashMap<String, Duration>,
+    /// Maximum number of tool calls executed concurrently in a batch
+    max_concurrent_tools: usize,
+    /// Maximum tool-call/tool-result cycles in `run_tool_loop`
+    max_tool_iterations: usize,
+    /// How arguments appear in the call history and logs
+    argument_redaction: ToolArgumentRedaction,
 }
 
+/// Default cap on tool loop iterations
+pub const DEFAULT_MAX_TOOL_ITERATIONS: usize = 10;
+
 impl ToolCallExecutor {
     /// Create a new tool call executor
     pub fn new(registry: FunctionRegistry) -> Self {
@@ -38,7 +55,57 @@ impl ToolCallExecutor {
             handlers: HashMap::new(),
             history: Vec::new(),
             max_history_size: 1000,
+            execution_timeout: None,
+            function_timeouts: HashMap::new(),
+            max_concurrent_tools: 1,
+            max_tool_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+            argument_redaction: ToolArgumentRedaction::Raw,
+        }
+    }
+
+    /// Create a tool call executor using the timeout and concurrency limits from configuration
+    pub fn from_config(registry: FunctionRegistry, config: &Config) -> Self {
+        let mut executor = Self::new(registry)
+            .with_max_concurrent_tools(config.max_concurrent_tools)
+            .with_argument_redaction(ToolArgumentRedaction::from_config(config));
+        if config.max_tool_iterations > 0 {
+            executor = executor.with_max_tool_iterations(config.max_tool_iterations);
         }
+        if config.tool_execution_timeout > 0 {
+            executor.with_execution_timeout(Duration::from_secs(config.tool_execution_timeout))
+        } else {
+            executor
+        }
+    }
+
+    /// Set the timeout applied to every function call
+    pub fn with_execution_timeout(mut self, timeout: Duration) -> Self {
+        self.execution_timeout = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for a single function, overriding the global timeout
+    pub fn with_function_timeout(mut self, name: impl Into<String>, timeout: Duration) -> Self {
+        self.function_timeouts.insert(name.into(), timeout);
+        self
+    }
+
+    /// Set how many tool calls of a batch may run at the same time
+    pub fn with_max_concurrent_tools(mut self, max_concurrent_tools: usize) -> Self {
+        self.max_concurrent_tools = max_concurrent_tools.max(1);
+        self
+    }
+
+    /// Set how many tool-call/tool-result cycles `run_tool_loop` may perform
+    pub fn with_max_tool_iterations(mut self, max_tool_iterations: usize) -> Self {
+        self.max_tool_iterations = max_tool_iterations;
+        self
+    }
+
+    /// Set how arguments appear in the call history and logs
+    pub fn with_argument_redaction(mut self, argument_redaction: ToolArgumentRedaction) -> Self {
+        self.argument_redaction = argument_redaction;
+        self
     }
 
     /// Register a function handler
@@ -62,14 +129,106 @@ impl ToolCallExecutor {
 
     /// Execute a single tool call
     pub async fn execute_tool_call(&mut self, tool_call: ToolCall) -> Result<Value, ToolError> {
+        let (history_entry, result) = self.run_tool_call(tool_call).await;
+        self.add_to_history(history_entry);
+        result
+    }
+
+    /// Execute multiple tool calls
+    ///
+    /// Up to `max_concurrent_tools` calls run at once. Results are returned in
+    /// the order of `tool_calls`, and a call that fails or times out does not
+    /// affect the others.
+    pub async fn execute_tool_calls(
+        &mut self,
+        tool_calls: Vec<ToolCall>,
+    ) -> Vec<Result<Value, ToolError>> {
+        let outcomes: Vec<_> = stream::iter(tool_calls)
+            .map(|tool_call| self.run_tool_call(tool_call))
+            .buffered(self.max_concurrent_tools)
+            .collect()
+            .await;
+
+        let mut results = Vec::with_capacity(outcomes.len());
+        for (history_entry, result) in outcomes {
+            self.add_to_history(history_entry);
+            results.push(result);
+        }
+
+        results
+    }
+
+    /// Drive a tool-calling conversation until the model answers without tool calls.
+    ///
+    /// `model` receives the conversation so far and returns the next assistant
+    /// message. Each message carrying tool calls is executed and its results are
+    /// appended as `tool` messages before the model is called again. After
+    /// `max_tool_iterations` cycles a further tool-calling response fails with
+    /// `ToolError::IterationLimitExceeded`; `messages` then holds the full
+    /// transcript including that last assistant message.
+    pub async fn run_tool_loop<F, Fut>(
+        &mut self,
+        messages: &mut Vec<Message>,
+        mut model: F,
+    ) -> Result<Message, ToolError>
+    where
+        F: FnMut(Vec<Message>) -> Fut,
+        Fut: Future<Output = Result<Message, ToolError>>,
+    {
+        let mut iterations = 0;
+
+        loop {
+            let assistant = model(messages.clone()).await?;
+            let tool_calls = assistant.tool_calls.clone().unwrap_or_default();
+            messages.push(assistant.clone());
+
+            if tool_calls.is_empty() {
+                return Ok(assistant);
+            }
+            if iterations >= self.max_tool_iterations {
+                return Err(ToolError::IterationLimitExceeded {
+                    max_iterations: self.max_tool_iterations,
+                });
+            }
+            iterations += 1;
+
+            let results = self.execute_tool_calls(tool_calls.clone()).await;
+            for (tool_call, result) in tool_calls.into_iter().zip(results) {
+                let content = match result {
+                    Ok(value) => value.to_string(),
+                    Err(error) => serde_json::json!({ "error": error.to_string() }).to_string(),
+                };
+                messages.push(Message {
+                    role: "tool".to_string(),
+                    content: Some(content),
+                    name: Some(tool_call.function.name),
+                    tool_calls: None,
+                    function_call: None,
+                    tool_call_id: Some(tool_call.id),
+                    audio: None,
+                    reasoning_content: None,
+                });
+            }
+        }
+    }
+
+    /// Run a tool call with its timeout, returning the history entry to record
+    async fn run_tool_call(&self, tool_call: ToolCall) -> (ToolCallHistoryEntry, Result<Value, ToolError>) {
         let function_name = tool_call.function.name.clone();
         let arguments: serde_json::Value = serde_json::from_str(&tool_call.function.arguments).unwrap_or_default();
 
-        // Create history entry
-        let mut history_entry = ToolCallHistoryEntry::new(
+        // Create history entry, recording only the redacted arguments
+        let recorded_arguments = self.argument_redaction.apply(&arguments);
+        tracing::debug!(
+            tool_call_id = %tool_call.id,
+            function = %function_name,
+            arguments = %recorded_arguments,
+            "Executing tool call"
+        );
+        let history_entry = ToolCallHistoryEntry::new(
             tool_call.id.clone(),
             function_name.clone(),
-            arguments.clone(),
+            recorded_arguments,
         );
 
         // Check if function is registered
@@ -77,9 +236,7 @@ impl ToolCallExecutor {
             let error = ToolError::FunctionNotFound {
                 name: function_name.clone(),
             };
-            history_entry = history_entry.with_error(error.to_string());
-            self.add_to_history(history_entry);
-            return Err(error);
+            return (history_entry.with_error(error.to_string()), Err(error));
         }
 
         // Check if handler is available
@@ -89,40 +246,35 @@ impl ToolCallExecutor {
                 let error = ToolError::ExecutionFailed {
                     message: format!("No handler registered for function: {}", function_name),
                 };
-                history_entry = history_entry.with_error(error.to_string());
-                self.add_to_history(history_entry);
-                return Err(error);
+                return (history_entry.with_error(error.to_string()), Err(error));
             }
         };
 
-        // Execute the function
-        match handler(arguments).await {
-            Ok(result) => {
-                history_entry = history_entry.with_result(result.clone());
-                self.add_to_history(history_entry);
-                Ok(result)
-            }
-            Err(error) => {
-                history_entry = history_entry.with_error(error.to_string());
-                self.add_to_history(history_entry);
-                Err(error)
-            }
-        }
-    }
-
-    /// Execute multiple tool calls
-    pub async fn execute_tool_calls(
-        &mut self,
-        tool_calls: Vec<ToolCall>,
-    ) -> Vec<Result<Value, ToolError>> {
-        let mut results = Vec::with_capacity(tool_calls.len());
+        // Execute the function, dropping (and so aborting) it once its timeout elapses
+        let timeout = self
+            .function_timeouts
+            .get(&function_name)
+            .copied()
+            .or(self.execution_timeout);
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, handler(arguments))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ToolError::ExecutionFailed {
+                        message: format!(
+                            "Function '{}' timed out after {}ms",
+                            function_name,
+                            timeout.as_millis()
+                        ),
+                    })
+                }),
+            None => handler(arguments).await,
+        };
 
-        for tool_call in tool_calls {
-            let result = self.execute_tool_call(tool_call).await;
-            results.push(result);
+        match result {
+            Ok(result) => (history_entry.with_result(result.clone()), Ok(result)),
+            Err(error) => (history_entry.with_error(error.to_string()), Err(error)),
         }
-
-        results
     }
 
     /// Get call history
@@ -335,4 +487,179 @@ mod tests {
         // History should be trimmed to max size
         assert_eq!(executor.history().len(), 2);
     }
-}
\ No newline at end of file
+
+    async fn hanging_function(_args: Value) -> FunctionResult {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(serde_json::json!({"result": "too late"}))
+    }
+
+    fn tool_call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            tool_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: "{}".to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hanging_function_times_out_while_fast_one_succeeds() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(FunctionDefinition::new("slow_func".to_string()));
+        registry.register(FunctionDefinition::new("fast_func".to_string()));
+
+        let mut executor = ToolCallExecutor::new(registry)
+            .with_execution_timeout(Duration::from_millis(100))
+            .with_max_concurrent_tools(4);
+        executor.register_handler("slow_func".to_string(), hanging_function).unwrap();
+        executor.register_handler("fast_func".to_string(), sample_function).unwrap();
+
+        let started = std::time::Instant::now();
+        let results = executor
+            .execute_tool_calls(vec![tool_call("call_slow", "slow_func"), tool_call("call_fast", "fast_func")])
+            .await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(matches!(&results[0], Err(ToolError::ExecutionFailed { message }) if message.contains("timed out")));
+        assert!(results[1].is_ok());
+
+        let history = executor.history();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].error.is_some());
+        assert!(history[1].result.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_per_function_timeout_overrides_global() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(FunctionDefinition::new("slow_func".to_string()));
+
+        let mut executor = ToolCallExecutor::new(registry)
+            .with_execution_timeout(Duration::from_secs(60))
+            .with_function_timeout("slow_func", Duration::from_millis(50));
+        executor.register_handler("slow_func".to_string(), hanging_function).unwrap();
+
+        let result = executor.execute_tool_call(tool_call("call_slow", "slow_func")).await;
+        assert!(matches!(result, Err(ToolError::ExecutionFailed { .. })));
+    }
+
+    fn assistant_message(tool_calls: Option<Vec<ToolCall>>) -> Message {
+        Message {
+            role: "assistant".to_string(),
+            content: tool_calls.is_none().then(|| "Done".to_string()),
+            name: None,
+            tool_calls,
+            function_call: None,
+            tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_stops_at_iteration_cap() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(FunctionDefinition::new("test_func".to_string()));
+
+        let mut executor = ToolCallExecutor::new(registry).with_max_tool_iterations(3);
+        executor.register_handler("test_func".to_string(), sample_function).unwrap();
+
+        // A model that never stops calling tools
+        let mut model_calls = 0;
+        let mut messages = Vec::new();
+        let result = executor
+            .run_tool_loop(&mut messages, |_| {
+                model_calls += 1;
+                let call = tool_call(&format!("call_{}", model_calls), "test_func");
+                async move { Ok(assistant_message(Some(vec![call]))) }
+            })
+            .await;
+
+        assert!(matches!(result, Err(ToolError::IterationLimitExceeded { max_iterations: 3 })));
+        assert_eq!(model_calls, 4);
+        assert_eq!(executor.history().len(), 3);
+        // Three assistant/tool pairs plus the final assistant message
+        assert_eq!(messages.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_returns_final_answer() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(FunctionDefinition::new("test_func".to_string()));
+
+        let mut executor = ToolCallExecutor::new(registry);
+        executor.register_handler("test_func".to_string(), sample_function).unwrap();
+
+        let mut messages = Vec::new();
+        let result = executor
+            .run_tool_loop(&mut messages, |conversation| async move {
+                let answered = conversation.iter().any(|message| message.role == "tool");
+                let tool_calls = (!answered).then(|| vec![tool_call("call_1", "test_func")]);
+                Ok(assistant_message(tool_calls))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result.content.as_deref(), Some("Done"));
+        assert_eq!(messages[1].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(messages.len(), 3);
+    }
+
+    /// Writer collecting log output for assertions
+    #[cfg(feature = "cli")]
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "cli")]
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    #[tokio::test]
+    async fn test_masked_tool_call_arguments_in_logs_and_history() {
+        let mut config = Config::for_test();
+        config.tool_argument_redaction = "mask".to_string();
+        config.log_level = "debug".to_string();
+        config.log_format = "json".to_string();
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let _guard = tracing::subscriber::set_default(config.log_subscriber(move || writer.clone()));
+
+        let mut registry = FunctionRegistry::new();
+        registry.register(FunctionDefinition::new("transfer".to_string()));
+        let mut executor = ToolCallExecutor::from_config(registry, &config);
+        executor.register_handler("transfer".to_string(), sample_function).unwrap();
+
+        let mut call = tool_call("call_1", "transfer");
+        call.function.arguments = r#"{"account_number":"DE89370400440532013000"}"#.to_string();
+        let result = executor.execute_tool_call(call).await.unwrap();
+        // The handler still receives the real arguments
+        assert_eq!(result["input"]["account_number"], "DE89370400440532013000");
+
+        let masked = serde_json::json!({"account_number": "[REDACTED]"});
+        let entry = &executor.history()[0];
+        assert_eq!(entry.function_name, "transfer");
+        assert_eq!(entry.arguments, masked);
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("DE89370400440532013000"));
+        let event: Value = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .find(|line| line["message"] == "Executing tool call")
+            .expect("tool call should be logged");
+        assert_eq!(event["function"], "transfer");
+        assert_eq!(serde_json::from_str::<Value>(event["arguments"].as_str().unwrap()).unwrap(), masked);
+    }
+}
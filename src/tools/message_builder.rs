@@ -48,6 +48,8 @@ impl ToolCallMessageBuilder {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         });
         self
     }
@@ -61,6 +63,8 @@ impl ToolCallMessageBuilder {
             tool_calls: None,
             function_call: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         });
         self
     }
@@ -74,6 +78,8 @@ impl ToolCallMessageBuilder {
             tool_calls: None,
             function_call: None,
             tool_call_id: Some(tool_call_id),
+            audio: None,
+            reasoning_content: None,
         });
         self
     }
@@ -201,11 +207,15 @@ impl ToolCallResponseFormatter {
                     tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
                     function_call: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 },
                 finish_reason: "tool_calls".to_string(),
                 logprobs: None,
+                content_filter_results: None,
             }],
             usage: Some(usage.unwrap_or(self.default_usage.clone())),
+            prompt_filter_results: None,
         }
     }
 
@@ -231,11 +241,15 @@ impl ToolCallResponseFormatter {
                     tool_calls: None,
                     function_call: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 },
                 finish_reason: "stop".to_string(),
                 logprobs: None,
+                content_filter_results: None,
             }],
             usage: Some(usage.unwrap_or(self.default_usage.clone())),
+            prompt_filter_results: None,
         }
     }
 
@@ -257,11 +271,15 @@ impl ToolCallResponseFormatter {
                     tool_calls: None,
                     function_call: None,
                     tool_call_id: None,
+                    audio: None,
+                    reasoning_content: None,
                 },
                 finish_reason: "error".to_string(),
                 logprobs: None,
+                content_filter_results: None,
             }],
             usage: Some(self.default_usage.clone()),
+            prompt_filter_results: None,
         }
     }
 
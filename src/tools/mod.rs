@@ -35,6 +35,9 @@ pub enum ToolError {
     #[error("Tool validation failed: {message}")]
     ValidationFailed { message: String },
 
+    #[error("Tool loop exceeded the maximum of {max_iterations} iterations")]
+    IterationLimitExceeded { max_iterations: usize },
+
     #[error("Serialization error: {source}")]
     Serialization { #[from] source: serde_json::Error },
 }
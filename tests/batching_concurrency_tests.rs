@@ -76,6 +76,8 @@ fn create_test_request(request_id: usize) -> ChatCompletionRequest {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
         ],
         stream: Some(false),
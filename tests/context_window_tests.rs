@@ -80,6 +80,8 @@ fn create_long_conversation(message_count: usize, message_length: usize) -> Vec<
             function_call: None,
             tool_call_id: None,
             tool_calls: None,
+            audio: None,
+            reasoning_content: None,
         });
     }
     
@@ -276,6 +276,7 @@ mod tests {
             Adapter::AzureOpenAI(_) => assert!(true),
             Adapter::AWSBedrock(_) => assert!(true),
             Adapter::Custom(_) => assert!(true),
+            Adapter::Template(_) => assert!(true),
             Adapter::Direct(_) => assert!(true),
         }
     }
@@ -298,6 +299,10 @@ mod tests {
                 ProxyError::BadRequest(_) => assert!(true),
                 ProxyError::Internal(_) => assert!(true),
                 ProxyError::Upstream(_) => assert!(true),
+                ProxyError::UpstreamStatus { .. } => assert!(true),
+                ProxyError::ModelLoading(_) => assert!(true),
+                ProxyError::Transport { .. } => assert!(true),
+                ProxyError::IncompleteResponse { .. } => assert!(true),
                 ProxyError::Serialization(_) => assert!(true),
             }
         }
@@ -315,6 +320,8 @@ mod tests {
             function_call: None,
             tool_calls: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         };
 
         let request = ChatCompletionRequest {
@@ -330,6 +337,7 @@ mod tests {
             model: "test-model".to_string(),
             choices: vec![],
             usage: None,
+            prompt_filter_results: None,
         };
 
         assert_eq!(request.messages.len(), 1);
@@ -4,7 +4,7 @@
 //! integration scenarios, and performance validation across all adapters.
 
 use nexus_nitro_llm::{
-    adapters::{LightLLMAdapter, OpenAIAdapter, VLLMAdapter, AzureOpenAIAdapter, CustomAdapter},
+    adapters::{LightLLMAdapter, LightLLMPromptTemplate, OpenAIAdapter, VLLMAdapter, AzureOpenAIAdapter, CustomAdapter},
     schemas::{Tool, FunctionDefinition},
     graceful_shutdown::GracefulShutdown,
     error::ProxyError,
@@ -26,6 +26,7 @@ async fn test_lightllm_streaming() {
         "test-model".to_string(),
         None,
         Client::new(),
+        LightLLMPromptTemplate::default(),
     );
     
     
@@ -40,6 +41,8 @@ async fn test_lightllm_streaming() {
             function_call: None,
             tool_call_id: None,
             tool_calls: None,
+            audio: None,
+            reasoning_content: None,
         }],
         stream: Some(true),
         ..Default::default()
@@ -69,6 +72,7 @@ async fn test_openai_streaming() {
         "https://api.openai.com/v1".to_string(),
         "gpt-3.5-turbo".to_string(),
         Some("test-token".to_string()),
+        None,
         Client::new(),
     );
     
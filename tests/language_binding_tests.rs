@@ -119,6 +119,8 @@ mod tests {
             function_call: None,
             tool_calls: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         };
 
         let request = ChatCompletionRequest {;
@@ -247,6 +249,8 @@ mod tests {
             function_call: None,
             tool_calls: None,
             tool_call_id: None,
+            audio: None,
+            reasoning_content: None,
         };
 
         let request = ChatCompletionRequest {;
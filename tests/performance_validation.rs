@@ -23,6 +23,7 @@ async fn test_connection_pooling() {
         retry_attempts: 1,
         retry_base_delay: Duration::from_millis(100),
         max_retry_delay: Duration::from_secs(1),
+        retry_total_budget_ms: None,
     };
     
     let client = HighPerformanceClient::new(config).unwrap();
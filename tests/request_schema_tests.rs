@@ -77,6 +77,8 @@ fn create_valid_test_request() -> ChatCompletionRequest {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
         ],
         stream: Some(false),
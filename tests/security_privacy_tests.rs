@@ -82,6 +82,8 @@ fn create_test_request() -> ChatCompletionRequest {
                 function_call: None,
                 tool_call_id: None,
                 tool_calls: None,
+                audio: None,
+                reasoning_content: None,
             },
         ],
         stream: Some(false),
@@ -92,6 +94,14 @@ fn create_test_request() -> ChatCompletionRequest {
         presence_penalty: Some(0.0),
         tools: None,
         tool_choice: None,
+        modalities: None,
+        audio: None,
+        suffix: None,
+        system_prompt_ref: None,
+        response_format: None,
+        top_k: None,
+        reasoning_effort: None,
+        conversation_id: None,
     }
 }
 
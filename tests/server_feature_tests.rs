@@ -35,6 +35,7 @@ mod tests {
             retry_attempts: 5,
             retry_base_delay: Duration::from_millis(200),
             max_retry_delay: Duration::from_secs(10),
+            retry_total_budget_ms: None,
         };
 
         let client_result = HighPerformanceClient::new(config.clone());
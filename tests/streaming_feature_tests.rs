@@ -82,7 +82,7 @@ mod tests {
     #[cfg(feature = "streaming")]
     fn test_lightllm_streaming() {
         use nexus_nitro_llm::streaming::adapters::lightllm_streaming;
-        use nexus_nitro_llm::adapters::LightLLMAdapter;
+        use nexus_nitro_llm::adapters::{LightLLMAdapter, LightLLMPromptTemplate};
         use nexus_nitro_llm::core::http_client::HttpClientBuilder;
 
         let client = HttpClientBuilder::new().build().unwrap();
@@ -91,6 +91,7 @@ mod tests {
             "test-model".to_string(),
             None,
             client,
+            LightLLMPromptTemplate::default(),
         );
 
         let request = ChatCompletionRequest {
@@ -101,6 +102,8 @@ mod tests {
                 function_call: None,
                 tool_calls: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }],
             model: Some("test-model".to_string()),
             stream: Some(true),
@@ -136,6 +139,8 @@ mod tests {
                 function_call: None,
                 tool_calls: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }],
             model: Some("gpt-3.5-turbo".to_string()),
             stream: Some(true),
@@ -170,6 +175,8 @@ mod tests {
                 function_call: None,
                 tool_calls: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }],
             model: Some("test-model".to_string()),
             stream: Some(true),
@@ -192,8 +199,10 @@ mod tests {
             "https://test.openai.azure.com".to_string(),
             "gpt-35-turbo".to_string(),
             None,
+            None,
             client,
-        );
+        )
+        .unwrap();
 
         let request = ChatCompletionRequest {
             messages: vec![Message {
@@ -203,6 +212,8 @@ mod tests {
                 function_call: None,
                 tool_calls: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }],
             model: Some("gpt-35-turbo".to_string()),
             stream: Some(true),
@@ -236,6 +247,8 @@ mod tests {
                 function_call: None,
                 tool_calls: None,
                 tool_call_id: None,
+                audio: None,
+                reasoning_content: None,
             }],
             model: Some("custom-model".to_string()),
             stream: Some(true),
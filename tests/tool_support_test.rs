@@ -50,6 +50,8 @@ fn test_tool_use_message_creation() {
         function_call: None,
         tool_call_id: None,
         tool_calls: None,
+        audio: None,
+        reasoning_content: None,
     };
     assert_eq!(user_message.role, "user");
     assert_eq!(user_message.content, Some("Hello, world!".to_string()));
@@ -64,6 +66,8 @@ fn test_tool_use_message_creation() {
         function_call: None,
         tool_call_id: Some("call-123".to_string()),
         tool_calls: None,
+        audio: None,
+        reasoning_content: None,
     };
     assert_eq!(tool_result.role, "tool");
     assert_eq!(tool_result.tool_call_id, Some("call-123".to_string()));
@@ -86,6 +90,8 @@ fn test_tool_use_message_creation() {
         function_call: None,
         tool_call_id: None,
         tool_calls: Some(vec![tool_call.clone()]),
+        audio: None,
+        reasoning_content: None,
     };
     assert_eq!(assistant_message.role, "assistant");
     assert_eq!(assistant_message.tool_calls, Some(vec![tool_call]));
@@ -117,6 +123,8 @@ fn test_message_conversion() {
         function_call: None,
         tool_call_id: Some("call-789".to_string()),
         tool_calls: None,
+        audio: None,
+        reasoning_content: None,
     };
     
     let converted_tool_message = ToolUseMessage::from_message(standard_message).unwrap();
@@ -132,6 +140,8 @@ fn test_message_conversion() {
         function_call: None,
         tool_call_id: None,
         tool_calls: None,
+        audio: None,
+        reasoning_content: None,
     };
     
     assert!(ToolUseMessage::from_message(invalid_message).is_err());
@@ -380,6 +390,8 @@ async fn test_tool_call_message_builder() {
             function_call: None,
             tool_call_id: None,
             tool_calls: None,
+            audio: None,
+            reasoning_content: None,
         },
         Message {
             role: "assistant".to_string(),
@@ -395,6 +407,8 @@ async fn test_tool_call_message_builder() {
                     arguments: json!({"a": 2, "b": 3}).to_string(),
                 },
             }]),
+            audio: None,
+            reasoning_content: None,
         },
     ];
     
@@ -547,6 +561,8 @@ async fn test_complete_tool_use_workflow() {
             function_call: None,
             tool_call_id: None,
             tool_calls: None,
+            audio: None,
+            reasoning_content: None,
         },
         Message {
             role: "assistant".to_string(),
@@ -572,6 +588,8 @@ async fn test_complete_tool_use_workflow() {
                     },
                 },
             ]),
+            audio: None,
+            reasoning_content: None,
         },
     ];
     